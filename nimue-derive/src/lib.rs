@@ -0,0 +1,255 @@
+//! Derive macros for [nimue](https://docs.rs/nimue) transcripts.
+//!
+//! `#[derive(TranscriptMessage)]` keeps the IO Pattern, the prover, and the verifier of a prover message in sync:
+//! for a struct whose fields are bytes, field elements, or group elements, it generates
+//!
+//! - `pattern(io)`, adding one operation per field to the IO Pattern, labeled with the field name;
+//! - `add_to(&mut transcript)`, writing each field to the protocol transcript (e.g. a `Merlin`);
+//! - `read_from(&mut transcript)`, reading each field back from the protocol transcript (e.g. an `Arthur`).
+//!
+//! Fields are processed in order of declaration. Each field is either a single element or an array of elements:
+//!
+//! - `u8` and `[u8; N]` are bytes;
+//! - `#[nimue(scalars)]` marks field elements, using the arkworks plugin;
+//! - `#[nimue(points)]` marks group elements, using the arkworks plugin.
+//!
+//! The label of a field can be changed with `#[nimue(label = "...")]`.
+//!
+//! ```ignore
+//! #[derive(TranscriptMessage)]
+//! struct Commitment<G: CurveGroup> {
+//!     #[nimue(points)]
+//!     commitment: G,
+//!     #[nimue(scalars)]
+//!     evaluations: [G::ScalarField; 2],
+//!     salt: [u8; 16],
+//! }
+//!
+//! let io = Commitment::<G>::pattern(IOPattern::<DefaultHash>::new("📝"));
+//! let mut merlin = io.to_merlin();
+//! commitment.add_to(&mut merlin)?;
+//! let mut arthur = io.to_arthur(merlin.transcript());
+//! assert_eq!(Commitment::<G>::read_from(&mut arthur)?, commitment);
+//! ```
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Fields, LitStr, Type};
+
+/// How a field is encoded in the protocol transcript.
+enum Kind {
+    Bytes,
+    Scalars,
+    Points,
+}
+
+/// A field of the message, with its encoding.
+struct Message {
+    ident: syn::Ident,
+    label: String,
+    kind: Kind,
+    /// The type of a single element.
+    element: Type,
+    /// The number of elements, if the field is an array.
+    length: Option<Expr>,
+}
+
+impl Message {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let ident = field.ident.clone().expect("named field");
+        let mut label = ident.to_string();
+        let mut kind = None;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("nimue")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("bytes") {
+                    kind = Some(Kind::Bytes);
+                } else if meta.path.is_ident("scalars") {
+                    kind = Some(Kind::Scalars);
+                } else if meta.path.is_ident("points") {
+                    kind = Some(Kind::Points);
+                } else if meta.path.is_ident("label") {
+                    label = meta.value()?.parse::<LitStr>()?.value();
+                } else {
+                    return Err(meta.error("expected `bytes`, `scalars`, `points`, or `label`"));
+                }
+                Ok(())
+            })?;
+        }
+
+        let (element, length) = match &field.ty {
+            Type::Array(array) => ((*array.elem).clone(), Some(array.len.clone())),
+            ty => (ty.clone(), None),
+        };
+        let kind = match kind {
+            Some(kind) => kind,
+            None if is_u8(&element) => Kind::Bytes,
+            None => {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "cannot infer the encoding: add `#[nimue(scalars)]` or `#[nimue(points)]`",
+                ))
+            }
+        };
+        Ok(Self {
+            ident,
+            label,
+            kind,
+            element,
+            length,
+        })
+    }
+
+    /// The number of elements, as an expression.
+    fn count(&self) -> TokenStream2 {
+        match &self.length {
+            Some(length) => quote!((#length)),
+            None => quote!(1),
+        }
+    }
+
+    fn pattern_bound(&self) -> TokenStream2 {
+        let element = &self.element;
+        match self.kind {
+            Kind::Bytes => quote!(::nimue::ByteIOPattern),
+            Kind::Scalars => quote!(::nimue::plugins::ark::FieldIOPattern<#element>),
+            Kind::Points => quote!(::nimue::plugins::ark::GroupIOPattern<#element>),
+        }
+    }
+
+    fn writer_bound(&self) -> TokenStream2 {
+        let element = &self.element;
+        match self.kind {
+            Kind::Bytes => quote!(::nimue::ByteWriter),
+            Kind::Scalars => quote!(::nimue::plugins::ark::FieldWriter<#element>),
+            Kind::Points => quote!(::nimue::plugins::ark::GroupWriter<#element>),
+        }
+    }
+
+    fn reader_bound(&self) -> TokenStream2 {
+        let element = &self.element;
+        match self.kind {
+            Kind::Bytes => quote!(::nimue::ByteReader),
+            Kind::Scalars => quote!(::nimue::plugins::ark::FieldReader<#element>),
+            Kind::Points => quote!(::nimue::plugins::ark::GroupReader<#element>),
+        }
+    }
+
+    fn pattern(&self) -> TokenStream2 {
+        let (bound, label, count) = (self.pattern_bound(), &self.label, self.count());
+        let method = match self.kind {
+            Kind::Bytes => quote!(add_bytes),
+            Kind::Scalars => quote!(add_scalars),
+            Kind::Points => quote!(add_points),
+        };
+        quote!(let __nimue_io = <__IO as #bound>::#method(__nimue_io, #count, #label);)
+    }
+
+    fn write(&self) -> TokenStream2 {
+        let (bound, ident) = (self.writer_bound(), &self.ident);
+        let method = match self.kind {
+            Kind::Bytes => quote!(add_bytes),
+            Kind::Scalars => quote!(add_scalars),
+            Kind::Points => quote!(add_points),
+        };
+        let input = match self.length {
+            Some(_) => quote!(&self.#ident),
+            None => quote!(::core::slice::from_ref(&self.#ident)),
+        };
+        quote!(<__T as #bound>::#method(__nimue_transcript, #input)?;)
+    }
+
+    fn read(&self) -> TokenStream2 {
+        let (bound, ident) = (self.reader_bound(), &self.ident);
+        let method = match self.kind {
+            Kind::Bytes => quote!(next_bytes),
+            Kind::Scalars => quote!(next_scalars),
+            Kind::Points => quote!(next_points),
+        };
+        match &self.length {
+            Some(length) => {
+                quote!(let #ident = <__T as #bound>::#method::<{ #length }>(__nimue_transcript)?;)
+            }
+            None => quote!(let [#ident] = <__T as #bound>::#method::<1>(__nimue_transcript)?;),
+        }
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("u8"))
+}
+
+/// Derive `pattern`, `add_to`, and `read_from` for a prover message.
+///
+/// See the [crate documentation](crate) for the supported fields and attributes.
+#[proc_macro_derive(TranscriptMessage, attributes(nimue))]
+pub fn derive_transcript_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "TranscriptMessage requires named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "TranscriptMessage can only be derived for structs",
+            ))
+        }
+    };
+    let messages = fields
+        .iter()
+        .map(Message::parse)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let pattern_bounds = messages.iter().map(Message::pattern_bound);
+    let writer_bounds = messages.iter().map(Message::writer_bound);
+    let reader_bounds = messages.iter().map(Message::reader_bound);
+    let patterns = messages.iter().map(Message::pattern);
+    let writes = messages.iter().map(Message::write);
+    let reads = messages.iter().map(Message::read);
+    let idents = messages.iter().map(|message| &message.ident);
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Add this message to the IO Pattern, one operation per field.
+            pub fn pattern<__IO>(__nimue_io: __IO) -> __IO
+            where
+                #(__IO: #pattern_bounds,)*
+            {
+                #(#patterns)*
+                __nimue_io
+            }
+
+            /// Write this message to the protocol transcript.
+            pub fn add_to<__T>(&self, __nimue_transcript: &mut __T) -> ::nimue::ProofResult<()>
+            where
+                #(__T: #writer_bounds,)*
+            {
+                #(#writes)*
+                Ok(())
+            }
+
+            /// Read this message from the protocol transcript.
+            pub fn read_from<__T>(__nimue_transcript: &mut __T) -> ::nimue::ProofResult<Self>
+            where
+                #(__T: #reader_bounds,)*
+            {
+                #(#reads)*
+                Ok(Self { #(#idents),* })
+            }
+        }
+    })
+}
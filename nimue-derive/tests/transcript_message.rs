@@ -0,0 +1,72 @@
+use ark_bls12_381::{Fr, G1Projective};
+use ark_ec::{CurveGroup, PrimeGroup};
+use nimue::{ByteIOPattern, ByteReader, ByteWriter, DefaultHash, IOPattern};
+use nimue_derive::TranscriptMessage;
+
+#[derive(Debug, PartialEq, TranscriptMessage)]
+struct Commitment<G: CurveGroup> {
+    #[nimue(points)]
+    commitment: G,
+    #[nimue(scalars, label = "evals")]
+    evaluations: [G::ScalarField; 2],
+    salt: [u8; 4],
+    version: u8,
+}
+
+/// The IO Pattern, prover, and verifier derived for a message agree.
+#[test]
+fn test_derive_transcript_message() {
+    let message = Commitment::<G1Projective> {
+        commitment: G1Projective::generator(),
+        evaluations: [Fr::from(1u64), Fr::from(2u64)],
+        salt: *b"salt",
+        version: 1,
+    };
+
+    let io = Commitment::<G1Projective>::pattern(IOPattern::<DefaultHash>::new("example.com"))
+        .add_bytes(1, "trailer");
+    let expected = IOPattern::<DefaultHash>::new("example.com")
+        .add_bytes(48, "commitment")
+        .add_bytes(64, "evals")
+        .add_bytes(4, "salt")
+        .add_bytes(1, "version")
+        .add_bytes(1, "trailer");
+    assert_eq!(io.as_bytes(), expected.as_bytes());
+
+    let mut merlin = io.to_merlin();
+    message.add_to(&mut merlin).unwrap();
+    merlin.add_bytes(&[0xff]).unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert_eq!(
+        Commitment::<G1Projective>::read_from(&mut arthur).unwrap(),
+        message
+    );
+    assert_eq!(arthur.next_bytes().unwrap(), [0xff]);
+
+    // the message does not fit a shorter transcript
+    let mut arthur = io.to_arthur(&merlin.transcript()[..100]);
+    assert!(Commitment::<G1Projective>::read_from(&mut arthur).is_err());
+}
+
+#[derive(Debug, PartialEq, TranscriptMessage)]
+struct Shadowing {
+    transcript: [u8; 2],
+    io: u8,
+}
+
+/// Fields named like the arguments of the derived methods do not shadow them.
+#[test]
+fn test_derive_field_names() {
+    let message = Shadowing {
+        transcript: [1, 2],
+        io: 3,
+    };
+
+    let io = Shadowing::pattern(IOPattern::<DefaultHash>::new("example.com"));
+    let mut merlin = io.to_merlin();
+    message.add_to(&mut merlin).unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert_eq!(Shadowing::read_from(&mut arthur).unwrap(), message);
+}
@@ -0,0 +1,107 @@
+/// Offload the Poseidon permutation of many independent sponges to a co-processor.
+///
+/// Provers squeezing many sponges at once (e.g. one per Merkle leaf, or one per parallel repetition)
+/// can use [`DuplexSponge::squeeze_many`], which hands the states to permute as a single batch
+/// to [`BatchPermutation::permute_many`]. The transcript logic stays in nimue.
+///
+/// Here, the co-processor is a pool of CPU threads. A GPU backend replaces [`Coprocessor::permute`]:
+/// it uploads the round constants of [`PoseidonSponge::params`] once, then, for each batch,
+/// copies the states to the device, launches one CUDA (or wgpu compute) thread per state, and copies them back.
+use ark_bls12_381::Fr;
+use nimue::hash::sponge::{BatchPermutation, DuplexSponge, Sponge};
+use nimue::hash::DuplexHash;
+use nimue_poseidon::bls12_381::PoseidonPermx5_255_3;
+use zeroize::Zeroize;
+
+/// The device permuting batches of Poseidon states.
+struct Coprocessor;
+
+impl Coprocessor {
+    /// Permute the `states`, splitting them across the available threads.
+    fn permute(states: &mut [PoseidonPermx5_255_3]) {
+        let threads = std::thread::available_parallelism().map_or(1, usize::from);
+        let chunk_len = states.len().div_ceil(threads).max(1);
+        std::thread::scope(|scope| {
+            for chunk in states.chunks_mut(chunk_len) {
+                scope.spawn(|| chunk.iter_mut().for_each(Sponge::permute));
+            }
+        });
+    }
+}
+
+/// Poseidon over the scalar field of BLS12-381, permuting batches on the [`Coprocessor`].
+#[derive(Clone, Default)]
+struct OffloadedPoseidon(PoseidonPermx5_255_3);
+
+impl Zeroize for OffloadedPoseidon {
+    fn zeroize(&mut self) {
+        self.0.zeroize()
+    }
+}
+
+impl AsRef<[Fr]> for OffloadedPoseidon {
+    fn as_ref(&self) -> &[Fr] {
+        self.0.as_ref()
+    }
+}
+
+impl AsMut<[Fr]> for OffloadedPoseidon {
+    fn as_mut(&mut self) -> &mut [Fr] {
+        self.0.as_mut()
+    }
+}
+
+impl Sponge for OffloadedPoseidon {
+    type U = Fr;
+    const N: usize = PoseidonPermx5_255_3::N;
+    const R: usize = PoseidonPermx5_255_3::R;
+
+    fn new(iv: [u8; 32]) -> Self {
+        Self(PoseidonPermx5_255_3::new(iv))
+    }
+
+    fn permute(&mut self) {
+        self.0.permute()
+    }
+}
+
+impl BatchPermutation for OffloadedPoseidon {
+    fn permute_many(states: &mut [Self]) {
+        let mut inner = states
+            .iter_mut()
+            .map(|s| core::mem::take(&mut s.0))
+            .collect::<Vec<_>>();
+        Coprocessor::permute(&mut inner);
+        for (state, permuted) in states.iter_mut().zip(inner) {
+            state.0 = permuted;
+        }
+    }
+}
+
+fn main() {
+    const LEAVES: usize = 256;
+    const SQUEEZED: usize = 5;
+
+    let mut sponges = (0..LEAVES)
+        .map(|i| {
+            let mut sponge = DuplexSponge::<OffloadedPoseidon>::new([0x42; 32]);
+            sponge.absorb_unchecked(&[Fr::from(i as u64)]);
+            sponge
+        })
+        .collect::<Vec<_>>();
+    let mut expected = sponges.clone();
+
+    let mut outputs = vec![[Fr::from(0u64); SQUEEZED]; LEAVES];
+    let mut output_refs = outputs.iter_mut().map(|o| &mut o[..]).collect::<Vec<_>>();
+    DuplexSponge::squeeze_many(&mut sponges, &mut output_refs);
+
+    for (sponge, output) in expected.iter_mut().zip(&outputs) {
+        let mut out = [Fr::from(0u64); SQUEEZED];
+        sponge.squeeze_unchecked(&mut out);
+        assert_eq!(&out, output);
+    }
+    println!(
+        "squeezed {} elements from each of {} sponges, permuting on the co-processor",
+        SQUEEZED, LEAVES
+    );
+}
@@ -7,6 +7,7 @@ pub type Poseidonx5_255_3 = DuplexSponge<PoseidonPermx5_255_3>;
 pub type Poseidonx5_255_5 = DuplexSponge<PoseidonPermx5_255_5>;
 
 mod x5_255_3 {
+    #[cfg(not(feature = "grain"))]
     use ark_ff::MontFp;
     pub type Field = ark_bls12_381::Fr;
     pub const ALPHA: u64 = 5;
@@ -15,6 +16,7 @@ mod x5_255_3 {
     pub const N: usize = 3;
     pub const R: usize = 2;
 
+    #[cfg(not(feature = "grain"))]
     pub const MDS: &'static [[Field; N]] = &[
         [
             MontFp!("0x3d955d6c02fe4d7cb500e12f2b55eff668a7b4386bd27413766713c93f2acfcd"),
@@ -33,6 +35,7 @@ mod x5_255_3 {
         ],
     ];
 
+    #[cfg(not(feature = "grain"))]
     pub const ARK: &'static [[Field; N]; R_F + R_P] = &[
         [
             MontFp!("0x6c4ffa723eaf1a7bf74905cc7dae4ca9ff4a2c3bc81d42e09540d1f250910880"),
@@ -363,6 +366,7 @@ mod x5_255_3 {
 }
 
 mod x5_255_5 {
+    #[cfg(not(feature = "grain"))]
     use ark_ff::MontFp;
     pub type Field = ark_bls12_381::Fr;
     pub const ALPHA: u64 = 5;
@@ -371,6 +375,7 @@ mod x5_255_5 {
     pub const N: usize = 5;
     pub const R: usize = 4;
 
+    #[cfg(not(feature = "grain"))]
     pub const MDS: &'static [[Field; N]] = &[
         [
             MontFp!("0x354423b163d1078b0dd645be56316e34a9b98e52dcf9f469be44b108be46c107"),
@@ -409,6 +414,7 @@ mod x5_255_5 {
         ],
     ];
 
+    #[cfg(not(feature = "grain"))]
     pub const ARK: &'static [[Field; N]; R_F + R_P] = &[
         [
             MontFp!("0x5ee52b2f39e240a4006e97a15a7609dce42fa9aa510d11586a56db98fa925158"),
@@ -1,6 +1,242 @@
 poseidon_sponge!(254, PoseidonPermx5_254_3, x5_254_3);
 poseidon_sponge!(254, PoseidonPermx5_254_5, x5_254_5);
+
+use nimue::hash::any::{backend_id, domain_separator};
+use nimue::hash::sponge::DuplexSponge;
+use nimue::hash::{AnyDuplexHash, HashBackend};
+use nimue::plugins::ark::bytes::BytesOverField;
+use nimue::{DuplexHash, IOPattern, OpParseError, OpParseErrorKind};
+
+/// A Poseidon hash over the scalar field of BN254, whose width is selected at runtime.
+///
+/// This is the field counterpart of [`nimue::hash::AnyDuplexHash`]:
+/// the identifier of the instance (`x5_254_3` or `x5_254_5`) prefixes the domain separator of the IO Pattern,
+/// see [`AnyPoseidonBn254::io_pattern`]. [`DuplexHash::new`] defaults to `x5_254_3`.
+#[derive(Clone)]
+pub enum AnyPoseidonBn254 {
+    X5_254_3(DuplexSponge<PoseidonPermx5_254_3>),
+    X5_254_5(DuplexSponge<PoseidonPermx5_254_5>),
+}
+
+impl AnyPoseidonBn254 {
+    /// The identifiers of the available instances.
+    pub const IDS: &'static [&'static str] = &["x5_254_3", "x5_254_5"];
+
+    /// Initialize the instance identified by `id` with `iv`, if any.
+    pub fn with_id(id: &str, iv: [u8; 32]) -> Option<Self> {
+        match id {
+            "x5_254_3" => Some(Self::X5_254_3(DuplexSponge::new(iv))),
+            "x5_254_5" => Some(Self::X5_254_5(DuplexSponge::new(iv))),
+            _ => None,
+        }
+    }
+
+    /// The identifier of the instance.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::X5_254_3(_) => "x5_254_3",
+            Self::X5_254_5(_) => "x5_254_5",
+        }
+    }
+
+    /// Create a new IO Pattern for the instance identified by `id`, prefixing the domain separator with it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` does not identify an instance.
+    pub fn io_pattern(id: &str, domsep: &str) -> IOPattern<Self, x5_254_3::Field> {
+        assert!(Self::IDS.contains(&id), "Unknown Poseidon instance");
+        IOPattern::new(&domain_separator(id, domsep))
+    }
+}
+
+impl Default for AnyPoseidonBn254 {
+    fn default() -> Self {
+        Self::X5_254_3(DuplexSponge::default())
+    }
+}
+
+impl zeroize::Zeroize for AnyPoseidonBn254 {
+    fn zeroize(&mut self) {
+        match self {
+            Self::X5_254_3(sponge) => sponge.zeroize(),
+            Self::X5_254_5(sponge) => sponge.zeroize(),
+        }
+    }
+}
+
+impl DuplexHash<x5_254_3::Field> for AnyPoseidonBn254 {
+    fn new(iv: [u8; 32]) -> Self {
+        Self::X5_254_3(DuplexSponge::new(iv))
+    }
+
+    /// Fails if the domain separator of the IO Pattern does not start with the identifier of an instance,
+    /// see [`AnyPoseidonBn254::io_pattern`].
+    fn new_for_io_pattern(
+        io_pattern: &[u8],
+        _digest: [u8; 32],
+        iv: [u8; 32],
+    ) -> Result<Self, OpParseError> {
+        backend_id(io_pattern)
+            .and_then(|id| Self::with_id(id, iv))
+            .ok_or(OpParseError {
+                position: 0,
+                kind: OpParseErrorKind::UnknownBackend,
+            })
+    }
+
+    fn new_like(&self, iv: [u8; 32]) -> Self {
+        Self::with_id(self.id(), iv).unwrap()
+    }
+
+    fn absorb_unchecked(&mut self, input: &[x5_254_3::Field]) -> &mut Self {
+        match self {
+            Self::X5_254_3(sponge) => _ = sponge.absorb_unchecked(input),
+            Self::X5_254_5(sponge) => _ = sponge.absorb_unchecked(input),
+        }
+        self
+    }
+
+    fn squeeze_unchecked(&mut self, output: &mut [x5_254_3::Field]) -> &mut Self {
+        match self {
+            Self::X5_254_3(sponge) => _ = sponge.squeeze_unchecked(output),
+            Self::X5_254_5(sponge) => _ = sponge.squeeze_unchecked(output),
+        }
+        self
+    }
+
+    fn ratchet_unchecked(&mut self) -> &mut Self {
+        match self {
+            Self::X5_254_3(sponge) => _ = sponge.ratchet_unchecked(),
+            Self::X5_254_5(sponge) => _ = sponge.ratchet_unchecked(),
+        }
+        self
+    }
+}
+
+/// Poseidon (`x5_254_3`) over the scalar field of BN254, as a duplex hash over bytes (see [`BytesOverField`]).
+pub type PoseidonBn254Bytes = BytesOverField<DuplexSponge<PoseidonPermx5_254_3>, x5_254_3::Field>;
+
+/// A duplex hash over bytes, selected at runtime among the backends of [`AnyDuplexHash`] and Poseidon over BN254.
+///
+/// Poseidon is identified by `poseidon-bn254`, the other backends by their [`HashBackend::id`].
+/// As for [`AnyDuplexHash`], the identifier prefixes the domain separator of the IO Pattern,
+/// see [`AnyDuplexHashBn254::io_pattern`]. [`DuplexHash::new`] defaults to [`HashBackend::Keccak`].
+#[derive(Clone)]
+pub enum AnyDuplexHashBn254 {
+    Bytes(AnyDuplexHash),
+    PoseidonBn254(PoseidonBn254Bytes),
+}
+
+impl AnyDuplexHashBn254 {
+    /// The identifier of Poseidon over BN254.
+    pub const POSEIDON_BN254: &'static str = "poseidon-bn254";
+
+    /// Initialize the backend identified by `id` with `iv`, if any.
+    pub fn with_id(id: &str, iv: [u8; 32]) -> Option<Self> {
+        if id == Self::POSEIDON_BN254 {
+            return Some(Self::PoseidonBn254(PoseidonBn254Bytes::new(iv)));
+        }
+        HashBackend::from_id(id)
+            .map(|backend| Self::Bytes(AnyDuplexHash::with_backend(backend, iv)))
+    }
+
+    /// The identifier of the backend.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Bytes(sponge) => sponge.backend().id(),
+            Self::PoseidonBn254(_) => Self::POSEIDON_BN254,
+        }
+    }
+
+    /// Create a new IO Pattern for the backend identified by `id`, prefixing the domain separator with it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` does not identify a backend.
+    pub fn io_pattern(id: &str, domsep: &str) -> IOPattern<Self> {
+        assert!(
+            id == Self::POSEIDON_BN254 || HashBackend::from_id(id).is_some(),
+            "Unknown hash backend"
+        );
+        IOPattern::new(&domain_separator(id, domsep))
+    }
+}
+
+impl Default for AnyDuplexHashBn254 {
+    fn default() -> Self {
+        Self::Bytes(AnyDuplexHash::default())
+    }
+}
+
+impl zeroize::Zeroize for AnyDuplexHashBn254 {
+    fn zeroize(&mut self) {
+        match self {
+            Self::Bytes(sponge) => sponge.zeroize(),
+            Self::PoseidonBn254(sponge) => sponge.zeroize(),
+        }
+    }
+}
+
+impl DuplexHash<u8> for AnyDuplexHashBn254 {
+    fn new(iv: [u8; 32]) -> Self {
+        Self::Bytes(AnyDuplexHash::new(iv))
+    }
+
+    /// Fails if the domain separator of the IO Pattern does not start with the identifier of a backend,
+    /// see [`AnyDuplexHashBn254::io_pattern`].
+    fn new_for_io_pattern(
+        io_pattern: &[u8],
+        _digest: [u8; 32],
+        iv: [u8; 32],
+    ) -> Result<Self, OpParseError> {
+        backend_id(io_pattern)
+            .and_then(|id| Self::with_id(id, iv))
+            .ok_or(OpParseError {
+                position: 0,
+                kind: OpParseErrorKind::UnknownBackend,
+            })
+    }
+
+    fn new_like(&self, iv: [u8; 32]) -> Self {
+        Self::with_id(self.id(), iv).unwrap()
+    }
+
+    /// Derive the IV with the backend prefixing the domain separator, as [`AnyDuplexHash`].
+    fn iv_for_io_pattern(io_pattern: &[u8]) -> Option<[u8; 32]> {
+        match backend_id(io_pattern)? {
+            Self::POSEIDON_BN254 => PoseidonBn254Bytes::iv_for_io_pattern(io_pattern),
+            _ => AnyDuplexHash::iv_for_io_pattern(io_pattern),
+        }
+    }
+
+    fn absorb_unchecked(&mut self, input: &[u8]) -> &mut Self {
+        match self {
+            Self::Bytes(sponge) => _ = sponge.absorb_unchecked(input),
+            Self::PoseidonBn254(sponge) => _ = sponge.absorb_unchecked(input),
+        }
+        self
+    }
+
+    fn squeeze_unchecked(&mut self, output: &mut [u8]) -> &mut Self {
+        match self {
+            Self::Bytes(sponge) => _ = sponge.squeeze_unchecked(output),
+            Self::PoseidonBn254(sponge) => _ = sponge.squeeze_unchecked(output),
+        }
+        self
+    }
+
+    fn ratchet_unchecked(&mut self) -> &mut Self {
+        match self {
+            Self::Bytes(sponge) => _ = sponge.ratchet_unchecked(),
+            Self::PoseidonBn254(sponge) => _ = sponge.ratchet_unchecked(),
+        }
+        self
+    }
+}
+
 mod x5_254_3 {
+    #[cfg(not(feature = "grain"))]
     use ark_ff::MontFp;
     pub type Field = ark_bn254::Fr;
     pub const ALPHA: u64 = 5;
@@ -9,6 +245,7 @@ mod x5_254_3 {
     pub const N: usize = 3;
     pub const R: usize = 2;
 
+    #[cfg(not(feature = "grain"))]
     pub const MDS: &'static [[Field; N]] = &[
         [
             MontFp!("0x109b7f411ba0e4c9b2b70caf5c36a7b194be7c11ad24378bfedb68592ba8118b"),
@@ -27,6 +264,7 @@ mod x5_254_3 {
         ],
     ];
 
+    #[cfg(not(feature = "grain"))]
     pub const ARK: &'static [[Field; N]; R_F + R_P] = &[
         [
             MontFp!("0x0ee9a592ba9a9518d05986d656f40c2114c4993c11bb29938d21d47304cd8e6e"),
@@ -357,6 +595,7 @@ mod x5_254_3 {
 }
 
 mod x5_254_5 {
+    #[cfg(not(feature = "grain"))]
     use ark_ff::MontFp;
     pub type Field = ark_bn254::Fr;
     pub const ALPHA: u64 = 5;
@@ -365,6 +604,7 @@ mod x5_254_5 {
     pub const N: usize = 5;
     pub const R: usize = 4;
 
+    #[cfg(not(feature = "grain"))]
     pub const MDS: &'static [[Field; N]] = &[
         [
             MontFp!("0x251e7fdf99591080080b0af133b9e4369f22e57ace3cd7f64fc6fdbcf38d7da1"),
@@ -403,6 +643,7 @@ mod x5_254_5 {
         ],
     ];
 
+    #[cfg(not(feature = "grain"))]
     pub const ARK: &'static [[Field; N]; R_F + R_P] = &[
         [
             MontFp!("0x0eb544fee2815dda7f53e29ccac98ed7d889bb4ebd47c3864f3c2bd81a6da891"),
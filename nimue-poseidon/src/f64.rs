@@ -11,6 +11,7 @@ poseidon_sponge!(64, PoseidonPermx3_64_24, x3_64_24);
 
 mod x3_64_24 {
 
+    #[cfg(not(feature = "grain"))]
     use ark_ff::MontFp;
 
     pub type Field = super::Field64;
@@ -20,6 +21,7 @@ mod x3_64_24 {
     pub const N: usize = 24;
     pub const R: usize = 20;
 
+    #[cfg(not(feature = "grain"))]
     pub const MDS: &'static [[Field; N]] = &[
         [
             MontFp!("0x70e6dae2c651cead"),
@@ -647,6 +649,7 @@ mod x3_64_24 {
         ],
     ];
 
+    #[cfg(not(feature = "grain"))]
     pub const ARK: &'static [[Field; N]; R_F + R_P] = &[
         [
             MontFp!("0x240ec2a793108b4a"),
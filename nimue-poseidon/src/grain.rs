@@ -0,0 +1,129 @@
+//! On-demand generation of Poseidon constants.
+//!
+//! The round constants and the MDS matrix of Poseidon are derived from the Grain LFSR,
+//! following the reference procedure from the [Poseidon paper](https://eprint.iacr.org/2019/458)
+//! (`generate_parameters_grain.sage`).
+//! All the parameter sets baked in this crate can be recomputed this way:
+//! generating them at startup instead trades some CPU time for a smaller binary.
+
+use ark_ff::{BigInteger, PrimeField};
+
+/// The Grain LFSR, in self-shrinking mode.
+pub struct GrainLFSR {
+    state: [bool; 80],
+    head: usize,
+}
+
+impl GrainLFSR {
+    /// Initialize the LFSR for a Poseidon instance over a prime field of `field_bits` bits,
+    /// with S-box $x^\alpha$, width `width`, and the given number of rounds.
+    pub fn new(field_bits: u64, width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        let mut state = [true; 80];
+        let fields = [
+            // prime field
+            (1, 2),
+            // S-box x^alpha
+            (0, 4),
+            (field_bits, 12),
+            (width as u64, 12),
+            (full_rounds as u64, 10),
+            (partial_rounds as u64, 10),
+        ];
+        let mut pos = 0;
+        for (value, len) in fields {
+            for i in (0..len).rev() {
+                state[pos] = (value >> i) & 1 == 1;
+                pos += 1;
+            }
+        }
+        // the remaining 30 bits are set to one.
+
+        let mut lfsr = Self { state, head: 0 };
+        for _ in 0..160 {
+            lfsr.update();
+        }
+        lfsr
+    }
+
+    /// Return the next bit of the LFSR, discarding the first bit of each pair that starts with zero.
+    pub fn next_bit(&mut self) -> bool {
+        loop {
+            let first = self.update();
+            let second = self.update();
+            if first {
+                return second;
+            }
+        }
+    }
+
+    /// Return the next `n` bits, in big-endian order.
+    pub fn next_bits(&mut self, n: usize) -> Vec<bool> {
+        (0..n).map(|_| self.next_bit()).collect()
+    }
+
+    /// Sample a field element, rejecting integers larger than the modulus.
+    pub fn next_field_element_with_rejection<F: PrimeField>(&mut self) -> F {
+        loop {
+            let bits = self.next_bits(F::MODULUS_BIT_SIZE as usize);
+            if let Some(f) = F::from_bigint(F::BigInt::from_bits_be(&bits)) {
+                return f;
+            }
+        }
+    }
+
+    /// Sample a field element, reducing the integer modulo the field size.
+    pub fn next_field_element_without_rejection<F: PrimeField>(&mut self) -> F {
+        let bits = self.next_bits(F::MODULUS_BIT_SIZE as usize);
+        F::from_be_bytes_mod_order(&F::BigInt::from_bits_be(&bits).to_bytes_be())
+    }
+
+    fn update(&mut self) -> bool {
+        let bit = |i: usize| self.state[(self.head + i) % 80];
+        let new_bit = bit(62) ^ bit(51) ^ bit(38) ^ bit(23) ^ bit(13) ^ bit(0);
+        self.state[self.head] = new_bit;
+        self.head = (self.head + 1) % 80;
+        new_bit
+    }
+}
+
+/// Generate the additive round keys and the MDS matrix of Poseidon over `F` with width `N`.
+///
+/// The S-box exponent $\alpha$ is not part of the derivation, but must satisfy $\gcd(\alpha, p-1) = 1$.
+/// Returns `(ark, mds)`, in the format of [`PoseidonSponge`](crate::PoseidonSponge).
+pub fn poseidon_constants<F: PrimeField, const N: usize>(
+    full_rounds: usize,
+    partial_rounds: usize,
+) -> (Vec<[F; N]>, Vec<[F; N]>) {
+    let mut lfsr = GrainLFSR::new(F::MODULUS_BIT_SIZE as u64, N, full_rounds, partial_rounds);
+
+    let ark = (0..full_rounds + partial_rounds)
+        .map(|_| core::array::from_fn(|_| lfsr.next_field_element_with_rejection()))
+        .collect();
+
+    loop {
+        let xs: [F; N] = core::array::from_fn(|_| lfsr.next_field_element_without_rejection());
+        let ys: [F; N] = core::array::from_fn(|_| lfsr.next_field_element_without_rejection());
+        let distinct = xs
+            .iter()
+            .chain(ys.iter())
+            .enumerate()
+            .all(|(i, a)| xs.iter().chain(ys.iter()).skip(i + 1).all(|b| a != b));
+        if !distinct {
+            continue;
+        }
+
+        let mds: Option<Vec<[F; N]>> = xs
+            .iter()
+            .map(|&x| {
+                let mut row = [F::ZERO; N];
+                for (entry, &y) in row.iter_mut().zip(ys.iter()) {
+                    *entry = (x + y).inverse()?;
+                }
+                Some(row)
+            })
+            .collect();
+        if let Some(mds) = mds {
+            return (ark, mds);
+        }
+    }
+}
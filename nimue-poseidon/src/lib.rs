@@ -3,6 +3,7 @@
 use std::fmt::Debug;
 
 use ark_ff::PrimeField;
+use nimue::hash::sponge::BatchPermutation;
 use nimue::hash::sponge::DuplexSponge;
 use nimue::hash::sponge::Sponge;
 use nimue::hash::Unit;
@@ -32,6 +33,28 @@ pub struct PoseidonSponge<const NAME: u32, F: PrimeField, const R: usize, const
 pub type PoseidonHash<const NAME: u32, F, const R: usize, const N: usize> =
     DuplexSponge<PoseidonSponge<NAME, F, R, N>>;
 
+/// The parameters of a Poseidon instance, borrowed from a [`PoseidonSponge`].
+///
+/// Circuit builders re-implementing the permutation (e.g. to re-verify a transcript in-circuit)
+/// can consume these parameters instead of duplicating the tables of constants.
+/// The state has width `N = rate + capacity`, and the IV of a sponge is absorbed in its element of index `rate`,
+/// as a big-endian integer reduced modulo the field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoseidonParameters<'a, F, const N: usize> {
+    /// Number of field elements absorbed (or squeezed) per permutation.
+    pub rate: usize,
+    /// Number of rounds in a full-round operation.
+    pub full_rounds: usize,
+    /// Number of rounds in a partial-round operation.
+    pub partial_rounds: usize,
+    /// Exponent used in S-boxes.
+    pub alpha: u64,
+    /// Additive round keys, indexed by `ark[round_num][state_element_index]`.
+    pub ark: &'a [[F; N]],
+    /// Maximally Distance Separating (MDS) Matrix.
+    pub mds: &'a [[F; N]],
+}
+
 impl<const NAME: u32, F: PrimeField, const R: usize, const N: usize> AsRef<[F]>
     for PoseidonSponge<NAME, F, R, N>
 {
@@ -49,6 +72,21 @@ impl<const NAME: u32, F: PrimeField, const R: usize, const N: usize> AsMut<[F]>
 }
 
 impl<const NAME: u32, F: PrimeField, const R: usize, const N: usize> PoseidonSponge<NAME, F, R, N> {
+    /// The IV is written in the first element of the capacity, that must thus exist.
+    const SHAPE_CHECK: () = assert!(R < N, "Capacity of the Poseidon sponge should be > 0.");
+
+    /// The parameters of the permutation, see [`PoseidonParameters`].
+    pub fn params(&self) -> PoseidonParameters<'static, F, N> {
+        PoseidonParameters {
+            rate: R,
+            full_rounds: self.full_rounds,
+            partial_rounds: self.partial_rounds,
+            alpha: self.alpha,
+            ark: self.ark,
+            mds: self.mds,
+        }
+    }
+
     fn apply_s_box(&self, state: &mut [F], is_full_round: bool) {
         // Full rounds apply the S Box (x^alpha) to every element of state
         if is_full_round {
@@ -101,7 +139,7 @@ where
     const R: usize = R;
 
     fn new(iv: [u8; 32]) -> Self {
-        assert!(N >= 1);
+        let () = Self::SHAPE_CHECK;
         let mut sponge = Self::default();
         sponge.state[R] = F::from_be_bytes_mod_order(&iv);
         sponge
@@ -132,6 +170,14 @@ where
     }
 }
 
+impl<const NAME: u32, F, const R: usize, const N: usize> BatchPermutation
+    for PoseidonSponge<NAME, F, R, N>
+where
+    PoseidonSponge<NAME, F, R, N>: Default,
+    F: PrimeField + Unit,
+{
+}
+
 impl<const NAME: u32, F: PrimeField, const R: usize, const N: usize> Debug
     for PoseidonSponge<NAME, F, R, N>
 {
@@ -146,15 +192,53 @@ macro_rules! poseidon_sponge {
     ($bits: expr, $name: ident, $path: tt) => {
         pub type $name = crate::PoseidonSponge<$bits, $path::Field, { $path::R }, { $path::N }>;
 
+        // Reject misconfigured parameters at build time.
+        const _: () = {
+            assert!(
+                $path::R < $path::N,
+                "Capacity of the Poseidon sponge should be > 0."
+            );
+            assert!(
+                $path::R_F % 2 == 0,
+                "Full rounds are split evenly around the partial rounds."
+            );
+        };
+        #[cfg(not(feature = "grain"))]
+        const _: () = {
+            assert!(
+                $path::ARK.len() == $path::R_F + $path::R_P,
+                "One row of round constants per round."
+            );
+            assert!(
+                $path::MDS.len() == $path::N,
+                "The MDS matrix should be N x N."
+            );
+        };
+
         impl Default for $name {
             fn default() -> Self {
                 let alpha = $path::ALPHA;
+                #[cfg(not(feature = "grain"))]
+                let (ark, mds) = ($path::ARK, $path::MDS);
+                // Generate the constants at first use instead of baking them in the binary.
+                #[cfg(feature = "grain")]
+                let (ark, mds) = {
+                    #[allow(clippy::type_complexity)]
+                    static CONSTANTS: std::sync::OnceLock<(
+                        Vec<[$path::Field; $path::N]>,
+                        Vec<[$path::Field; $path::N]>,
+                    )> = std::sync::OnceLock::new();
+                    let (ark, mds) = CONSTANTS.get_or_init(|| {
+                        crate::grain::poseidon_constants($path::R_F, $path::R_P)
+                    });
+                    (ark.as_slice(), mds.as_slice())
+                };
                 Self {
                     full_rounds: $path::R_F,
                     partial_rounds: $path::R_P,
                     alpha,
-                    ark: $path::ARK,
-                    mds: $path::MDS,
+                    ark,
+                    mds,
                     state: [ark_ff::Zero::zero(); $path::N],
                 }
             }
@@ -162,6 +246,9 @@ macro_rules! poseidon_sponge {
     };
 }
 
+/// Generation of Poseidon constants via the Grain LFSR.
+pub mod grain;
+
 #[cfg(feature = "bls12-381")]
 pub mod bls12_381;
 
@@ -171,6 +258,9 @@ pub mod bn254;
 #[cfg(feature = "solinas")]
 pub mod f64;
 
+#[cfg(feature = "pasta")]
+pub mod pasta;
+
 /// Unit-tests.
 #[cfg(test)]
 mod tests;
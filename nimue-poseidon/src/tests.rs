@@ -14,15 +14,14 @@ where
 #[cfg(feature = "bls12-381")]
 #[test]
 fn test_squeeze_bytes_from_algebraic_hash() {
-    use nimue::ByteChallenges;
+    use nimue::{ByteChallenges, ByteIOPattern};
 
     type F = ark_bls12_381::Fr;
     type H = crate::bls12_381::Poseidonx5_255_3;
 
-    let io = nimue::IOPattern::<H, F>::new("test").absorb(1, "in");
-    let io = <nimue::IOPattern<H, F> as nimue::plugins::ark::ByteIOPattern>::challenge_bytes(
-        io, 2048, "out",
-    );
+    let io = nimue::IOPattern::<H, F>::new("test")
+        .absorb(1, "in")
+        .challenge_bytes(2048, "out");
     let mut merlin = io.to_merlin();
     merlin.add_units(&[F::from(0x42)]).unwrap();
 
@@ -46,6 +45,115 @@ fn test_squeeze_bytes_from_algebraic_hash() {
     );
 }
 
+/// The Grain LFSR reproduces reference constants, independently of the `grain` feature.
+///
+/// The reference values are those of the x5-255-3 tables over BLS12-381 shipped by this crate
+/// (as baked without the `grain` feature), whose permutation matches the test vectors of [`test_poseidon_bls12_381`].
+#[cfg(feature = "bls12-381")]
+#[test]
+fn test_grain_constants() {
+    use crate::grain::poseidon_constants;
+    use ark_ff::MontFp;
+
+    type F = ark_bls12_381::Fr;
+
+    const MDS: [[F; 3]; 3] = [
+        [
+            MontFp!("0x3d955d6c02fe4d7cb500e12f2b55eff668a7b4386bd27413766713c93f2acfcd"),
+            MontFp!("0x3798866f4e6058035dcf8addb2cf1771fac234bcc8fc05d6676e77e797f224bf"),
+            MontFp!("0x2c51456a7bf2467eac813649f3f25ea896eac27c5da020dae54a6e640278fda2"),
+        ],
+        [
+            MontFp!("0x20088ca07bbcd7490a0218ebc0ecb31d0ea34840e2dc2d33a1a5adfecff83b43"),
+            MontFp!("0x1d04ba0915e7807c968ea4b1cb2d610c7f9a16b4033f02ebacbb948c86a988c3"),
+            MontFp!("0x5387ccd5729d7acbd09d96714d1d18bbd0eeaefb2ddee3d2ef573c9c7f953307"),
+        ],
+        [
+            MontFp!("0x1e208f585a72558534281562cad89659b428ec61433293a8d7f0f0e38a6726ac"),
+            MontFp!("0x0455ebf862f0b60f69698e97d36e8aafd4d107cae2b61be1858b23a3363642e0"),
+            MontFp!("0x569e2c206119e89455852059f707370e2c1fc9721f6c50991cedbbf782daef54"),
+        ],
+    ];
+    const FIRST_ARK: [F; 3] = [
+        MontFp!("0x6c4ffa723eaf1a7bf74905cc7dae4ca9ff4a2c3bc81d42e09540d1f250910880"),
+        MontFp!("0x54dd837eccf180c92c2f53a3476e45a156ab69a403b6b9fdfd8dd970fddcdd9a"),
+        MontFp!("0x64f56d735286c35f0e7d0a29680d49d54fb924adccf8962eeee225bf9423a85e"),
+    ];
+    const LAST_ARK: [F; 3] = [
+        MontFp!("0x22abe8ea470a0372911bcef1367e10aa220491d76caeaa5959feb5d75f4a1f9f"),
+        MontFp!("0x5caab387eb997f774f64151ed21abfa5364a83c6f065d92bd9c92f2719b8e80b"),
+        MontFp!("0x57b33094aeff828377897b56e1c432978d07c668ef25a36bc5e2e835aaeff725"),
+    ];
+
+    let (ark, mds) = poseidon_constants::<F, 3>(8, 57);
+    assert_eq!(mds, MDS);
+    assert_eq!(ark.len(), 8 + 57);
+    assert_eq!(ark[0], FIRST_ARK);
+    assert_eq!(ark[ark.len() - 1], LAST_ARK);
+
+    // without the `grain` feature, the whole baked tables are checked too.
+    #[cfg(not(feature = "grain"))]
+    {
+        use crate::bls12_381::{PoseidonPermx5_255_3, PoseidonPermx5_255_5};
+
+        let baked = PoseidonPermx5_255_3::default();
+        assert_eq!(ark, baked.ark);
+        assert_eq!(mds, baked.mds);
+
+        let baked = PoseidonPermx5_255_5::default();
+        let (ark, mds) = poseidon_constants::<F, 5>(baked.full_rounds, baked.partial_rounds);
+        assert_eq!(ark, baked.ark);
+        assert_eq!(mds, baked.mds);
+    }
+}
+
+/// The permutation, re-implemented from the exported parameters only (as a circuit would), matches the native one.
+#[cfg(feature = "bls12-381")]
+#[test]
+fn test_poseidon_params() {
+    use crate::bls12_381::PoseidonPermx5_255_5;
+    use ark_ff::Field;
+
+    type F = ark_bls12_381::Fr;
+
+    let sponge = PoseidonPermx5_255_5::default();
+    let params = sponge.params();
+    assert_eq!((params.rate, params.alpha), (4, 5));
+    assert_eq!(params.ark.len(), params.full_rounds + params.partial_rounds);
+
+    let mut state: [F; 5] = core::array::from_fn(|i| F::from(i as u64));
+    let mut native = sponge.clone();
+    native.as_mut().clone_from_slice(&state);
+    native.permute();
+
+    let half = params.full_rounds / 2;
+    for (round, ark) in params.ark.iter().enumerate() {
+        state.iter_mut().zip(ark).for_each(|(x, c)| *x += c);
+        let full = round < half || round >= half + params.partial_rounds;
+        let sboxes = if full {
+            &mut state[..]
+        } else {
+            &mut state[..1]
+        };
+        sboxes.iter_mut().for_each(|x| *x = x.pow([params.alpha]));
+        state = core::array::from_fn(|i| (0..5).map(|j| params.mds[i][j] * state[j]).sum());
+    }
+    assert_eq!(native.as_ref(), state);
+}
+
+/// With the `grain` feature, the baked tables are generated, hence not compared.
+#[cfg(all(feature = "solinas", not(feature = "grain")))]
+#[test]
+fn test_grain_constants_f64() {
+    use crate::f64::{Field64, PoseidonPermx3_64_24};
+
+    let baked = PoseidonPermx3_64_24::default();
+    let (ark, mds) =
+        crate::grain::poseidon_constants::<Field64, 24>(baked.full_rounds, baked.partial_rounds);
+    assert_eq!(ark, baked.ark);
+    assert_eq!(mds, baked.mds);
+}
+
 #[cfg(feature = "bls12-381")]
 #[test]
 fn test_poseidon_bls12_381() {
@@ -199,3 +307,205 @@ fn test_poseidon_f64() {
     ];
     test_vector::<PoseidonPermx3_64_24>(&tv_x5_255_3_input, &tv_x5_255_3_output);
 }
+
+/// The published test vectors of `halo2_gadgets::poseidon::P128Pow5T3`, from
+/// [zcash-test-vectors](https://github.com/zcash-hackworks/zcash-test-vectors/tree/master/orchard_poseidon).
+///
+/// The hash vectors are for halo2's `ConstantLength<2>` domain:
+/// a single permutation of the two inputs, with $2 \cdot 2^{64}$ in the capacity.
+#[cfg(feature = "pasta")]
+#[test]
+fn test_poseidon_pasta() {
+    use crate::pasta::{PoseidonPermx5_pasta_pallas_3, PoseidonPermx5_pasta_vesta_3};
+    use ark_ff::MontFp;
+
+    type Fp = ark_vesta::Fr;
+    type Fq = ark_pallas::Fr;
+
+    fn test_hash_vector<H: Sponge>(input: [H::U; 2], output: H::U)
+    where
+        H::U: ark_ff::PrimeField,
+    {
+        let mut hash = H::default();
+        hash.as_mut()
+            .clone_from_slice(&[input[0], input[1], H::U::from(2u128 << 64)]);
+        hash.permute();
+        assert_eq!(hash.as_ref()[0], output);
+    }
+
+    // over the base field of Pallas
+    let input: [Fp; 3] = [
+        MontFp!("0x0000000000000000000000000000000000000000000000000000000000000000"),
+        MontFp!("0x0000000000000000000000000000000000000000000000000000000000000001"),
+        MontFp!("0x0000000000000000000000000000000000000000000000000000000000000002"),
+    ];
+    let output: [Fp; 3] = [
+        MontFp!("0x2a526acd0b64b45394efb364f966240ff7e69a71d0b642a0aeb1bc024aeca456"),
+        MontFp!("0x13c5d1568b4aa43076ff7dae343d5512dcd42e7fbed9dafe012a3e9628e5b82a"),
+        MontFp!("0x0a49c868c6976544256fcd597984561af7cfdfe1bda42c7b359029a1d34e9ddd"),
+    ];
+    test_vector::<PoseidonPermx5_pasta_vesta_3>(&input, &output);
+    let input: [Fp; 3] = [
+        MontFp!("0x082169eef62efaaf9d9364b1666e4d4c07576bac4994133ffb70fcad738f7a5c"),
+        MontFp!("0x0dcdb1cf014253b3c78849f2a39cefb0e6772b980e2e5d2aa6bde1f2b386dd1a"),
+        MontFp!("0x1435a7304e9de2a5d6368e9c7e1fe01f27c7a99b670f59f20f94b63225b869bd"),
+    ];
+    let output: [Fp; 3] = [
+        MontFp!("0x21ddae20d1d6227a036952a16129fda2cd878092770c38e77e8a9238832f6ed0"),
+        MontFp!("0x0d1451eeb8b98c361f1e4809bdf4e549c969c0d094ac79bcd6106f415fa45529"),
+        MontFp!("0x105bf9ac68bb569d795cbe4430401cc587ab82d9b76f131e2b5dd6e9bb76830d"),
+    ];
+    test_vector::<PoseidonPermx5_pasta_vesta_3>(&input, &output);
+    let input: [Fp; 2] = [
+        MontFp!("0x0000000000000000000000000000000000000000000000000000000000000000"),
+        MontFp!("0x0000000000000000000000000000000000000000000000000000000000000001"),
+    ];
+    test_hash_vector::<PoseidonPermx5_pasta_vesta_3>(
+        input,
+        MontFp!("0x062ff1c32bb0ef109d6a1bc9399a083eed83c2a7fb54cdbe389d32a011d75883"),
+    );
+    let input: [Fp; 2] = [
+        MontFp!("0x082169eef62efaaf9d9364b1666e4d4c07576bac4994133ffb70fcad738f7a5c"),
+        MontFp!("0x0dcdb1cf014253b3c78849f2a39cefb0e6772b980e2e5d2aa6bde1f2b386dd1a"),
+    ];
+    test_hash_vector::<PoseidonPermx5_pasta_vesta_3>(
+        input,
+        MontFp!("0x03e63b302667d2794b3992be2385a0f18e2ac0ca61ded5c430fef83eff7526db"),
+    );
+
+    // over the base field of Vesta
+    let input: [Fq; 3] = [
+        MontFp!("0x0000000000000000000000000000000000000000000000000000000000000000"),
+        MontFp!("0x0000000000000000000000000000000000000000000000000000000000000001"),
+        MontFp!("0x0000000000000000000000000000000000000000000000000000000000000002"),
+    ];
+    let output: [Fq; 3] = [
+        MontFp!("0x315a1f4cdb942f7ceddd74f22f8f2ff74d43d1973dd336c60eb08ea813bebe59"),
+        MontFp!("0x3be475f2d7642bde642adee0dd13aa48413ee0eb7bbd2198f9f126e61ea165f1"),
+        MontFp!("0x25ab8aece9537168117fdb2420d8ea605019bfd4e0423fa014d542372a7ba0d9"),
+    ];
+    test_vector::<PoseidonPermx5_pasta_pallas_3>(&input, &output);
+    let input: [Fq; 3] = [
+        MontFp!("0x082169eef62efaaf9d9364b1666e4d4c07576bac494c637e08574279738f7a5c"),
+        MontFp!("0x0dcdb1cf014253b3c78849f2a39cefb0e6772b980d9efda6c08a6d8ab386dd1a"),
+        MontFp!("0x1435a7304e9de2a5d6368e9c7e1fe01f27c7a99b667ffa6e296141ca25b869bd"),
+    ];
+    let output: [Fq; 3] = [
+        MontFp!("0x0e5e23dec34ae2cb65dc2d33807aba0ca47060c44c856d5f8f72c7df92838fcd"),
+        MontFp!("0x271b24d4bd6a4d54faf5d1467d1f33787ec0bf7f58d5d4238aaa833c95e553c2"),
+        MontFp!("0x3464766650675a131a6f464d7fbd5c1ca92c81995eb727c5d12a47f99b33c8b8"),
+    ];
+    test_vector::<PoseidonPermx5_pasta_pallas_3>(&input, &output);
+    let input: [Fq; 2] = [
+        MontFp!("0x0000000000000000000000000000000000000000000000000000000000000000"),
+        MontFp!("0x0000000000000000000000000000000000000000000000000000000000000001"),
+    ];
+    test_hash_vector::<PoseidonPermx5_pasta_pallas_3>(
+        input,
+        MontFp!("0x15ba96df939d77224664b1e35e194f514e3101097a6b54bff357297085f6684e"),
+    );
+    let input: [Fq; 2] = [
+        MontFp!("0x082169eef62efaaf9d9364b1666e4d4c07576bac494c637e08574279738f7a5c"),
+        MontFp!("0x0dcdb1cf014253b3c78849f2a39cefb0e6772b980d9efda6c08a6d8ab386dd1a"),
+    ];
+    test_hash_vector::<PoseidonPermx5_pasta_pallas_3>(
+        input,
+        MontFp!("0x2b43dbdcd13a70ad3d3e30b5ae980439c935a438c55aa5e60eba0c2e0ad90a0c"),
+    );
+}
+
+/// The baked pasta tables are those of the Grain LFSR.
+#[cfg(all(feature = "pasta", not(feature = "grain")))]
+#[test]
+fn test_grain_constants_pasta() {
+    use crate::grain::poseidon_constants;
+    use crate::pasta::{PoseidonPermx5_pasta_pallas_3, PoseidonPermx5_pasta_vesta_3};
+
+    let baked = PoseidonPermx5_pasta_pallas_3::default();
+    let (ark, mds) =
+        poseidon_constants::<ark_pallas::Fr, 3>(baked.full_rounds, baked.partial_rounds);
+    assert_eq!(ark, baked.ark);
+    assert_eq!(mds, baked.mds);
+
+    let baked = PoseidonPermx5_pasta_vesta_3::default();
+    let (ark, mds) =
+        poseidon_constants::<ark_vesta::Fr, 3>(baked.full_rounds, baked.partial_rounds);
+    assert_eq!(ark, baked.ark);
+    assert_eq!(mds, baked.mds);
+}
+
+#[cfg(feature = "bn254")]
+#[test]
+fn test_any_poseidon_bn254() {
+    use crate::bn254::{AnyPoseidonBn254, PoseidonPermx5_254_5};
+    use nimue::hash::sponge::DuplexSponge;
+    use nimue::{IOPattern, UnitTranscript};
+
+    type F = ark_bn254::Fr;
+
+    fn challenge<H: nimue::DuplexHash<F>>(io: &IOPattern<H, F>) -> F {
+        let mut merlin = io.to_merlin();
+        merlin.add_units(&[F::from(0x42)]).unwrap();
+        let mut out = [F::from(0)];
+        merlin.fill_challenge_units(&mut out).unwrap();
+        out[0]
+    }
+
+    let io = AnyPoseidonBn254::io_pattern("x5_254_5", "📝")
+        .absorb(1, "in")
+        .squeeze(1, "out");
+    let expected = IOPattern::<DuplexSponge<PoseidonPermx5_254_5>, F>::new("[x5_254_5]📝")
+        .absorb(1, "in")
+        .squeeze(1, "out");
+    assert_eq!(challenge(&io), challenge(&expected));
+
+    let other = AnyPoseidonBn254::io_pattern("x5_254_3", "📝")
+        .absorb(1, "in")
+        .squeeze(1, "out");
+    assert_ne!(challenge(&io), challenge(&other));
+
+    let unknown = IOPattern::<AnyPoseidonBn254, F>::new("[x5_254_7]📝").absorb(1, "in");
+    assert!(nimue::Safe::try_new(&unknown).is_err());
+}
+
+#[cfg(feature = "bn254")]
+#[test]
+fn test_any_duplex_hash_bn254() {
+    use crate::bn254::{AnyDuplexHashBn254, PoseidonBn254Bytes};
+    use nimue::hash::Keccak;
+    use nimue::{ByteChallenges, ByteReader, ByteWriter, DuplexHash, IOPattern};
+
+    fn prove<H: DuplexHash>(io: &IOPattern<H>) -> (Vec<u8>, [u8; 16]) {
+        let mut merlin = io.to_merlin();
+        merlin.add_bytes(&[0x42]).unwrap();
+        let chal = merlin.challenge_bytes().unwrap();
+        (merlin.transcript().to_vec(), chal)
+    }
+    fn io<H: DuplexHash>(domsep: &str) -> IOPattern<H> {
+        IOPattern::new(domsep).absorb(1, "in").squeeze(16, "out")
+    }
+
+    // each backend behaves as the hash it dispatches to.
+    let poseidon_io = AnyDuplexHashBn254::io_pattern("poseidon-bn254", "📝")
+        .absorb(1, "in")
+        .squeeze(16, "out");
+    let (transcript, chal) = prove(&poseidon_io);
+    assert_eq!(
+        prove(&io::<PoseidonBn254Bytes>("[poseidon-bn254]📝")).1,
+        chal
+    );
+    let keccak_io = AnyDuplexHashBn254::io_pattern("keccak", "📝")
+        .absorb(1, "in")
+        .squeeze(16, "out");
+    let keccak_chal = prove(&keccak_io).1;
+    assert_eq!(prove(&io::<Keccak>("[keccak]📝")).1, keccak_chal);
+    assert_ne!(chal, keccak_chal);
+
+    // the verifier selects the backend from the IO Pattern.
+    let mut arthur = poseidon_io.to_arthur(&transcript);
+    assert_eq!(arthur.next_bytes().unwrap(), [0x42]);
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+
+    let unknown = io::<AnyDuplexHashBn254>("[poseidon-bn255]📝");
+    assert!(nimue::Safe::try_new(&unknown).is_err());
+}
@@ -0,0 +1,78 @@
+//! Memory-hard proof of work, based on [Argon2id](https://datatracker.ietf.org/doc/html/rfc9106).
+//!
+//! Each [`check`](PowStrategy::check) fills `M_COST` KiB of memory, making grinding on GPUs and ASICs
+//! considerably less advantageous than for [`Blake3PoW`](crate::blake3::Blake3PoW) or [`KeccakPoW`](crate::keccak::KeccakPoW).
+//! The Argon2 instance always uses a single lane, so that the output does not depend on the number of threads:
+//! parallelism is obtained by the [`PowStrategy::solve`] default implementation, testing different nonces on different threads.
+use argon2::{Algorithm, Argon2, Block, Params, Version};
+
+use super::PowStrategy;
+
+/// Argon2id proof of work using `M_COST` KiB of memory and `T_COST` passes over it.
+///
+/// A nonce is accepted if the first 8 bytes of `Argon2id(password = nonce, salt = challenge)`,
+/// interpreted as a little-endian 64-bit integer, are strictly smaller than the threshold.
+/// The memory and time costs are **not** encoded within the [`IOPattern`](nimue::IOPattern):
+/// changing them requires changing the domain separator or the label.
+#[derive(Clone)]
+pub struct Argon2PoW<const M_COST: u32 = 4096, const T_COST: u32 = 1> {
+    challenge: [u8; 32],
+    threshold: u64,
+    argon2: Argon2<'static>,
+    memory: Vec<Block>,
+}
+
+impl<const M_COST: u32, const T_COST: u32> PowStrategy for Argon2PoW<M_COST, T_COST> {
+    fn new_with_threshold(challenge: [u8; 32], threshold: u64) -> Self {
+        let params = Params::new(M_COST, T_COST, 1, Some(8)).expect("invalid Argon2 parameters");
+        Self {
+            challenge,
+            threshold,
+            argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, params),
+            memory: vec![Block::default(); M_COST as usize],
+        }
+    }
+
+    /// Deterministic, and independent of the platform and of the number of threads available.
+    fn check(&mut self, nonce: u64) -> bool {
+        let mut result_bytes = [0; 8];
+        self.argon2
+            .hash_password_into_with_memory(
+                &nonce.to_le_bytes(),
+                &self.challenge,
+                &mut result_bytes,
+                &mut self.memory,
+            )
+            .expect("Argon2 parameters are validated at construction");
+        u64::from_le_bytes(result_bytes) < self.threshold
+    }
+}
+
+#[test]
+fn test_pow_argon2() {
+    use crate::{ByteIOPattern, ByteReader, ByteWriter, PoWChallenge, PoWIOPattern};
+    use nimue::{DefaultHash, IOPattern};
+
+    const BITS: u32 = 4;
+    // small memory cost, to keep the test fast.
+    type TestPoW = Argon2PoW<64, 1>;
+
+    let iopattern = IOPattern::<DefaultHash>::new("the proof of work lottery 🎰")
+        .add_bytes(1, "something")
+        .challenge_pow("rolling dices");
+
+    let mut prover = iopattern.to_merlin();
+    prover.add_bytes(b"\0").expect("Invalid IOPattern");
+    prover.challenge_pow_with_bits::<TestPoW>(BITS).unwrap();
+
+    let mut verifier = iopattern.to_arthur(prover.transcript());
+    let byte = verifier.next_bytes::<1>().unwrap();
+    assert_eq!(&byte, b"\0");
+    verifier.challenge_pow_with_bits::<TestPoW>(BITS).unwrap();
+
+    // the solution is the minimal nonce, regardless of how the search is split.
+    let mut pow = TestPoW::new_with_bits([42; 32], BITS);
+    let nonce = pow.solve().unwrap();
+    assert!(pow.check(nonce));
+    assert!((0..nonce).all(|n| !pow.check(n)));
+}
@@ -23,11 +23,13 @@ pub struct Blake3PoW {
 }
 
 impl PowStrategy for Blake3PoW {
-    fn new(challenge: [u8; 32], bits: f64) -> Self {
+    /// # Panics
+    ///
+    /// Panics if `threshold` requires 60 bits of work or more, i.e. if it is at most $2^4$.
+    fn new_with_threshold(challenge: [u8; 32], threshold: u64) -> Self {
         assert_eq!(BLOCK_LEN, 64);
         assert_eq!(OUT_LEN, 32);
-        assert!((0.0..60.0).contains(&bits), "bits must be smaller than 60");
-        let threshold = (64.0 - bits).exp2().ceil() as u64;
+        assert!(threshold > 1 << 4, "bits must be smaller than 60");
         let platform = Platform::detect();
         let mut inputs = [0; BLOCK_LEN * MAX_SIMD_DEGREE];
         for input in inputs.chunks_exact_mut(BLOCK_LEN) {
@@ -147,7 +149,7 @@ fn test_pow_blake3() {
     use crate::{ByteIOPattern, ByteReader, ByteWriter, PoWChallenge, PoWIOPattern};
     use nimue::{DefaultHash, IOPattern};
 
-    const BITS: f64 = 10.0;
+    const BITS: u32 = 10;
 
     let iopattern = IOPattern::<DefaultHash>::new("the proof of work lottery 🎰")
         .add_bytes(1, "something")
@@ -155,10 +157,16 @@ fn test_pow_blake3() {
 
     let mut prover = iopattern.to_merlin();
     prover.add_bytes(b"\0").expect("Invalid IOPattern");
-    prover.challenge_pow::<Blake3PoW>(BITS).unwrap();
+    prover.challenge_pow_with_bits::<Blake3PoW>(BITS).unwrap();
 
     let mut verifier = iopattern.to_arthur(prover.transcript());
     let byte = verifier.next_bytes::<1>().unwrap();
     assert_eq!(&byte, b"\0");
-    verifier.challenge_pow::<Blake3PoW>(BITS).unwrap();
+    verifier.challenge_pow_with_bits::<Blake3PoW>(BITS).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "bits must be smaller than 60")]
+fn test_pow_blake3_too_many_bits() {
+    Blake3PoW::new_with_bits([0; 32], 60);
 }
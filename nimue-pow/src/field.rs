@@ -0,0 +1,209 @@
+//! Proof of work for transcripts over a prime field.
+//!
+//! [`PoWChallenge`](crate::PoWChallenge) goes through the byte interface, which for algebraic sponges
+//! costs a byte conversion for the challenge and one field element per byte of the nonce.
+//! Here instead the challenge is squeezed as field elements, and the nonce is absorbed as field elements
+//! of `MODULUS_BIT_SIZE - 1` bits each: a single one for fields larger than 64 bits.
+use ark_ff::{BigInteger, Fp, FpConfig, PrimeField};
+use nimue::{Arthur, DuplexHash, IOPattern, Merlin, ProofError, ProofResult, UnitTranscript};
+
+use crate::{threshold_from_bits, PowStrategy};
+
+/// Number of field elements squeezed for a proof-of-work challenge.
+///
+/// Each element contributes its `floor(log2(p) / 8)` least significant bytes, until 32 bytes are collected.
+/// The resulting challenge is not uniformly distributed, but has close to 256 bits of min-entropy,
+/// which is what a proof of work requires.
+fn challenge_len<C: FpConfig<N>, const N: usize>() -> usize {
+    32usize.div_ceil(bytes_per_element::<C, N>())
+}
+
+fn bytes_per_element<C: FpConfig<N>, const N: usize>() -> usize {
+    (Fp::<C, N>::MODULUS_BIT_SIZE as usize - 1) / 8
+}
+
+fn challenge_from_elements<C: FpConfig<N>, const N: usize>(elements: &[Fp<C, N>]) -> [u8; 32] {
+    let bytes = elements
+        .iter()
+        .flat_map(|x| {
+            let mut bytes = x.into_bigint().to_bytes_le();
+            bytes.truncate(bytes_per_element::<C, N>());
+            bytes
+        })
+        .collect::<Vec<_>>();
+    bytes[..32].try_into().unwrap()
+}
+
+/// Number of bits of the nonce absorbed in each field element, so that the nonce is never reduced modulo p.
+fn nonce_bits<C: FpConfig<N>, const N: usize>() -> usize {
+    (Fp::<C, N>::MODULUS_BIT_SIZE as usize - 1).min(64)
+}
+
+/// Number of field elements absorbed for a 64-bit nonce.
+fn nonce_len<C: FpConfig<N>, const N: usize>() -> usize {
+    64usize.div_ceil(nonce_bits::<C, N>())
+}
+
+/// Split `nonce` into little-endian limbs of [`nonce_bits`] bits.
+fn nonce_to_elements<C: FpConfig<N>, const N: usize>(nonce: u64) -> Vec<Fp<C, N>> {
+    let bits = nonce_bits::<C, N>();
+    let mask = u64::MAX >> (64 - bits);
+    (0..nonce_len::<C, N>())
+        .map(|i| Fp::from((nonce >> (i * bits)) & mask))
+        .collect()
+}
+
+/// Recompose a nonce from its limbs, rejecting limbs that are not the canonical decomposition of a 64-bit integer.
+fn nonce_from_elements<C: FpConfig<N>, const N: usize>(elements: &[Fp<C, N>]) -> Option<u64> {
+    let bits = nonce_bits::<C, N>();
+    let mut nonce = 0u128;
+    for (i, element) in elements.iter().enumerate() {
+        let limb = element.into_bigint();
+        if limb.as_ref()[1..].iter().any(|&l| l != 0) || limb.as_ref()[0] >> (bits - 1) >> 1 != 0 {
+            return None;
+        }
+        nonce |= (limb.as_ref()[0] as u128) << (i * bits);
+    }
+    u64::try_from(nonce).ok()
+}
+
+/// [`IOPattern`] for proof-of-work challenges over a prime field.
+pub trait FieldPoWIOPattern {
+    /// Adds a [`FieldPoWChallenge`] to the [`IOPattern`]:
+    /// squeezing the field elements of the challenge, then absorbing the field elements of the nonce.
+    ///
+    /// As for [`PoWIOPattern`](crate::PoWIOPattern), the difficulty is **not** encoded within the [`IOPattern`].
+    fn challenge_field_pow(self, label: &str) -> Self;
+}
+
+impl<H, C, const N: usize> FieldPoWIOPattern for IOPattern<H, Fp<C, N>>
+where
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+{
+    fn challenge_field_pow(self, label: &str) -> Self {
+        self.squeeze(challenge_len::<C, N>(), label)
+            .absorb(nonce_len::<C, N>(), "pow-nonce")
+    }
+}
+
+/// Extension trait for generating a proof-of-work challenge over a prime field.
+pub trait FieldPoWChallenge {
+    /// A nonce is accepted if the (64-bit) output of the proof-of-work function is strictly smaller than `threshold`.
+    fn challenge_field_pow_with_threshold<S: PowStrategy>(
+        &mut self,
+        threshold: u64,
+    ) -> ProofResult<()>;
+
+    /// Generate a proof-of-work challenge requiring `bits` bits of work.
+    fn challenge_field_pow_with_bits<S: PowStrategy>(&mut self, bits: u32) -> ProofResult<()> {
+        self.challenge_field_pow_with_threshold::<S>(threshold_from_bits(bits))
+    }
+}
+
+impl<H, C, R, const N: usize> FieldPoWChallenge for Merlin<H, Fp<C, N>, R>
+where
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+    R: rand::CryptoRng + rand::RngCore,
+{
+    fn challenge_field_pow_with_threshold<S: PowStrategy>(
+        &mut self,
+        threshold: u64,
+    ) -> ProofResult<()> {
+        let mut challenge = vec![Fp::default(); challenge_len::<C, N>()];
+        self.fill_challenge_units(&mut challenge)?;
+        let nonce = S::new_with_threshold(challenge_from_elements(&challenge), threshold)
+            .solve()
+            .ok_or(ProofError::InvalidProof)?;
+        self.add_units(&nonce_to_elements(nonce))?;
+        Ok(())
+    }
+}
+
+impl<H, C, const N: usize> FieldPoWChallenge for Arthur<'_, H, Fp<C, N>>
+where
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+{
+    fn challenge_field_pow_with_threshold<S: PowStrategy>(
+        &mut self,
+        threshold: u64,
+    ) -> ProofResult<()> {
+        let mut challenge = vec![Fp::default(); challenge_len::<C, N>()];
+        self.fill_challenge_units(&mut challenge)?;
+        let mut nonce = vec![Fp::default(); nonce_len::<C, N>()];
+        self.fill_next_units(&mut nonce)?;
+        let nonce = nonce_from_elements(&nonce).ok_or(ProofError::InvalidProof)?;
+        if S::new_with_threshold(challenge_from_elements(&challenge), threshold).check(nonce) {
+            Ok(())
+        } else {
+            Err(ProofError::InvalidProof)
+        }
+    }
+}
+
+#[cfg(test)]
+fn check_field_pow<C: FpConfig<N>, const N: usize>() {
+    use crate::blake3::Blake3PoW;
+    use nimue::hash::sponge::DuplexSponge;
+    use nimue::testing::ToySponge;
+
+    const BITS: u32 = 10;
+
+    let iopattern = IOPattern::<DuplexSponge<ToySponge<Fp<C, N>>>, Fp<C, N>>::new(
+        "the proof of work lottery 🎰",
+    )
+    .absorb(1, "something")
+    .challenge_field_pow("rolling dices");
+
+    let mut prover = iopattern.to_merlin();
+    prover.add_units(&[Fp::from(1u64)]).unwrap();
+    prover
+        .challenge_field_pow_with_bits::<Blake3PoW>(BITS)
+        .unwrap();
+
+    let mut verifier = iopattern.to_arthur(prover.transcript());
+    let mut something = [Fp::default()];
+    verifier.fill_next_units(&mut something).unwrap();
+    assert_eq!(something, [Fp::from(1u64)]);
+    verifier
+        .challenge_field_pow_with_bits::<Blake3PoW>(BITS)
+        .unwrap();
+}
+
+#[test]
+fn test_field_pow() {
+    type C = ark_ff::MontBackend<ark_bls12_381::FrConfig, 4>;
+
+    assert_eq!(challenge_len::<C, 4>(), 2);
+    assert_eq!(nonce_len::<C, 4>(), 1);
+    check_field_pow::<C, 4>();
+}
+
+/// Over fields smaller than 64 bits, the nonce is split in several elements, and never reduced.
+#[test]
+fn test_field_pow_small_fields() {
+    use ark_ff::{MontBackend, MontConfig};
+
+    #[derive(MontConfig)]
+    #[modulus = "2013265921"]
+    #[generator = "31"]
+    struct BabyBearConfig;
+    type C = MontBackend<BabyBearConfig, 1>;
+
+    assert_eq!(nonce_len::<C, 1>(), 3);
+    for nonce in [0, 1, 1 << 30, u64::MAX - 1, u64::MAX] {
+        let elements = nonce_to_elements::<C, 1>(nonce);
+        assert_eq!(nonce_from_elements(&elements), Some(nonce));
+    }
+    // limbs must be canonical
+    let mut elements = nonce_to_elements::<C, 1>(42);
+    elements[0] += Fp::from(1u64 << 30);
+    assert_eq!(nonce_from_elements(&elements), None);
+    assert_eq!(
+        nonce_from_elements::<C, 1>(&[Fp::from(0), Fp::from(0), Fp::from(1 << 4)]),
+        None
+    );
+    check_field_pow::<C, 1>();
+}
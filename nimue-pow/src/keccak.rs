@@ -8,8 +8,7 @@ pub struct KeccakPoW {
 }
 
 impl PowStrategy for KeccakPoW {
-    fn new(challenge: [u8; 32], bits: f64) -> Self {
-        let threshold = (64.0 - bits).exp2().ceil() as u64;
+    fn new_with_threshold(challenge: [u8; 32], threshold: u64) -> Self {
         Self {
             challenge: bytemuck::cast(challenge),
             threshold,
@@ -33,7 +32,7 @@ fn test_pow_keccak() {
     use crate::{ByteIOPattern, ByteReader, ByteWriter, PoWChallenge, PoWIOPattern};
     use nimue::{DefaultHash, IOPattern};
 
-    const BITS: f64 = 10.0;
+    const BITS: u32 = 10;
 
     let iopattern = IOPattern::<DefaultHash>::new("the proof of work lottery 🎰")
         .add_bytes(1, "something")
@@ -41,10 +40,10 @@ fn test_pow_keccak() {
 
     let mut prover = iopattern.to_merlin();
     prover.add_bytes(b"\0").expect("Invalid IOPattern");
-    prover.challenge_pow::<KeccakPoW>(BITS).unwrap();
+    prover.challenge_pow_with_bits::<KeccakPoW>(BITS).unwrap();
 
     let mut verifier = iopattern.to_arthur(prover.transcript());
     let byte = verifier.next_bytes::<1>().unwrap();
     assert_eq!(&byte, b"\0");
-    verifier.challenge_pow::<KeccakPoW>(BITS).unwrap();
+    verifier.challenge_pow_with_bits::<KeccakPoW>(BITS).unwrap();
 }
@@ -1,9 +1,13 @@
+#[cfg(feature = "argon2")]
+pub mod argon2;
 pub mod blake3;
+#[cfg(feature = "ark")]
+pub mod field;
 pub mod keccak;
 
 use nimue::{
-    Arthur, ByteChallenges, ByteIOPattern, ByteReader, ByteWriter, DuplexHash, Merlin, ProofError,
-    ProofResult, Unit,
+    Arthur, ByteChallenges, ByteIOPattern, ByteReader, ByteWriter, DuplexHash, IOPattern, Merlin,
+    ProofError, ProofResult, Unit,
 };
 
 /// [`IOPattern`] for proof-of-work challenges.
@@ -19,6 +23,12 @@ pub trait PoWIOPattern {
     /// It is up to the implementor to change the domain separator or the label in order to reflect changes in the proof
     /// in order to preserve simulation extractability.
     fn challenge_pow(self, label: &str) -> Self;
+
+    /// Adds one [`PoWChallenge`] per round, the `i`-th one requiring `schedule[i]` bits of work.
+    ///
+    /// The label of each round is derived from `label`, the round index, and its difficulty (e.g. `grinding[0].16bits`),
+    /// so that the schedule is recorded in the [`IOPattern`]. See [`PoWChallenge::challenge_pow_schedule`].
+    fn challenge_pow_schedule(self, schedule: &[f64], label: &str) -> Self;
 }
 
 impl<IOPattern> PoWIOPattern for IOPattern
@@ -29,11 +39,124 @@ where
         // 16 bytes challenge and 16 bytes nonce (that will be written)
         self.challenge_bytes(32, label).add_bytes(8, "pow-nonce")
     }
+
+    fn challenge_pow_schedule(self, schedule: &[f64], label: &str) -> Self {
+        schedule.iter().enumerate().fold(self, |io, (i, bits)| {
+            io.challenge_pow(&format!("{}[{}].{}bits", label, i, bits))
+        })
+    }
+}
+
+/// Builder for a proof-of-work challenge in the [`IOPattern`].
+///
+/// Unlike [`PoWIOPattern::challenge_pow`], which always labels the nonce `pow-nonce`,
+/// the nonce label is derived from the challenge label (e.g. `grinding.nonce`), or set explicitly.
+/// The sponge can optionally be ratcheted after the nonce, so that what follows the proof-of-work
+/// starts from a clean rate.
+///
+/// ```
+/// # use nimue::{DefaultHash, IOPattern};
+/// # use nimue_pow::{blake3::Blake3PoW, PoWChallenge, PoWPattern};
+/// let pow = PoWPattern::new("grinding").ratchet();
+/// let io = pow.add_to(IOPattern::<DefaultHash>::new("🎰"));
+/// assert_eq!(io.as_bytes(), "🎰\0S32grinding\0A8grinding.nonce\0R".as_bytes());
+///
+/// let mut merlin = io.to_merlin();
+/// merlin.challenge_pow_with_pattern::<Blake3PoW>(&pow, 8).unwrap();
+/// let mut arthur = io.to_arthur(merlin.transcript());
+/// arthur.challenge_pow_with_pattern::<Blake3PoW>(&pow, 8).unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoWPattern {
+    label: String,
+    nonce_label: String,
+    ratchet: bool,
+}
+
+impl PoWPattern {
+    /// A proof-of-work challenge labeled `label`, whose nonce is labeled `{label}.nonce`.
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            nonce_label: format!("{}.nonce", label),
+            ratchet: false,
+        }
+    }
+
+    /// Label the nonce with `nonce_label`.
+    pub fn nonce_label(mut self, nonce_label: &str) -> Self {
+        self.nonce_label = nonce_label.to_string();
+        self
+    }
+
+    /// Ratchet the sponge after the nonce.
+    pub fn ratchet(mut self) -> Self {
+        self.ratchet = true;
+        self
+    }
+
+    /// Whether the sponge is ratcheted after the nonce.
+    pub fn is_ratcheted(&self) -> bool {
+        self.ratchet
+    }
+
+    /// Add the proof-of-work challenge to `io`.
+    pub fn add_to<H, U>(&self, io: IOPattern<H, U>) -> IOPattern<H, U>
+    where
+        U: Unit,
+        H: DuplexHash<U>,
+        IOPattern<H, U>: ByteIOPattern,
+    {
+        let io = io
+            .challenge_bytes(32, &self.label)
+            .add_bytes(8, &self.nonce_label);
+        if self.ratchet {
+            io.ratchet()
+        } else {
+            io
+        }
+    }
 }
 
 pub trait PoWChallenge {
     /// Extension trait for generating a proof-of-work challenge.
-    fn challenge_pow<S: PowStrategy>(&mut self, bits: f64) -> ProofResult<()>;
+    ///
+    /// A nonce is accepted if the (64-bit) output of the proof-of-work function is strictly smaller than `threshold`.
+    fn challenge_pow_with_threshold<S: PowStrategy>(&mut self, threshold: u64) -> ProofResult<()>;
+
+    /// Generate a proof-of-work challenge requiring `bits` bits of work.
+    ///
+    /// See [`threshold_from_bits`] for how `bits` is converted into a threshold.
+    fn challenge_pow_with_bits<S: PowStrategy>(&mut self, bits: u32) -> ProofResult<()> {
+        self.challenge_pow_with_threshold::<S>(threshold_from_bits(bits))
+    }
+
+    /// Generate a proof-of-work challenge requiring (possibly fractional) `bits` bits of work.
+    #[deprecated(
+        note = "floating-point difficulties are platform-sensitive, use `challenge_pow_with_bits` or `challenge_pow_with_threshold`"
+    )]
+    fn challenge_pow<S: PowStrategy>(&mut self, bits: f64) -> ProofResult<()> {
+        self.challenge_pow_with_threshold::<S>(threshold_from_f64(bits))
+    }
+
+    /// Run the proof-of-work challenges declared by [`PoWIOPattern::challenge_pow_schedule`], one per round.
+    ///
+    /// Integer difficulties are converted exactly into thresholds;
+    /// fractional ones go through floating-point arithmetic, and may be platform-sensitive.
+    fn challenge_pow_schedule<S: PowStrategy>(&mut self, schedule: &[f64]) -> ProofResult<()> {
+        schedule
+            .iter()
+            .try_for_each(|&bits| self.challenge_pow_with_threshold::<S>(threshold_from_f64(bits)))
+    }
+
+    /// Run a proof-of-work challenge declared with [`PoWPattern::add_to`], requiring `bits` bits of work.
+    ///
+    /// The sponge is ratcheted after the nonce if `pow` says so.
+    fn challenge_pow_with_pattern<S: PowStrategy>(
+        &mut self,
+        pow: &PoWPattern,
+        bits: u32,
+    ) -> ProofResult<()>;
 }
 
 impl<H, U, R> PoWChallenge for Merlin<H, U, R>
@@ -43,14 +166,26 @@ where
     R: rand::CryptoRng + rand::RngCore,
     Merlin<H, U, R>: ByteWriter + ByteChallenges,
 {
-    fn challenge_pow<S: PowStrategy>(&mut self, bits: f64) -> ProofResult<()> {
+    fn challenge_pow_with_threshold<S: PowStrategy>(&mut self, threshold: u64) -> ProofResult<()> {
         let challenge = self.challenge_bytes()?;
-        let nonce = S::new(challenge, bits)
+        let nonce = S::new_with_threshold(challenge, threshold)
             .solve()
             .ok_or(ProofError::InvalidProof)?;
         self.add_bytes(&nonce.to_be_bytes())?;
         Ok(())
     }
+
+    fn challenge_pow_with_pattern<S: PowStrategy>(
+        &mut self,
+        pow: &PoWPattern,
+        bits: u32,
+    ) -> ProofResult<()> {
+        self.challenge_pow_with_bits::<S>(bits)?;
+        if pow.is_ratcheted() {
+            self.ratchet()?;
+        }
+        Ok(())
+    }
 }
 
 impl<'a, H, U> PoWChallenge for Arthur<'a, H, U>
@@ -59,23 +194,73 @@ where
     H: DuplexHash<U>,
     Arthur<'a, H, U>: ByteReader + ByteChallenges,
 {
-    fn challenge_pow<S: PowStrategy>(&mut self, bits: f64) -> ProofResult<()> {
+    fn challenge_pow_with_threshold<S: PowStrategy>(&mut self, threshold: u64) -> ProofResult<()> {
         let challenge = self.challenge_bytes()?;
         let nonce = u64::from_be_bytes(self.next_bytes()?);
-        if S::new(challenge, bits).check(nonce) {
+        if S::new_with_threshold(challenge, threshold).check(nonce) {
             Ok(())
         } else {
             Err(ProofError::InvalidProof)
         }
     }
+
+    fn challenge_pow_with_pattern<S: PowStrategy>(
+        &mut self,
+        pow: &PoWPattern,
+        bits: u32,
+    ) -> ProofResult<()> {
+        self.challenge_pow_with_bits::<S>(bits)?;
+        if pow.is_ratcheted() {
+            self.ratchet()?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert a number of bits of work into a proof-of-work threshold.
+///
+/// A uniformly random 64-bit integer is smaller than the returned threshold with probability $2^{-\mathsf{bits}}$,
+/// i.e. the threshold is $2^{64 - \mathsf{bits}}$ (saturated to `u64::MAX` for `bits = 0`).
+/// The computation is integer-only, and therefore deterministic across platforms.
+pub const fn threshold_from_bits(bits: u32) -> u64 {
+    assert!(bits <= 64, "bits must be at most 64");
+    match bits {
+        0 => u64::MAX,
+        _ => 1 << (64 - bits),
+    }
+}
+
+/// Convert a (possibly fractional) number of bits of work into a proof-of-work threshold.
+///
+/// This matches the behavior of the (deprecated) floating-point interfaces.
+fn threshold_from_f64(bits: f64) -> u64 {
+    (64.0 - bits).exp2().ceil() as u64
 }
 
 pub trait PowStrategy: Clone + Sync {
     /// Creates a new proof-of-work challenge.
     /// The `challenge` is a 32-byte array that represents the challenge.
+    /// A nonce satisfies the challenge when the output of the proof-of-work function,
+    /// interpreted as a 64-bit integer, is strictly smaller than `threshold`.
+    fn new_with_threshold(challenge: [u8; 32], threshold: u64) -> Self;
+
+    /// Creates a new proof-of-work challenge.
     /// The `bits` is the binary logarithm of the expected amount of work.
     /// When `bits` is large (i.e. close to 64), a valid solution may not be found.
-    fn new(challenge: [u8; 32], bits: f64) -> Self;
+    fn new_with_bits(challenge: [u8; 32], bits: u32) -> Self {
+        Self::new_with_threshold(challenge, threshold_from_bits(bits))
+    }
+
+    /// Creates a new proof-of-work challenge.
+    /// The `challenge` is a 32-byte array that represents the challenge.
+    /// The `bits` is the binary logarithm of the expected amount of work.
+    /// When `bits` is large (i.e. close to 64), a valid solution may not be found.
+    #[deprecated(
+        note = "floating-point difficulties are platform-sensitive, use `new_with_bits` or `new_with_threshold`"
+    )]
+    fn new(challenge: [u8; 32], bits: f64) -> Self {
+        Self::new_with_threshold(challenge, threshold_from_f64(bits))
+    }
 
     /// Check if the `nonce` satisfies the challenge.
     fn check(&mut self, nonce: u64) -> bool;
@@ -119,3 +304,76 @@ pub trait PowStrategy: Clone + Sync {
         }
     }
 }
+
+#[test]
+fn test_threshold_from_bits() {
+    // The integer conversion agrees with the legacy floating-point one on integer inputs.
+    for bits in 0..=64 {
+        assert_eq!(threshold_from_bits(bits), threshold_from_f64(bits as f64));
+    }
+    assert_eq!(threshold_from_bits(64), 1);
+}
+
+#[test]
+fn test_pow_schedule() {
+    use crate::blake3::Blake3PoW;
+    use nimue::{DefaultHash, IOPattern};
+
+    let schedule = [1.0, 4.5, 0.0];
+    let iopattern = IOPattern::<DefaultHash>::new("the proof of work lottery 🎰")
+        .challenge_pow_schedule(&schedule, "grinding");
+    assert!(String::from_utf8_lossy(iopattern.as_bytes()).contains("grinding[1].4.5bits"));
+
+    let mut prover = iopattern.to_merlin();
+    prover
+        .challenge_pow_schedule::<Blake3PoW>(&schedule)
+        .unwrap();
+
+    let mut verifier = iopattern.to_arthur(prover.transcript());
+    verifier
+        .challenge_pow_schedule::<Blake3PoW>(&schedule)
+        .unwrap();
+
+    // A schedule longer than the one in the IO Pattern is rejected.
+    let mut verifier = iopattern.to_arthur(prover.transcript());
+    assert!(verifier
+        .challenge_pow_schedule::<Blake3PoW>(&[1.0, 4.5, 0.0, 1.0])
+        .is_err());
+}
+
+#[test]
+fn test_pow_pattern() {
+    use crate::blake3::Blake3PoW;
+    use nimue::DefaultHash;
+
+    let first = PoWPattern::new("first").ratchet();
+    let second = PoWPattern::new("second").nonce_label("second-nonce");
+    let iopattern = second.add_to(first.add_to(IOPattern::<DefaultHash>::new("🎰")));
+    assert_eq!(
+        iopattern.as_bytes(),
+        "🎰\0S32first\0A8first.nonce\0R\0S32second\0A8second-nonce".as_bytes()
+    );
+
+    let mut prover = iopattern.to_merlin();
+    prover
+        .challenge_pow_with_pattern::<Blake3PoW>(&first, 4)
+        .unwrap();
+    prover
+        .challenge_pow_with_pattern::<Blake3PoW>(&second, 4)
+        .unwrap();
+
+    let mut verifier = iopattern.to_arthur(prover.transcript());
+    verifier
+        .challenge_pow_with_pattern::<Blake3PoW>(&first, 4)
+        .unwrap();
+    verifier
+        .challenge_pow_with_pattern::<Blake3PoW>(&second, 4)
+        .unwrap();
+
+    // skipping the ratchet is a deviation from the IO Pattern
+    let mut verifier = iopattern.to_arthur(prover.transcript());
+    verifier.challenge_pow_with_bits::<Blake3PoW>(4).unwrap();
+    assert!(verifier
+        .challenge_pow_with_pattern::<Blake3PoW>(&second, 4)
+        .is_err());
+}
@@ -0,0 +1,275 @@
+//! The [Tip5](https://eprint.iacr.org/2023/107) permutation, over the Goldilocks field $p = 2^{64} - 2^{32} + 1$.
+//!
+//! Tip5 is the algebraic hash of the Triton VM (and of the Neptune blockchain).
+//! It has a state of 16 field elements, of which 10 are the rate, and 5 rounds, each composed of:
+//!
+//! - an S-box layer: the first 4 elements are split in bytes (in Montgomery form), each mapped through a lookup table,
+//!   and the remaining 12 are raised to the power 7;
+//! - a circulant MDS matrix;
+//! - the addition of the round constants.
+//!
+//! [`Tip5Hash`] is a duplex hash over [`Goldilocks`] for nimue's IO Patterns,
+//! and [`Tip5::compress`] is the 2-to-1 compression of Merkle trees over Tip5.
+//!
+//! ```
+//! use nimue::{IOPattern, UnitTranscript};
+//! use nimue_tip5::{Goldilocks, Tip5Hash};
+//!
+//! let io = IOPattern::<Tip5Hash, Goldilocks>::new("📝").absorb(3, "in").squeeze(5, "digest");
+//! let mut merlin = io.to_merlin();
+//! merlin.add_units(&[Goldilocks::from(1u64), Goldilocks::from(2u64), Goldilocks::from(3u64)]).unwrap();
+//! let mut digest = [Goldilocks::from(0u64); 5];
+//! merlin.fill_challenge_units(&mut digest).unwrap();
+//! ```
+use ark_ff::{BigInt, Field, Fp64, MontBackend, MontConfig, MontFp};
+use nimue::hash::sponge::{BatchPermutation, DuplexSponge, Sponge};
+use zeroize::Zeroize;
+
+#[derive(MontConfig)]
+#[modulus = "18446744069414584321"]
+#[generator = "7"]
+pub struct GoldilocksConfig;
+
+/// The Goldilocks field, $p = 2^{64} - 2^{32} + 1$.
+pub type Goldilocks = Fp64<MontBackend<GoldilocksConfig, 1>>;
+
+/// The number of elements of the state.
+pub const STATE_SIZE: usize = 16;
+/// The number of elements of the rate.
+pub const RATE: usize = 10;
+/// The number of elements of a digest.
+pub const DIGEST_LENGTH: usize = 5;
+/// The number of rounds.
+pub const NUM_ROUNDS: usize = 5;
+/// The number of state elements going through the lookup table in each round.
+const NUM_SPLIT_AND_LOOKUP: usize = 4;
+
+/// The lookup table $x \mapsto (x + 1)^3 - 1 \bmod 257$, a permutation of the bytes fixing `0` and `255`.
+pub const LOOKUP_TABLE: [u8; 256] = {
+    let mut table = [0u8; 256];
+    let mut x = 0;
+    while x < 256 {
+        table[x] = (((x + 1) * (x + 1) % 257 * (x + 1) + 256) % 257) as u8;
+        x += 1;
+    }
+    table
+};
+
+/// The first column of the circulant MDS matrix.
+pub const MDS_MATRIX_FIRST_COLUMN: [u64; STATE_SIZE] = [
+    61402, 1108, 28750, 33823, 7454, 43244, 53865, 12034, 56951, 27521, 41351, 40901, 12021, 59689,
+    26798, 17845,
+];
+
+/// The round constants, one row per round.
+///
+/// The $i$-th constant is the element whose Montgomery form is the first 16 bytes of `BLAKE3("Tip5" || i)`,
+/// read as a little-endian integer and reduced modulo $p$.
+pub const ROUND_CONSTANTS: [[Goldilocks; STATE_SIZE]; NUM_ROUNDS] = [
+    [
+        MontFp!("13630775303355457758"),
+        MontFp!("16896927574093233874"),
+        MontFp!("10379449653650130495"),
+        MontFp!("1965408364413093495"),
+        MontFp!("15232538947090185111"),
+        MontFp!("15892634398091747074"),
+        MontFp!("3989134140024871768"),
+        MontFp!("2851411912127730865"),
+        MontFp!("8709136439293758776"),
+        MontFp!("3694858669662939734"),
+        MontFp!("12692440244315327141"),
+        MontFp!("10722316166358076749"),
+        MontFp!("12745429320441639448"),
+        MontFp!("17932424223723990421"),
+        MontFp!("7558102534867937463"),
+        MontFp!("15551047435855531404"),
+    ],
+    [
+        MontFp!("17532528648579384106"),
+        MontFp!("5216785850422679555"),
+        MontFp!("15418071332095031847"),
+        MontFp!("11921929762955146258"),
+        MontFp!("9738718993677019874"),
+        MontFp!("3464580399432997147"),
+        MontFp!("13408434769117164050"),
+        MontFp!("264428218649616431"),
+        MontFp!("4436247869008081381"),
+        MontFp!("4063129435850804221"),
+        MontFp!("2865073155741120117"),
+        MontFp!("5749834437609765994"),
+        MontFp!("6804196764189408435"),
+        MontFp!("17060469201292988508"),
+        MontFp!("9475383556737206708"),
+        MontFp!("12876344085611465020"),
+    ],
+    [
+        MontFp!("13835756199368269249"),
+        MontFp!("1648753455944344172"),
+        MontFp!("9836124473569258483"),
+        MontFp!("12867641597107932229"),
+        MontFp!("11254152636692960595"),
+        MontFp!("16550832737139861108"),
+        MontFp!("11861573970480733262"),
+        MontFp!("1256660473588673495"),
+        MontFp!("13879506000676455136"),
+        MontFp!("10564103842682358721"),
+        MontFp!("16142842524796397521"),
+        MontFp!("3287098591948630584"),
+        MontFp!("685911471061284805"),
+        MontFp!("5285298776918878023"),
+        MontFp!("18310953571768047354"),
+        MontFp!("3142266350630002035"),
+    ],
+    [
+        MontFp!("549990724933663297"),
+        MontFp!("4901984846118077401"),
+        MontFp!("11458643033696775769"),
+        MontFp!("8706785264119212710"),
+        MontFp!("12521758138015724072"),
+        MontFp!("11877914062416978196"),
+        MontFp!("11333318251134523752"),
+        MontFp!("3933899631278608623"),
+        MontFp!("16635128972021157924"),
+        MontFp!("10291337173108950450"),
+        MontFp!("4142107155024199350"),
+        MontFp!("16973934533787743537"),
+        MontFp!("11068111539125175221"),
+        MontFp!("17546769694830203606"),
+        MontFp!("5315217744825068993"),
+        MontFp!("4609594252909613081"),
+    ],
+    [
+        MontFp!("3350107164315270407"),
+        MontFp!("17715942834299349177"),
+        MontFp!("9600609149219873996"),
+        MontFp!("12894357635820003949"),
+        MontFp!("4597649658040514631"),
+        MontFp!("7735563950920491847"),
+        MontFp!("1663379455870887181"),
+        MontFp!("13889298103638829706"),
+        MontFp!("7375530351220884434"),
+        MontFp!("3502022433285269151"),
+        MontFp!("9231805330431056952"),
+        MontFp!("9252272755288523725"),
+        MontFp!("10014268662326746219"),
+        MontFp!("15565031632950843234"),
+        MontFp!("1209725273521819323"),
+        MontFp!("6024642864597845108"),
+    ],
+];
+
+/// The Tip5 permutation, over a state of [`STATE_SIZE`] Goldilocks elements.
+#[derive(Clone, Default, Zeroize)]
+pub struct Tip5 {
+    pub state: [Goldilocks; STATE_SIZE],
+}
+
+/// A duplex hash over [`Goldilocks`] built on the top of [`Tip5`].
+pub type Tip5Hash = DuplexSponge<Tip5>;
+
+impl Tip5 {
+    /// Map each byte of the Montgomery form of `element` through [`LOOKUP_TABLE`].
+    ///
+    /// The table fixes `0` and `255`, so that the result is again a canonical Montgomery form.
+    fn split_and_lookup(element: &mut Goldilocks) {
+        let bytes = element.0 .0[0]
+            .to_le_bytes()
+            .map(|byte| LOOKUP_TABLE[byte as usize]);
+        *element = Goldilocks::new_unchecked(BigInt([u64::from_le_bytes(bytes)]));
+    }
+
+    fn sbox_layer(&mut self) {
+        let (lookups, powers) = self.state.split_at_mut(NUM_SPLIT_AND_LOOKUP);
+        lookups.iter_mut().for_each(Self::split_and_lookup);
+        for x in powers {
+            let square = x.square();
+            *x *= square * square.square();
+        }
+    }
+
+    #[allow(clippy::needless_range_loop)]
+    fn mds(&mut self) {
+        let mut state = [Goldilocks::from(0u64); STATE_SIZE];
+        for i in 0..STATE_SIZE {
+            for j in 0..STATE_SIZE {
+                let entry = MDS_MATRIX_FIRST_COLUMN[(STATE_SIZE + i - j) % STATE_SIZE];
+                state[i] += self.state[j] * Goldilocks::from(entry);
+            }
+        }
+        self.state = state;
+    }
+
+    fn round(&mut self, round: usize) {
+        self.sbox_layer();
+        self.mds();
+        for (x, constant) in self.state.iter_mut().zip(ROUND_CONSTANTS[round]) {
+            *x += constant;
+        }
+    }
+
+    /// Hash exactly [`RATE`] elements, in the fixed-length domain (the capacity is set to ones).
+    pub fn hash_10(input: &[Goldilocks; RATE]) -> [Goldilocks; DIGEST_LENGTH] {
+        let mut tip5 = Self {
+            state: [Goldilocks::from(1u64); STATE_SIZE],
+        };
+        tip5.state[..RATE].copy_from_slice(input);
+        tip5.permute();
+        tip5.state[..DIGEST_LENGTH].try_into().unwrap()
+    }
+
+    /// Compress two digests into one, e.g. for the nodes of a Merkle tree.
+    pub fn compress(
+        left: &[Goldilocks; DIGEST_LENGTH],
+        right: &[Goldilocks; DIGEST_LENGTH],
+    ) -> [Goldilocks; DIGEST_LENGTH] {
+        let mut input = [Goldilocks::from(0u64); RATE];
+        input[..DIGEST_LENGTH].copy_from_slice(left);
+        input[DIGEST_LENGTH..].copy_from_slice(right);
+        Self::hash_10(&input)
+    }
+}
+
+impl AsRef<[Goldilocks]> for Tip5 {
+    fn as_ref(&self) -> &[Goldilocks] {
+        &self.state
+    }
+}
+
+impl AsMut<[Goldilocks]> for Tip5 {
+    fn as_mut(&mut self) -> &mut [Goldilocks] {
+        &mut self.state
+    }
+}
+
+impl Sponge for Tip5 {
+    type U = Goldilocks;
+    const N: usize = STATE_SIZE;
+    const R: usize = RATE;
+
+    /// The IV is written in the capacity, as four 8-byte little-endian integers reduced modulo $p$.
+    fn new(iv: [u8; 32]) -> Self {
+        let mut tip5 = Self::default();
+        for (x, chunk) in tip5.state[RATE..].iter_mut().zip(iv.chunks_exact(8)) {
+            *x = Goldilocks::from(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        tip5
+    }
+
+    fn permute(&mut self) {
+        for round in 0..NUM_ROUNDS {
+            self.round(round);
+        }
+    }
+}
+
+impl BatchPermutation for Tip5 {}
+
+impl core::fmt::Debug for Tip5 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Ensure that the state isn't accidentally logged
+        write!(f, "Tip5: STATE OMITTED")
+    }
+}
+
+#[cfg(test)]
+mod tests;
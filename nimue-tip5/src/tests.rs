@@ -0,0 +1,45 @@
+use ark_ff::{BigInt, PrimeField};
+
+use crate::*;
+
+#[test]
+fn test_lookup_table() {
+    assert_eq!(LOOKUP_TABLE[..8], [0, 7, 26, 63, 124, 215, 85, 254]);
+    assert_eq!(LOOKUP_TABLE[255], 255);
+    let mut sorted = LOOKUP_TABLE;
+    sorted.sort();
+    assert!(sorted.iter().enumerate().all(|(i, &x)| i == x as usize));
+}
+
+#[test]
+fn test_round_constants() {
+    let p = Goldilocks::MODULUS.0[0] as u128;
+    for (i, constant) in ROUND_CONSTANTS.iter().flatten().enumerate() {
+        let hash = blake3::hash(&[b"Tip5".as_slice(), &[i as u8]].concat());
+        let montgomery = u128::from_le_bytes(hash.as_bytes()[..16].try_into().unwrap()) % p;
+        assert_eq!(
+            *constant,
+            Goldilocks::new_unchecked(BigInt([montgomery as u64]))
+        );
+    }
+}
+
+#[test]
+fn test_hash_10_vectors() {
+    // test vector of the reference implementation (`twenty-first`), chaining digests into the preimage
+    let mut preimage = [Goldilocks::from(0u64); RATE];
+    for i in 0..6 {
+        let digest = Tip5::hash_10(&preimage);
+        preimage[i..DIGEST_LENGTH + i].copy_from_slice(&digest);
+    }
+    let digest = Tip5::hash_10(&preimage);
+    let expected = [
+        10869784347448351760u64,
+        1853783032222938415,
+        6856460589287344822,
+        17178399545409290325,
+        7650660984651717733,
+    ]
+    .map(Goldilocks::from);
+    assert_eq!(digest, expected);
+}
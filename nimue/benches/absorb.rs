@@ -0,0 +1,39 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use nimue::hash::{DuplexHash, Keccak};
+
+fn bench_absorb(c: &mut Criterion) {
+    let mut group = c.benchmark_group("absorb");
+    for size in [1 << 10, 1 << 20, 1 << 24] {
+        let message = vec![0x42u8; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(
+            BenchmarkId::new("keccak", size),
+            &message,
+            |bench, message| {
+                bench.iter(|| {
+                    let mut sponge = Keccak::new([0u8; 32]);
+                    sponge.absorb_unchecked(message);
+                    sponge
+                })
+            },
+        );
+        // streaming in chunks not aligned with the rate
+        group.bench_with_input(
+            BenchmarkId::new("keccak-chunked", size),
+            &message,
+            |bench, message| {
+                bench.iter(|| {
+                    let mut sponge = Keccak::new([0u8; 32]);
+                    for chunk in message.chunks(1000) {
+                        sponge.absorb_unchecked(chunk);
+                    }
+                    sponge
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_absorb);
+criterion_main!(benches);
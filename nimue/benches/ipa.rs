@@ -0,0 +1,40 @@
+use ark_bls12_381::{Fr, G1Projective as G};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use nimue::protocols::ipa::{self, Generators, IpaIOPattern};
+use nimue::{DefaultHash, IOPattern};
+use rand::rngs::OsRng;
+
+fn bench_ipa(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ipa");
+    for size in [64, 256, 1024] {
+        let io: IOPattern<DefaultHash> = IpaIOPattern::<G>::new_ipa("nimue::ipa", size);
+        let generators = Generators::<G>::rand(size, &mut OsRng);
+        let a = (0..size as u64).map(Fr::from).collect::<Vec<_>>();
+        let b = (0..size as u64)
+            .map(|i| Fr::from(i + 42))
+            .collect::<Vec<_>>();
+        let statement = generators.commit(&a, &b);
+
+        group.bench_with_input(BenchmarkId::new("prove", size), &size, |bench, _| {
+            bench.iter(|| {
+                let mut merlin = io.to_merlin();
+                ipa::prove(&mut merlin, &generators, &statement, (&a, &b)).unwrap();
+            })
+        });
+
+        let mut merlin = io.to_merlin();
+        let proof = ipa::prove(&mut merlin, &generators, &statement, (&a, &b))
+            .unwrap()
+            .to_vec();
+        group.bench_with_input(BenchmarkId::new("verify", size), &size, |bench, _| {
+            bench.iter(|| {
+                let mut arthur = io.to_arthur(&proof);
+                ipa::verify(&mut arthur, &generators, &statement).unwrap();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_ipa);
+criterion_main!(benches);
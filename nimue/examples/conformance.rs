@@ -0,0 +1,189 @@
+/// Example: conformance testing against known-answer test (KAT) vectors.
+///
+/// Run with `cargo run --example conformance -- kat/transcripts.json`.
+///
+/// KAT files are JSON documents meant to be shared with implementations of nimue in other languages.
+/// They have the following schema:
+///
+/// ```json
+/// {
+///   "vectors": [
+///     {
+///       "name": "a short description",
+///       "hash": "keccak" | "sha2-256" | "blake2b-512",
+///       "domain_separator": "example.com",
+///       "operations": [
+///         { "op": "absorb", "label": "commitment", "input": "<hex>" },
+///         { "op": "public", "label": "statement", "input": "<hex>" },
+///         { "op": "squeeze", "label": "challenge", "output": "<hex>" },
+///         { "op": "ratchet" }
+///       ],
+///       "io_pattern": "example.com\u0000A32commitment...",
+///       "narg": "<hex>"
+///     }
+///   ]
+/// }
+/// ```
+///
+/// - `absorb` is a prover message: its `input` is written to the protocol transcript (the NARG string);
+/// - `public` is absorbed but not written to the transcript;
+/// - `squeeze` is a verifier challenge, whose expected `output` is given;
+/// - `ratchet` ratchets the sponge.
+///
+/// For each vector, the IO Pattern built from the operations must equal `io_pattern`,
+/// the prover must produce the NARG string `narg` and the expected challenges,
+/// and the verifier must read back the same messages and challenges from `narg`.
+use nimue::hash::legacy::DigestBridge;
+use nimue::hash::Keccak;
+use nimue::{
+    ByteChallenges, ByteIOPattern, BytePublic, ByteReader, ByteWriter, DuplexHash, IOPattern,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct KatFile {
+    vectors: Vec<Vector>,
+}
+
+#[derive(Deserialize)]
+struct Vector {
+    name: String,
+    hash: String,
+    domain_separator: String,
+    operations: Vec<Operation>,
+    io_pattern: String,
+    narg: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Operation {
+    Absorb { label: String, input: String },
+    Public { label: String, input: String },
+    Squeeze { label: String, output: String },
+    Ratchet,
+}
+
+fn decode(hex_string: &str) -> Result<Vec<u8>, String> {
+    hex::decode(hex_string).map_err(|e| format!("invalid hex {:?}: {}", hex_string, e))
+}
+
+/// Check a single vector, returning a description of the first mismatch.
+fn check<H: DuplexHash>(vector: &Vector) -> Result<(), String> {
+    let mut io = IOPattern::<H>::new(&vector.domain_separator);
+    for op in &vector.operations {
+        io = match op {
+            Operation::Absorb { label, input } | Operation::Public { label, input } => {
+                io.add_bytes(input.len() / 2, label)
+            }
+            Operation::Squeeze { label, output } => io.challenge_bytes(output.len() / 2, label),
+            Operation::Ratchet => io.ratchet(),
+        };
+    }
+    if io.as_bytes() != vector.io_pattern.as_bytes() {
+        return Err(format!(
+            "IO Pattern mismatch: got {:?}",
+            String::from_utf8_lossy(io.as_bytes())
+        ));
+    }
+
+    // Prover side.
+    let mut merlin = io.to_merlin();
+    for (i, op) in vector.operations.iter().enumerate() {
+        match op {
+            Operation::Absorb { input, .. } => merlin.add_bytes(&decode(input)?),
+            Operation::Public { input, .. } => merlin.public_bytes(&decode(input)?),
+            Operation::Squeeze { output, .. } => {
+                let mut challenge = vec![0u8; output.len() / 2];
+                merlin
+                    .fill_challenge_bytes(&mut challenge)
+                    .map_err(|e| e.to_string())?;
+                if challenge != decode(output)? {
+                    return Err(format!(
+                        "prover challenge mismatch at operation {}: got {}",
+                        i,
+                        hex::encode(challenge)
+                    ));
+                }
+                Ok(())
+            }
+            Operation::Ratchet => merlin.ratchet(),
+        }
+        .map_err(|e| e.to_string())?;
+    }
+    if merlin.transcript() != decode(&vector.narg)? {
+        return Err(format!(
+            "NARG string mismatch: got {}",
+            hex::encode(merlin.transcript())
+        ));
+    }
+
+    // Verifier side.
+    let narg = decode(&vector.narg)?;
+    let mut arthur = io.to_arthur(&narg);
+    for (i, op) in vector.operations.iter().enumerate() {
+        let (expected, got) = match op {
+            Operation::Absorb { input, .. } => {
+                let mut message = vec![0u8; input.len() / 2];
+                arthur
+                    .fill_next_bytes(&mut message)
+                    .map_err(|e| e.to_string())?;
+                (decode(input)?, message)
+            }
+            Operation::Public { input, .. } => {
+                arthur
+                    .public_bytes(&decode(input)?)
+                    .map_err(|e| e.to_string())?;
+                continue;
+            }
+            Operation::Squeeze { output, .. } => {
+                let mut challenge = vec![0u8; output.len() / 2];
+                arthur
+                    .fill_challenge_bytes(&mut challenge)
+                    .map_err(|e| e.to_string())?;
+                (decode(output)?, challenge)
+            }
+            Operation::Ratchet => {
+                arthur.ratchet().map_err(|e| e.to_string())?;
+                continue;
+            }
+        };
+        if expected != got {
+            return Err(format!(
+                "verifier mismatch at operation {}: got {}",
+                i,
+                hex::encode(got)
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| concat!(env!("CARGO_MANIFEST_DIR"), "/kat/transcripts.json").into());
+    let file = std::fs::read_to_string(&path).expect("Unable to read the KAT file");
+    let kat: KatFile = serde_json::from_str(&file).expect("Malformed KAT file");
+
+    let mut failures = 0;
+    for vector in &kat.vectors {
+        let result = match vector.hash.as_str() {
+            "keccak" => check::<Keccak>(vector),
+            "sha2-256" => check::<DigestBridge<sha2::Sha256>>(vector),
+            "blake2b-512" => check::<DigestBridge<blake2::Blake2b512>>(vector),
+            other => Err(format!("unknown hash {:?}", other)),
+        };
+        match result {
+            Ok(()) => println!("ok      {}", vector.name),
+            Err(e) => {
+                println!("FAILED  {}: {}", vector.name, e);
+                failures += 1;
+            }
+        }
+    }
+    println!("{} vectors, {} failed", kat.vectors.len(), failures);
+    if failures > 0 {
+        std::process::exit(1);
+    }
+}
@@ -20,7 +20,7 @@
 /// It can be used to verify a proof.
 use ark_ec::{CurveGroup, PrimeGroup};
 use ark_std::UniformRand;
-use nimue::plugins::ark::*;
+use nimue::plugins::ark::prelude::*;
 use rand::rngs::OsRng;
 
 /// Extend the IO pattern with the Schnorr protocol.
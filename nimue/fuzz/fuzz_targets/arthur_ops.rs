@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (&[u8], &[u8], &[u8])| {
+    let (io_pattern, ops, transcript) = input;
+    nimue::fuzzing::run_arthur(io_pattern, ops, transcript);
+});
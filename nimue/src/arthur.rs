@@ -1,22 +1,204 @@
-use crate::errors::IOPatternError;
+use core::mem::MaybeUninit;
+use std::io;
+
+use crate::errors::{IOPatternError, OpParseError, ProofError};
 use crate::hash::{DuplexHash, Unit};
 use crate::iopattern::IOPattern;
 use crate::safe::Safe;
 use crate::traits::{ByteReader, UnitTranscript};
-use crate::DefaultHash;
+use crate::{DefaultHash, OpProgress, SharedStatement};
 
 /// [`Arthur`] contains the verifier state.
 ///
 /// Internally, it is a wrapper around a SAFE sponge.
 /// Given as input an [`IOPattern`] and a protocol transcript, it allows to
 /// de-serialize elements from the transcript and make them available to the zero-knowledge verifier.
+///
+/// By default, the last operation of the IO Pattern fails if the transcript has not been read entirely,
+/// so that a proof cannot be altered by appending bytes to it. See [`Arthur::allow_trailing_bytes`].
 pub struct Arthur<'a, H = DefaultHash, U = u8>
 where
     H: DuplexHash<U>,
     U: Unit,
 {
     pub(crate) safe: Safe<H, U>,
-    pub(crate) transcript: &'a [u8],
+    pub(crate) transcript: Transcript<'a>,
+    /// Whether bytes left in the transcript after the last operation are accepted.
+    pub(crate) allow_trailing_bytes: bool,
+    /// The failure reported by the verifier, if any. See [`Arthur::fail_at_current_op`].
+    pub(crate) failure: Option<OpFailure>,
+}
+
+/// The position in the IO Pattern at which a verifier reported a failure, for error telemetry.
+///
+/// Operations are counted as in [`OpProgress`]: consecutive absorbs (resp. squeezes) count as one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpFailure {
+    /// The label of the last operation completed before the failure, empty if none (or for ratchets and segment boundaries).
+    pub label: String,
+    /// The number of operations completed before the failure.
+    pub completed: usize,
+    /// The total number of operations in the IO Pattern.
+    pub total: usize,
+    /// The reason given by the verifier.
+    pub reason: String,
+}
+
+impl core::fmt::Display for OpFailure {
+    /// Show the failure, with the label only if allowed by the [`RedactionLevel`](crate::RedactionLevel).
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "verification failed after operation {}/{}",
+            self.completed, self.total
+        )?;
+        if crate::RedactionLevel::global() == crate::RedactionLevel::Labels {
+            write!(f, " ({:?})", self.label)?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// The origin of the prover messages, that is, the wire encoding of the protocol transcript.
+///
+/// An [`Arthur`] pulls messages from its source (see [`Arthur::with_source`]) as it reads the transcript,
+/// so that proofs stored in containers (e.g. hex strings, JSON arrays, protobuf fields)
+/// can be verified without first concatenating them into a byte string.
+/// Messages are read as if they were concatenated: their boundaries need not match the operations of the IO Pattern.
+///
+/// Any iterator over byte vectors is a source.
+pub trait TranscriptSource {
+    /// Return the next prover message, or `None` at the end of the transcript.
+    fn next_message(&mut self) -> Option<Vec<u8>>;
+}
+
+impl<I: Iterator<Item = Vec<u8>>> TranscriptSource for I {
+    fn next_message(&mut self) -> Option<Vec<u8>> {
+        self.next()
+    }
+}
+
+/// The protocol transcript left to read, possibly split in several (discontiguous) chunks,
+/// and followed by the messages of a [`TranscriptSource`].
+///
+/// Reads spanning multiple chunks are served without copying the chunks into a contiguous buffer.
+#[derive(Default)]
+pub(crate) struct Transcript<'a> {
+    /// The whole transcript, when given as a single slice.
+    whole: Option<&'a [u8]>,
+    current: &'a [u8],
+    chunks: &'a [&'a [u8]],
+    source: Option<Box<dyn TranscriptSource + Send + Sync>>,
+    /// Messages pulled from `source` and not read yet, from position `read` on.
+    pulled: Vec<u8>,
+    read: usize,
+}
+
+impl<'a> Transcript<'a> {
+    pub(crate) fn from_chunks(chunks: &'a [&'a [u8]]) -> Self {
+        Self {
+            chunks,
+            ..Default::default()
+        }
+    }
+
+    /// Read the messages of `source` once the chunks are exhausted.
+    pub(crate) fn set_source(&mut self, source: Box<dyn TranscriptSource + Send + Sync>) {
+        self.whole = None;
+        self.source = Some(source);
+    }
+
+    /// The transcript given as a single slice, if any.
+    pub(crate) fn whole(&self) -> Option<&'a [u8]> {
+        self.whole
+    }
+
+    /// The prefix of the single-slice transcript read so far.
+    pub(crate) fn consumed(&self) -> Option<&'a [u8]> {
+        self.whole
+            .map(|whole| &whole[..whole.len() - self.current.len()])
+    }
+
+    /// Whether the transcript has been read entirely.
+    ///
+    /// This pulls (at most) one non-empty message from the source.
+    pub(crate) fn is_empty(&mut self) -> bool {
+        self.current.is_empty() && self.chunks.iter().all(|chunk| chunk.is_empty()) && !self.pull(1)
+    }
+
+    /// The number of bytes left to read, excluding the messages not pulled from the source yet.
+    pub(crate) fn len(&self) -> usize {
+        self.current.len()
+            + self.chunks.iter().map(|chunk| chunk.len()).sum::<usize>()
+            + (self.pulled.len() - self.read)
+    }
+
+    /// Whether at least `len` bytes are left to read.
+    ///
+    /// Messages are pulled from the source as needed.
+    pub(crate) fn has_len(&mut self, len: usize) -> bool {
+        let borrowed = self.len() - (self.pulled.len() - self.read);
+        len <= borrowed || self.pull(len - borrowed)
+    }
+
+    /// Pull messages from the source until at least `len` bytes are buffered, returning whether it succeeded.
+    fn pull(&mut self, len: usize) -> bool {
+        let Some(source) = &mut self.source else {
+            return false;
+        };
+        while self.pulled.len() - self.read < len {
+            match source.next_message() {
+                Some(message) => {
+                    self.pulled.drain(..self.read);
+                    self.read = 0;
+                    self.pulled.extend(message);
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Advance the transcript by (at most) `max_len` bytes, returning them.
+    /// The slice returned is shorter if it reaches the end of a chunk, and empty at the end of the transcript.
+    pub(crate) fn next_slice(&mut self, max_len: usize) -> &[u8] {
+        while self.current.is_empty() {
+            match self.chunks.split_first() {
+                Some((first, rest)) => (self.current, self.chunks) = (first, rest),
+                None => break,
+            }
+        }
+        if !self.current.is_empty() {
+            let (slice, current) = self.current.split_at(max_len.min(self.current.len()));
+            self.current = current;
+            return slice;
+        }
+        if self.pulled.len() == self.read && !self.pull(1) {
+            return &[];
+        }
+        let start = self.read;
+        self.read += max_len.min(self.pulled.len() - start);
+        &self.pulled[start..self.read]
+    }
+}
+
+impl<'a> From<&'a [u8]> for Transcript<'a> {
+    fn from(current: &'a [u8]) -> Self {
+        Self {
+            whole: Some(current),
+            current,
+            ..Default::default()
+        }
+    }
+}
+
+impl io::Read for Transcript<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let slice = self.next_slice(buf.len());
+        let len = slice.len();
+        buf[..len].copy_from_slice(slice);
+        Ok(len)
+    }
 }
 
 impl<'a, U: Unit, H: DuplexHash<U>> Arthur<'a, H, U> {
@@ -38,7 +220,200 @@ impl<'a, U: Unit, H: DuplexHash<U>> Arthur<'a, H, U> {
     /// ```
     pub fn new(io_pattern: &IOPattern<H, U>, transcript: &'a [u8]) -> Self {
         let safe = Safe::new(io_pattern);
-        Self { safe, transcript }
+        let transcript = transcript.into();
+        Self {
+            safe,
+            transcript,
+            allow_trailing_bytes: false,
+            failure: None,
+        }
+    }
+
+    /// Creates a new [`Arthur`] instance reading the protocol transcript from several `chunks`,
+    /// as if they were concatenated.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(4, "header").absorb(1, "nonce");
+    /// let chunks: &[&[u8]] = &[&[1, 2], &[], &[3, 4, 5]];
+    /// let mut arthur = io.to_arthur_chunks(chunks);
+    /// assert_eq!(arthur.next_bytes().unwrap(), [1, 2, 3, 4]);
+    /// assert_eq!(arthur.next_bytes().unwrap(), [5]);
+    /// ```
+    pub fn new_from_chunks(io_pattern: &IOPattern<H, U>, chunks: &'a [&'a [u8]]) -> Self {
+        let safe = Safe::new(io_pattern);
+        let transcript = Transcript::from_chunks(chunks);
+        Self {
+            safe,
+            transcript,
+            allow_trailing_bytes: false,
+            failure: None,
+        }
+    }
+
+    /// Creates a new [`Arthur`] instance continuing from a prepared `statement`, see [`SharedStatement::to_arthur`].
+    ///
+    /// Preparing the statement once and sharing it behind an [`Arc`](std::sync::Arc) lets many threads verify proofs
+    /// for the same statement: each verifier starts from a clone of the prepared sponge,
+    /// which is a copy of the sponge state and of the operations left, without absorbing the statement again.
+    ///
+    /// ```
+    /// # use nimue::*;
+    /// use std::sync::Arc;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(4096, "srs").absorb(1, "🍝").squeeze(16, "🍷");
+    /// let mut statement = SharedStatement::new(&io);
+    /// statement.public_bytes(&[0x42; 4096]).unwrap();
+    /// let statement = Arc::new(statement);
+    ///
+    /// let proofs = (0..4u8).map(|pasta| {
+    ///     let mut merlin = statement.to_merlin();
+    ///     merlin.add_bytes(&[pasta]).unwrap();
+    ///     merlin.challenge_bytes::<16>().unwrap();
+    ///     merlin.transcript().to_vec()
+    /// });
+    /// let threads = proofs.map(|proof| {
+    ///     let statement = Arc::clone(&statement);
+    ///     std::thread::spawn(move || Arthur::from_statement(&statement, &proof).next_bytes::<1>().unwrap())
+    /// });
+    /// for (pasta, thread) in threads.collect::<Vec<_>>().into_iter().enumerate() {
+    ///     assert_eq!(thread.join().unwrap(), [pasta as u8]);
+    /// }
+    /// ```
+    pub fn from_statement(statement: &SharedStatement<H, U>, transcript: &'a [u8]) -> Self {
+        statement.to_arthur(transcript)
+    }
+
+    /// Creates a new [`Arthur`] instance, returning an error if the IO Pattern is malformed.
+    ///
+    /// Use this constructor when the IO Pattern comes from an untrusted source, e.g. [`IOPattern::from_bytes`].
+    pub fn try_new(
+        io_pattern: &IOPattern<H, U>,
+        transcript: &'a [u8],
+    ) -> Result<Self, OpParseError> {
+        let safe = Safe::try_new(io_pattern)?;
+        let transcript = transcript.into();
+        Ok(Self {
+            safe,
+            transcript,
+            allow_trailing_bytes: false,
+            failure: None,
+        })
+    }
+
+    /// Accept transcripts with bytes left after the last operation of the IO Pattern.
+    ///
+    /// This is useful when the proof is followed by other data in the same buffer.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝");
+    /// assert!(io.to_arthur(&[0x42, 0x43]).next_bytes::<1>().is_err());
+    ///
+    /// let mut arthur = io.to_arthur(&[0x42, 0x43]);
+    /// arthur.allow_trailing_bytes();
+    /// assert_eq!(arthur.next_bytes().unwrap(), [0x42]);
+    /// ```
+    pub fn allow_trailing_bytes(&mut self) {
+        self.allow_trailing_bytes = true;
+    }
+
+    /// The protocol transcript this verifier was constructed over (or, after [`Arthur::open_segment`], the transcript of the current segment).
+    ///
+    /// Returns `None` if the transcript is not a single slice,
+    /// i.e. if the verifier reads from chunks (see [`Arthur::new_from_chunks`]) or from a [`TranscriptSource`].
+    pub fn transcript(&self) -> Option<&'a [u8]> {
+        self.transcript.whole()
+    }
+
+    /// The prefix of [`Arthur::transcript`] holding the prover messages read so far.
+    ///
+    /// Wrappers can forward a partial proof, e.g. the first rounds of the protocol, without keeping a copy of the transcript.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(2, "round 1").squeeze(16, "🎲").absorb(3, "round 2");
+    /// let transcript = [1, 2, 3, 4, 5];
+    /// let mut arthur = io.to_arthur(&transcript);
+    /// assert_eq!(arthur.consumed_transcript(), Some(&[][..]));
+    /// arthur.next_bytes::<2>().unwrap();
+    /// assert_eq!(arthur.consumed_transcript(), Some(&[1, 2][..]));
+    /// assert_eq!(arthur.transcript(), Some(&transcript[..]));
+    /// ```
+    pub fn consumed_transcript(&self) -> Option<&'a [u8]> {
+        self.transcript.consumed()
+    }
+
+    /// Report that verification failed at the current position of the IO Pattern, returning [`ProofError::InvalidProof`].
+    ///
+    /// Verifiers returning `Err(arthur.fail_at_current_op(reason))` instead of `Err(ProofError::InvalidProof)`
+    /// record which operation they had reached (see [`Arthur::failure`]),
+    /// so that failures deep into long protocols can be located.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").squeeze(1, "🍷").absorb(1, "🧂");
+    /// let mut arthur = io.to_arthur(&[0x42, 0x43]);
+    /// arthur.next_bytes::<1>().unwrap();
+    /// arthur.challenge_bytes::<1>().unwrap();
+    /// let err = arthur.fail_at_current_op("not enough salt");
+    /// assert!(matches!(err, ProofError::InvalidProof));
+    ///
+    /// let failure = arthur.failure().unwrap();
+    /// assert_eq!((failure.label.as_str(), failure.completed, failure.total), ("🍷", 2, 3));
+    /// ```
+    pub fn fail_at_current_op(&mut self, reason: impl Into<String>) -> ProofError {
+        let (completed, label) = self.safe.last_op();
+        self.failure = Some(OpFailure {
+            label: label.to_string(),
+            completed,
+            total: self.safe.total_ops(),
+            reason: reason.into(),
+        });
+        ProofError::InvalidProof
+    }
+
+    /// The failure reported with [`Arthur::fail_at_current_op`], if any.
+    pub fn failure(&self) -> Option<&OpFailure> {
+        self.failure.as_ref()
+    }
+
+    /// Read the prover messages from `source`, once the transcript given at construction has been read.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").squeeze(1, "🧂").absorb(2, "🍷");
+    /// let mut merlin = io.to_merlin();
+    /// merlin.add_bytes(&[0x42]).unwrap();
+    /// let chal = merlin.challenge_bytes::<1>().unwrap();
+    /// merlin.add_bytes(&[0xca, 0xfe]).unwrap();
+    ///
+    /// // e.g. a proof shipped as hex-encoded messages
+    /// let proof = ["42", "cafe"];
+    /// let source = proof.into_iter().map(|message| hex::decode(message).unwrap());
+    /// let mut arthur = io.to_arthur(&[]).with_source(source);
+    /// assert_eq!(arthur.next_bytes().unwrap(), [0x42]);
+    /// assert_eq!(arthur.challenge_bytes::<1>().unwrap(), chal);
+    /// assert_eq!(arthur.next_bytes().unwrap(), [0xca, 0xfe]);
+    /// ```
+    pub fn with_source(mut self, source: impl TranscriptSource + Send + Sync + 'static) -> Self {
+        self.transcript.set_source(Box::new(source));
+        self
+    }
+
+    /// Fail if all operations have been performed, but the transcript has not been read entirely.
+    fn check_trailing_bytes(&mut self) -> Result<(), IOPatternError> {
+        if self.allow_trailing_bytes || self.safe.remaining_ops() > 0 || self.transcript.is_empty()
+        {
+            Ok(())
+        } else {
+            Err(format!("Transcript has {} trailing bytes", self.transcript.len()).into())
+        }
     }
 
     /// Read `input.len()` elements from the transcript.
@@ -46,13 +421,56 @@ impl<'a, U: Unit, H: DuplexHash<U>> Arthur<'a, H, U> {
     pub fn fill_next_units(&mut self, input: &mut [U]) -> Result<(), IOPatternError> {
         U::read(&mut self.transcript, input)?;
         self.safe.absorb(input)?;
+        self.check_trailing_bytes()
+    }
+
+    /// Read `count` elements from the transcript and absorb them, without returning them.
+    ///
+    /// Elements are decoded in small batches, so that skipping large messages does not allocate.
+    pub fn skip_units(&mut self, count: usize) -> Result<(), IOPatternError>
+    where
+        U: Default,
+    {
+        let mut buf: [U; 32] = core::array::from_fn(|_| U::default());
+        let mut remaining = count;
+        while remaining > 0 {
+            let len = remaining.min(buf.len());
+            self.fill_next_units(&mut buf[..len])?;
+            remaining -= len;
+        }
         Ok(())
     }
 
+    /// The total number of operations in the IO Pattern. See [`Safe::total_ops`].
+    pub fn total_ops(&self) -> usize {
+        self.safe.total_ops()
+    }
+
+    /// The number of operations left to perform. See [`Safe::remaining_ops`].
+    pub fn remaining_ops(&self) -> usize {
+        self.safe.remaining_ops()
+    }
+
+    /// Invoke `callback` after each completed operation. See [`Safe::on_op_completed`].
+    pub fn on_op_completed(&mut self, callback: impl Fn(OpProgress<'_>) + Send + Sync + 'static) {
+        self.safe.on_op_completed(callback)
+    }
+
     /// Signals the end of the statement.
     #[inline]
     pub fn ratchet(&mut self) -> Result<(), IOPatternError> {
-        self.safe.ratchet()
+        self.safe.ratchet()?;
+        self.check_trailing_bytes()
+    }
+
+    /// Cross the phase boundary `label`. See [`Safe::marker`].
+    #[inline]
+    pub fn marker(&mut self, label: &str) -> Result<(), IOPatternError>
+    where
+        U: Default,
+    {
+        self.safe.marker(label)?;
+        self.check_trailing_bytes()
     }
 
     /// Signals the end of the statement and returns the (compressed) sponge state.
@@ -62,17 +480,110 @@ impl<'a, U: Unit, H: DuplexHash<U>> Arthur<'a, H, U> {
     }
 }
 
+impl<'a, H: DuplexHash<u8>> Arthur<'a, H, u8> {
+    /// Absorb the next `len` bytes of the transcript, without copying them out.
+    ///
+    /// This is useful for messages that the verifier does not need to inspect (e.g., opaque committed blobs).
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1024, "blob").squeeze(16, "chal");
+    /// let mut merlin = io.to_merlin();
+    /// merlin.add_bytes(&[0x42; 1024]).unwrap();
+    /// let chal = merlin.challenge_bytes::<16>().unwrap();
+    ///
+    /// let mut arthur = io.to_arthur(merlin.transcript());
+    /// arthur.skip_bytes(1024).unwrap();
+    /// assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+    /// ```
+    pub fn skip_bytes(&mut self, len: usize) -> Result<(), IOPatternError> {
+        if !self.transcript.has_len(len) {
+            return Err("Transcript too short".into());
+        }
+        let mut remaining = len;
+        while remaining > 0 {
+            let slice = self.transcript.next_slice(remaining);
+            self.safe.absorb(slice)?;
+            remaining -= slice.len();
+        }
+        self.check_trailing_bytes()
+    }
+
+    /// Once the transcript has been verified, return a 32-byte digest of the final sponge state.
+    ///
+    /// The sponge is ratcheted and a digest is squeezed from it, binding the IO Pattern, the hash function,
+    /// and every element absorbed (public or read from the transcript).
+    /// Aggregators can commit to "this proof verified under this IO Pattern" with the digest alone.
+    /// The digest is the same as the one of the last event of [`replay::describe`](crate::replay::describe).
+    ///
+    /// Fails if operations of the IO Pattern are left, or if the transcript has trailing bytes
+    /// (unless allowed with [`Arthur::allow_trailing_bytes`]).
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").squeeze(16, "🍷");
+    /// let mut arthur = io.to_arthur(&[0x42]);
+    /// arthur.next_bytes::<1>().unwrap();
+    /// arthur.challenge_bytes::<16>().unwrap();
+    /// let digest = arthur.final_digest().unwrap();
+    ///
+    /// let events = replay::describe(io.as_bytes(), &[0x42]).unwrap();
+    /// assert_eq!(events.last().unwrap().digest(), &digest);
+    /// ```
+    pub fn final_digest(mut self) -> Result<[u8; 32], IOPatternError> {
+        let digest = self.safe.final_digest()?;
+        self.check_trailing_bytes()?;
+        Ok(digest)
+    }
+
+    /// End the current segment, checking that its chaining digest is `digest`,
+    /// and continue verification on the transcript of the next segment.
+    ///
+    /// The transcript of the current segment must have been read entirely.
+    pub fn open_segment(
+        &mut self,
+        digest: &[u8; 32],
+        transcript: &'a [u8],
+    ) -> Result<(), IOPatternError> {
+        if !self.transcript.is_empty() {
+            return Err("Segment transcript not fully read".into());
+        }
+        if &self.safe.seal_segment()? != digest {
+            return Err("Segment digest mismatch".into());
+        }
+        self.transcript = transcript.into();
+        self.check_trailing_bytes()
+    }
+}
+
 impl<H: DuplexHash<U>, U: Unit> UnitTranscript<U> for Arthur<'_, H, U> {
     /// Add native elements to the sponge without writing them to the protocol transcript.
     #[inline]
     fn public_units(&mut self, input: &[U]) -> Result<(), IOPatternError> {
-        self.safe.absorb(input)
+        self.safe.absorb(input)?;
+        self.check_trailing_bytes()
     }
 
     /// Get a challenge of `count` elements.
     #[inline]
     fn fill_challenge_units(&mut self, input: &mut [U]) -> Result<(), IOPatternError> {
-        self.safe.squeeze(input)
+        self.safe.squeeze(input)?;
+        self.check_trailing_bytes()
+    }
+
+    /// Fill an uninitialized buffer with challenges, without initializing it first.
+    fn fill_challenge_units_uninit<'b>(
+        &mut self,
+        output: &'b mut [MaybeUninit<U>],
+    ) -> Result<&'b mut [U], IOPatternError>
+    where
+        U: Default,
+    {
+        let output = self.safe.squeeze_uninit(output)?;
+        self.check_trailing_bytes()?;
+        Ok(output)
     }
 }
 
@@ -89,3 +600,35 @@ impl<H: DuplexHash<u8>> ByteReader for Arthur<'_, H, u8> {
         self.fill_next_units(input)
     }
 }
+
+/// Prover messages are read as a byte stream, e.g. by a deserializer, from the protocol transcript.
+///
+/// Each read is a (possibly partial) absorb, of at most the bytes left in the current message:
+/// reads return `Ok(0)` (end of file) when the next operation of the IO Pattern is not a prover message,
+/// so that deserializers cannot read past the message declared.
+/// A read failing on the transcript (e.g. because it is too short) fails with an error of kind
+/// [`std::io::ErrorKind::InvalidData`], wrapping the [`IOPatternError`].
+///
+/// ```
+/// # use nimue::*;
+/// use std::io::Read;
+///
+/// let io = IOPattern::<DefaultHash>::new("📝").absorb(4, "header").squeeze(16, "🍷");
+/// let mut arthur = io.to_arthur(b"v1:\x01");
+/// let mut header = Vec::new();
+/// // reading to the end stops at the end of the message
+/// arthur.read_to_end(&mut header).unwrap();
+/// assert_eq!(header, b"v1:\x01");
+/// assert!(arthur.challenge_bytes::<16>().is_ok());
+/// ```
+impl<H: DuplexHash<u8>> io::Read for Arthur<'_, H, u8> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = match self.safe.pending_absorb() {
+            Some(length) => length.min(buf.len()),
+            None => return Ok(0),
+        };
+        self.fill_next_units(&mut buf[..len])
+            .map(|()| len)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
@@ -0,0 +1,49 @@
+//! Replay a proof against its IO Pattern, printing each message, challenge, and running sponge digest.
+//!
+//! Usage: `nimue-replay <io-pattern-file> <proof-file> [<label>=<public-input-file>...]`, where all files contain raw bytes.
+//! Public inputs are matched, in order, with the absorbs of the same label (see `nimue::replay::describe_public`).
+//! Only the default hash function is supported.
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.len() < 3 {
+        eprintln!(
+            "usage: {} <io-pattern-file> <proof-file> [<label>=<public-input-file>...]",
+            args[0]
+        );
+        return ExitCode::FAILURE;
+    }
+    let read = |path: &str| {
+        std::fs::read(path).unwrap_or_else(|e| {
+            eprintln!("cannot read {}: {}", path, e);
+            std::process::exit(1)
+        })
+    };
+    let (domsep, narg) = (read(&args[1]), read(&args[2]));
+    let mut public = Vec::new();
+    for arg in &args[3..] {
+        let Some((label, path)) = arg.split_once('=') else {
+            eprintln!("invalid public input {:?}: expected <label>=<file>", arg);
+            return ExitCode::FAILURE;
+        };
+        public.push((label, read(path)));
+    }
+    let public = public
+        .iter()
+        .map(|(label, bytes)| (*label, bytes.as_slice()))
+        .collect::<Vec<_>>();
+
+    match nimue::replay::describe_public::<nimue::DefaultHash>(&domsep, &public, &narg) {
+        Ok(events) => {
+            for event in events {
+                println!("{}", event);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
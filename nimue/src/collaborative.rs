@@ -0,0 +1,176 @@
+//! Collaborative provers.
+//!
+//! In a distributed prover, the prover messages are additively secret-shared among several parties.
+//! Each party runs its own [`CollaborativeMerlin`]: messages are absorbed once *reconstructed*,
+//! so that the public sponge (and hence the challenges and the protocol transcript) is the same for all parties,
+//! while every party keeps its own private random coins.
+//!
+//! The verifier is unchanged: it reads the reconstructed messages with an [`Arthur`](crate::Arthur).
+use core::ops::{Deref, DerefMut};
+
+use rand::{CryptoRng, RngCore};
+
+use crate::hash::{DuplexHash, Unit};
+use crate::{DefaultHash, DefaultRng, IOPattern, IOPatternError, Merlin, UnitTranscript};
+
+/// Units that can be additively secret-shared.
+///
+/// Bytes are shared modulo 256; field elements are shared over the field.
+pub trait AdditiveShare: Unit {
+    /// Add two shares.
+    fn add_share(&self, other: &Self) -> Self;
+}
+
+impl AdditiveShare for u8 {
+    fn add_share(&self, other: &Self) -> Self {
+        self.wrapping_add(*other)
+    }
+}
+
+/// The prover state of one party in a distributed prover.
+///
+/// Dereferences to the inner [`Merlin`], so that challenges, messages in the clear, and the party's private randomness
+/// are available as usual.
+/// Secret-shared messages go through [`CollaborativeMerlin::add_shares`] (or [`CollaborativeMerlin::add_shares_with`]).
+///
+/// ```
+/// use nimue::*;
+/// use nimue::collaborative::CollaborativeMerlin;
+/// use rand::RngCore;
+///
+/// let io = IOPattern::<DefaultHash>::new("📝").add_bytes(2, "shared").challenge_bytes(16, "chal");
+/// let shares: [&[u8]; 2] = [&[0x40, 0xff], &[0x02, 0x02]];
+///
+/// let mut alice = CollaborativeMerlin::new(&io, 0, rand::rngs::OsRng);
+/// let mut bob = CollaborativeMerlin::new(&io, 1, rand::rngs::OsRng);
+/// alice.add_shares(&shares).unwrap();
+/// bob.add_shares(&shares).unwrap();
+/// assert_eq!(alice.transcript(), [0x42, 0x01]);
+/// assert_eq!(alice.challenge_bytes::<16>().unwrap(), bob.challenge_bytes::<16>().unwrap());
+/// assert_ne!(alice.rng().next_u64(), bob.rng().next_u64());
+/// ```
+pub struct CollaborativeMerlin<H = DefaultHash, U = u8, R = DefaultRng>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+    R: RngCore + CryptoRng,
+{
+    inner: Merlin<H, U, R>,
+    party: usize,
+}
+
+impl<H, U, R> CollaborativeMerlin<H, U, R>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+    R: RngCore + CryptoRng,
+{
+    /// Create the prover state of the `party`-th party, seeding its private coins with `csrng`.
+    ///
+    /// The party index is bound to the private coins,
+    /// so that parties never share their coins, even if their `csrng` are.
+    pub fn new(io_pattern: &IOPattern<H, U>, party: usize, csrng: R) -> Self {
+        let mut inner = Merlin::new(io_pattern, csrng);
+        inner
+            .rng
+            .sponge
+            .absorb_unchecked(&(party as u64).to_le_bytes());
+        Self { inner, party }
+    }
+
+    /// The index of this party.
+    pub fn party(&self) -> usize {
+        self.party
+    }
+
+    /// Reconstruct a message from the `shares` of all parties, and add it to the protocol transcript.
+    pub fn add_shares<S: AsRef<[U]>>(&mut self, shares: &[S]) -> Result<(), IOPatternError>
+    where
+        U: AdditiveShare,
+    {
+        self.add_shares_with(shares, |shares| {
+            let (first, rest) = shares.split_first().expect("checked by add_shares_with");
+            rest.iter().fold(first.as_ref().to_vec(), |acc, share| {
+                acc.iter()
+                    .zip(share.as_ref())
+                    .map(|(a, b)| a.add_share(b))
+                    .collect()
+            })
+        })
+    }
+
+    /// Reconstruct a message from the `shares` of all parties with `combine`, and add it to the protocol transcript.
+    ///
+    /// This allows for sharings other than additive ones, or for reconstructing through an MPC protocol.
+    /// All shares must have the same length.
+    pub fn add_shares_with<S: AsRef<[U]>>(
+        &mut self,
+        shares: &[S],
+        combine: impl FnOnce(&[S]) -> Vec<U>,
+    ) -> Result<(), IOPatternError> {
+        let len = match shares.first() {
+            Some(share) => share.as_ref().len(),
+            None => return Err("No shares to reconstruct".into()),
+        };
+        if shares.iter().any(|share| share.as_ref().len() != len) {
+            return Err("Shares have different lengths".into());
+        }
+        self.inner.add_units(&combine(shares))
+    }
+
+    /// Return the inner prover state.
+    pub fn into_inner(self) -> Merlin<H, U, R> {
+        self.inner
+    }
+}
+
+impl<H, U, R> Deref for CollaborativeMerlin<H, U, R>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+    R: RngCore + CryptoRng,
+{
+    type Target = Merlin<H, U, R>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<H, U, R> DerefMut for CollaborativeMerlin<H, U, R>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+    R: RngCore + CryptoRng,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<H, U, R> UnitTranscript<U> for CollaborativeMerlin<H, U, R>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+    R: RngCore + CryptoRng,
+{
+    fn public_units(&mut self, input: &[U]) -> Result<(), IOPatternError> {
+        self.inner.public_units(input)
+    }
+
+    fn fill_challenge_units(&mut self, output: &mut [U]) -> Result<(), IOPatternError> {
+        self.inner.fill_challenge_units(output)
+    }
+}
+
+impl<H, U, R> core::fmt::Debug for CollaborativeMerlin<H, U, R>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+    R: RngCore + CryptoRng,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "CollaborativeMerlin(party {}): ", self.party)?;
+        self.inner.fmt(f)
+    }
+}
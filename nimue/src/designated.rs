@@ -0,0 +1,258 @@
+//! Designated-verifier transcripts.
+//!
+//! A [`DesignatedVerifier`] wraps a [`Merlin`] (or an [`Arthur`]) so that prover messages are encrypted,
+//! in the protocol transcript, under a key shared with a designated verifier.
+//! The sponge always absorbs the *plaintext*: challenges are the same as in the non-encrypted protocol,
+//! and tampering with the ciphertext is caught by the verification equation, exactly as tampering with a plaintext transcript would.
+//! Public messages and challenges are passed through unchanged.
+//!
+//! The key exchange is not fixed by this module: any shared secret can be used to build a [`TranscriptCipher`].
+//! The default [`KeccakCipher`] binds the keystream to the IO Pattern and to a per-proof nonce,
+//! so that a static shared secret never encrypts two proofs with the same keystream.
+//! For instance, the [`ark`](crate::plugins::ark) plugin provides an ephemeral Diffie-Hellman,
+//! whose ephemeral key is absorbed as a prover message at statement time.
+//!
+//! Keystreams can also be bound to the protocol transcript itself, see [`ChallengeKeystream`].
+use rand::{CryptoRng, RngCore};
+
+use crate::hash::{DuplexHash, Keccak, Unit};
+use crate::{
+    Arthur, ByteChallenges, ByteIOPattern, ByteReader, ByteWriter, IOPatternError, Merlin,
+    UnitTranscript,
+};
+
+/// A stream cipher used to encrypt the protocol transcript.
+///
+/// Encryption and decryption are the same operation, applied to the transcript in the order it is written.
+pub trait TranscriptCipher {
+    /// Encrypt (or decrypt) `data` in place.
+    fn apply_keystream(&mut self, data: &mut [u8]);
+}
+
+/// The default [`TranscriptCipher`]: a keystream squeezed from [`Keccak`], keyed with the shared secret.
+#[derive(Clone)]
+pub struct KeccakCipher(Keccak);
+
+impl KeccakCipher {
+    /// Domain separator for the keystream, padded to 32 bytes.
+    const IV: [u8; 32] = *b"nimue/designated-verifier/keccak";
+
+    /// Derive a keystream from `shared_secret`, bound to the IO Pattern with digest `io_digest`
+    /// (see [`IOPattern::digest`](crate::IOPattern::digest)) and to a per-proof `nonce`.
+    ///
+    /// The nonce must not repeat for the same shared secret and IO Pattern, or the keystream repeats.
+    /// Typically, the prover samples it and writes it in the clear, before any encrypted message.
+    pub fn new(shared_secret: &[u8], io_digest: [u8; 32], nonce: &[u8]) -> Self {
+        Self::from_parts(Self::IV, &[shared_secret, &io_digest, nonce])
+    }
+
+    /// Derive a keystream from the domain separator `iv` and the `parts` of the key, each absorbed with its length.
+    pub(crate) fn from_parts(iv: [u8; 32], parts: &[&[u8]]) -> Self {
+        let mut sponge = Keccak::new(iv);
+        for part in parts {
+            sponge.absorb_unchecked(&(part.len() as u64).to_le_bytes());
+            sponge.absorb_unchecked(part);
+        }
+        sponge.ratchet_unchecked();
+        Self(sponge)
+    }
+}
+
+impl KeccakCipher {
+    /// Domain separator for keystreams bound to the protocol transcript, see [`ChallengeKeystream`].
+    const CHALLENGE_IV: [u8; 32] = *b"nimue/challenge-keystream/keccak";
+
+    /// Derive a keystream from a challenge `seed` squeezed from the protocol transcript.
+    fn from_challenge(seed: [u8; 32]) -> Self {
+        let mut sponge = Keccak::new(Self::CHALLENGE_IV);
+        sponge.absorb_unchecked(&seed);
+        sponge.ratchet_unchecked();
+        Self(sponge)
+    }
+}
+
+impl TranscriptCipher for KeccakCipher {
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        let mut keystream = vec![0u8; data.len()];
+        self.0.squeeze_unchecked(&mut keystream);
+        data.iter_mut().zip(keystream).for_each(|(d, k)| *d ^= k);
+    }
+}
+
+impl core::fmt::Debug for KeccakCipher {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Ensure that the keystream isn't accidentally logged
+        write!(f, "KeccakCipher: STATE OMITTED")
+    }
+}
+
+/// Declare a keystream bound to the protocol transcript, as used by [`ChallengeKeystream`].
+///
+/// The keystream is derived from a 32-byte challenge, whose label is suffixed with `.keystream`.
+pub trait ChallengeKeystreamIOPattern: ByteIOPattern + Sized {
+    fn challenge_keystream(self, label: &str) -> Self {
+        self.challenge_bytes(32, &format!("{}.keystream", label))
+    }
+}
+
+/// Derive a keystream bound to the protocol transcript, for instance to mask prover messages with one-time pads.
+///
+/// A 32-byte challenge is squeezed and expanded with [`Keccak`] (see [`KeccakCipher`]) into a keystream of any length.
+/// Both prover and verifier derive the same keystream.
+///
+/// ```
+/// use nimue::*;
+/// use nimue::designated::{ChallengeKeystream, ChallengeKeystreamIOPattern};
+///
+/// let io = IOPattern::<DefaultHash>::new("📝").add_bytes(1, "🍕").challenge_keystream("pad").add_bytes(4, "masked");
+/// let mut merlin = io.to_merlin();
+/// merlin.add_bytes(&[1]).unwrap();
+/// let pad = merlin.challenge_keystream(4).unwrap();
+/// let masked = [0xde, 0xad, 0xbe, 0xef].iter().zip(&pad).map(|(m, k)| m ^ k).collect::<Vec<_>>();
+/// merlin.add_bytes(&masked).unwrap();
+///
+/// let mut arthur = io.to_arthur(merlin.transcript());
+/// arthur.next_bytes::<1>().unwrap();
+/// let pad = arthur.challenge_keystream(4).unwrap();
+/// let masked = arthur.next_bytes::<4>().unwrap();
+/// assert_eq!(masked.iter().zip(&pad).map(|(m, k)| m ^ k).collect::<Vec<_>>(), [0xde, 0xad, 0xbe, 0xef]);
+/// ```
+pub trait ChallengeKeystream: ByteChallenges {
+    /// Squeeze a seed and return the cipher applying its keystream, of arbitrary length.
+    fn challenge_cipher(&mut self) -> Result<KeccakCipher, IOPatternError> {
+        let seed = self.challenge_bytes::<32>()?;
+        Ok(KeccakCipher::from_challenge(seed))
+    }
+
+    /// Squeeze a seed and return the first `len` bytes of its keystream.
+    fn challenge_keystream(&mut self, len: usize) -> Result<Vec<u8>, IOPatternError> {
+        let mut keystream = vec![0u8; len];
+        self.challenge_cipher()?.apply_keystream(&mut keystream);
+        Ok(keystream)
+    }
+}
+
+impl<T: ByteIOPattern> ChallengeKeystreamIOPattern for T {}
+impl<T: ByteChallenges + ?Sized> ChallengeKeystream for T {}
+
+/// A prover (or verifier) state whose transcript is encrypted to a designated verifier.
+///
+/// Challenges and public messages are passed through to the inner state,
+/// while prover messages go through [`ByteWriter`] (resp. [`ByteReader`]) and are encrypted.
+/// The wrapper does not dereference to the inner state, so that no prover message can be written in the clear:
+/// every writer and reader is implemented on the wrapper itself.
+///
+/// ```
+/// use nimue::*;
+/// use nimue::designated::{DesignatedVerifier, KeccakCipher};
+/// use rand::RngCore;
+///
+/// let io = IOPattern::<DefaultHash>::new("📝")
+///     .add_bytes(16, "nonce")
+///     .add_bytes(4, "secret")
+///     .challenge_bytes(16, "chal");
+/// let shared_secret = b"key exchanged out of band";
+///
+/// // a fresh nonce for every proof, sent in the clear.
+/// let mut merlin = io.to_merlin();
+/// let mut nonce = [0u8; 16];
+/// merlin.rng().fill_bytes(&mut nonce);
+/// merlin.add_bytes(&nonce).unwrap();
+/// let mut merlin = DesignatedVerifier::new(merlin, KeccakCipher::new(shared_secret, io.digest(), &nonce));
+/// merlin.add_bytes("🍕".as_bytes()).unwrap();
+/// let chal = merlin.challenge_bytes::<16>().unwrap();
+/// assert!(!merlin.transcript().ends_with("🍕".as_bytes()));
+///
+/// let mut arthur = io.to_arthur(merlin.transcript());
+/// let nonce = arthur.next_bytes::<16>().unwrap();
+/// let mut arthur = DesignatedVerifier::new(arthur, KeccakCipher::new(shared_secret, io.digest(), &nonce));
+/// assert_eq!(&arthur.next_bytes::<4>().unwrap(), "🍕".as_bytes());
+/// assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+/// ```
+pub struct DesignatedVerifier<T, C = KeccakCipher> {
+    inner: T,
+    cipher: C,
+}
+
+impl<T, C: TranscriptCipher> DesignatedVerifier<T, C> {
+    /// Wrap `inner`, encrypting its prover messages with `cipher`.
+    pub fn new(inner: T, cipher: C) -> Self {
+        Self { inner, cipher }
+    }
+
+    /// Return the inner state, dropping the cipher.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T, C> DesignatedVerifier<T, C> {
+    /// Return a reference to the inner state.
+    ///
+    /// There is no mutable access: prover messages written (or read) directly on the inner state would bypass the cipher.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<H, U, R, C> DesignatedVerifier<Merlin<H, U, R>, C>
+where
+    H: DuplexHash<U>,
+    U: Unit,
+    R: RngCore + CryptoRng,
+{
+    /// The encrypted protocol transcript, see [`Merlin::transcript`].
+    pub fn transcript(&self) -> &[u8] {
+        self.inner.transcript()
+    }
+
+    /// The prover's private coins, see [`Merlin::rng`].
+    pub fn rng(&mut self) -> &mut (impl CryptoRng + RngCore) {
+        self.inner.rng()
+    }
+}
+
+impl<T, C, U> UnitTranscript<U> for DesignatedVerifier<T, C>
+where
+    U: Unit,
+    T: UnitTranscript<U>,
+{
+    fn public_units(&mut self, input: &[U]) -> Result<(), IOPatternError> {
+        self.inner.public_units(input)
+    }
+
+    fn fill_challenge_units(&mut self, output: &mut [U]) -> Result<(), IOPatternError> {
+        self.inner.fill_challenge_units(output)
+    }
+}
+
+impl<H, R, C> ByteWriter for DesignatedVerifier<Merlin<H, u8, R>, C>
+where
+    H: DuplexHash<u8>,
+    R: RngCore + CryptoRng,
+    C: TranscriptCipher,
+{
+    /// Absorb `input` and write it, encrypted, to the protocol transcript.
+    fn add_bytes(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
+        self.inner.public_units(input)?;
+        let old_len = self.inner.transcript.len();
+        self.inner.transcript.extend_from_slice(input);
+        self.cipher
+            .apply_keystream(&mut self.inner.transcript[old_len..]);
+        self.inner.flush_message(old_len);
+        Ok(())
+    }
+}
+
+impl<H, C> ByteReader for DesignatedVerifier<Arthur<'_, H, u8>, C>
+where
+    H: DuplexHash<u8>,
+    C: TranscriptCipher,
+{
+    /// Read `input.len()` encrypted bytes from the protocol transcript, and absorb their decryption.
+    fn fill_next_bytes(&mut self, input: &mut [u8]) -> Result<(), IOPatternError> {
+        u8::read(&mut self.inner.transcript, input)?;
+        self.cipher.apply_keystream(input);
+        self.inner.public_units(input)
+    }
+}
@@ -26,6 +26,36 @@ use std::{borrow::Borrow, error::Error, fmt::Display};
 #[derive(Debug, Clone)]
 pub struct IOPatternError(String);
 
+/// Signals a malformed IO Pattern string.
+///
+/// IO Patterns built with [`IOPattern::new`](crate::IOPattern::new) and its builder methods are always well-formed;
+/// this error arises when parsing untrusted patterns, e.g. with [`IOPattern::from_bytes`](crate::IOPattern::from_bytes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpParseError {
+    /// The byte offset, within the IO Pattern, of the offending operation.
+    pub position: usize,
+    /// What went wrong.
+    pub kind: OpParseErrorKind,
+}
+
+/// The kind of [`OpParseError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpParseErrorKind {
+    /// The IO Pattern is not valid UTF-8.
+    InvalidUtf8,
+    /// Two separators follow each other, or the pattern ends with a separator.
+    EmptyOp,
+    /// The operation is not one of `A`, `S`, `R`.
+    UnknownOp(u8),
+    /// The length is missing for an absorb or a squeeze, or non-zero for a ratchet or a segment boundary.
+    InvalidLength,
+    /// The length does not fit in a `usize`.
+    LengthOverflow,
+    /// The domain separator does not identify a hash function known to the sponge,
+    /// see [`DuplexHash::new_for_io_pattern`](crate::DuplexHash::new_for_io_pattern).
+    UnknownBackend,
+}
+
 /// An error happened when creating or verifying a proof.
 #[derive(Debug, Clone)]
 pub enum ProofError {
@@ -56,7 +86,26 @@ impl Display for ProofError {
     }
 }
 
+impl Display for OpParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self.kind {
+            OpParseErrorKind::InvalidUtf8 => "invalid UTF-8",
+            OpParseErrorKind::EmptyOp => "empty operation",
+            OpParseErrorKind::UnknownOp(_) => "unknown operation",
+            OpParseErrorKind::InvalidLength => "invalid length",
+            OpParseErrorKind::LengthOverflow => "length overflow",
+            OpParseErrorKind::UnknownBackend => "unknown hash backend",
+        };
+        write!(
+            f,
+            "Malformed IO Pattern: {} at byte {}",
+            reason, self.position
+        )
+    }
+}
+
 impl Error for IOPatternError {}
+impl Error for OpParseError {}
 impl Error for ProofError {}
 
 impl From<&str> for IOPatternError {
@@ -71,6 +120,12 @@ impl From<String> for IOPatternError {
     }
 }
 
+impl From<OpParseError> for IOPatternError {
+    fn from(value: OpParseError) -> Self {
+        IOPatternError(value.to_string())
+    }
+}
+
 impl<B: Borrow<IOPatternError>> From<B> for ProofError {
     fn from(value: B) -> Self {
         ProofError::InvalidIO(value.borrow().clone())
@@ -78,7 +133,14 @@ impl<B: Borrow<IOPatternError>> From<B> for ProofError {
 }
 
 impl From<std::io::Error> for IOPatternError {
+    /// I/O errors raised by the transcript itself (e.g. when writing to a [`Merlin`](crate::Merlin)) are unwrapped.
     fn from(value: std::io::Error) -> Self {
-        IOPatternError(value.to_string())
+        match value
+            .get_ref()
+            .and_then(|err| err.downcast_ref::<IOPatternError>())
+        {
+            Some(err) => err.clone(),
+            None => IOPatternError(value.to_string()),
+        }
     }
 }
@@ -0,0 +1,37 @@
+//! Fuzz-friendly entry points, used by the targets in `fuzz/`.
+//!
+//! These functions accept arbitrary bytes and must never panic:
+//! malformed inputs are expected to be rejected with an error.
+use crate::{Arthur, DefaultHash, IOPattern, IOPatternError, UnitTranscript};
+
+/// Parse arbitrary bytes as an [`IOPattern`], returning the number of (merged) operations.
+pub fn parse_iopattern(io_pattern: &[u8]) -> Result<usize, IOPatternError> {
+    Ok(IOPattern::<DefaultHash>::parse_io(io_pattern)?.len())
+}
+
+/// Run the operations encoded in `ops` against the verifier for `io_pattern` and `transcript`.
+///
+/// Each byte of `ops` encodes an operation in its two least significant bits
+/// (read from the transcript, public absorption, challenge, ratchet) and its length in the remaining ones.
+/// Returns the number of operations that succeeded.
+pub fn run_arthur(io_pattern: &[u8], ops: &[u8], transcript: &[u8]) -> usize {
+    let Ok(io_pattern) = IOPattern::<DefaultHash>::from_bytes(io_pattern) else {
+        return 0;
+    };
+    let Ok(mut arthur) = Arthur::try_new(&io_pattern, transcript) else {
+        return 0;
+    };
+    let mut buf = [0u8; 64];
+    ops.iter()
+        .map(|&op| {
+            let len = (op >> 2) as usize;
+            match op & 0b11 {
+                0 => arthur.fill_next_units(&mut buf[..len]),
+                1 => arthur.public_units(&buf[..len]),
+                2 => arthur.fill_challenge_units(&mut buf[..len]),
+                _ => arthur.ratchet(),
+            }
+        })
+        .filter(Result::is_ok)
+        .count()
+}
@@ -0,0 +1,283 @@
+//! A duplex hash over bytes, selected at runtime.
+//!
+//! Proof formats letting the prover choose the hash function require the verifier to pick it at runtime,
+//! while the types of [`Merlin`](crate::Merlin) and [`Arthur`](crate::Arthur) are generic over the hash.
+//! [`AnyDuplexHash`] dispatches each operation to one of the hashes of this crate, chosen by a [`HashBackend`].
+//! The identifier of the backend prefixes the domain separator of the IO Pattern,
+//! so that it is absorbed in the IV, and proofs for a backend are rejected for any other:
+//!
+//! ```
+//! use nimue::hash::{AnyDuplexHash, HashBackend};
+//! use nimue::*;
+//!
+//! let io = IOPattern::<AnyDuplexHash>::with_backend(HashBackend::Keccak, "📝").absorb(1, "🍝").squeeze(16, "🍷");
+//! assert!(io.as_bytes().starts_with(b"[keccak]"));
+//! let mut merlin = io.to_merlin();
+//! merlin.add_bytes(&[0x42]).unwrap();
+//! let chal = merlin.challenge_bytes::<16>().unwrap();
+//!
+//! // the verifier reads the backend from the proof, e.g. from a header
+//! let backend = HashBackend::from_id("keccak").unwrap();
+//! let io = IOPattern::<AnyDuplexHash>::with_backend(backend, "📝").absorb(1, "🍝").squeeze(16, "🍷");
+//! let mut arthur = io.to_arthur(merlin.transcript());
+//! assert_eq!(arthur.next_bytes().unwrap(), [0x42]);
+//! assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+//! ```
+//!
+//! Hashes over other units follow the same convention, via [`domain_separator`] and [`backend_id`].
+//!
+//! Independently of the protocol hash, the hash deriving the IV of an IO Pattern can be chosen with an [`IvHash`]
+//! (see [`IOPattern::with_iv_hash`]): a backend, or the protocol hash itself.
+//!
+//! Algebraic hashes live in their own crates, which depend on this one, and so cannot be variants of [`AnyDuplexHash`].
+//! For Poseidon over BN254, `nimue-poseidon` provides `bn254::AnyDuplexHashBn254`,
+//! which selects among these backends and Poseidon (run over bytes, see [`BytesOverField`](crate::plugins::ark::bytes::BytesOverField)),
+//! and `bn254::AnyPoseidonBn254`, which selects among Poseidon instances over field units.
+use zeroize::Zeroize;
+
+#[cfg(feature = "sha2")]
+use super::legacy::DigestBridge;
+#[cfg(feature = "shake")]
+use super::Shake128;
+use super::{DuplexHash, Keccak};
+use crate::{IOPattern, OpParseError, OpParseErrorKind};
+
+/// Prefix `domsep` with the identifier of a backend, as `[id]domsep`.
+pub fn domain_separator(id: &str, domsep: &str) -> String {
+    assert!(
+        !id.contains(']'),
+        "Backend identifier cannot contain the character ']'."
+    );
+    format!("[{}]{}", id, domsep)
+}
+
+/// The identifier of the backend prefixing the domain separator of `io_pattern` (as bytes), if any.
+pub fn backend_id(io_pattern: &[u8]) -> Option<&str> {
+    let domsep = io_pattern.split(|&b| b == 0).next()?;
+    let rest = domsep.strip_prefix(b"[")?;
+    let end = rest.iter().position(|&b| b == b']')?;
+    core::str::from_utf8(&rest[..end]).ok()
+}
+
+/// The hash functions available to [`AnyDuplexHash`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HashBackend {
+    /// [`Keccak`], identified by `keccak`.
+    Keccak,
+    /// [`Shake128`], identified by `shake128`.
+    #[cfg(feature = "shake")]
+    Shake128,
+    /// [`DigestBridge`] over SHA-256, identified by `sha256-bridge`.
+    #[cfg(feature = "sha2")]
+    Sha256,
+}
+
+impl HashBackend {
+    /// All the available backends.
+    pub const ALL: &'static [Self] = &[
+        Self::Keccak,
+        #[cfg(feature = "shake")]
+        Self::Shake128,
+        #[cfg(feature = "sha2")]
+        Self::Sha256,
+    ];
+
+    /// The stable identifier of the backend, absorbed in the IV.
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::Keccak => "keccak",
+            #[cfg(feature = "shake")]
+            Self::Shake128 => "shake128",
+            #[cfg(feature = "sha2")]
+            Self::Sha256 => "sha256-bridge",
+        }
+    }
+
+    /// The backend identified by `id`, if any.
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|backend| backend.id() == id)
+    }
+}
+
+/// The hash deriving the IV of an IO Pattern, recorded in its header (see [`IOPattern::with_iv_hash`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IvHash {
+    /// One of the [`HashBackend`]s, identified as the backend.
+    Backend(HashBackend),
+    /// The protocol hash itself, identified by `protocol` (see [`DuplexHash::iv_for_io_pattern`]).
+    Protocol,
+}
+
+impl IvHash {
+    /// The stable identifier of the hash, recorded in the header of the IO Pattern.
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::Backend(backend) => backend.id(),
+            Self::Protocol => "protocol",
+        }
+    }
+
+    /// The hash identified by `id`, if any.
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "protocol" => Some(Self::Protocol),
+            id => HashBackend::from_id(id).map(Self::Backend),
+        }
+    }
+}
+
+impl From<HashBackend> for IvHash {
+    fn from(backend: HashBackend) -> Self {
+        Self::Backend(backend)
+    }
+}
+
+/// A duplex hash over bytes, dispatching to the [`HashBackend`] selected at runtime.
+///
+/// The backend is read from the domain separator of the IO Pattern,
+/// which must be built with [`IOPattern::with_backend`].
+/// [`DuplexHash::new`] (used where no IO Pattern is available) defaults to [`HashBackend::Keccak`].
+#[derive(Clone)]
+pub enum AnyDuplexHash {
+    Keccak(Keccak),
+    #[cfg(feature = "shake")]
+    Shake128(Shake128),
+    #[cfg(feature = "sha2")]
+    Sha256(DigestBridge<sha2::Sha256>),
+}
+
+impl AnyDuplexHash {
+    /// Initialize the sponge of `backend` with `iv`.
+    pub fn with_backend(backend: HashBackend, iv: [u8; 32]) -> Self {
+        match backend {
+            HashBackend::Keccak => Self::Keccak(Keccak::new(iv)),
+            #[cfg(feature = "shake")]
+            HashBackend::Shake128 => Self::Shake128(Shake128::new(iv)),
+            #[cfg(feature = "sha2")]
+            HashBackend::Sha256 => Self::Sha256(DigestBridge::new(iv)),
+        }
+    }
+
+    /// The sponge of `backend`, with its state set to zero (see [`Default`]).
+    pub(crate) fn zeroed(backend: HashBackend) -> Self {
+        match backend {
+            HashBackend::Keccak => Self::Keccak(Keccak::default()),
+            #[cfg(feature = "shake")]
+            HashBackend::Shake128 => Self::Shake128(Shake128::default()),
+            #[cfg(feature = "sha2")]
+            HashBackend::Sha256 => Self::Sha256(DigestBridge::default()),
+        }
+    }
+
+    /// The backend of the sponge.
+    pub fn backend(&self) -> HashBackend {
+        match self {
+            Self::Keccak(_) => HashBackend::Keccak,
+            #[cfg(feature = "shake")]
+            Self::Shake128(_) => HashBackend::Shake128,
+            #[cfg(feature = "sha2")]
+            Self::Sha256(_) => HashBackend::Sha256,
+        }
+    }
+}
+
+impl IOPattern<AnyDuplexHash> {
+    /// Create a new IO Pattern for `backend`, prefixing the domain separator with its identifier.
+    pub fn with_backend(backend: HashBackend, domsep: &str) -> Self {
+        Self::new(&domain_separator(backend.id(), domsep))
+    }
+}
+
+impl Default for AnyDuplexHash {
+    fn default() -> Self {
+        Self::Keccak(Keccak::default())
+    }
+}
+
+impl Zeroize for AnyDuplexHash {
+    fn zeroize(&mut self) {
+        match self {
+            Self::Keccak(sponge) => sponge.zeroize(),
+            #[cfg(feature = "shake")]
+            Self::Shake128(sponge) => sponge.zeroize(),
+            #[cfg(feature = "sha2")]
+            Self::Sha256(sponge) => sponge.zeroize(),
+        }
+    }
+}
+
+impl DuplexHash<u8> for AnyDuplexHash {
+    fn new(iv: [u8; 32]) -> Self {
+        Self::with_backend(HashBackend::Keccak, iv)
+    }
+
+    /// Fails if the domain separator of the IO Pattern does not start with the identifier of a backend,
+    /// see [`IOPattern::with_backend`].
+    fn new_for_io_pattern(
+        io_pattern: &[u8],
+        _digest: [u8; 32],
+        iv: [u8; 32],
+    ) -> Result<Self, OpParseError> {
+        let backend = backend_id(io_pattern)
+            .and_then(HashBackend::from_id)
+            .ok_or(OpParseError {
+                position: 0,
+                kind: OpParseErrorKind::UnknownBackend,
+            })?;
+        Ok(Self::with_backend(backend, iv))
+    }
+
+    fn new_like(&self, iv: [u8; 32]) -> Self {
+        Self::with_backend(self.backend(), iv)
+    }
+
+    /// Derive the IV with the backend prefixing the domain separator, in its zero state.
+    fn iv_for_io_pattern(io_pattern: &[u8]) -> Option<[u8; 32]> {
+        let backend = backend_id(io_pattern).and_then(HashBackend::from_id)?;
+        let mut iv = [0u8; 32];
+        Self::zeroed(backend)
+            .absorb_unchecked(io_pattern)
+            .squeeze_unchecked(&mut iv);
+        Some(iv)
+    }
+
+    fn absorb_unchecked(&mut self, input: &[u8]) -> &mut Self {
+        match self {
+            Self::Keccak(sponge) => _ = sponge.absorb_unchecked(input),
+            #[cfg(feature = "shake")]
+            Self::Shake128(sponge) => _ = sponge.absorb_unchecked(input),
+            #[cfg(feature = "sha2")]
+            Self::Sha256(sponge) => _ = sponge.absorb_unchecked(input),
+        }
+        self
+    }
+
+    fn squeeze_unchecked(&mut self, output: &mut [u8]) -> &mut Self {
+        match self {
+            Self::Keccak(sponge) => _ = sponge.squeeze_unchecked(output),
+            #[cfg(feature = "shake")]
+            Self::Shake128(sponge) => _ = sponge.squeeze_unchecked(output),
+            #[cfg(feature = "sha2")]
+            Self::Sha256(sponge) => _ = sponge.squeeze_unchecked(output),
+        }
+        self
+    }
+
+    fn ratchet_unchecked(&mut self) -> &mut Self {
+        match self {
+            Self::Keccak(sponge) => _ = sponge.ratchet_unchecked(),
+            #[cfg(feature = "shake")]
+            Self::Shake128(sponge) => _ = sponge.ratchet_unchecked(),
+            #[cfg(feature = "sha2")]
+            Self::Sha256(sponge) => _ = sponge.ratchet_unchecked(),
+        }
+        self
+    }
+}
+
+impl core::fmt::Debug for AnyDuplexHash {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Ensure that the state isn't accidentally logged
+        write!(f, "AnyDuplexHash({}): STATE OMITTED", self.backend().id())
+    }
+}
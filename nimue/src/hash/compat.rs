@@ -0,0 +1,399 @@
+//! Compatibility layer with [merlin](https://github.com/dalek-cryptography/merlin) transcripts.
+//!
+//! Merlin transcripts are built on the top of STROBE-128 (cf. [strobe.sourceforge.io](https://strobe.sourceforge.io/)),
+//! framing each operation as follows:
+//! - labels are absorbed via `meta-AD`, together with the little-endian 32-bit length of the message;
+//! - prover messages are absorbed via `AD`;
+//! - verifier challenges are squeezed via `PRF`.
+//!
+//! [`Strobe128`] implements these operations, and exposes the merlin framing in [`Strobe128::append_message`]
+//! and [`Strobe128::challenge_bytes`].
+//!
+//! The [`DuplexHash`] implementation applies the same framing, driven by the [`IOPattern`]:
+//! the sponge is initialized as `merlin::Transcript::new(domsep)`, and each absorb (resp. squeeze) declared
+//! in the IO Pattern is framed with its label and length, whatever the size of the chunks
+//! it is actually absorbed (resp. squeezed) in.
+//! A protocol written with nimue over [`Strobe128`] thus yields the same challenges as the same protocol
+//! written with merlin, so that transcripts of already-deployed verifiers can be reproduced:
+//!
+//! ```
+//! # use nimue::{*, hash::compat::Strobe128};
+//! let io = IOPattern::<Strobe128>::new("example").absorb(5, "greeting").squeeze(16, "challenge");
+//! let mut merlin = io.to_merlin();
+//! merlin.add_bytes(b"hello").unwrap();
+//! let challenge = merlin.challenge_bytes::<16>().unwrap();
+//!
+//! let mut transcript = merlin::Transcript::new(b"example");
+//! transcript.append_message(b"greeting", b"hello");
+//! let mut expected = [0u8; 16];
+//! transcript.challenge_bytes(b"challenge", &mut expected);
+//! assert_eq!(challenge, expected);
+//! ```
+//!
+//! Operations that merlin lacks are framed as follows:
+//! - a marker is a 1-byte message, labeled as the marker;
+//! - a ratchet is STROBE's `RATCHET` of 16 bytes;
+//! - a segment boundary is a ratchet, followed by the 32-byte challenge `b"segment"` (the chaining digest).
+//!
+//! The IV is the [`IOPattern::digest`] when instantiating the sponge from an IO Pattern, and it is then
+//! left out: as in merlin, the protocol is bound by its domain separator and by the framing of each operation.
+//! Any other IV (e.g. the chaining digest at the start of a segment) is appended as the message `b"iv"`.
+//! A sponge created with [`DuplexHash::new`] has no IO Pattern, and streams absorptions via `AD`
+//! and squeezes via `PRF` without any label.
+use std::collections::VecDeque;
+
+use zeroize::Zeroize;
+
+use super::keccak::AlignedKeccakState;
+use super::sponge::Sponge;
+use super::{iv_from_bytes, DuplexHash};
+use crate::iopattern::Op;
+use crate::{IOPattern, OpParseError, OpParseErrorKind};
+
+/// STROBE rate for the 128-bit security level.
+const STROBE_R: u8 = 166;
+
+const FLAG_I: u8 = 1;
+const FLAG_A: u8 = 1 << 1;
+const FLAG_C: u8 = 1 << 2;
+const FLAG_T: u8 = 1 << 3;
+const FLAG_M: u8 = 1 << 4;
+const FLAG_K: u8 = 1 << 5;
+
+/// The protocol label used by merlin to initialize STROBE.
+const MERLIN_PROTOCOL_LABEL: &[u8] = b"Merlin v1.0";
+
+/// A STROBE-128 context, as used by merlin.
+///
+/// Only `meta-AD`, `AD`, `PRF`, `KEY` and `RATCHET` operations are supported.
+#[derive(Clone, Zeroize)]
+pub struct Strobe128 {
+    state: AlignedKeccakState,
+    pos: u8,
+    pos_begin: u8,
+    cur_flags: u8,
+    /// Current [`DuplexHash`] operation, for streaming absorb/squeeze.
+    #[zeroize(skip)]
+    mode: Mode,
+    /// The units left in the current framed operation.
+    #[zeroize(skip)]
+    remaining: usize,
+    /// The operations of the IO Pattern still to be framed.
+    #[zeroize(skip)]
+    frames: VecDeque<Frame>,
+    /// The domain separator of the IO Pattern, if the sponge was created from one.
+    #[zeroize(skip)]
+    domain_separator: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Start,
+    Absorb,
+    Squeeze,
+}
+
+/// The merlin framing of an operation declared in the IO Pattern.
+#[derive(Clone)]
+enum Frame {
+    /// An absorb (resp. squeeze) of the given label and length.
+    Op(Mode, Vec<u8>, usize),
+    Ratchet,
+    Segment,
+}
+
+impl Strobe128 {
+    /// Initialize STROBE with the given protocol label.
+    pub fn new(protocol_label: &[u8]) -> Self {
+        let mut state = AlignedKeccakState::default();
+        state.as_mut()[0..6].copy_from_slice(&[1, STROBE_R + 2, 1, 0, 1, 96]);
+        state.as_mut()[6..18].copy_from_slice(b"STROBEv1.0.2");
+        state.permute();
+
+        let mut strobe = Self {
+            state,
+            pos: 0,
+            pos_begin: 0,
+            cur_flags: 0,
+            mode: Mode::Start,
+            remaining: 0,
+            frames: VecDeque::new(),
+            domain_separator: None,
+        };
+        strobe.meta_ad(protocol_label, false);
+        strobe
+    }
+
+    /// Absorb framing metadata (`meta-AD`), such as labels and lengths.
+    ///
+    /// If `more` is set, continue the previous `meta-AD` operation instead of starting a new one.
+    pub fn meta_ad(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_M | FLAG_A, more);
+        self.absorb(data);
+    }
+
+    /// Absorb associated data (`AD`), such as prover messages.
+    ///
+    /// If `more` is set, continue the previous `AD` operation instead of starting a new one.
+    pub fn ad(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_A, more);
+        self.absorb(data);
+    }
+
+    /// Fill `data` with pseudorandom output (`PRF`), such as verifier challenges.
+    ///
+    /// If `more` is set, continue the previous `PRF` operation instead of starting a new one.
+    pub fn prf(&mut self, data: &mut [u8], more: bool) {
+        self.begin_op(FLAG_I | FLAG_A | FLAG_C, more);
+        self.squeeze(data);
+    }
+
+    /// Overwrite the state with secret key material (`KEY`).
+    ///
+    /// If `more` is set, continue the previous `KEY` operation instead of starting a new one.
+    pub fn key(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_A | FLAG_C, more);
+        self.overwrite(data);
+    }
+
+    /// Erase `len` bytes of the state, preventing rollback.
+    pub fn ratchet(&mut self, len: usize, more: bool) {
+        self.begin_op(FLAG_C, more);
+        for _ in 0..len {
+            self.state.as_mut()[self.pos as usize] = 0;
+            self.advance();
+        }
+    }
+
+    /// Append a labeled message, as `merlin::Transcript::append_message` does.
+    pub fn append_message(&mut self, label: &[u8], message: &[u8]) {
+        self.mode = Mode::Start;
+        self.meta_ad(label, false);
+        self.meta_ad(&(message.len() as u32).to_le_bytes(), true);
+        self.ad(message, false);
+    }
+
+    /// Fill `dest` with labeled challenge bytes, as `merlin::Transcript::challenge_bytes` does.
+    pub fn challenge_bytes(&mut self, label: &[u8], dest: &mut [u8]) {
+        self.mode = Mode::Start;
+        self.meta_ad(label, false);
+        self.meta_ad(&(dest.len() as u32).to_le_bytes(), true);
+        self.prf(dest, false);
+    }
+
+    /// A fresh merlin transcript with the given domain separator, framing `frames` in order.
+    fn with_frames(domain_separator: &[u8], frames: VecDeque<Frame>) -> Self {
+        let mut strobe = Self::default();
+        strobe.append_message(b"dom-sep", domain_separator);
+        strobe.domain_separator = Some(domain_separator.to_vec());
+        strobe.frames = frames;
+        strobe
+    }
+
+    /// Return how many of the next `len` units belong to the current operation of kind `mode`.
+    ///
+    /// If no such operation is ongoing, the next one declared in the IO Pattern is started
+    /// by absorbing its label and length.
+    /// Operations that are not declared in the IO Pattern are streamed whole, without framing.
+    fn next_chunk(&mut self, mode: Mode, len: usize) -> usize {
+        if self.mode != mode || self.remaining == 0 {
+            match self.frames.front() {
+                Some(Frame::Op(next, _, _)) if *next == mode => {
+                    let Some(Frame::Op(_, label, length)) = self.frames.pop_front() else {
+                        unreachable!()
+                    };
+                    self.meta_ad(&label, false);
+                    self.meta_ad(&(length as u32).to_le_bytes(), true);
+                    self.mode = Mode::Start;
+                    self.remaining = length;
+                }
+                _ => {
+                    if self.mode != mode {
+                        self.mode = Mode::Start;
+                    }
+                    self.remaining = 0;
+                    return len;
+                }
+            }
+        }
+        let chunk = usize::min(len, self.remaining);
+        self.remaining -= chunk;
+        chunk
+    }
+
+    fn run_f(&mut self) {
+        let state = self.state.as_mut();
+        state[self.pos as usize] ^= self.pos_begin;
+        state[(self.pos + 1) as usize] ^= 0x04;
+        state[(STROBE_R + 1) as usize] ^= 0x80;
+        self.state.permute();
+        self.pos = 0;
+        self.pos_begin = 0;
+    }
+
+    #[inline]
+    fn advance(&mut self) {
+        self.pos += 1;
+        if self.pos == STROBE_R {
+            self.run_f();
+        }
+    }
+
+    fn absorb(&mut self, data: &[u8]) {
+        for byte in data {
+            self.state.as_mut()[self.pos as usize] ^= byte;
+            self.advance();
+        }
+    }
+
+    fn overwrite(&mut self, data: &[u8]) {
+        for byte in data {
+            self.state.as_mut()[self.pos as usize] = *byte;
+            self.advance();
+        }
+    }
+
+    fn squeeze(&mut self, data: &mut [u8]) {
+        for byte in data {
+            let state = self.state.as_mut();
+            *byte = state[self.pos as usize];
+            state[self.pos as usize] = 0;
+            self.advance();
+        }
+    }
+
+    fn begin_op(&mut self, flags: u8, more: bool) {
+        // Check if we're continuing an operation
+        if more {
+            assert_eq!(
+                self.cur_flags, flags,
+                "You tried to continue op {:#b} but changed flags to {:#b}",
+                self.cur_flags, flags,
+            );
+            return;
+        }
+        // Transport (T) operations are meaningless for Fiat-Shamir.
+        assert_eq!(flags & FLAG_T, 0u8, "The T flag is not supported");
+
+        let old_begin = self.pos_begin;
+        self.pos_begin = self.pos + 1;
+        self.cur_flags = flags;
+
+        self.absorb(&[old_begin, flags]);
+
+        // Force running F if C or K is set
+        let force_f = 0 != (flags & (FLAG_C | FLAG_K));
+        if force_f && self.pos != 0 {
+            self.run_f();
+        }
+    }
+}
+
+impl Default for Strobe128 {
+    /// A fresh merlin transcript, before the domain separator is appended.
+    fn default() -> Self {
+        Self::new(MERLIN_PROTOCOL_LABEL)
+    }
+}
+
+impl DuplexHash<u8> for Strobe128 {
+    /// Equivalent to `merlin::Transcript::new(&iv)`.
+    fn new(iv: [u8; 32]) -> Self {
+        let mut strobe = Self::default();
+        strobe.append_message(b"dom-sep", &iv);
+        strobe
+    }
+
+    /// Equivalent to `merlin::Transcript::new(domsep)`, where `domsep` is the domain separator of the IO Pattern.
+    ///
+    /// Fails if the IO Pattern is malformed, or if an operation is longer than `u32::MAX` bytes.
+    fn new_for_io_pattern(
+        io_pattern: &[u8],
+        digest: [u8; 32],
+        iv: [u8; 32],
+    ) -> Result<Self, OpParseError> {
+        let frames = IOPattern::<Self>::parse_ops(io_pattern)?
+            .into_iter()
+            .map(|(position, op, label)| match op {
+                Op::Absorb(length) | Op::Squeeze(length) if u32::try_from(length).is_err() => {
+                    Err(OpParseError {
+                        position,
+                        kind: OpParseErrorKind::LengthOverflow,
+                    })
+                }
+                Op::Absorb(length) => Ok(Frame::Op(Mode::Absorb, label.to_vec(), length)),
+                Op::Squeeze(length) => Ok(Frame::Op(Mode::Squeeze, label.to_vec(), length)),
+                Op::Marker => Ok(Frame::Op(Mode::Absorb, label.to_vec(), 1)),
+                Op::Ratchet => Ok(Frame::Ratchet),
+                Op::Segment => Ok(Frame::Segment),
+            })
+            .collect::<Result<_, _>>()?;
+        let domain_separator = io_pattern.split(|&b| b == 0).next().unwrap_or_default();
+        let mut strobe = Self::with_frames(domain_separator, frames);
+        if iv != digest {
+            strobe.append_message(b"iv", &iv);
+        }
+        Ok(strobe)
+    }
+
+    fn iv_for_io_pattern(io_pattern: &[u8]) -> Option<[u8; 32]> {
+        Some(iv_from_bytes::<Self>(io_pattern))
+    }
+
+    /// Continue framing the remaining operations of the IO Pattern, after appending `iv`.
+    fn new_like(&self, iv: [u8; 32]) -> Self {
+        match &self.domain_separator {
+            Some(domain_separator) => {
+                let mut strobe = Self::with_frames(domain_separator, self.frames.clone());
+                strobe.append_message(b"iv", &iv);
+                strobe
+            }
+            None => <Self as DuplexHash>::new(iv),
+        }
+    }
+
+    fn absorb_unchecked(&mut self, mut input: &[u8]) -> &mut Self {
+        while !input.is_empty() {
+            let (chunk, rest) = input.split_at(self.next_chunk(Mode::Absorb, input.len()));
+            self.ad(chunk, self.mode == Mode::Absorb);
+            self.mode = Mode::Absorb;
+            input = rest;
+        }
+        self
+    }
+
+    fn squeeze_unchecked(&mut self, mut output: &mut [u8]) -> &mut Self {
+        while !output.is_empty() {
+            let len = self.next_chunk(Mode::Squeeze, output.len());
+            let (chunk, rest) = output.split_at_mut(len);
+            self.prf(chunk, self.mode == Mode::Squeeze);
+            self.mode = Mode::Squeeze;
+            output = rest;
+        }
+        self
+    }
+
+    fn ratchet_unchecked(&mut self) -> &mut Self {
+        let frame = match self.frames.front() {
+            Some(Frame::Ratchet | Frame::Segment) => self.frames.pop_front(),
+            _ => None,
+        };
+        // 128-bit security level
+        self.ratchet(16, false);
+        self.mode = Mode::Start;
+        self.remaining = 0;
+        if let Some(Frame::Segment) = frame {
+            // the chaining digest is squeezed next
+            self.frames
+                .push_front(Frame::Op(Mode::Squeeze, b"segment".to_vec(), 32));
+        }
+        self
+    }
+}
+
+impl core::fmt::Debug for Strobe128 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Ensure that the state isn't accidentally logged
+        write!(f, "Strobe128: STATE OMITTED")
+    }
+}
@@ -2,7 +2,7 @@
 //! Despite internally we use the same permutation function,
 //! we build a duplex sponge in overwrite mode
 //! on the top of it using the `DuplexSponge` trait.
-use super::sponge::{DuplexSponge, Sponge};
+use super::sponge::{BatchPermutation, DuplexSponge, Sponge};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// A duplex sponge based on the permutation [`keccak::f1600`]
@@ -36,6 +36,8 @@ impl Sponge for AlignedKeccakState {
     }
 }
 
+impl BatchPermutation for AlignedKeccakState {}
+
 impl Default for AlignedKeccakState {
     fn default() -> Self {
         Self([0u8; Self::N])
@@ -20,7 +20,7 @@ use digest::{core_api::BlockSizeUser, typenum::Unsigned, Digest, FixedOutputRese
 use digest::crypto_common::generic_array::GenericArray;
 use zeroize::Zeroize;
 
-use super::DuplexHash;
+use super::{iv_from_bytes, DuplexHash};
 
 /// A Bridge to our sponge interface for legacy `Digest` implementations.
 #[derive(Clone)]
@@ -122,6 +122,10 @@ impl<D: BlockSizeUser + Digest + Clone + FixedOutputReset> DuplexHash<u8> for Di
         bridge
     }
 
+    fn iv_for_io_pattern(io_pattern: &[u8]) -> Option<[u8; 32]> {
+        Some(iv_from_bytes::<Self>(io_pattern))
+    }
+
     fn absorb_unchecked(&mut self, input: &[u8]) -> &mut Self {
         self.squeeze_end();
 
@@ -146,29 +150,29 @@ impl<D: BlockSizeUser + Digest + Clone + FixedOutputReset> DuplexHash<u8> for Di
         self
     }
 
-    fn squeeze_unchecked(&mut self, output: &mut [u8]) -> &mut Self {
+    fn squeeze_unchecked(&mut self, mut output: &mut [u8]) -> &mut Self {
+        // If Absorbing, ratchet
+        if self.mode == Mode::Absorb {
+            self.ratchet_unchecked();
+        }
         if self.mode == Mode::Start {
             self.mode = Mode::Squeeze(0);
             // create the prefix hash
             Digest::update(&mut self.hasher, Self::mask_squeeze());
             Digest::update(&mut self.hasher, &self.cv);
-            self.squeeze_unchecked(output)
-        // If Absorbing, ratchet
-        } else if self.mode == Mode::Absorb {
-            self.ratchet_unchecked();
-            self.squeeze_unchecked(output)
-        // If we have no more data to squeeze, return
-        } else if output.is_empty() {
-            self
+        }
         // If we still have some digest not yet squeezed
         // from previous invocations, write it to the output.
-        } else if !self.leftovers.is_empty() {
-            let len = usize::min(output.len(), self.leftovers.len());
-            output[..len].copy_from_slice(&self.leftovers[..len]);
-            self.leftovers.drain(..len);
-            self.squeeze_unchecked(&mut output[len..])
-        // Squeeze another digest
-        } else if let Mode::Squeeze(i) = self.mode {
+        let len = usize::min(output.len(), self.leftovers.len());
+        output[..len].copy_from_slice(&self.leftovers[..len]);
+        self.leftovers.drain(..len);
+        output = &mut output[len..];
+
+        // Squeeze more digests until the output is filled
+        while !output.is_empty() {
+            let Mode::Squeeze(i) = self.mode else {
+                unreachable!()
+            };
             // Add the squeeze mask, current digest, and index
             let mut output_hasher_prefix = self.hasher.clone();
             Digest::update(&mut output_hasher_prefix, i.to_be_bytes());
@@ -179,10 +183,9 @@ impl<D: BlockSizeUser + Digest + Clone + FixedOutputReset> DuplexHash<u8> for Di
             self.leftovers.extend_from_slice(&digest[chunk_len..]);
             // Update the state
             self.mode = Mode::Squeeze(i + 1);
-            self.squeeze_unchecked(&mut output[chunk_len..])
-        } else {
-            unreachable!()
+            output = &mut output[chunk_len..];
         }
+        self
     }
 }
 
@@ -3,30 +3,107 @@
 //! Hashes in nume operate over some native elements satisfying the trait [`Unit`] which, roughly speaking, requires
 //! the basic type to support cloning, size, read/write procedures, and secure deletion.
 //!
-//! Additionally, the module exports some utilities:
+//! Additionally, the module exports some utilities, each backend behind its own feature flag (except for [`Keccak`], always available):
 //! - [`hash::sponge::DuplexSponge`] allows to implement a [`crate::DuplexHash`] using a secure permutation function, specifying the rate `R` and the width `N`.
 //! This is done using the standard duplex sponge cosntruction in overwrite mode (cf. [Wikipedia](https://en.wikipedia.org/wiki/Sponge_function#Duplex_construction)).
-//! - [`hash::legacy::DigestBridge`] takes as input any hash function implementing the NIST API via the standard [`digest::Digest`] trait and makes it suitable for usage in duplex mode for continuous absorb/squeeze.
+//! - [`hash::shake::Shake128`] (feature `shake`) is a duplex over the Keccak-f\[1600\] permutation whose squeezes are exactly SHAKE128, for deployments requiring standardized constructions.
+//! - [`hash::legacy::DigestBridge`] (feature `legacy`) takes as input any hash function implementing the NIST API via the standard [`digest::Digest`] trait and makes it suitable for usage in duplex mode for continuous absorb/squeeze.
+//! - [`hash::AnyDuplexHash`] dispatches at runtime to one of the hashes above, selected by the domain separator of the IO Pattern.
+//! - [`hash::xof::DuplexDigest`] (feature `legacy`) goes the other way around, and exposes any [`crate::DuplexHash`] over bytes via the standard [`digest::Digest`] and [`digest::ExtendableOutput`] traits.
 
+/// Hash functions selected at runtime.
+pub mod any;
+/// Compatibility with [merlin](https://github.com/dalek-cryptography/merlin) (STROBE-128) transcripts.
+#[cfg(feature = "strobe")]
+pub mod compat;
 /// A wrapper around the Keccak-f\[1600\] permutation.
 pub mod keccak;
 /// Legacy hash functions support (e.g. [`sha2`](https://crates.io/crates/sha2), [`blake2`](https://crates.io/crates/blake2)).
+///
+/// [`legacy::DigestBridge`] is generic over any [`digest::Digest`], and the hash crate itself is brought by the caller.
+/// The `sha2` feature additionally enables the SHA-256 variant of [`AnyDuplexHash`].
+#[cfg(feature = "legacy")]
 pub mod legacy;
+/// A duplex hash compatible with SHAKE128.
+#[cfg(feature = "shake")]
+pub mod shake;
 /// Sponge functions.
 pub mod sponge;
+/// Duplex hashes as standard digests and extendable-output functions.
+#[cfg(feature = "legacy")]
+pub mod xof;
+
+/// Tests for the hash functions.
+#[cfg(all(test, feature = "strobe"))]
+mod tests;
+
+use core::mem::MaybeUninit;
+
+use crate::OpParseError;
 
 // Re-export the supported hash functions.
+pub use any::{AnyDuplexHash, HashBackend, IvHash};
 pub use keccak::Keccak;
+#[cfg(feature = "shake")]
+pub use shake::Shake128;
+
+/// Initializes every element of `output` with `next`, returning the initialized slice.
+pub(crate) fn init_with<U>(output: &mut [MaybeUninit<U>], mut next: impl FnMut() -> U) -> &mut [U] {
+    output.iter_mut().for_each(|x| {
+        x.write(next());
+    });
+    // SAFETY: all elements of `output` have been initialized above.
+    unsafe { assume_init(output) }
+}
+
+/// Returns the elements of `output`, once initialized.
+///
+/// # Safety
+///
+/// Every element of `output` must have been initialized.
+pub(crate) unsafe fn assume_init<U>(output: &mut [MaybeUninit<U>]) -> &mut [U] {
+    &mut *(output as *mut [MaybeUninit<U>] as *mut [U])
+}
+
+/// Derive an IV from `io_pattern` with the hash `H` over bytes: absorb it in the zero state of `H` (see [`Default`]),
+/// and squeeze 32 bytes.
+///
+/// This is the implementation of [`DuplexHash::iv_for_io_pattern`] for the hashes over bytes of this crate.
+pub fn iv_from_bytes<H: DuplexHash<u8>>(io_pattern: &[u8]) -> [u8; 32] {
+    let mut iv = [0u8; 32];
+    H::default()
+        .absorb_unchecked(io_pattern)
+        .squeeze_unchecked(&mut iv);
+    iv
+}
 
 /// Basic units over which a sponge operates.
 ///
 /// We require the units to have a precise size in memory, to be cloneable,
 /// and that we can zeroize them.
 pub trait Unit: Clone + Sized + zeroize::Zeroize {
+    /// The size in bytes of a unit on the wire, if all units are encoded with the same size.
+    const SIZE: Option<usize> = None;
+
     /// Write a bunch of units in the wire.
     fn write(bunch: &[Self], w: &mut impl std::io::Write) -> Result<(), std::io::Error>;
     /// Read a bunch of units from the wire
     fn read(r: &mut impl std::io::Read, bunch: &mut [Self]) -> Result<(), std::io::Error>;
+
+    /// The number of units absorbed for a message of `count` bytes (see [`BytePublic`](crate::BytePublic)).
+    ///
+    /// Used by [`ByteIOPattern::add_bytes`](crate::ByteIOPattern::add_bytes), so that byte messages are declared
+    /// in bytes whatever the unit of the sponge. Defaults to one unit per byte.
+    fn units_for_absorbed_bytes(count: usize) -> usize {
+        count
+    }
+
+    /// The number of units squeezed for `count` challenge bytes (see [`ByteChallenges`](crate::ByteChallenges)).
+    ///
+    /// Used by [`ByteIOPattern::challenge_bytes`](crate::ByteIOPattern::challenge_bytes). Defaults to one unit per byte.
+    fn units_for_squeezed_bytes(count: usize) -> usize {
+        count
+    }
 }
 
 /// A [`DuplexHash`] is an abstract interface for absorbing and squeezing data.
@@ -44,12 +121,62 @@ where
     /// Initializes a new sponge, setting up the state.
     fn new(iv: [u8; 32]) -> Self;
 
+    /// Initializes the sponge of a protocol, given its IO Pattern (as bytes), the `digest` of the IO Pattern, and its `iv`.
+    ///
+    /// The `digest` is the one cached by the IO Pattern (see [`IOPattern::digest`](crate::IOPattern::digest)),
+    /// so that implementations need not compute it again. `iv` equals it when starting the protocol,
+    /// and differs from it for an IV binding several IO Patterns together, or for the chaining digest of a segment.
+    ///
+    /// Defaults to [`DuplexHash::new`]. Hashes selected at runtime (see [`AnyDuplexHash`]) read their parameters
+    /// from the domain separator of the IO Pattern, and fail with [`OpParseErrorKind::UnknownBackend`](crate::OpParseErrorKind::UnknownBackend)
+    /// if it does not identify one.
+    /// When verifying a segment on its own (see [`IOPattern::to_arthur_segment`](crate::IOPattern::to_arthur_segment)),
+    /// the IO Pattern is the domain separator followed by the operations of the remaining segments,
+    /// and `iv` is the chaining digest.
+    fn new_for_io_pattern(
+        _io_pattern: &[u8],
+        _digest: [u8; 32],
+        iv: [u8; 32],
+    ) -> Result<Self, OpParseError> {
+        Ok(Self::new(iv))
+    }
+
+    /// Initializes a new sponge with the same parameters as `self`, e.g. for the next segment of a transcript.
+    ///
+    /// Defaults to [`DuplexHash::new`], as the parameters of most hashes are fixed by their type.
+    fn new_like(&self, iv: [u8; 32]) -> Self {
+        Self::new(iv)
+    }
+
+    /// Derives the IV of an IO Pattern (its canonical form, as bytes) with this hash itself,
+    /// for IO Patterns choosing [`IvHash::Protocol`].
+    ///
+    /// Defaults to `None`, for hashes that cannot absorb bytes (e.g. algebraic hashes over field elements).
+    /// Hashes over bytes absorb the IO Pattern in their zero state and squeeze the IV, see [`iv_from_bytes`].
+    fn iv_for_io_pattern(_io_pattern: &[u8]) -> Option<[u8; 32]> {
+        None
+    }
+
     /// Absorbs new elements in the sponge.
     fn absorb_unchecked(&mut self, input: &[U]) -> &mut Self;
 
     /// Squeezes out new elements.
     fn squeeze_unchecked(&mut self, output: &mut [U]) -> &mut Self;
 
+    /// Squeezes out new elements into an uninitialized buffer, returning the initialized slice.
+    ///
+    /// This is equivalent to [`DuplexHash::squeeze_unchecked`].
+    /// The default implementation initializes the buffer to [`Default::default`] first;
+    /// implementors can override it to write directly in `output`.
+    fn squeeze_uninit_unchecked<'a>(&mut self, output: &'a mut [MaybeUninit<U>]) -> &'a mut [U]
+    where
+        U: Default,
+    {
+        let output = init_with(output, U::default);
+        self.squeeze_unchecked(output);
+        output
+    }
+
     /// Ratcheting.
     ///
     /// This operations makes sure that different elements are processed in different blocks.
@@ -69,6 +196,8 @@ where
 }
 
 impl Unit for u8 {
+    const SIZE: Option<usize> = Some(1);
+
     fn write(bunch: &[Self], w: &mut impl std::io::Write) -> Result<(), std::io::Error> {
         w.write_all(bunch)
     }
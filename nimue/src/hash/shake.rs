@@ -0,0 +1,175 @@
+//! A duplex hash compatible with SHAKE128 (cf. [FIPS 202](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.202.pdf)).
+//!
+//! [`Keccak`](super::Keccak) builds an overwrite-mode duplex on the top of the Keccak-f\[1600\] permutation,
+//! with its own rate and IV placement.
+//! [`Shake128`] instead follows the standard duplex construction with SHAKE128's parameters:
+//! input is XORed into a rate of 168 bytes, and each transition from absorbing to squeezing
+//! pads the input with SHAKE's `pad10*1` (domain-separation suffix `0x1F`).
+//! Therefore:
+//! - the first challenge squeezed is exactly the output of SHAKE128 over the bytes absorbed so far, starting with the IV;
+//! - after each squeeze, the following absorbs are XORed into the state from the beginning of the rate,
+//!   as in the duplex construction of [BDPV11](https://keccak.team/files/SpongeDuplex.pdf).
+//!
+//! ```
+//! use nimue::hash::{DuplexHash, Shake128};
+//!
+//! // SHAKE128 of the empty string, from the NIST test vectors
+//! let mut out = [0u8; 8];
+//! Shake128::default().squeeze_unchecked(&mut out);
+//! assert_eq!(hex::encode(out), "7f9c2ba4e88f827d");
+//! ```
+use zeroize::Zeroize;
+
+use super::keccak::AlignedKeccakState;
+use super::sponge::Sponge;
+use super::{iv_from_bytes, DuplexHash};
+
+/// SHAKE128 rate, in bytes.
+const SHAKE128_R: usize = 168;
+/// SHAKE's domain-separation suffix, followed by the first bit of `pad10*1`.
+const SHAKE_SUFFIX: u8 = 0x1f;
+
+/// A duplex hash over bytes, whose first squeeze is SHAKE128 of the IV and the bytes absorbed.
+#[derive(Clone, Zeroize)]
+pub struct Shake128 {
+    state: AlignedKeccakState,
+    /// The position within the rate of the next byte absorbed or squeezed.
+    pos: usize,
+    #[zeroize(skip)]
+    mode: Mode,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Absorb,
+    Squeeze,
+}
+
+impl Shake128 {
+    /// Pad the input absorbed so far and permute, starting a squeeze.
+    fn pad(&mut self) {
+        if self.pos == SHAKE128_R {
+            self.state.permute();
+            self.pos = 0;
+        }
+        let state = self.state.as_mut();
+        state[self.pos] ^= SHAKE_SUFFIX;
+        state[SHAKE128_R - 1] ^= 0x80;
+        self.state.permute();
+        self.pos = 0;
+        self.mode = Mode::Squeeze;
+    }
+}
+
+impl Default for Shake128 {
+    /// A fresh SHAKE128 instance, with no IV absorbed.
+    fn default() -> Self {
+        Self {
+            state: AlignedKeccakState::default(),
+            pos: 0,
+            mode: Mode::Absorb,
+        }
+    }
+}
+
+impl DuplexHash<u8> for Shake128 {
+    /// Absorb the IV, as the first 32 bytes of the SHAKE128 input.
+    fn new(iv: [u8; 32]) -> Self {
+        let mut shake = Self::default();
+        shake.absorb_unchecked(&iv);
+        shake
+    }
+
+    fn iv_for_io_pattern(io_pattern: &[u8]) -> Option<[u8; 32]> {
+        Some(iv_from_bytes::<Self>(io_pattern))
+    }
+
+    fn absorb_unchecked(&mut self, input: &[u8]) -> &mut Self {
+        if self.mode == Mode::Squeeze {
+            self.mode = Mode::Absorb;
+            self.pos = 0;
+        }
+        for byte in input {
+            if self.pos == SHAKE128_R {
+                self.state.permute();
+                self.pos = 0;
+            }
+            self.state.as_mut()[self.pos] ^= byte;
+            self.pos += 1;
+        }
+        self
+    }
+
+    fn squeeze_unchecked(&mut self, output: &mut [u8]) -> &mut Self {
+        if self.mode == Mode::Absorb {
+            self.pad();
+        }
+        for byte in output {
+            if self.pos == SHAKE128_R {
+                self.state.permute();
+                self.pos = 0;
+            }
+            *byte = self.state.as_ref()[self.pos];
+            self.pos += 1;
+        }
+        self
+    }
+
+    /// End the current absorb (padding it) or squeeze, permute, and zero the rate.
+    fn ratchet_unchecked(&mut self) -> &mut Self {
+        if self.mode == Mode::Absorb {
+            self.pad();
+        }
+        self.state.permute();
+        self.state.as_mut()[..SHAKE128_R].zeroize();
+        self.pos = 0;
+        self.mode = Mode::Absorb;
+        self
+    }
+}
+
+impl core::fmt::Debug for Shake128 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Ensure that the state isn't accidentally logged
+        write!(f, "Shake128: STATE OMITTED")
+    }
+}
+
+#[test]
+fn test_shake128_equivalence() {
+    use sha3::digest::{ExtendableOutput, Update, XofReader};
+
+    // absorb lengths around the rate, in several chunks
+    for len in [0, 1, 167, 168, 169, 500] {
+        let input = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+        let mut expected = [0u8; 400];
+        let mut shake = sha3::Shake128::default();
+        shake.update(&[0x42; 32]);
+        shake.update(&input);
+        shake.finalize_xof().read(&mut expected);
+
+        let mut duplex = Shake128::new([0x42; 32]);
+        let (left, right) = input.split_at(len / 3);
+        duplex.absorb_unchecked(left).absorb_unchecked(right);
+        let mut got = [0u8; 400];
+        let (first, second) = got.split_at_mut(168);
+        duplex.squeeze_unchecked(first).squeeze_unchecked(second);
+        assert_eq!(got, expected);
+    }
+}
+
+#[test]
+fn test_shake128_nist_vectors() {
+    // SHAKE128 of the empty string, and of 200 bytes 0xA3
+    let mut out = [0u8; 32];
+    Shake128::default().squeeze_unchecked(&mut out);
+    assert_eq!(
+        hex::encode(out),
+        "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+    );
+    let mut out = [0u8; 16];
+    Shake128::default()
+        .absorb_unchecked(&[0xa3; 200])
+        .squeeze_unchecked(&mut out);
+    assert_eq!(hex::encode(out), "131ab8d2b594946b9c81333f9bb6e0ce");
+}
@@ -1,4 +1,6 @@
-use super::{DuplexHash, Unit};
+use core::mem::MaybeUninit;
+
+use super::{assume_init, DuplexHash, Unit};
 
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
@@ -17,7 +19,7 @@ use zeroize::{Zeroize, ZeroizeOnDrop};
 /// as well as constraints in the final zero-knowledge proof implementing the hash function.
 /// - The [`std::default::Default`] implementation *MUST* initialize the state to zero.
 /// - The [`Sponge::new`] method should initialize the sponge writing the entropy provided in the `iv` in the last
-///     [`Sponge::N`]-[`Sponge::R`] elements of the state.
+///   [`Sponge::N`]-[`Sponge::R`] elements of the state.
 pub trait Sponge: Zeroize + Default + Clone + AsRef<[Self::U]> + AsMut<[Self::U]> {
     /// The basic unit over which the sponge operates.
     type U: Unit;
@@ -36,63 +38,264 @@ pub trait Sponge: Zeroize + Default + Clone + AsRef<[Self::U]> + AsMut<[Self::U]
     fn permute(&mut self);
 }
 
+/// A permutation that can be applied to many sponge states at once.
+///
+/// Provers hashing many independent states (e.g., the nodes of a Merkle tree layer)
+/// can hand them over as a single batch, that implementors may offload to a co-processor (e.g. a GPU)
+/// instead of permuting them one by one.
+/// The transcript itself is still processed sequentially by nimue.
+/// Batches are formed by [`DuplexSponge::squeeze_many`].
+///
+/// The default implementation permutes each state in turn.
+///
+/// ```
+/// # use nimue::hash::sponge::{BatchPermutation, Sponge};
+/// # use nimue::hash::keccak::AlignedKeccakState;
+///
+/// let mut states = vec![AlignedKeccakState::new([1; 32]), AlignedKeccakState::new([2; 32])];
+/// let mut expected = states.clone();
+/// expected.iter_mut().for_each(Sponge::permute);
+///
+/// AlignedKeccakState::permute_many(&mut states);
+/// assert_eq!(states[0].as_ref(), expected[0].as_ref());
+/// assert_eq!(states[1].as_ref(), expected[1].as_ref());
+/// ```
+pub trait BatchPermutation: Sponge {
+    /// Permute each state in `states`.
+    ///
+    /// The result must be the same as calling [`Sponge::permute`] on each state.
+    fn permute_many(states: &mut [Self]) {
+        states.iter_mut().for_each(Self::permute)
+    }
+}
+
 /// A cryptographic sponge.
+///
+/// Cloning a sponge copies its state (e.g. 200 bytes for [`Keccak`](crate::hash::Keccak)) without allocating,
+/// so that snapshots of a sponge, e.g. after absorbing a statement, can be cheaply cloned for each transcript.
 #[derive(Clone, Default, Zeroize, ZeroizeOnDrop)]
 pub struct DuplexSponge<C: Sponge> {
     sponge: C,
     absorb_pos: usize,
     squeeze_pos: usize,
+    permutations: usize,
+}
+
+impl<C: Sponge> DuplexSponge<C> {
+    /// Checks on the parameters of the sponge, evaluated at compile time when the duplex sponge is instantiated.
+    ///
+    /// ```compile_fail
+    /// # use nimue::hash::{keccak::AlignedKeccakState, sponge::{DuplexSponge, Sponge}, DuplexHash};
+    /// #[derive(Clone, Default, zeroize::Zeroize)]
+    /// struct NoCapacity(AlignedKeccakState);
+    /// # impl AsRef<[u8]> for NoCapacity { fn as_ref(&self) -> &[u8] { self.0.as_ref() } }
+    /// # impl AsMut<[u8]> for NoCapacity { fn as_mut(&mut self) -> &mut [u8] { self.0.as_mut() } }
+    ///
+    /// impl Sponge for NoCapacity {
+    ///     type U = u8;
+    ///     const N: usize = 200;
+    ///     const R: usize = 200;
+    ///     // ...
+    /// #   fn new(iv: [u8; 32]) -> Self { Self(AlignedKeccakState::new(iv)) }
+    /// #   fn permute(&mut self) { self.0.permute() }
+    /// }
+    ///
+    /// // error: Capacity of the sponge should be > 0.
+    /// let sponge = DuplexSponge::<NoCapacity>::new([0; 32]);
+    /// ```
+    const PARAMETERS_CHECK: () = {
+        assert!(C::R > 0, "Rate of the sponge should be > 0.");
+        assert!(C::N > C::R, "Capacity of the sponge should be > 0.");
+    };
+
+    /// The number of permutations performed since initialization.
+    ///
+    /// The count carries over the re-initialization at segment boundaries (see [`DuplexHash::new_like`]).
+    ///
+    /// Useful to check the static estimate of [`IOPattern::permutation_count`](crate::IOPattern::permutation_count).
+    pub fn permutations(&self) -> usize {
+        self.permutations
+    }
+
+    fn permute(&mut self) {
+        self.sponge.permute();
+        self.permutations += 1;
+    }
+}
+
+impl<C: Sponge> DuplexSponge<C> {
+    /// Squeezes at most `len` units out of the rate, permuting the state when it is exhausted.
+    ///
+    /// Squeezes are performed one chunk of the rate at a time, in a loop rather than recursively,
+    /// so that long squeezes do not overflow the stack.
+    fn squeeze_chunk(&mut self, len: usize) -> &[C::U] {
+        if self.squeeze_pos == C::R {
+            self.squeeze_pos = 0;
+            self.absorb_pos = 0;
+            self.permute();
+        }
+        let start = self.squeeze_pos;
+        let chunk_len = usize::min(len, C::R - start);
+        self.squeeze_pos += chunk_len;
+        &self.sponge.as_ref()[start..start + chunk_len]
+    }
+}
+
+impl<C: BatchPermutation> DuplexSponge<C> {
+    /// Squeeze `outputs[i]` from `sponges[i]`, for each sponge, permuting their states in batches
+    /// with [`BatchPermutation::permute_many`].
+    ///
+    /// The result is the same as squeezing each sponge in turn with [`DuplexHash::squeeze_unchecked`].
+    ///
+    /// ```
+    /// # use nimue::hash::{keccak::AlignedKeccakState, sponge::DuplexSponge, DuplexHash};
+    /// let mut sponges = vec![DuplexSponge::<AlignedKeccakState>::new([1; 32]); 4];
+    /// sponges.iter_mut().enumerate().for_each(|(i, s)| _ = s.absorb_unchecked(&[i as u8]));
+    /// let mut expected = sponges.clone();
+    ///
+    /// let mut outputs = vec![[0u8; 300]; 4];
+    /// let mut output_refs = outputs.iter_mut().map(|o| &mut o[..]).collect::<Vec<_>>();
+    /// DuplexSponge::squeeze_many(&mut sponges, &mut output_refs);
+    /// for (sponge, output) in expected.iter_mut().zip(&outputs) {
+    ///     let mut out = [0u8; 300];
+    ///     sponge.squeeze_unchecked(&mut out);
+    ///     assert_eq!(&out, output);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sponges` and `outputs` have different lengths.
+    pub fn squeeze_many(sponges: &mut [Self], outputs: &mut [&mut [C::U]]) {
+        assert_eq!(
+            sponges.len(),
+            outputs.len(),
+            "one output is required for each sponge"
+        );
+        let mut positions = vec![0; sponges.len()];
+        let mut batch = Vec::new();
+        loop {
+            let pending = (0..sponges.len())
+                .filter(|&i| positions[i] < outputs[i].len())
+                .collect::<Vec<_>>();
+            if pending.is_empty() {
+                return;
+            }
+
+            let to_permute = pending
+                .iter()
+                .copied()
+                .filter(|&i| sponges[i].squeeze_pos == C::R)
+                .collect::<Vec<_>>();
+            batch.extend(
+                to_permute
+                    .iter()
+                    .map(|&i| core::mem::take(&mut sponges[i].sponge)),
+            );
+            C::permute_many(&mut batch);
+            for (&i, state) in to_permute.iter().zip(batch.drain(..)) {
+                let sponge = &mut sponges[i];
+                sponge.sponge = state;
+                sponge.permutations += 1;
+                sponge.squeeze_pos = 0;
+                sponge.absorb_pos = 0;
+            }
+
+            for i in pending {
+                let sponge = &mut sponges[i];
+                let chunk_len =
+                    usize::min(outputs[i].len() - positions[i], C::R - sponge.squeeze_pos);
+                outputs[i][positions[i]..positions[i] + chunk_len].clone_from_slice(
+                    &sponge.sponge.as_ref()[sponge.squeeze_pos..sponge.squeeze_pos + chunk_len],
+                );
+                sponge.squeeze_pos += chunk_len;
+                positions[i] += chunk_len;
+            }
+        }
+    }
 }
 
 impl<U: Unit, C: Sponge<U = U>> DuplexHash<U> for DuplexSponge<C> {
     fn new(iv: [u8; 32]) -> Self {
-        assert!(C::N > C::R, "Capacity of the sponge should be > 0.");
+        let () = Self::PARAMETERS_CHECK;
         Self {
             sponge: C::new(iv),
             absorb_pos: 0,
             squeeze_pos: C::R,
+            permutations: 0,
         }
     }
 
+    /// Initialize a new sponge with `iv`, keeping the count of permutations of `self`.
+    fn new_like(&self, iv: [u8; 32]) -> Self {
+        let mut sponge = Self::new(iv);
+        sponge.permutations = self.permutations;
+        sponge
+    }
+
+    /// Over units of one byte on the wire (e.g. [`Keccak`](crate::hash::Keccak)), absorb the IO Pattern as it is,
+    /// as in [`iv_from_bytes`](crate::hash::iv_from_bytes).
+    fn iv_for_io_pattern(io_pattern: &[u8]) -> Option<[u8; 32]> {
+        if U::SIZE != Some(1) {
+            return None;
+        }
+        let zero = C::default().as_ref().first()?.clone();
+        let mut units = vec![zero; io_pattern.len().max(32)];
+        U::read(&mut &io_pattern[..], &mut units[..io_pattern.len()]).ok()?;
+        let mut sponge = Self::default();
+        sponge.absorb_unchecked(&units[..io_pattern.len()]);
+        sponge.squeeze_unchecked(&mut units[..32]);
+        let mut iv = Vec::with_capacity(32);
+        U::write(&units[..32], &mut iv).ok()?;
+        iv.try_into().ok()
+    }
+
     fn absorb_unchecked(&mut self, mut input: &[U]) -> &mut Self {
+        // fill the rest of the current block, then overwrite whole blocks without checking positions on each step.
+        // For `Copy` units (e.g. bytes), `clone_from_slice` is a `memcpy`.
+        let chunk_len = usize::min(input.len(), C::R - self.absorb_pos);
+        let (chunk, rest) = input.split_at(chunk_len);
+        self.sponge.as_mut()[self.absorb_pos..self.absorb_pos + chunk_len].clone_from_slice(chunk);
+        self.absorb_pos += chunk_len;
+        input = rest;
+
         while !input.is_empty() {
-            if self.absorb_pos == C::R {
-                self.sponge.permute();
-                self.absorb_pos = 0;
-            } else {
-                assert!(!input.is_empty() && self.absorb_pos < C::R);
-                let chunk_len = usize::min(input.len(), C::R - self.absorb_pos);
-                let (chunk, rest) = input.split_at(chunk_len);
-
-                self.sponge.as_mut()[self.absorb_pos..self.absorb_pos + chunk_len]
-                    .clone_from_slice(chunk);
-                self.absorb_pos += chunk_len;
-                input = rest;
-            }
+            self.permute();
+            let chunk_len = usize::min(input.len(), C::R);
+            let (chunk, rest) = input.split_at(chunk_len);
+            self.sponge.as_mut()[..chunk_len].clone_from_slice(chunk);
+            self.absorb_pos = chunk_len;
+            input = rest;
         }
         self.squeeze_pos = C::R;
         self
     }
 
     fn squeeze_unchecked(&mut self, output: &mut [U]) -> &mut Self {
-        if output.is_empty() {
-            return self;
+        // For `Copy` units (e.g. bytes), `clone_from_slice` is a `memcpy`.
+        let mut pos = 0;
+        while pos < output.len() {
+            let chunk = self.squeeze_chunk(output.len() - pos);
+            output[pos..pos + chunk.len()].clone_from_slice(chunk);
+            pos += chunk.len();
         }
+        self
+    }
 
-        if self.squeeze_pos == C::R {
-            self.squeeze_pos = 0;
-            self.absorb_pos = 0;
-            self.sponge.permute();
+    fn squeeze_uninit_unchecked<'a>(&mut self, output: &'a mut [MaybeUninit<U>]) -> &'a mut [U]
+    where
+        U: Default,
+    {
+        let mut pos = 0;
+        while pos < output.len() {
+            let chunk = self.squeeze_chunk(output.len() - pos);
+            for (o, x) in output[pos..].iter_mut().zip(chunk) {
+                o.write(x.clone());
+            }
+            pos += chunk.len();
         }
-
-        assert!(self.squeeze_pos < C::R && !output.is_empty());
-        let chunk_len = usize::min(output.len(), C::R - self.squeeze_pos);
-        let (output, rest) = output.split_at_mut(chunk_len);
-        output.clone_from_slice(
-            &self.sponge.as_ref()[self.squeeze_pos..self.squeeze_pos + chunk_len],
-        );
-        self.squeeze_pos += chunk_len;
-        self.squeeze_unchecked(rest)
+        // SAFETY: all elements of `output` have been initialized above.
+        unsafe { assume_init(output) }
     }
 
     // fn tag(self) -> &'static [Self::U] {
@@ -100,7 +303,7 @@ impl<U: Unit, C: Sponge<U = U>> DuplexHash<U> for DuplexSponge<C> {
     // }
 
     fn ratchet_unchecked(&mut self) -> &mut Self {
-        self.sponge.permute();
+        self.permute();
         // set to zero the state up to rate
         // XXX. is the compiler really going to do this?
         self.sponge.as_mut()[0..C::R]
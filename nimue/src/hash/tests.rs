@@ -0,0 +1,97 @@
+use super::compat::Strobe128;
+use crate::{ByteChallenges, ByteReader, ByteWriter, IOPattern, Safe};
+
+#[test]
+fn test_merlin_equivalence() {
+    let mut merlin_transcript = merlin::Transcript::new(b"test protocol");
+    let mut strobe = Strobe128::default();
+    strobe.append_message(b"dom-sep", b"test protocol");
+
+    merlin_transcript.append_message(b"some label", b"some data");
+    strobe.append_message(b"some label", b"some data");
+    // long enough to span multiple blocks
+    merlin_transcript.append_message(b"big label", &[0x42; 1000]);
+    strobe.append_message(b"big label", &[0x42; 1000]);
+
+    let mut expected = [0u8; 500];
+    let mut got = [0u8; 500];
+    merlin_transcript.challenge_bytes(b"challenge", &mut expected);
+    strobe.challenge_bytes(b"challenge", &mut got);
+    assert_eq!(expected, got);
+
+    merlin_transcript.append_message(b"after", b"challenge");
+    strobe.append_message(b"after", b"challenge");
+    merlin_transcript.challenge_bytes(b"challenge", &mut expected[..32]);
+    strobe.challenge_bytes(b"challenge", &mut got[..32]);
+    assert_eq!(expected, got);
+}
+
+/// A protocol run through [`crate::Merlin`] and [`crate::Arthur`] yields the same challenges as with `merlin::Transcript`.
+#[test]
+fn test_duplex_hash_merlin_kat() {
+    let io = IOPattern::<Strobe128>::new("test protocol")
+        .absorb(5, "greeting")
+        .absorb(1000, "big message")
+        .squeeze(500, "challenge")
+        .marker("phase")
+        .absorb(7, "after")
+        .squeeze(16, "first")
+        .squeeze(32, "second");
+
+    let mut transcript = merlin::Transcript::new(b"test protocol");
+    let mut expected = [0u8; 500];
+    transcript.append_message(b"greeting", b"hello");
+    transcript.append_message(b"big message", &[0x42; 1000]);
+    transcript.challenge_bytes(b"challenge", &mut expected);
+    transcript.append_message(b"phase", &[Safe::<Strobe128>::marker_unit("phase")]);
+    transcript.append_message(b"after", b"message");
+    let mut first = [0u8; 16];
+    let mut second = [0u8; 32];
+    transcript.challenge_bytes(b"first", &mut first);
+    transcript.challenge_bytes(b"second", &mut second);
+
+    let mut merlin = io.to_merlin();
+    // the first message spans two operations, and the framing is independent of the chunking
+    merlin
+        .add_bytes(&[b"hello".as_slice(), &[0x42; 500]].concat())
+        .unwrap();
+    merlin.add_bytes(&[0x42; 500]).unwrap();
+    assert_eq!(merlin.challenge_bytes::<500>().unwrap(), expected);
+    merlin.marker("phase").unwrap();
+    merlin.add_bytes(b"message").unwrap();
+    // a single squeeze spanning two operations
+    assert_eq!(
+        merlin.challenge_bytes::<48>().unwrap(),
+        [first.as_slice(), &second].concat()[..]
+    );
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.next_bytes::<1005>().unwrap();
+    assert_eq!(arthur.challenge_bytes::<500>().unwrap(), expected);
+    arthur.marker("phase").unwrap();
+    assert_eq!(&arthur.next_bytes::<7>().unwrap(), b"message");
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), first);
+    assert_eq!(arthur.challenge_bytes::<32>().unwrap(), second);
+}
+
+/// Segments can be verified on their own, given the chaining digest.
+#[test]
+fn test_duplex_hash_merlin_segments() {
+    let io = IOPattern::<Strobe128>::new("test protocol")
+        .absorb(1, "first")
+        .segment()
+        .squeeze(16, "receipt")
+        .absorb(1, "second")
+        .squeeze(16, "final");
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[0x42]).unwrap();
+    let (_, digest) = merlin.seal_segment().unwrap();
+    let receipt = merlin.challenge_bytes::<16>().unwrap();
+    merlin.add_bytes(&[0x43]).unwrap();
+    let last = merlin.challenge_bytes::<16>().unwrap();
+
+    let mut arthur = io.to_arthur_segment(1, &digest, &[0x43]).unwrap();
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), receipt);
+    assert_eq!(arthur.next_bytes().unwrap(), [0x43]);
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), last);
+}
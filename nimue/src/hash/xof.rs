@@ -0,0 +1,132 @@
+//! The reverse of [`DigestBridge`](super::legacy::DigestBridge):
+//! a [`DuplexHash`] over bytes used as a standard [`digest::Digest`] or extendable-output function.
+//!
+//! Input is absorbed; on finalization, the input length in bytes is absorbed as a 64-bit little-endian integer,
+//! the sponge is ratcheted, and the output is squeezed.
+//! Absorbing the length makes the encoding injective: since the sponge pads the rate with zeros,
+//! messages differing only by trailing zero bytes (say, `a` and `a\0`) would otherwise hash to the same value.
+//! The 32-byte digest is a prefix of the extendable output.
+//!
+//! To bind the digest to a protocol transcript, squeeze a 32-byte challenge and use it as IV:
+//!
+//! ```
+//! # use nimue::*;
+//! use digest::{Digest, ExtendableOutput, Update, XofReader};
+//! use nimue::hash::xof::DuplexDigest;
+//!
+//! let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").challenge_bytes(32, "hash-to-curve iv");
+//! let mut merlin = io.to_merlin();
+//! merlin.add_bytes(&[0x42]).unwrap();
+//! let iv = merlin.challenge_bytes::<32>().unwrap();
+//!
+//! let digest = DuplexDigest::<DefaultHash>::new_with_iv(iv).chain_update(b"message").finalize();
+//! let mut xof = DuplexDigest::<DefaultHash>::new_with_iv(iv).chain(b"message").finalize_xof();
+//! let mut output = [0u8; 64];
+//! xof.read(&mut output);
+//! assert_eq!(&output[..32], &digest[..]);
+//! ```
+use digest::consts::U32;
+use digest::{
+    ExtendableOutput, FixedOutput, FixedOutputReset, HashMarker, Output, OutputSizeUser, Reset,
+    Update, XofReader,
+};
+
+use super::DuplexHash;
+
+/// A [`digest::Digest`] and [`digest::ExtendableOutput`] backed by the duplex sponge `H`.
+#[derive(Clone)]
+pub struct DuplexDigest<H: DuplexHash<u8>> {
+    iv: [u8; 32],
+    sponge: H,
+    /// Bytes absorbed so far.
+    len: u64,
+}
+
+/// The [`XofReader`] of a [`DuplexDigest`], squeezing the output from the sponge.
+#[derive(Clone)]
+pub struct DuplexXofReader<H: DuplexHash<u8>>(H);
+
+impl<H: DuplexHash<u8>> DuplexDigest<H> {
+    /// Create a new digest, initializing the sponge with `iv`.
+    pub fn new_with_iv(iv: [u8; 32]) -> Self {
+        Self {
+            iv,
+            sponge: H::new(iv),
+            len: 0,
+        }
+    }
+
+    /// Absorb the input length and ratchet, before squeezing the output.
+    fn finish(&mut self) -> &mut H {
+        self.sponge
+            .absorb_unchecked(&self.len.to_le_bytes())
+            .ratchet_unchecked()
+    }
+}
+
+impl<H: DuplexHash<u8>> Default for DuplexDigest<H> {
+    /// Create a new digest with the all-zero IV.
+    fn default() -> Self {
+        Self::new_with_iv([0u8; 32])
+    }
+}
+
+impl<H: DuplexHash<u8>> HashMarker for DuplexDigest<H> {}
+
+impl<H: DuplexHash<u8>> Update for DuplexDigest<H> {
+    fn update(&mut self, data: &[u8]) {
+        self.sponge.absorb_unchecked(data);
+        self.len += data.len() as u64;
+    }
+}
+
+impl<H: DuplexHash<u8>> OutputSizeUser for DuplexDigest<H> {
+    type OutputSize = U32;
+}
+
+impl<H: DuplexHash<u8>> FixedOutput for DuplexDigest<H> {
+    fn finalize_into(mut self, out: &mut Output<Self>) {
+        self.finish().squeeze_unchecked(out);
+    }
+}
+
+impl<H: DuplexHash<u8>> Reset for DuplexDigest<H> {
+    fn reset(&mut self) {
+        self.sponge.zeroize();
+        self.sponge = H::new(self.iv);
+        self.len = 0;
+    }
+}
+
+impl<H: DuplexHash<u8>> FixedOutputReset for DuplexDigest<H> {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        self.finish().squeeze_unchecked(out);
+        Reset::reset(self);
+    }
+}
+
+impl<H: DuplexHash<u8>> ExtendableOutput for DuplexDigest<H> {
+    type Reader = DuplexXofReader<H>;
+
+    fn finalize_xof(mut self) -> Self::Reader {
+        DuplexXofReader(self.finish().clone())
+    }
+}
+
+impl<H: DuplexHash<u8>> XofReader for DuplexXofReader<H> {
+    fn read(&mut self, buffer: &mut [u8]) {
+        self.0.squeeze_unchecked(buffer);
+    }
+}
+
+impl<H: DuplexHash<u8>> Drop for DuplexDigest<H> {
+    fn drop(&mut self) {
+        self.sponge.zeroize();
+    }
+}
+
+impl<H: DuplexHash<u8>> Drop for DuplexXofReader<H> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
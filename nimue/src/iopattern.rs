@@ -3,12 +3,15 @@
 // which was a pain to use
 // (plain integers don't cast to NonZeroUsize automatically)
 
-use crate::ByteIOPattern;
+use crate::{ByteIOPattern, Safe};
+use core::ops::Range;
 use std::collections::VecDeque;
 use std::marker::PhantomData;
+use std::sync::OnceLock;
 
-use super::errors::IOPatternError;
-use super::hash::{DuplexHash, Unit};
+use super::errors::{IOPatternError, OpParseError, OpParseErrorKind, ProofError};
+use super::hash::sponge::{DuplexSponge, Sponge};
+use super::hash::{AnyDuplexHash, DuplexHash, HashBackend, IvHash, Unit};
 
 /// This is the separator between operations in the IO Pattern
 /// and as such is the only forbidden character in labels.
@@ -26,13 +29,18 @@ const SEP_BYTE: &str = "\0";
 /// The letter `A` indicates the absorption of a public input (an `ABSORB`), while the letter `S` indicates the squeezing (a `SQUEEZE`) of a challenge.
 /// The letter `R` indicates a ratcheting operation: ratcheting means invoking the hash function even on an incomplete block.
 /// It provides forward secrecy and allows it to start from a clean rate.
+/// The letter `C` indicates the end of a segment: the hash function is ratcheted and re-initialized from a chaining digest.
 /// After the operation type, is the number of elements in base 10 that are being absorbed/squeezed.
 /// Then, follows the label associated with the element being absorbed/squeezed. This often comes from the underlying description of the protocol. The label cannot start with a digit or contain the NULL byte.
+/// The domain separator may be followed by the tag `I`, recording the hash deriving the IV (see [`IOPattern::with_iv_hash`]):
+/// as the domain separator cannot contain the NULL byte, no domain separator can be mistaken for it.
 ///
 /// ## Guarantees
 ///
 /// The struct [`IOPattern`] guarantees the creation of a valid IO Pattern string, whose lengths are coherent with the types described in the protocol. No information about the types themselves is stored in an IO Pattern.
 /// This means that [`Merlin`][`crate::Merlin`] or [`Arthur`][`crate::Arthur`] instances can generate successfully a protocol transcript respecting the length constraint but not the types. See [issue #6](https://github.com/arkworks-rs/nimue/issues/6) for a discussion on the topic.
+///
+/// IO Patterns are `Send + Sync`, and can be built once and shared across threads (see [`static_io_pattern!`](crate::static_io_pattern)).
 
 #[derive(Clone)]
 pub struct IOPattern<H = crate::DefaultHash, U = u8>
@@ -41,7 +49,76 @@ where
     H: DuplexHash<U>,
 {
     io: String,
-    _hash: PhantomData<(H, U)>,
+    /// The digest of `io`, computed lazily on first use.
+    digest: OnceLock<[u8; 32]>,
+    /// The IO Pattern holds no hash state: it is `Send + Sync` whatever `H` and `U`.
+    _hash: PhantomData<fn() -> (H, U)>,
+}
+
+/// A labeled range of units within an operation of the IO Pattern.
+///
+/// Consecutive absorbs (resp. squeezes) are merged into a single operation of the sponge;
+/// spans record which units of the merged operation belong to which label (see [`IOPattern::label_spans`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabelSpan {
+    /// The label, as declared in the IO Pattern.
+    pub label: String,
+    /// The units of the operation under this label.
+    pub range: Range<usize>,
+}
+
+/// Check that `label` can be parsed back from the IO Pattern.
+///
+/// # Panics
+///
+/// If the label contains the separator byte, or starts with a digit (which would be read as part of the count).
+fn check_label(label: &str) {
+    assert!(
+        !label.contains(SEP_BYTE),
+        "Label cannot contain the separator BYTE."
+    );
+    assert!(
+        match label.chars().next() {
+            Some(char) => !char.is_ascii_digit(),
+            None => true,
+        },
+        "Label cannot start with a digit."
+    );
+}
+
+/// The tag recording the hash deriving the IV, right after the domain separator.
+const IV_TAG: u8 = b'I';
+
+/// Split the header of `io_pattern`: its domain separator, and the identifier of the hash deriving the IV, if recorded.
+fn split_header(io_pattern: &[u8]) -> (&[u8], Option<&[u8]>) {
+    let mut parts = io_pattern.splitn(3, |&b| b == SEP_BYTE.as_bytes()[0]);
+    let domsep = parts.next().unwrap_or_default();
+    let id = parts.next().and_then(|tag| tag.strip_prefix(&[IV_TAG]));
+    (domsep, id)
+}
+
+/// The length of the header of `io_pattern`: its domain separator, followed by the tag of the IV hash if any.
+fn header_len(io_pattern: &[u8]) -> usize {
+    match split_header(io_pattern) {
+        (domsep, Some(id)) => domsep.len() + id.len() + 2,
+        (domsep, None) => domsep.len(),
+    }
+}
+
+/// The hash deriving the IV recorded in the header of `io_pattern`, see [`IOPattern::with_iv_hash`].
+fn parse_iv_hash(io_pattern: &[u8]) -> Result<IvHash, OpParseError> {
+    let (domsep, id) = split_header(io_pattern);
+    let Some(id) = id else {
+        return Ok(IvHash::Backend(HashBackend::Keccak));
+    };
+    core::str::from_utf8(id)
+        .ok()
+        .and_then(IvHash::from_id)
+        .ok_or(OpParseError {
+            // the position of the tag
+            position: domsep.len() + 1,
+            kind: OpParseErrorKind::UnknownBackend,
+        })
 }
 
 /// Sponge operations.
@@ -62,28 +139,80 @@ pub(crate) enum Op {
     /// This allows for a more efficient preprocessing, and for removal of
     /// private information stored in the rate.
     Ratchet,
+    /// Indicates the end of a segment of the protocol transcript.
+    ///
+    /// In a tag, a segment boundary is indicated with 'C'.
+    /// The sponge is ratcheted, and re-initialized from a 32-byte chaining digest,
+    /// so that the next segment can be verified given the digest alone.
+    Segment,
+    /// Indicates a phase boundary.
+    ///
+    /// In a tag, a marker is indicated with 'M', followed by its label.
+    /// The sponge absorbs a single unit derived from the label, and no data.
+    Marker,
 }
 
+/// An operation as declared in the IO Pattern: its position in the IO Pattern, the operation, and its label.
+type DeclaredOp<'a> = (usize, Op, &'a [u8]);
+
 impl Op {
     /// Create a new OP from the portion of a tag.
-    fn new(id: char, count: Option<usize>) -> Result<Self, IOPatternError> {
+    ///
+    /// Absorbs and squeezes of zero elements are no-ops, and return `None`.
+    fn new(id: u8, count: Option<usize>) -> Result<Option<Self>, OpParseErrorKind> {
         match (id, count) {
-            ('A', Some(c)) if c > 0 => Ok(Op::Absorb(c)),
-            ('R', None) | ('R', Some(0)) => Ok(Op::Ratchet),
-            ('S', Some(c)) if c > 0 => Ok(Op::Squeeze(c)),
-            _ => Err("Invalid tag".into()),
+            (b'A' | b'S', Some(0)) => Ok(None),
+            (b'A', Some(c)) => Ok(Some(Op::Absorb(c))),
+            (b'R', None) | (b'R', Some(0)) => Ok(Some(Op::Ratchet)),
+            (b'S', Some(c)) => Ok(Some(Op::Squeeze(c))),
+            (b'C', None) | (b'C', Some(0)) => Ok(Some(Op::Segment)),
+            (b'M', None) => Ok(Some(Op::Marker)),
+            (b'A' | b'R' | b'S' | b'C' | b'M', _) => Err(OpParseErrorKind::InvalidLength),
+            (id, _) => Err(OpParseErrorKind::UnknownOp(id)),
         }
     }
+
+    /// Append the tag of the operation, labeled `label`, to the IO Pattern `io`.
+    fn push_to(self, io: &mut String, label: &str) {
+        io.push_str(SEP_BYTE);
+        io.push_str(&match self {
+            Op::Absorb(count) => format!("A{}{}", count, label),
+            Op::Squeeze(count) => format!("S{}{}", count, label),
+            Op::Ratchet => format!("R{}", label),
+            Op::Segment => format!("C{}", label),
+            Op::Marker => format!("M{}", label),
+        });
+    }
 }
 
 impl<H: DuplexHash<U>, U: Unit> IOPattern<H, U> {
     pub fn from_string(io: String) -> Self {
         Self {
             io,
+            digest: OnceLock::new(),
             _hash: PhantomData,
         }
     }
 
+    /// Parse an IO Pattern from (untrusted) bytes.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").ratchet();
+    /// assert!(IOPattern::<DefaultHash>::from_bytes(io.as_bytes()).is_ok());
+    /// let err = IOPattern::<DefaultHash>::from_bytes(b"\xf0\x9f\x93\x9d\0A").unwrap_err();
+    /// assert_eq!(err, OpParseError { position: 5, kind: OpParseErrorKind::InvalidLength });
+    /// ```
+    pub fn from_bytes(io: &[u8]) -> Result<Self, OpParseError> {
+        let io = String::from_utf8(io.to_vec()).map_err(|e| OpParseError {
+            position: e.utf8_error().valid_up_to(),
+            kind: OpParseErrorKind::InvalidUtf8,
+        })?;
+        Self::parse_io(io.as_bytes())?;
+        Ok(Self::from_string(io))
+    }
+
     /// Create a new IOPattern with the domain separator.
     pub fn new(domsep: &str) -> Self {
         assert!(
@@ -93,39 +222,56 @@ impl<H: DuplexHash<U>, U: Unit> IOPattern<H, U> {
         Self::from_string(domsep.to_string())
     }
 
+    /// Derive the IV with `iv_hash` instead of [`Keccak`](crate::hash::Keccak) (see [`IOPattern::digest`]).
+    ///
+    /// The choice is recorded in the header of the IO Pattern, as a tag `I<id>` right after the domain separator,
+    /// so that prover and verifier agree on it.
+    /// For a protocol hash over bytes, choosing its own backend (or [`IvHash::Protocol`]) avoids depending on Keccak.
+    ///
+    /// ```
+    /// # use nimue::*;
+    /// use nimue::hash::{HashBackend, IvHash};
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").with_iv_hash(IvHash::Protocol).absorb(1, "🍝");
+    /// assert_eq!(io.as_bytes(), "📝\0Iprotocol\0A1🍝".as_bytes());
+    /// assert_eq!(io.iv_hash(), Ok(IvHash::Protocol));
+    /// assert_eq!(IOPattern::<DefaultHash>::new("📝").iv_hash(), Ok(IvHash::Backend(HashBackend::Keccak)));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the IV hash is already set.
+    pub fn with_iv_hash(self, iv_hash: impl Into<IvHash>) -> Self {
+        let (domsep, id) = split_header(self.as_bytes());
+        assert!(id.is_none(), "The IV hash is already set.");
+        let (domsep, rest) = self.io.split_at(domsep.len());
+        let tag = format!("{}{}{}", SEP_BYTE, IV_TAG as char, iv_hash.into().id());
+        Self::from_string(format!("{}{}{}", domsep, tag, rest))
+    }
+
+    /// The hash deriving the IV, as recorded in the header (see [`IOPattern::with_iv_hash`]).
+    ///
+    /// Defaults to [`Keccak`](crate::hash::Keccak), and fails with [`OpParseErrorKind::UnknownBackend`]
+    /// if the header records an unknown hash.
+    pub fn iv_hash(&self) -> Result<IvHash, OpParseError> {
+        parse_iv_hash(self.as_bytes())
+    }
+
     /// Absorb `count` native elements.
+    ///
+    /// When `count` is zero, no operation is performed:
+    /// the label is kept in the IO Pattern as a comment, but is dropped from its canonical form,
+    /// and thus does not change its digest (see [`IOPattern::canonicalize`]).
     pub fn absorb(self, count: usize, label: &str) -> Self {
-        assert!(count > 0, "Count must be positive.");
-        assert!(
-            !label.contains(SEP_BYTE),
-            "Label cannot contain the separator BYTE."
-        );
-        assert!(
-            match label.chars().next() {
-                Some(char) => !char.is_ascii_digit(),
-                None => true,
-            },
-            "Label cannot start with a digit."
-        );
-
+        check_label(label);
         Self::from_string(self.io + SEP_BYTE + &format!("A{}", count) + label)
     }
 
     /// Squeeze `count` native elements.
+    ///
+    /// When `count` is zero, no operation is performed, as for [`IOPattern::absorb`].
     pub fn squeeze(self, count: usize, label: &str) -> Self {
-        assert!(count > 0, "Count must be positive.");
-        assert!(
-            !label.contains(SEP_BYTE),
-            "Label cannot contain the separator BYTE."
-        );
-        assert!(
-            match label.chars().next() {
-                Some(char) => !char.is_ascii_digit(),
-                None => true,
-            },
-            "Label cannot start with a digit."
-        );
-
+        check_label(label);
         Self::from_string(self.io + SEP_BYTE + &format!("S{}", count) + label)
     }
 
@@ -134,71 +280,397 @@ impl<H: DuplexHash<U>, U: Unit> IOPattern<H, U> {
         Self::from_string(self.io + SEP_BYTE + "R")
     }
 
+    /// End the current segment of the protocol transcript.
+    ///
+    /// The prover seals each segment with [`Merlin::seal_segment`](crate::Merlin::seal_segment),
+    /// and each segment can be verified given the chaining digest of the previous one
+    /// (see [`IOPattern::to_arthur_segment`]).
+    pub fn segment(self) -> Self {
+        Self::from_string(self.io + SEP_BYTE + "C")
+    }
+
+    /// Mark a phase boundary (e.g. between the commit and the query phase of a protocol).
+    ///
+    /// Prover and verifier must call `marker` with the same label at this point of the protocol
+    /// (see [`Safe::marker`](crate::Safe::marker)).
+    /// Unlike a 1-element absorb, a marker reads nothing from the protocol transcript:
+    /// the sponge absorbs a domain-separating constant derived from `label`.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").marker("queries").squeeze(1, "🍷");
+    /// let mut merlin = io.to_merlin();
+    /// merlin.add_bytes(&[0x42]).unwrap();
+    /// merlin.marker("queries").unwrap();
+    /// merlin.challenge_bytes::<1>().unwrap();
+    /// assert_eq!(merlin.transcript(), [0x42]);
+    ///
+    /// let mut arthur = io.to_arthur(merlin.transcript());
+    /// arthur.next_bytes::<1>().unwrap();
+    /// assert!(arthur.marker("commitments").is_err());
+    /// ```
+    pub fn marker(self, label: &str) -> Self {
+        check_label(label);
+        Self::from_string(self.io + SEP_BYTE + "M" + label)
+    }
+
+    /// Repeat `count` times the operations added by `f`.
+    ///
+    /// Each label is prefixed with the index of the repetition, `[i].`, except for the labels of markers,
+    /// which prover and verifier pass as they are (see [`IOPattern::marker`]):
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").repeat(2, |io| io.absorb(1, "🍝").squeeze(1, "🍷"));
+    /// assert_eq!(io.as_bytes(), "📝\0A1[0].🍝\0S1[0].🍷\0A1[1].🍝\0S1[1].🍷".as_bytes());
+    /// ```
+    pub fn repeat(self, count: usize, f: impl Fn(Self) -> Self) -> Self {
+        (0..count).fold(self, |io, i| {
+            io.extend_prefixed(&format!("[{}].", i), f(Self::new("")))
+        })
+    }
+
+    /// Add `count` rounds, the `i`-th round consisting of the operations added by `f(io, i)`.
+    ///
+    /// Each label is prefixed with the label of the round and its index, `label[i].`, except for the labels of markers:
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").rounds("fold", 2, |io, i| io.absorb(1 + i, "com"));
+    /// assert_eq!(io.as_bytes(), "📝\0A1fold[0].com\0A2fold[1].com".as_bytes());
+    /// ```
+    pub fn rounds(self, label: &str, count: usize, f: impl Fn(Self, usize) -> Self) -> Self {
+        check_label(label);
+        (0..count).fold(self, |io, i| {
+            io.extend_prefixed(&format!("{}[{}].", label, i), f(Self::new(""), i))
+        })
+    }
+
+    /// Append the operations of `round` (whose domain separator is ignored), prefixing their labels with `prefix`.
+    ///
+    /// A malformed `round` (see [`IOPattern::from_string`]) is appended unchanged, so that the IO Pattern fails to parse.
+    fn extend_prefixed(self, prefix: &str, round: Self) -> Self {
+        let Ok(ops) = Self::parse_ops(round.io.as_bytes()) else {
+            let ops = round.io.find(SEP_BYTE).map_or("", |start| &round.io[start..]);
+            return Self::from_string(self.io + ops);
+        };
+        let mut io = self.io;
+        for (_, op, label) in ops {
+            // labels are substrings of a string, split at ASCII separators and digits
+            let label = core::str::from_utf8(label).unwrap_or_default();
+            // ratchets and segment boundaries have no label,
+            // and marker labels are passed as they are by prover and verifier
+            match op {
+                Op::Ratchet | Op::Segment => op.push_to(&mut io, ""),
+                Op::Marker => op.push_to(&mut io, label),
+                Op::Absorb(_) | Op::Squeeze(_) => {
+                    op.push_to(&mut io, &format!("{}{}", prefix, label))
+                }
+            }
+        }
+        Self::from_string(io)
+    }
+
+    /// Return the IO Pattern with the domain separator and every label replaced by a salted hash.
+    ///
+    /// The labels of markers, which prover and verifier pass at runtime (see [`IOPattern::marker`]),
+    /// and the IV hash (see [`IOPattern::with_iv_hash`]) are kept.
+    /// The sequence of operations is unchanged, so the redacted IO Pattern accepts the same transcripts,
+    /// but its IV (and its string, if shipped with the verifier) does not leak the naming of the protocol.
+    /// The human-readable pattern can be kept locally for debugging and tracing.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "secret-sauce").squeeze(16, "🍷");
+    /// let redacted = io.redact_labels(b"salt");
+    /// assert_ne!(redacted.digest(), io.digest());
+    /// assert!(!String::from_utf8_lossy(redacted.as_bytes()).contains("secret-sauce"));
+    /// ```
+    ///
+    /// Malformed patterns (see [`IOPattern::from_string`]) are returned unchanged.
+    pub fn redact_labels(&self, salt: &[u8]) -> Self {
+        let redact = |label: &str| {
+            let mut keccak = crate::hash::Keccak::default();
+            keccak.absorb_unchecked(&(salt.len() as u64).to_le_bytes());
+            keccak.absorb_unchecked(salt);
+            keccak.absorb_unchecked(label.as_bytes());
+            let mut digest = [0u8; 16];
+            keccak.squeeze_unchecked(&mut digest);
+            // labels cannot start with a digit
+            format!("~{}", hex::encode(digest))
+        };
+
+        let Ok(ops) = Self::parse_ops(self.io.as_bytes()) else {
+            return self.clone();
+        };
+        let (domsep, _) = split_header(self.io.as_bytes());
+        let mut io = redact(core::str::from_utf8(domsep).unwrap_or_default());
+        // the IV hash is not a label, and is kept as it is
+        io.push_str(&self.io[domsep.len()..header_len(self.io.as_bytes())]);
+        for (_, op, label) in ops {
+            let label = core::str::from_utf8(label).unwrap_or_default();
+            // ratchets and segment boundaries have no label,
+            // and marker labels are passed as they are by prover and verifier
+            match op {
+                Op::Ratchet | Op::Segment => op.push_to(&mut io, ""),
+                Op::Marker => op.push_to(&mut io, label),
+                Op::Absorb(_) | Op::Squeeze(_) => op.push_to(&mut io, &redact(label)),
+            }
+        }
+        Self::from_string(io)
+    }
+
+    /// Return the canonical form of the IO Pattern.
+    ///
+    /// In the canonical form:
+    /// - absorbs and squeezes of zero elements are dropped, together with their labels;
+    /// - labels of absorbs and squeezes are trimmed of surrounding whitespace, unless they would then start with a digit;
+    /// - consecutive absorbs (resp. squeezes) are merged into one, whose label joins theirs with `", "`;
+    /// - lengths are written without leading zeros, and ratchets and segment boundaries without length.
+    ///
+    /// The domain separator and the labels of markers are kept as they are.
+    /// Two IO Patterns with the same canonical form perform the same operations on the sponge,
+    /// and have the same IV (see [`IOPattern::digest`]), so they produce identical transcripts:
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝")
+    ///     .absorb(1, "🍝")
+    ///     .squeeze(0, "a comment")
+    ///     .absorb(2, " 🍕")
+    ///     .squeeze(16, " 🍷 ");
+    /// let canonical = IOPattern::<DefaultHash>::new("📝").absorb(3, "🍝, 🍕").squeeze(16, "🍷");
+    /// assert_eq!(io.canonicalize().as_bytes(), canonical.as_bytes());
+    /// assert_eq!(io.digest(), canonical.digest());
+    /// ```
+    ///
+    /// Malformed patterns (see [`IOPattern::from_string`]) are returned unchanged.
+    pub fn canonicalize(&self) -> Self {
+        let Ok(ops) = Self::parse_ops(self.io.as_bytes()) else {
+            return self.clone();
+        };
+        let mut merged: Vec<(Op, String)> = Vec::new();
+        for (_, op, label) in ops {
+            // labels are substrings of a string, split at ASCII separators and digits
+            let label = core::str::from_utf8(label).unwrap_or_default();
+            match (merged.last_mut(), op) {
+                (Some((Op::Absorb(length), labels)), Op::Absorb(count))
+                | (Some((Op::Squeeze(length), labels)), Op::Squeeze(count)) => {
+                    let Some(total) = length.checked_add(count) else {
+                        return self.clone();
+                    };
+                    *length = total;
+                    *labels += ", ";
+                    *labels += label.trim();
+                }
+                (_, Op::Absorb(_) | Op::Squeeze(_)) => {
+                    let label = match label.trim() {
+                        trimmed if trimmed.starts_with(|c: char| c.is_ascii_digit()) => {
+                            label.trim_end()
+                        }
+                        trimmed => trimmed,
+                    };
+                    merged.push((op, label.to_string()));
+                }
+                _ => merged.push((op, label.to_string())),
+            }
+        }
+
+        // the header is kept as it is, as it records the hash deriving the IV
+        let mut io = self.io[..header_len(self.as_bytes())].to_string();
+        for (op, label) in merged {
+            op.push_to(&mut io, &label);
+        }
+        Self::from_string(io)
+    }
+
     /// Return the IO Pattern as bytes.
     pub fn as_bytes(&self) -> &[u8] {
         self.io.as_bytes()
     }
 
+    /// Return a 32-byte identifier of the IO Pattern.
+    ///
+    /// The digest is computed over the canonical form of the IO Pattern (see [`IOPattern::canonicalize`])
+    /// with the hash recorded in its header, [`Keccak`](crate::hash::Keccak) by default (see [`IOPattern::with_iv_hash`]),
+    /// and is the IV used to initialize the sponge `H`.
+    /// Unless the header chooses [`IvHash::Protocol`], the digest does not depend on `H`:
+    /// algebraic hashes (e.g. Poseidon) are initialized from 32 bytes, and never permute over the IO Pattern string itself.
+    /// It can be used as a short identifier for caching, or to make sure that prover and verifier
+    /// loaded the same protocol description (see [`IOPattern::to_arthur_checked`]).
+    ///
+    /// The digest is computed once and cached, so that instantiating many transcripts
+    /// (e.g. for batch proving or verification) from the same IO Pattern does not hash it again.
+    ///
+    /// # Panics
+    ///
+    /// If the IV hash cannot be used, see [`IOPattern::try_digest`].
+    pub fn digest(&self) -> [u8; 32] {
+        self.try_digest().expect(
+            "The IO Pattern records an IV hash that cannot be used, see IOPattern::try_digest",
+        )
+    }
+
+    /// Return the digest of the IO Pattern, as [`IOPattern::digest`].
+    ///
+    /// Fails with [`OpParseErrorKind::UnknownBackend`] if the header records an unknown hash,
+    /// or [`IvHash::Protocol`] for a hash `H` that cannot absorb bytes (see [`DuplexHash::iv_for_io_pattern`]).
+    pub fn try_digest(&self) -> Result<[u8; 32], OpParseError> {
+        if let Some(digest) = self.digest.get() {
+            return Ok(*digest);
+        }
+        let canonical = self.canonicalize();
+        let digest = match self.iv_hash()? {
+            IvHash::Backend(backend) => {
+                let mut digest = [0u8; 32];
+                AnyDuplexHash::zeroed(backend)
+                    .absorb_unchecked(canonical.as_bytes())
+                    .squeeze_unchecked(&mut digest);
+                digest
+            }
+            IvHash::Protocol => {
+                H::iv_for_io_pattern(canonical.as_bytes()).ok_or(OpParseError {
+                    // the position of the tag
+                    position: split_header(self.as_bytes()).0.len() + 1,
+                    kind: OpParseErrorKind::UnknownBackend,
+                })?
+            }
+        };
+        Ok(*self.digest.get_or_init(|| digest))
+    }
+
+    /// Check that the IO Pattern matches the `expected` digest.
+    fn check_digest(&self, expected: &[u8; 32]) -> Result<(), IOPatternError> {
+        if &self.try_digest()? == expected {
+            Ok(())
+        } else {
+            Err(format!("IOPattern digest mismatch for {:?}", self).into())
+        }
+    }
+
     /// Parse the givern IO Pattern into a sequence of [`Op`]'s.
-    pub(crate) fn finalize(&self) -> VecDeque<Op> {
-        // Guaranteed to succeed as instances are all valid iopatterns
+    ///
+    /// Fails only for patterns that were not built with the [`IOPattern`] builder methods, e.g. with [`IOPattern::from_string`].
+    pub(crate) fn finalize(&self) -> Result<VecDeque<Op>, OpParseError> {
         Self::parse_io(self.io.as_bytes())
-            .expect("Internal error. Please submit issue to m@orru.net")
     }
 
-    fn parse_io(io_pattern: &[u8]) -> Result<VecDeque<Op>, IOPatternError> {
-        let mut stack = VecDeque::new();
+    /// The labeled spans of each operation of the IO Pattern, after merging consecutive absorbs (resp. squeezes).
+    ///
+    /// Tracing tools can attribute each unit of a merged operation back to the label it was declared with.
+    /// Ratchets and segment boundaries have no span; a marker has a single, empty span with its label.
+    /// Malformed IO Patterns have no operations, and thus no spans.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").squeeze(16, "a").squeeze(16, "b").ratchet();
+    /// let spans = io.label_spans();
+    /// assert_eq!(spans.len(), 2);
+    /// assert_eq!(spans[0][1], LabelSpan { label: "b".to_string(), range: 16..32 });
+    /// assert!(spans[1].is_empty());
+    /// ```
+    pub fn label_spans(&self) -> Vec<Vec<LabelSpan>> {
+        Self::parse_labeled(self.io.as_bytes())
+            .map(|(_, spans)| spans)
+            .unwrap_or_default()
+    }
 
-        // skip the domain separator
-        for part in io_pattern.split(|&b| b == SEP_BYTE.as_bytes()[0]).skip(1) {
-            let next_id = part[0] as char;
-            let next_length = part[1..]
-                .iter()
-                .take_while(|x| x.is_ascii_digit())
-                .fold(0, |acc, x| acc * 10 + (x - b'0') as usize);
+    /// The label and length of each absorb declared in the IO Pattern, in order and without merging consecutive ones.
+    ///
+    /// Zero-length absorbs are skipped.
+    #[cfg(feature = "ark")]
+    pub(crate) fn absorbs(&self) -> Result<Vec<(String, usize)>, OpParseError> {
+        Ok(Self::parse_ops(self.io.as_bytes())?
+            .into_iter()
+            .filter_map(|(_, op, label)| match op {
+                Op::Absorb(count) => Some((String::from_utf8_lossy(label).into_owned(), count)),
+                _ => None,
+            })
+            .collect())
+    }
 
-            // check that next_length != 0 is performed internally on Op::new
-            let next_op = Op::new(next_id, Some(next_length))?;
-            stack.push_back(next_op);
-        }
+    /// Parse the IO Pattern into a sequence of [`Op`]'s, merging consecutive absorbs (resp. squeezes).
+    pub(crate) fn parse_io(io_pattern: &[u8]) -> Result<VecDeque<Op>, OpParseError> {
+        Ok(Self::parse_labeled(io_pattern)?.0)
+    }
 
-        // consecutive calls are merged into one
-        match stack.pop_front() {
-            None => Ok(stack),
-            Some(x) => Self::simplify_stack(VecDeque::from([x]), stack),
+    /// Parse the IO Pattern into a sequence of [`Op`]'s, merging consecutive absorbs (resp. squeezes),
+    /// together with the labeled spans of each of them (see [`IOPattern::label_spans`]).
+    fn parse_labeled(
+        io_pattern: &[u8],
+    ) -> Result<(VecDeque<Op>, Vec<Vec<LabelSpan>>), OpParseError> {
+        let mut stack = VecDeque::new();
+        let mut spans: Vec<Vec<LabelSpan>> = Vec::new();
+        for (position, op, label) in Self::parse_ops(io_pattern)? {
+            let label = String::from_utf8_lossy(label).into_owned();
+            match (stack.back_mut(), op) {
+                // consecutive calls are merged into one
+                (Some(Op::Absorb(length)), Op::Absorb(count))
+                | (Some(Op::Squeeze(length)), Op::Squeeze(count)) => {
+                    let start = *length;
+                    *length = length.checked_add(count).ok_or(OpParseError {
+                        position,
+                        kind: OpParseErrorKind::LengthOverflow,
+                    })?;
+                    // guaranteed never to fail, since each operation has its spans
+                    spans.last_mut().unwrap().push(LabelSpan {
+                        label,
+                        range: start..*length,
+                    });
+                    continue;
+                }
+                (_, Op::Absorb(count) | Op::Squeeze(count)) => spans.push(vec![LabelSpan {
+                    label,
+                    range: 0..count,
+                }]),
+                (_, Op::Marker) => spans.push(vec![LabelSpan { label, range: 0..0 }]),
+                (_, Op::Ratchet | Op::Segment) => spans.push(Vec::new()),
+            }
+            stack.push_back(op);
         }
+        Ok((stack, spans))
     }
 
-    fn simplify_stack(
-        mut dst: VecDeque<Op>,
-        mut stack: VecDeque<Op>,
-    ) -> Result<VecDeque<Op>, IOPatternError> {
-        if stack.is_empty() {
-            Ok(dst)
-        } else {
-            // guaranteed never to fail, since:
-            assert!(!dst.is_empty() && !stack.is_empty());
-            let previous = dst.pop_back().unwrap();
-            let next = stack.pop_front().unwrap();
-
-            match (previous, next) {
-                (Op::Squeeze(a), Op::Squeeze(b)) => {
-                    dst.push_back(Op::Squeeze(a + b));
-                    Self::simplify_stack(dst, stack)
-                }
-                (Op::Absorb(a), Op::Absorb(b)) => {
-                    dst.push_back(Op::Absorb(a + b));
-                    Self::simplify_stack(dst, stack)
-                }
-                // (Op::Divide, Op::Divide)
-                // is useless but unharmful
-                (a, b) => {
-                    dst.push_back(a);
-                    dst.push_back(b);
-                    Self::simplify_stack(dst, stack)
-                }
+    /// Parse the operations of the IO Pattern as declared, without merging them,
+    /// each with its position in the IO Pattern and its label.
+    ///
+    /// Absorbs and squeezes of zero elements are dropped.
+    /// This is the only parser of the IO Pattern grammar: all other views of the IO Pattern are built on it.
+    pub(crate) fn parse_ops(io_pattern: &[u8]) -> Result<Vec<DeclaredOp<'_>>, OpParseError> {
+        let mut ops = Vec::new();
+
+        // skip the header, checking the hash deriving the IV
+        parse_iv_hash(io_pattern)?;
+        let mut position = header_len(io_pattern) + 1;
+        let parts = io_pattern
+            .get(position..)
+            .map(|ops| ops.split(|&b| b == SEP_BYTE.as_bytes()[0]));
+        for part in parts.into_iter().flatten() {
+            let error = |kind| OpParseError { position, kind };
+            let (&next_id, rest) = part.split_first().ok_or(error(OpParseErrorKind::EmptyOp))?;
+            let digits = rest.iter().take_while(|x| x.is_ascii_digit()).count();
+            let next_length = rest[..digits]
+                .iter()
+                .try_fold(0usize, |acc, x| {
+                    acc.checked_mul(10)?.checked_add((x - b'0') as usize)
+                })
+                .ok_or(error(OpParseErrorKind::LengthOverflow))?;
+
+            // the length is checked internally on Op::new, and zero-length absorbs and squeezes are dropped
+            if let Some(next_op) =
+                Op::new(next_id, (digits > 0).then_some(next_length)).map_err(error)?
+            {
+                ops.push((position, next_op, &rest[digits..]));
             }
+            position += part.len() + 1;
         }
+        Ok(ops)
     }
 
     /// Create an [`crate::Merlin`] instance from the IO Pattern.
@@ -210,23 +682,388 @@ impl<H: DuplexHash<U>, U: Unit> IOPattern<H, U> {
     pub fn to_arthur<'a>(&self, transcript: &'a [u8]) -> crate::Arthur<'a, H, U> {
         crate::Arthur::<H, U>::new(self, transcript)
     }
+
+    /// Create a [`crate::Arthur`] instance, checking first that the transcript has the length declared by the IO Pattern.
+    ///
+    /// The expected length is the number of absorbed units times their size on the wire (see [`Unit::SIZE`]),
+    /// so that malformed proofs are rejected before doing any sponge work.
+    /// For units without a fixed size, the length is not checked.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(2, "🍝").squeeze(16, "🍷");
+    /// assert!(io.try_to_arthur(b"\x42\x43").is_ok());
+    /// assert!(io.try_to_arthur(b"\x42").is_err());
+    /// assert!(io.try_to_arthur(b"\x42\x43\x44").is_err());
+    /// ```
+    pub fn try_to_arthur<'a>(
+        &self,
+        transcript: &'a [u8],
+    ) -> Result<crate::Arthur<'a, H, U>, ProofError> {
+        let absorbed = self
+            .finalize()
+            .map_err(IOPatternError::from)?
+            .into_iter()
+            .map(|op| match op {
+                Op::Absorb(len) => len,
+                _ => 0,
+            })
+            .sum::<usize>();
+        if let Some(size) = U::SIZE {
+            let expected = absorbed
+                .checked_mul(size)
+                .ok_or(IOPatternError::from("Transcript length overflow"))?;
+            if transcript.len() != expected {
+                return Err(IOPatternError::from(format!(
+                    "Transcript has {} bytes, expected {}",
+                    transcript.len(),
+                    expected
+                ))
+                .into());
+            }
+        }
+        Ok(self.to_arthur(transcript))
+    }
+
+    /// Create a [`crate::Arthur`] instance from the IO Pattern and a protocol transcript split in several `chunks`.
+    ///
+    /// See [`crate::Arthur::new_from_chunks`].
+    pub fn to_arthur_chunks<'a>(&self, chunks: &'a [&'a [u8]]) -> crate::Arthur<'a, H, U> {
+        crate::Arthur::<H, U>::new_from_chunks(self, chunks)
+    }
+
+    /// Create a [`crate::Arthur`] instance verifying the segment following the `index`-th segment boundary,
+    /// given the chaining `digest` of the previous segment and the segment `transcript`.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").segment().absorb(1, "🍷").squeeze(16, "🧾");
+    /// let mut merlin = io.to_merlin();
+    /// merlin.add_bytes(&[0x42]).unwrap();
+    /// let (_first, digest) = merlin.seal_segment().unwrap();
+    /// merlin.add_bytes(&[0x43]).unwrap();
+    /// let receipt = merlin.challenge_bytes::<16>().unwrap();
+    ///
+    /// let mut arthur = io.to_arthur_segment(1, &digest, merlin.transcript()).unwrap();
+    /// assert_eq!(arthur.next_bytes().unwrap(), [0x43]);
+    /// assert_eq!(arthur.challenge_bytes::<16>().unwrap(), receipt);
+    /// ```
+    pub fn to_arthur_segment<'a>(
+        &self,
+        index: usize,
+        digest: &[u8; 32],
+        transcript: &'a [u8],
+    ) -> Result<crate::Arthur<'a, H, U>, IOPatternError> {
+        let mut stack = self.finalize()?;
+        let mut labels = VecDeque::from(self.label_spans());
+        let mut boundaries = 0;
+        while boundaries < index {
+            labels.pop_front();
+            match stack.pop_front() {
+                Some(Op::Segment) => boundaries += 1,
+                Some(_) => (),
+                None => {
+                    return Err(format!("IO Pattern has only {} segments", boundaries + 1).into())
+                }
+            }
+        }
+        // the IO Pattern of the segment: the domain separator, followed by the operations after its boundary
+        let io = self.as_bytes();
+        let mut segment_io = io.to_vec();
+        let boundary = index.checked_sub(1).and_then(|i| {
+            Self::parse_ops(io)
+                .ok()?
+                .into_iter()
+                .filter(|(_, op, _)| *op == Op::Segment)
+                .nth(i)
+        });
+        if let Some((position, _, _)) = boundary {
+            let domsep = io
+                .iter()
+                .position(|&b| b == SEP_BYTE.as_bytes()[0])
+                .unwrap_or(io.len());
+            let end = io[position..]
+                .iter()
+                .position(|&b| b == SEP_BYTE.as_bytes()[0])
+                .map_or(io.len(), |end| position + end);
+            segment_io = [&io[..domsep], &io[end..]].concat();
+        }
+        Ok(crate::Arthur {
+            safe: Safe::from_stack(
+                H::new_for_io_pattern(&segment_io, self.digest(), *digest)?,
+                stack,
+                labels,
+            ),
+            transcript: transcript.into(),
+            allow_trailing_bytes: false,
+            failure: None,
+        })
+    }
+
+    /// Create a [`crate::Merlin`] instance, checking first that the IO Pattern has digest `expected`.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝");
+    /// let digest = io.digest();
+    /// assert!(io.to_merlin_checked(&digest).is_ok());
+    /// assert!(io.to_merlin_checked(&[0u8; 32]).is_err());
+    /// ```
+    pub fn to_merlin_checked(
+        &self,
+        expected: &[u8; 32],
+    ) -> Result<crate::Merlin<H, U, crate::DefaultRng>, IOPatternError> {
+        self.check_digest(expected)?;
+        Ok(self.to_merlin())
+    }
+
+    /// Create a [`crate::Merlin`] instance for an IO Pattern pinned to the digest `pinned`, e.g. a constant next to the prover.
+    ///
+    /// Protects against building a prover for a stale or modified IO Pattern, when the pattern is assembled
+    /// far from the prover: unlike [`IOPattern::to_merlin_checked`], a mismatch is treated as a bug.
+    /// The pin can be written in hexadecimal with [`digest_from_hex`], and updated with the digest printed on mismatch.
+    ///
+    /// ```
+    /// # use nimue::*;
+    /// const PINNED: [u8; 32] = digest_from_hex("01b0eded3e7a80dfd9a29da2c65bd78a84f128049ebfc7833d5da8a94d4fe61c");
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝");
+    /// let mut merlin = io.to_merlin_pinned(&PINNED);
+    /// # merlin.add_bytes(&[0x42]).unwrap();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the digest of the IO Pattern is not `pinned`.
+    pub fn to_merlin_pinned(&self, pinned: &[u8; 32]) -> crate::Merlin<H, U, crate::DefaultRng> {
+        let digest = self.digest();
+        assert!(
+            &digest == pinned,
+            "IOPattern digest mismatch: pinned {}, got {} for {:?}",
+            hex::encode(pinned),
+            hex::encode(digest),
+            self
+        );
+        self.to_merlin()
+    }
+
+    /// Create a [`crate::Arthur`] instance, checking first that the IO Pattern has digest `expected`.
+    pub fn to_arthur_checked<'a>(
+        &self,
+        transcript: &'a [u8],
+        expected: &[u8; 32],
+    ) -> Result<crate::Arthur<'a, H, U>, IOPatternError> {
+        self.check_digest(expected)?;
+        Ok(self.to_arthur(transcript))
+    }
+}
+
+/// Parse a 32-byte IO Pattern digest from its 64-character hexadecimal encoding, e.g. to pin it in a constant.
+///
+/// See [`IOPattern::to_merlin_pinned`].
+///
+/// # Panics
+///
+/// Panics (at compile time, in a constant) if `hex` is not 64 hexadecimal characters.
+pub const fn digest_from_hex(hex: &str) -> [u8; 32] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            b'A'..=b'F' => c - b'A' + 10,
+            _ => panic!("Invalid hexadecimal character in digest."),
+        }
+    }
+
+    let hex = hex.as_bytes();
+    assert!(hex.len() == 64, "A digest is 64 hexadecimal characters.");
+    let mut digest = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        digest[i] = nibble(hex[2 * i]) << 4 | nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+    digest
+}
+
+impl<C: Sponge> IOPattern<DuplexSponge<C>, C::U> {
+    /// The number of permutations performed by a prover or verifier following the IO Pattern, from initialization.
+    ///
+    /// The count is computed statically from the operations, and is exact for hashes built with [`DuplexSponge`]
+    /// (see [`DuplexSponge::permutations`]), across segments.
+    /// A verifier starting from a segment boundary (see [`IOPattern::to_arthur_segment`]) counts from that boundary.
+    /// This is useful for estimating the cost of verifying the transcript in a circuit.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// // Keccak has a rate of 136 bytes.
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(200, "🍝").squeeze(16, "🍷").ratchet();
+    /// assert_eq!(io.permutation_count(), 3);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the IO Pattern is malformed.
+    pub fn permutation_count(&self) -> usize {
+        let ops = self.finalize().expect("Malformed IO Pattern");
+        // mirror the positions kept by DuplexSponge, starting from a fresh state
+        let (mut absorb_pos, mut squeeze_pos, mut count) = (0, C::R, 0);
+        let squeeze = |mut len: usize, absorb_pos: &mut usize, squeeze_pos: &mut usize| {
+            let mut count = 0;
+            while len > 0 {
+                if *squeeze_pos == C::R {
+                    (*absorb_pos, *squeeze_pos) = (0, 0);
+                    count += 1;
+                }
+                let chunk_len = usize::min(len, C::R - *squeeze_pos);
+                *squeeze_pos += chunk_len;
+                len -= chunk_len;
+            }
+            count
+        };
+        for op in ops {
+            match op {
+                Op::Absorb(mut len) => {
+                    while len > 0 {
+                        if absorb_pos == C::R {
+                            absorb_pos = 0;
+                            count += 1;
+                        }
+                        let chunk_len = usize::min(len, C::R - absorb_pos);
+                        absorb_pos += chunk_len;
+                        len -= chunk_len;
+                    }
+                    squeeze_pos = C::R;
+                }
+                Op::Squeeze(len) => count += squeeze(len, &mut absorb_pos, &mut squeeze_pos),
+                Op::Ratchet => {
+                    count += 1;
+                    squeeze_pos = C::R;
+                }
+                Op::Segment => {
+                    // ratchet, squeeze the chaining digest, and re-initialize
+                    squeeze_pos = C::R;
+                    count += 1 + squeeze(32, &mut absorb_pos, &mut squeeze_pos);
+                    (absorb_pos, squeeze_pos) = (0, C::R);
+                }
+                Op::Marker => {
+                    // the constant depends only on the label, and is absorbed as a single unit
+                    if absorb_pos == C::R {
+                        absorb_pos = 0;
+                        count += 1;
+                    }
+                    absorb_pos += 1;
+                    squeeze_pos = C::R;
+                }
+            }
+        }
+        count
+    }
 }
 
 impl<U: Unit, H: DuplexHash<U>> core::fmt::Debug for IOPattern<H, U> {
+    /// Show the IO Pattern, or only its digest, as allowed by the [`RedactionLevel`](crate::RedactionLevel).
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        // Ensure that the state isn't accidentally logged
-        write!(f, "IOPattern({:?})", self.io)
+        match crate::RedactionLevel::global() {
+            crate::RedactionLevel::Opaque => write!(f, "IOPattern"),
+            crate::RedactionLevel::Progress => match self.try_digest() {
+                Ok(digest) => write!(f, "IOPattern(~{})", hex::encode(digest)),
+                Err(_) => write!(f, "IOPattern(~?)"),
+            },
+            crate::RedactionLevel::Labels => write!(f, "IOPattern({:?})", self.io),
+        }
+    }
+}
+
+impl<H: DuplexHash> IOPattern<H> {
+    /// Produce a random protocol transcript with the structure of the IO Pattern, without running the prover.
+    ///
+    /// Each prover message is filled with random bytes of the right size:
+    /// the transcript can be read by a verifier, but is not a valid proof
+    /// (and its messages may not even decode, e.g. as group elements).
+    /// This is useful to test the size of proofs, or verifier services, before the prover is written.
+    ///
+    /// ```
+    /// # use nimue::*;
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(32, "🍝").squeeze(16, "🍷").absorb(8, "🧾");
+    /// let transcript = io.simulate(&mut rand::rngs::OsRng);
+    /// assert_eq!(transcript.len(), 40);
+    /// assert!(replay::describe(io.as_bytes(), &transcript).is_ok());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the IO Pattern is malformed.
+    pub fn simulate(&self, rng: &mut impl rand::RngCore) -> Vec<u8> {
+        let ops = self.finalize().expect("Malformed IO Pattern");
+        let mut transcript = Vec::new();
+        for op in ops {
+            if let Op::Absorb(len) = op {
+                let start = transcript.len();
+                transcript.resize(start + len, 0);
+                rng.fill_bytes(&mut transcript[start..]);
+            }
+        }
+        transcript
     }
 }
 
-impl<H: DuplexHash> ByteIOPattern for IOPattern<H> {
+/// Byte messages and challenges, over any unit.
+///
+/// Byte counts are translated into unit counts with [`Unit::units_for_absorbed_bytes`] and [`Unit::units_for_squeezed_bytes`]:
+///
+/// ```
+/// # use nimue::*;
+///
+/// let io = IOPattern::<DefaultHash>::new("📝").add_bytes(4, "🍝").challenge_bytes(16, "🍷");
+/// assert_eq!(io.as_bytes(), "📝\0A4🍝\0S16🍷".as_bytes());
+/// ```
+impl<H: DuplexHash<U>, U: Unit> ByteIOPattern for IOPattern<H, U> {
     #[inline]
     fn add_bytes(self, count: usize, label: &str) -> Self {
-        self.absorb(count, label)
+        self.absorb(U::units_for_absorbed_bytes(count), label)
     }
 
     #[inline]
     fn challenge_bytes(self, count: usize, label: &str) -> Self {
-        self.squeeze(count, label)
+        self.squeeze(U::units_for_squeezed_bytes(count), label)
     }
 }
+
+/// Declare a function returning a `&'static` [`IOPattern`], built on first use and shared afterwards.
+///
+/// The IV (see [`IOPattern::digest`]) is computed when the IO Pattern is built,
+/// so that servers handling many protocols neither rebuild patterns nor rehash them for each request.
+///
+/// ```
+/// use nimue::{static_io_pattern, DefaultHash, IOPattern};
+///
+/// static_io_pattern! {
+///     /// The IO Pattern of the first version of the transfer protocol.
+///     pub fn transfer_v1() -> IOPattern<DefaultHash> =
+///         IOPattern::new("zkapp.com/transfer/v1").absorb(32, "commitment").squeeze(16, "challenge");
+/// }
+///
+/// assert!(std::ptr::eq(transfer_v1(), transfer_v1()));
+/// let handle = std::thread::spawn(|| transfer_v1().digest());
+/// assert_eq!(handle.join().unwrap(), transfer_v1().digest());
+/// ```
+#[macro_export]
+macro_rules! static_io_pattern {
+    ($(#[$meta:meta])* $vis:vis fn $name:ident() -> $ty:ty = $init:expr;) => {
+        $(#[$meta])*
+        $vis fn $name() -> &'static $ty {
+            static IO_PATTERN: ::std::sync::OnceLock<$ty> = ::std::sync::OnceLock::new();
+            IO_PATTERN.get_or_init(|| {
+                let io_pattern: $ty = $init;
+                io_pattern.digest();
+                io_pattern
+            })
+        }
+    };
+}
@@ -47,6 +47,7 @@
 //! - with feature flag `--feature=ark`, the module [`plugins::ark`] provides extension traits for arkworks fields and groups;
 //! - with feature flag `--feature=group`, the module [`plugins::group`] provides extension traits for zkcrypto's field and group traits.
 //! See the [`plugins`] module for more information.
+//! Ready-to-use Fiat-Shamir protocols built on top of these (with `--feature=ark`) live in the module `protocols`.
 //!
 //!
 //! # Protocol transcripts
@@ -121,31 +122,67 @@ This crate doesn't support big-endian targets.
 
 /// Verifier state and transcript deserialization.
 mod arthur;
+/// Distributed provers over secret-shared messages.
+pub mod collaborative;
+/// Encryption of the protocol transcript to a designated verifier.
+pub mod designated;
 /// Built-in proof results.
 mod errors;
+/// Entry points for fuzzing.
+#[doc(hidden)]
+pub mod fuzzing;
 /// Hash functions traits and implementations.
 pub mod hash;
 /// IO Pattern
 mod iopattern;
 /// Prover's internal state and transcript generation.
 mod merlin;
+/// Hooks on every absorb and squeeze of a prover (or verifier) state.
+pub mod middleware;
 /// APIs for common zkp libraries.
 pub mod plugins;
+/// Commonly-needed types and traits.
+pub mod prelude;
+/// Finished protocol transcripts.
+mod proof;
+/// Ready-to-use Fiat-Shamir protocols.
+#[cfg(feature = "ark")]
+pub mod protocols;
+/// Redaction of the prover and verifier states in logs.
+mod redaction;
+/// Registry of related protocols.
+mod registry;
+/// Replay of protocol transcripts, for debugging.
+pub mod replay;
 /// SAFE API.
 mod safe;
+/// Public elements absorbed once, shared by many transcripts.
+mod statement;
+/// Negative tests for transcript misuse, for protocols built on the top of nimue.
+pub mod testing;
 /// Unit-tests.
 #[cfg(test)]
 mod tests;
 
 /// Traits for byte support.
 pub mod traits;
+/// Compile-time checked transcripts for fixed protocols.
+pub mod typestate;
 
-pub use arthur::Arthur;
-pub use errors::{IOPatternError, ProofError, ProofResult};
-pub use hash::{legacy::DigestBridge, DuplexHash, Unit};
-pub use iopattern::IOPattern;
-pub use merlin::Merlin;
-pub use safe::Safe;
+pub use arthur::{Arthur, OpFailure, TranscriptSource};
+pub use errors::{IOPatternError, OpParseError, OpParseErrorKind, ProofError, ProofResult};
+#[cfg(feature = "legacy")]
+pub use hash::legacy::DigestBridge;
+pub use hash::{DuplexHash, Unit};
+pub use iopattern::{digest_from_hex, IOPattern, LabelSpan};
+pub use merlin::{CsprngFn, DeterministicProverRng, Merlin, TranscriptSink};
+#[cfg(feature = "derive")]
+pub use nimue_derive::TranscriptMessage;
+pub use proof::Proof;
+pub use redaction::RedactionLevel;
+pub use registry::ProtocolRegistry;
+pub use safe::{OpProgress, Safe};
+pub use statement::{BatchedStatement, SharedStatement};
 pub use traits::*;
 
 /// Default random number generator used ([`rand::rngs::OsRng`]).
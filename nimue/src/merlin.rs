@@ -1,11 +1,29 @@
+use core::mem::MaybeUninit;
+
 use rand::{CryptoRng, RngCore};
+use zeroize::Zeroize;
 
 use crate::hash::Unit;
-use crate::{ByteWriter, IOPattern, Safe, UnitTranscript};
+use crate::{ByteWriter, IOPattern, OpProgress, Safe, UnitTranscript};
 
 use super::hash::{DuplexHash, Keccak};
 use super::{DefaultHash, DefaultRng, IOPatternError};
 
+/// The destination of the prover messages, that is, the wire encoding of the protocol transcript.
+///
+/// [`Merlin`] hands each prover message, encoded, to its sink (see [`Merlin::with_sink`]).
+/// By default, messages are concatenated in a byte vector.
+pub trait TranscriptSink {
+    /// Write the encoding of the next prover message.
+    fn write_message(&mut self, message: &[u8]);
+}
+
+impl TranscriptSink for Vec<u8> {
+    fn write_message(&mut self, message: &[u8]) {
+        self.extend_from_slice(message)
+    }
+}
+
 /// A cryptographically-secure random number generator that is bound to the protocol transcript.
 ///
 /// For most public-coin protocols it is *vital* not to have two different verifier messages for the same prover message.
@@ -50,6 +68,122 @@ impl<R: RngCore + CryptoRng> RngCore for ProverRng<R> {
     }
 }
 
+/// A deterministic source for the prover's private coins, derived from a secret (à la [RFC 6979]).
+///
+/// Used as the random number generator of a [`Merlin`], it seeds the prover's coins,
+/// which are still bound to the protocol transcript: the same secret and the same messages
+/// lead to the same coins, with no need for a cryptographic random number generator.
+/// Make sure everything the coins should depend on (e.g., the message being signed)
+/// is absorbed before drawing them.
+///
+/// ```
+/// # use nimue::*;
+/// # use rand::RngCore;
+///
+/// let io = IOPattern::<DefaultHash>::new("📝").absorb(5, "message");
+/// let nonce = || {
+///     let mut merlin = Merlin::new(&io, DeterministicProverRng::from_secret(b"secret key"));
+///     merlin.public_bytes(b"hello").unwrap();
+///     merlin.rng().next_u64()
+/// };
+/// assert_eq!(nonce(), nonce());
+/// ```
+///
+/// [RFC 6979]: https://www.rfc-editor.org/rfc/rfc6979
+#[derive(Clone)]
+pub struct DeterministicProverRng(Keccak);
+
+impl DeterministicProverRng {
+    /// Derive the prover's coins from `secret`.
+    pub fn from_secret(secret: &[u8]) -> Self {
+        let mut sponge = Keccak::default();
+        sponge.absorb_unchecked(b"nimue/deterministic-prover-rng");
+        sponge.absorb_unchecked(&(secret.len() as u64).to_le_bytes());
+        sponge.absorb_unchecked(secret);
+        sponge.ratchet_unchecked();
+        Self(sponge)
+    }
+}
+
+impl RngCore for DeterministicProverRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0u8; 4];
+        self.fill_bytes(buf.as_mut());
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.fill_bytes(buf.as_mut());
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.squeeze_unchecked(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for DeterministicProverRng {}
+
+impl Drop for DeterministicProverRng {
+    /// Destroy the secret.
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// A cryptographically-secure random number generator given by a function filling byte buffers.
+///
+/// [`Merlin`] expects a random number generator implementing the traits of `rand_core` 0.6.
+/// This adapter allows to seed the prover's coins from any other source,
+/// e.g. a random number generator from a different version of `rand_core`,
+/// without having both versions agree on the traits.
+///
+/// The function must fill its input with cryptographically-secure randomness.
+///
+/// ```
+/// # use nimue::*;
+/// # use rand::RngCore;
+///
+/// // for instance, with rand_core 0.9: `CsprngFn(|buf: &mut [u8]| rng.fill_bytes(buf))`
+/// let csrng = CsprngFn(|buf: &mut [u8]| rand::rngs::OsRng.fill_bytes(buf));
+/// let io = IOPattern::<DefaultHash>::new("📝");
+/// let mut merlin = Merlin::new(&io, csrng);
+/// assert_ne!(merlin.rng().next_u64(), 0, "You won the lottery!");
+/// ```
+#[derive(Clone, Copy)]
+pub struct CsprngFn<F: FnMut(&mut [u8])>(pub F);
+
+impl<F: FnMut(&mut [u8])> RngCore for CsprngFn<F> {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0u8; 4];
+        self.fill_bytes(buf.as_mut());
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.fill_bytes(buf.as_mut());
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        (self.0)(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<F: FnMut(&mut [u8])> CryptoRng for CsprngFn<F> {}
+
 impl<H, U, R> Merlin<H, U, R>
 where
     H: DuplexHash<U>,
@@ -67,6 +201,7 @@ where
             rng,
             safe,
             transcript: Vec::new(),
+            sink: None,
         }
     }
 }
@@ -100,6 +235,8 @@ where
     pub(crate) safe: Safe<H, U>,
     /// The encoded data.
     pub(crate) transcript: Vec<u8>,
+    /// Where prover messages are written to, if not concatenated in `transcript`.
+    pub(crate) sink: Option<Box<dyn TranscriptSink + Send + Sync>>,
 }
 
 impl<H, U, R> Merlin<H, U, R>
@@ -123,6 +260,14 @@ where
     /// ```
     #[inline(always)]
     pub fn add_units(&mut self, input: &[U]) -> Result<(), IOPatternError> {
+        let old_len = self.transcript.len();
+        self.absorb_and_encode(input)?;
+        self.flush_message(old_len);
+        Ok(())
+    }
+
+    /// Absorb `input`, append its encoding to the protocol transcript, and re-seed the prover's random number generator with it.
+    fn absorb_and_encode(&mut self, input: &[U]) -> Result<(), IOPatternError> {
         // let serialized = bincode::serialize(input).unwrap();
         // self.merlin.sponge.absorb_unchecked(&serialized);
         let old_len = self.transcript.len();
@@ -132,16 +277,99 @@ where
         self.rng
             .sponge
             .absorb_unchecked(&self.transcript[old_len..]);
-
         Ok(())
     }
 
+    /// Write the prover messages to `sink`, instead of concatenating them in [`Merlin::transcript`].
+    ///
+    /// Each call writing to the protocol transcript (e.g. [`Merlin::add_units`], or the `add_*` methods of the plugins)
+    /// hands its encoded message to the sink.
+    /// Messages written before the sink is set are handed to the sink at once, as a single message.
+    /// Absorption, and hence Fiat-Shamir, is unaffected by the choice of the sink.
+    ///
+    /// Once a sink is set, the protocol transcript is held by the sink:
+    /// [`Merlin::transcript`] is empty, and [`Merlin::try_transcript`], [`Merlin::seal_segment`]
+    /// and the conversion into a [`Proof`](crate::Proof) fail.
+    ///
+    /// ```
+    /// # use nimue::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct HexLines(Arc<Mutex<Vec<String>>>);
+    ///
+    /// impl TranscriptSink for HexLines {
+    ///     fn write_message(&mut self, message: &[u8]) {
+    ///         self.0.lock().unwrap().push(hex::encode(message));
+    ///     }
+    /// }
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").squeeze(1, "🧂").absorb(2, "🍷");
+    /// let lines = HexLines::default();
+    /// let mut merlin = io.to_merlin().with_sink(lines.clone());
+    /// merlin.add_bytes(&[0x42]).unwrap();
+    /// let chal = merlin.challenge_bytes::<1>().unwrap();
+    /// merlin.add_bytes(&[0xca, 0xfe]).unwrap();
+    /// assert_eq!(*lines.0.lock().unwrap(), ["42", "cafe"]);
+    ///
+    /// // the challenges are the same as with the default concatenation
+    /// let mut arthur = io.to_arthur(&[0x42, 0xca, 0xfe]);
+    /// arthur.next_bytes::<1>().unwrap();
+    /// assert_eq!(arthur.challenge_bytes::<1>().unwrap(), chal);
+    /// ```
+    pub fn with_sink(mut self, sink: impl TranscriptSink + Send + Sync + 'static) -> Self {
+        self.sink = Some(Box::new(sink));
+        if !self.transcript.is_empty() {
+            self.flush_message(0);
+        }
+        self
+    }
+
+    /// Hand the message encoded in the protocol transcript from `start` on to the sink, if any.
+    pub(crate) fn flush_message(&mut self, start: usize) {
+        if let Some(sink) = &mut self.sink {
+            sink.write_message(&self.transcript[start..]);
+            self.transcript.truncate(start);
+        }
+    }
+
+    /// Write an encoded prover message to the protocol transcript (or to the sink, if any).
+    #[cfg(any(feature = "ark", feature = "group"))]
+    pub(crate) fn write_message(&mut self, message: &[u8]) {
+        let start = self.transcript.len();
+        self.transcript.extend_from_slice(message);
+        self.flush_message(start);
+    }
+
     /// Ratchet the verifier's state.
     #[inline(always)]
     pub fn ratchet(&mut self) -> Result<(), IOPatternError> {
         self.safe.ratchet()
     }
 
+    /// Cross the phase boundary `label`. See [`Safe::marker`].
+    pub fn marker(&mut self, label: &str) -> Result<(), IOPatternError>
+    where
+        U: Default,
+    {
+        self.safe.marker(label)
+    }
+
+    /// The total number of operations in the IO Pattern. See [`Safe::total_ops`].
+    pub fn total_ops(&self) -> usize {
+        self.safe.total_ops()
+    }
+
+    /// The number of operations left to perform. See [`Safe::remaining_ops`].
+    pub fn remaining_ops(&self) -> usize {
+        self.safe.remaining_ops()
+    }
+
+    /// Invoke `callback` after each completed operation. See [`Safe::on_op_completed`].
+    pub fn on_op_completed(&mut self, callback: impl Fn(OpProgress<'_>) + Send + Sync + 'static) {
+        self.safe.on_op_completed(callback)
+    }
+
     /// Return a reference to the random number generator associated to the protocol transcript.
     ///
     /// ```
@@ -175,8 +403,44 @@ where
     /// merlin.add_bytes(b"1tbsp:3l").unwrap();
     /// assert_eq!(merlin.transcript(), b"1tbsp:3l");
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the prover messages are written to a sink (see [`Merlin::with_sink`]): they are not held here,
+    /// and returning an empty transcript would silently lose them. Use [`Merlin::try_transcript`] to handle this case.
     pub fn transcript(&self) -> &[u8] {
-        self.transcript.as_slice()
+        self.try_transcript()
+            .expect("The protocol transcript is written to a sink, see Merlin::try_transcript")
+    }
+
+    /// Return the current protocol transcript, as [`Merlin::transcript`].
+    ///
+    /// Fails if the prover messages are written to a sink (see [`Merlin::with_sink`]).
+    pub fn try_transcript(&self) -> Result<&[u8], IOPatternError> {
+        if self.sink.is_some() {
+            return Err("The protocol transcript is written to a sink".into());
+        }
+        Ok(self.transcript.as_slice())
+    }
+}
+
+impl<H, R> Merlin<H, u8, R>
+where
+    H: DuplexHash<u8>,
+    R: RngCore + CryptoRng,
+{
+    /// End the current segment, as declared by [`IOPattern::segment`].
+    ///
+    /// Returns the protocol transcript of the segment, and the chaining digest
+    /// from which the next segment can be verified independently (see [`IOPattern::to_arthur_segment`]).
+    ///
+    /// Fails if the prover messages are written to a sink (see [`Merlin::with_sink`]).
+    pub fn seal_segment(&mut self) -> Result<(Vec<u8>, [u8; 32]), IOPatternError> {
+        if self.sink.is_some() {
+            return Err("Cannot seal a segment written to a sink".into());
+        }
+        let digest = self.safe.seal_segment()?;
+        Ok((core::mem::take(&mut self.transcript), digest))
     }
 }
 
@@ -200,7 +464,7 @@ where
     /// ```
     fn public_units(&mut self, input: &[U]) -> Result<(), IOPatternError> {
         let len = self.transcript.len();
-        self.add_units(input)?;
+        self.absorb_and_encode(input)?;
         self.transcript.truncate(len);
         Ok(())
     }
@@ -209,10 +473,28 @@ where
     fn fill_challenge_units(&mut self, output: &mut [U]) -> Result<(), IOPatternError> {
         self.safe.squeeze(output)
     }
+
+    /// Fill an uninitialized buffer with challenges, without initializing it first.
+    fn fill_challenge_units_uninit<'b>(
+        &mut self,
+        output: &'b mut [MaybeUninit<U>],
+    ) -> Result<&'b mut [U], IOPatternError>
+    where
+        U: Default,
+    {
+        self.safe.squeeze_uninit(output)
+    }
 }
 
 impl<R: RngCore + CryptoRng> CryptoRng for ProverRng<R> {}
 
+impl<R: RngCore + CryptoRng> Drop for ProverRng<R> {
+    /// Destroy the secret coins of the prover.
+    fn drop(&mut self) {
+        self.sponge.zeroize();
+    }
+}
+
 impl<H, U, R> core::fmt::Debug for Merlin<H, U, R>
 where
     U: Unit,
@@ -234,3 +516,43 @@ where
         self.add_units(input)
     }
 }
+
+/// Prover messages written as a byte stream, e.g. by a serializer, are added to the protocol transcript.
+///
+/// Each write is a (possibly partial) absorb: writes can be split arbitrarily,
+/// as long as their total length matches the absorbs declared in the IO Pattern.
+/// A write that does not follow the IO Pattern fails with an error of kind [`std::io::ErrorKind::InvalidInput`],
+/// wrapping the [`IOPatternError`] (recovered when converting the error back into an [`IOPatternError`]).
+///
+/// ```
+/// # use nimue::*;
+/// use std::io::Write;
+///
+/// let io = IOPattern::<DefaultHash>::new("📝").absorb(8, "header").squeeze(16, "🍷");
+/// let mut merlin = io.to_merlin();
+/// write!(merlin, "v1:").unwrap();
+/// merlin.write_all(&[1, 2, 3, 4, 5]).unwrap();
+/// assert_eq!(merlin.transcript(), b"v1:\x01\x02\x03\x04\x05");
+///
+/// // writes beyond the declared absorb are rejected
+/// let mut merlin = io.to_merlin();
+/// let err = merlin.write_all(&[0u8; 9]).unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+/// assert!(IOPatternError::from(err).to_string().contains("Invalid tag"));
+/// ```
+impl<H, R> std::io::Write for Merlin<H, u8, R>
+where
+    H: DuplexHash<u8>,
+    R: RngCore + CryptoRng,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.add_units(buf)
+            .map(|()| buf.len())
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))
+    }
+
+    /// Messages are added to the protocol transcript as they are written: flushing is a no-op.
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
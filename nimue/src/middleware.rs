@@ -0,0 +1,260 @@
+//! Hooks on the operations of a prover (or verifier) state.
+//!
+//! A [`LayeredTranscript`] wraps a [`Merlin`] (or an [`Arthur`]) and calls a [`TranscriptMiddleware`]
+//! on every absorb, squeeze, and ratchet, before forwarding it to the inner state.
+//! The wrapper only gives read access to the inner state, so that no operation can skip the middleware.
+//! Middlewares can observe operations (e.g. for metrics or tracing), reject them (e.g. to enforce a policy),
+//! or transform the challenges returned to the caller.
+//!
+//! ```
+//! use nimue::*;
+//! use nimue::middleware::{LayeredTranscript, TranscriptMiddleware};
+//!
+//! /// Refuse to squeeze challenges before the statement is sealed with a ratchet.
+//! #[derive(Default)]
+//! struct RatchetFirst(bool);
+//!
+//! impl TranscriptMiddleware for RatchetFirst {
+//!     fn before_challenge(&mut self, _len: usize) -> Result<(), IOPatternError> {
+//!         match self.0 {
+//!             true => Ok(()),
+//!             false => Err("challenge squeezed before the statement ratchet".to_string().into()),
+//!         }
+//!     }
+//!
+//!     fn on_ratchet(&mut self) -> Result<(), IOPatternError> {
+//!         self.0 = true;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let io = IOPattern::<DefaultHash>::new("📝").absorb(4, "statement").ratchet().squeeze(16, "chal");
+//! let mut merlin = LayeredTranscript::new(io.to_merlin(), RatchetFirst::default());
+//! merlin.public_bytes(b"pk42").unwrap();
+//! merlin.ratchet().unwrap();
+//! merlin.challenge_bytes::<16>().unwrap();
+//!
+//! let io = IOPattern::<DefaultHash>::new("📝").absorb(4, "statement").squeeze(16, "chal");
+//! let mut merlin = LayeredTranscript::new(io.to_merlin(), RatchetFirst::default());
+//! merlin.public_bytes(b"pk42").unwrap();
+//! assert!(merlin.challenge_bytes::<16>().is_err());
+//! ```
+use core::ops::Deref;
+
+use rand::{CryptoRng, RngCore};
+
+use crate::hash::{DuplexHash, Unit};
+use crate::{Arthur, ByteReader, ByteWriter, IOPatternError, Merlin, ProofError, UnitTranscript};
+
+/// Hooks called by a [`LayeredTranscript`] on each operation of the inner state.
+///
+/// All hooks default to accepting the operation unchanged.
+/// Returning an error aborts the operation, before it reaches the inner state
+/// (after, for [`TranscriptMiddleware::on_message`] when reading and for [`TranscriptMiddleware::on_challenge`]).
+pub trait TranscriptMiddleware<U: Unit = u8> {
+    /// Called on each prover message, before it is written (resp. after it is read).
+    fn on_message(&mut self, _message: &[U]) -> Result<(), IOPatternError> {
+        Ok(())
+    }
+
+    /// Called on each public message, before it is absorbed.
+    fn on_public(&mut self, _input: &[U]) -> Result<(), IOPatternError> {
+        Ok(())
+    }
+
+    /// Called before squeezing `len` challenges.
+    fn before_challenge(&mut self, _len: usize) -> Result<(), IOPatternError> {
+        Ok(())
+    }
+
+    /// Called on the challenges squeezed, before they are returned to the caller.
+    fn on_challenge(&mut self, _output: &mut [U]) -> Result<(), IOPatternError> {
+        Ok(())
+    }
+
+    /// Called before ratcheting, including at the end of a segment (see [`Merlin::seal_segment`]).
+    fn on_ratchet(&mut self) -> Result<(), IOPatternError> {
+        Ok(())
+    }
+
+    /// Called before crossing the phase boundary `label` (see [`Merlin::marker`]).
+    fn on_marker(&mut self, _label: &str) -> Result<(), IOPatternError> {
+        Ok(())
+    }
+}
+
+/// The middleware that accepts every operation unchanged.
+impl<U: Unit> TranscriptMiddleware<U> for () {}
+
+/// A prover (or verifier) state, whose operations go through a [`TranscriptMiddleware`].
+///
+/// Dereferences to the inner state for reading (e.g. [`Merlin::transcript`] and [`Merlin::remaining_ops`]).
+/// There is no mutable access to the inner state:
+/// operations go through the wrapper, which provides the same traits and methods as the inner state.
+/// Settings that do not operate on the transcript (e.g. [`Merlin::with_sink`]) are applied before wrapping.
+pub struct LayeredTranscript<T, M> {
+    pub(crate) inner: T,
+    pub(crate) middleware: M,
+}
+
+impl<T, M> LayeredTranscript<T, M> {
+    /// Wrap `inner`, calling `middleware` on each of its operations.
+    pub fn new(inner: T, middleware: M) -> Self {
+        Self { inner, middleware }
+    }
+
+    /// The middleware, e.g. to read the metrics it collected.
+    pub fn middleware(&self) -> &M {
+        &self.middleware
+    }
+
+    /// Return the inner state and the middleware.
+    pub fn into_parts(self) -> (T, M) {
+        (self.inner, self.middleware)
+    }
+}
+
+impl<T, M> Deref for LayeredTranscript<T, M> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T, M, U> UnitTranscript<U> for LayeredTranscript<T, M>
+where
+    U: Unit,
+    T: UnitTranscript<U>,
+    M: TranscriptMiddleware<U>,
+{
+    fn public_units(&mut self, input: &[U]) -> Result<(), IOPatternError> {
+        self.middleware.on_public(input)?;
+        self.inner.public_units(input)
+    }
+
+    fn fill_challenge_units(&mut self, output: &mut [U]) -> Result<(), IOPatternError> {
+        self.middleware.before_challenge(output.len())?;
+        self.inner.fill_challenge_units(output)?;
+        self.middleware.on_challenge(output)
+    }
+}
+
+impl<T, M> ByteWriter for LayeredTranscript<T, M>
+where
+    T: ByteWriter,
+    M: TranscriptMiddleware<u8>,
+{
+    fn add_bytes(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
+        self.middleware.on_message(input)?;
+        self.inner.add_bytes(input)
+    }
+}
+
+impl<T, M> ByteReader for LayeredTranscript<T, M>
+where
+    T: ByteReader,
+    M: TranscriptMiddleware<u8>,
+{
+    fn fill_next_bytes(&mut self, input: &mut [u8]) -> Result<(), IOPatternError> {
+        self.inner.fill_next_bytes(input)?;
+        self.middleware.on_message(input)
+    }
+}
+
+impl<H, U, R, M> LayeredTranscript<Merlin<H, U, R>, M>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+    R: RngCore + CryptoRng,
+    M: TranscriptMiddleware<U>,
+{
+    /// Write `input` to the protocol transcript, see [`Merlin::add_units`].
+    pub fn add_units(&mut self, input: &[U]) -> Result<(), IOPatternError> {
+        self.middleware.on_message(input)?;
+        self.inner.add_units(input)
+    }
+
+    /// Ratchet the inner state, see [`Merlin::ratchet`].
+    pub fn ratchet(&mut self) -> Result<(), IOPatternError> {
+        self.middleware.on_ratchet()?;
+        self.inner.ratchet()
+    }
+
+    /// Cross the phase boundary `label`, see [`Merlin::marker`].
+    pub fn marker(&mut self, label: &str) -> Result<(), IOPatternError>
+    where
+        U: Default,
+    {
+        self.middleware.on_marker(label)?;
+        self.inner.marker(label)
+    }
+
+    /// The random number generator of the inner state, see [`Merlin::rng`].
+    pub fn rng(&mut self) -> &mut (impl CryptoRng + RngCore) {
+        self.inner.rng()
+    }
+}
+
+impl<H, R, M> LayeredTranscript<Merlin<H, u8, R>, M>
+where
+    H: DuplexHash<u8>,
+    R: RngCore + CryptoRng,
+    M: TranscriptMiddleware<u8>,
+{
+    /// End the current segment, see [`Merlin::seal_segment`].
+    pub fn seal_segment(&mut self) -> Result<(Vec<u8>, [u8; 32]), IOPatternError> {
+        self.middleware.on_ratchet()?;
+        self.inner.seal_segment()
+    }
+}
+
+impl<H, U, M> LayeredTranscript<Arthur<'_, H, U>, M>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+    M: TranscriptMiddleware<U>,
+{
+    /// Read `input.len()` units from the protocol transcript, see [`Arthur::fill_next_units`].
+    pub fn fill_next_units(&mut self, input: &mut [U]) -> Result<(), IOPatternError> {
+        self.inner.fill_next_units(input)?;
+        self.middleware.on_message(input)
+    }
+
+    /// Read and absorb `count` units, without returning them, see [`Arthur::skip_units`].
+    ///
+    /// The units are read in small batches, each handed to the middleware.
+    pub fn skip_units(&mut self, count: usize) -> Result<(), IOPatternError>
+    where
+        U: Default,
+    {
+        let mut buf: [U; 32] = core::array::from_fn(|_| U::default());
+        let mut remaining = count;
+        while remaining > 0 {
+            let len = remaining.min(buf.len());
+            self.fill_next_units(&mut buf[..len])?;
+            remaining -= len;
+        }
+        Ok(())
+    }
+
+    /// Ratchet the inner state, see [`Arthur::ratchet`].
+    pub fn ratchet(&mut self) -> Result<(), IOPatternError> {
+        self.middleware.on_ratchet()?;
+        self.inner.ratchet()
+    }
+
+    /// Cross the phase boundary `label`, see [`Arthur::marker`].
+    pub fn marker(&mut self, label: &str) -> Result<(), IOPatternError>
+    where
+        U: Default,
+    {
+        self.middleware.on_marker(label)?;
+        self.inner.marker(label)
+    }
+
+    /// Record a failure at the current operation, see [`Arthur::fail_at_current_op`].
+    pub fn fail_at_current_op(&mut self, reason: impl Into<String>) -> ProofError {
+        self.inner.fail_at_current_op(reason)
+    }
+}
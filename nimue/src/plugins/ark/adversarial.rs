@@ -0,0 +1,188 @@
+//! Structurally valid transcripts with adversarial contents.
+//!
+//! Random transcripts (see [`IOPattern::simulate`]) are almost always rejected at the first decoding error,
+//! and thus exercise little of a verifier.
+//! [`AdversarialTranscripts`] starts instead from an honest transcript, and replaces one element of one prover message at a time
+//! with an adversarial encoding: non-canonical or boundary field elements, invalid or identity points.
+//! The layout of the transcript (the length of each prover message) is preserved,
+//! so that every mutation reaches the decoding, or the verification equation, of the targeted element.
+//!
+//! ```
+//! use ark_bls12_381::G1Projective as G;
+//! use nimue::DefaultHash;
+//! use nimue::plugins::ark::adversarial::AdversarialTranscripts;
+//! use nimue::plugins::ark::IOPattern;
+//! use nimue::protocols::schnorr::{self, SchnorrIOPattern};
+//!
+//! let (sk, pk) = schnorr::keygen::<G>(&mut rand::rngs::OsRng);
+//! let signature = schnorr::sign::<G, DefaultHash>("my-app", sk, b"hello").unwrap();
+//!
+//! let io = <IOPattern as SchnorrIOPattern<G>>::new_schnorr_signature("my-app", 5);
+//! let adversary = AdversarialTranscripts::new(&io, &signature, &["public key (X)", "message"]).unwrap();
+//! let mutations = adversary.points::<G>("commitment (K)").into_iter()
+//!     .chain(adversary.scalars::<ark_bls12_381::Fr>("response (r)"));
+//! for mutation in mutations {
+//!     assert!(schnorr::verify::<G, DefaultHash>("my-app", pk, b"hello", &mutation.transcript).is_err(), "{:?}", mutation.kind);
+//! }
+//! ```
+use core::ops::Range;
+
+use ark_ec::CurveGroup;
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use crate::{DuplexHash, IOPattern, IOPatternError};
+
+/// The number of candidates tried when looking for the encoding of an invalid point.
+const INVALID_POINT_CANDIDATES: u64 = 256;
+
+/// The adversarial contents of a [`Mutation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MutationKind {
+    /// The modulus of the field, a non-canonical encoding of zero.
+    Modulus,
+    /// All bits set: a non-canonical field element, or a point encoding with invalid flags.
+    AllOnes,
+    /// The field element zero.
+    Zero,
+    /// The field element one.
+    One,
+    /// The field element minus one.
+    MinusOne,
+    /// A well-formed encoding of a point that is not on the curve, or not in the prime-order subgroup.
+    InvalidPoint,
+    /// The identity point.
+    Identity,
+}
+
+/// A transcript where one element of a prover message has been replaced.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mutation {
+    /// The label of the prover message.
+    pub label: String,
+    /// The index of the replaced element within the prover message.
+    pub index: usize,
+    /// The adversarial contents of the replaced element.
+    pub kind: MutationKind,
+    /// The mutated transcript.
+    pub transcript: Vec<u8>,
+}
+
+/// A generator of adversarial transcripts, built from an honest transcript and its [`IOPattern`].
+#[derive(Clone, Debug)]
+pub struct AdversarialTranscripts {
+    transcript: Vec<u8>,
+    messages: Vec<(String, Range<usize>)>,
+}
+
+impl AdversarialTranscripts {
+    /// Locate the prover messages of `io_pattern` within the honest `transcript`.
+    ///
+    /// The IO Pattern does not distinguish prover messages from public elements, that are absorbed but not written in the transcript:
+    /// absorbs labeled as one of `public` are skipped.
+    /// Fails if the remaining absorbs do not add up to the length of `transcript`.
+    pub fn new<H: DuplexHash>(
+        io_pattern: &IOPattern<H>,
+        transcript: &[u8],
+        public: &[&str],
+    ) -> Result<Self, IOPatternError> {
+        let mut messages = Vec::new();
+        let mut offset = 0;
+        for (label, len) in io_pattern.absorbs()? {
+            if !public.contains(&label.as_str()) {
+                messages.push((label, offset..offset + len));
+                offset += len;
+            }
+        }
+        if offset != transcript.len() {
+            return Err(format!(
+                "Transcript has {} bytes, but the prover messages add up to {}",
+                transcript.len(),
+                offset
+            )
+            .into());
+        }
+        Ok(Self {
+            transcript: transcript.to_vec(),
+            messages,
+        })
+    }
+
+    /// Mutations of the prover messages labeled `label`, seen as sequences of elements of the field `F`.
+    pub fn scalars<F: PrimeField>(&self, label: &str) -> Vec<Mutation> {
+        let size = F::zero().compressed_size();
+        let encode = |x: F| {
+            let mut buf = Vec::with_capacity(size);
+            x.serialize_compressed(&mut buf)
+                .expect("serialization to a vector does not fail");
+            buf
+        };
+        let mut modulus = F::MODULUS.to_bytes_le();
+        modulus.resize(size, 0);
+        let contents = [
+            (MutationKind::Modulus, modulus),
+            (MutationKind::AllOnes, vec![0xff; size]),
+            (MutationKind::Zero, encode(F::zero())),
+            (MutationKind::One, encode(F::one())),
+            (MutationKind::MinusOne, encode(-F::one())),
+        ];
+        self.mutate(label, size, &contents)
+    }
+
+    /// Mutations of the prover messages labeled `label`, seen as sequences of points of `G`.
+    ///
+    /// Invalid points are searched among the encodings of small $x$-coordinates,
+    /// and omitted if the search fails or if points are not encoded as a single coordinate.
+    pub fn points<G: CurveGroup>(&self, label: &str) -> Vec<Mutation> {
+        let size = G::zero().compressed_size();
+        let mut identity = Vec::with_capacity(size);
+        G::zero()
+            .serialize_compressed(&mut identity)
+            .expect("serialization to a vector does not fail");
+        let mut contents = vec![
+            (MutationKind::AllOnes, vec![0xff; size]),
+            (MutationKind::Identity, identity),
+        ];
+        contents.extend(invalid_point::<G>(size).map(|point| (MutationKind::InvalidPoint, point)));
+        self.mutate(label, size, &contents)
+    }
+
+    /// Replace, one at a time, each `size`-byte element of the messages labeled `label` with each of `contents`.
+    fn mutate(
+        &self,
+        label: &str,
+        size: usize,
+        contents: &[(MutationKind, Vec<u8>)],
+    ) -> Vec<Mutation> {
+        let mut mutations = Vec::new();
+        for (_, range) in self.messages.iter().filter(|(l, _)| l == label) {
+            for index in 0..range.len() / size {
+                let start = range.start + index * size;
+                for (kind, bytes) in contents {
+                    let mut transcript = self.transcript.clone();
+                    transcript[start..start + size].copy_from_slice(bytes);
+                    if transcript != self.transcript {
+                        mutations.push(Mutation {
+                            label: label.to_string(),
+                            index,
+                            kind: *kind,
+                            transcript,
+                        });
+                    }
+                }
+            }
+        }
+        mutations
+    }
+}
+
+/// Find the `size`-byte encoding of an $x$-coordinate that does not decode to a valid point of `G`.
+fn invalid_point<G: CurveGroup>(size: usize) -> Option<Vec<u8>> {
+    (0..INVALID_POINT_CANDIDATES).find_map(|x| {
+        let mut buf = Vec::with_capacity(size);
+        G::BaseField::from(x).serialize_compressed(&mut buf).ok()?;
+        let invalid =
+            buf.len() == size && G::Affine::deserialize_compressed(buf.as_slice()).is_err();
+        invalid.then_some(buf)
+    })
+}
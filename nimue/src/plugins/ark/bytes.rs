@@ -0,0 +1,188 @@
+//! A duplex hash over bytes, running a duplex hash over a prime field.
+//!
+//! [`BytesOverField`] lets an algebraic hash (e.g. Poseidon) be used wherever a [`DuplexHash<u8>`] is expected,
+//! for instance as a backend selected at runtime alongside byte-oriented hashes (see [`crate::hash::AnyDuplexHash`]).
+//! Bytes are packed as byte messages in transcripts over field units (see [`BytePublic`](crate::BytePublic)):
+//! each absorb is padded with a `0x01` byte and packed as little-endian chunks of `(MODULUS_BIT_SIZE - 1) / 8` bytes.
+//! Squeezed bytes are the least significant bytes of each squeezed element, as many as are statistically close to uniform.
+//!
+//! Consecutive absorbs (resp. squeezes) are buffered, so that absorbing (resp. squeezing) in several calls
+//! is the same as doing it at once.
+//!
+//! ```
+//! # #[cfg(feature = "test-utils")]
+//! # fn main() {
+//! use nimue::*;
+//! use nimue::hash::sponge::DuplexSponge;
+//! use nimue::plugins::ark::bytes::BytesOverField;
+//! use nimue::testing::ToySponge;
+//! use ark_bls12_381::Fr;
+//!
+//! type H = BytesOverField<DuplexSponge<ToySponge<Fr>>, Fr>;
+//! let io = IOPattern::<H>::new("📝").absorb(1, "🍝").squeeze(16, "🍷");
+//! let mut merlin = io.to_merlin();
+//! merlin.add_bytes(&[0x42]).unwrap();
+//! let chal = merlin.challenge_bytes::<16>().unwrap();
+//!
+//! let mut arthur = io.to_arthur(merlin.transcript());
+//! assert_eq!(arthur.next_bytes().unwrap(), [0x42]);
+//! assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+//! # }
+//! # #[cfg(not(feature = "test-utils"))]
+//! # fn main() {}
+//! ```
+use std::marker::PhantomData;
+
+use ark_ff::{BigInteger, Fp, FpConfig, PrimeField};
+use zeroize::Zeroize;
+
+use crate::hash::iv_from_bytes;
+use crate::{DuplexHash, OpParseError};
+
+/// A duplex hash over bytes, running the duplex hash `H` over the prime field `F`.
+///
+/// # Panics
+///
+/// Squeezing panics if the modulus of `F` has at most 128 bits: no byte of its elements is close to uniform.
+#[derive(Clone)]
+pub struct BytesOverField<H, F> {
+    sponge: H,
+    /// Bytes absorbed since the last squeeze or ratchet, not packed yet.
+    absorbed: Vec<u8>,
+    /// Whether bytes were absorbed since the last squeeze or ratchet, so that the padding is due.
+    absorbing: bool,
+    /// Bytes squeezed from the last element, not output yet.
+    squeezed: Vec<u8>,
+    _field: PhantomData<F>,
+}
+
+impl<H, C, const N: usize> BytesOverField<H, Fp<C, N>>
+where
+    H: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    /// The number of bytes packed in each field element, so that any packing is smaller than the modulus.
+    const PACKED_BYTES: usize = (Fp::<C, N>::MODULUS_BIT_SIZE as usize - 1) / 8;
+
+    fn from_sponge(sponge: H) -> Self {
+        Self {
+            sponge,
+            absorbed: Vec::new(),
+            absorbing: false,
+            squeezed: Vec::new(),
+            _field: PhantomData,
+        }
+    }
+
+    /// Absorb the bytes of `self.absorbed` that fill whole field elements.
+    fn absorb_packed(&mut self, len: usize) {
+        let units = self.absorbed[..len]
+            .chunks(Self::PACKED_BYTES)
+            .map(Fp::from_le_bytes_mod_order)
+            .collect::<Vec<_>>();
+        self.sponge.absorb_unchecked(&units);
+        self.absorbed.drain(..len);
+    }
+
+    /// Pad and absorb the pending bytes, if an absorb is in progress.
+    fn end_absorb(&mut self) {
+        if self.absorbing {
+            self.absorbed.push(1);
+            self.absorb_packed(self.absorbed.len());
+            self.absorbing = false;
+        }
+    }
+}
+
+impl<H, C, const N: usize> Default for BytesOverField<H, Fp<C, N>>
+where
+    H: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    fn default() -> Self {
+        Self::from_sponge(H::default())
+    }
+}
+
+impl<H: Zeroize, F> Zeroize for BytesOverField<H, F> {
+    fn zeroize(&mut self) {
+        self.sponge.zeroize();
+        self.absorbed.zeroize();
+        self.absorbing = false;
+        self.squeezed.zeroize();
+    }
+}
+
+impl<H, C, const N: usize> DuplexHash<u8> for BytesOverField<H, Fp<C, N>>
+where
+    H: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    fn new(iv: [u8; 32]) -> Self {
+        Self::from_sponge(H::new(iv))
+    }
+
+    fn new_for_io_pattern(
+        io_pattern: &[u8],
+        digest: [u8; 32],
+        iv: [u8; 32],
+    ) -> Result<Self, OpParseError> {
+        H::new_for_io_pattern(io_pattern, digest, iv).map(Self::from_sponge)
+    }
+
+    fn new_like(&self, iv: [u8; 32]) -> Self {
+        Self::from_sponge(self.sponge.new_like(iv))
+    }
+
+    fn iv_for_io_pattern(io_pattern: &[u8]) -> Option<[u8; 32]> {
+        Some(iv_from_bytes::<Self>(io_pattern))
+    }
+
+    fn absorb_unchecked(&mut self, input: &[u8]) -> &mut Self {
+        if input.is_empty() {
+            return self;
+        }
+        self.squeezed.clear();
+        self.absorbing = true;
+        self.absorbed.extend_from_slice(input);
+        // keep the last (possibly full) chunk, so that the padding is packed with it.
+        let full = (self.absorbed.len() - 1) / Self::PACKED_BYTES * Self::PACKED_BYTES;
+        self.absorb_packed(full);
+        self
+    }
+
+    fn squeeze_unchecked(&mut self, output: &mut [u8]) -> &mut Self {
+        self.end_absorb();
+        let len_good = crate::plugins::random_bytes_in_random_modp(Fp::<C, N>::MODULUS);
+        assert!(len_good > 0, "Cannot squeeze uniform bytes from the field.");
+        let mut filled = 0;
+        while filled < output.len() {
+            if self.squeezed.is_empty() {
+                let mut unit = [Fp::default()];
+                self.sponge.squeeze_unchecked(&mut unit);
+                let mut bytes = unit[0].into_bigint().to_bytes_le();
+                bytes.truncate(len_good);
+                self.squeezed = bytes;
+            }
+            let len = (output.len() - filled).min(self.squeezed.len());
+            output[filled..filled + len].copy_from_slice(&self.squeezed[..len]);
+            self.squeezed.drain(..len);
+            filled += len;
+        }
+        self
+    }
+
+    fn ratchet_unchecked(&mut self) -> &mut Self {
+        self.end_absorb();
+        self.squeezed.clear();
+        self.sponge.ratchet_unchecked();
+        self
+    }
+}
+
+impl<H, F> core::fmt::Debug for BytesOverField<H, F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Ensure that the state isn't accidentally logged
+        write!(f, "BytesOverField: STATE OMITTED")
+    }
+}
@@ -1,37 +1,67 @@
 use std::io;
 
+use ark_ec::short_weierstrass::{Affine as SWAffine, Projective as SWCurve, SWCurveConfig};
+use ark_ec::twisted_edwards::{Affine as EdwardsAffine, Projective as EdwardsCurve, TECurveConfig};
 use ark_ec::{AffineRepr, CurveGroup};
-use ark_ff::{BigInteger, Field, Fp, FpConfig, PrimeField};
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_ff::{BigInteger, Field, Fp, FpConfig, PrimeField, Zero};
+use ark_serialize::{CanonicalSerialize, SerializationError};
 use rand::{CryptoRng, RngCore};
 
-use super::{FieldChallenges, FieldPublic, GroupPublic};
-use crate::plugins::bytes_uniform_modp;
+use super::{
+    AffineCoordinates, CosetDomain, DistinctChallenges, DomainPointChallenges, FieldChallenges,
+    FieldPublic, GroupPublic, ReprBytes, VerifyingKeyPublic,
+};
+use crate::collaborative::AdditiveShare;
+use crate::middleware::{LayeredTranscript, TranscriptMiddleware};
+use crate::plugins::{bytes_modp, bytes_uniform_modp};
 use crate::{
     Arthur, ByteChallenges, BytePublic, DuplexHash, IOPatternError, Merlin, ProofError,
-    ProofResult, Unit, UnitTranscript,
+    ProofResult, Safe, Unit, UnitTranscript,
 };
 
 // Implementation of basic traits for bridging arkworks and nimue
 
+/// Field elements are encoded in canonical form, as fixed-size little-endian strings of `bytes_modp(MODULUS_BIT_SIZE)` bytes.
 impl<C: FpConfig<N>, const N: usize> Unit for Fp<C, N> {
-    fn write(bunch: &[Self], mut w: &mut impl io::Write) -> Result<(), io::Error> {
+    const SIZE: Option<usize> = Some(bytes_modp(Self::MODULUS_BIT_SIZE));
+
+    fn write(bunch: &[Self], w: &mut impl io::Write) -> Result<(), io::Error> {
+        let size = bytes_modp(Self::MODULUS_BIT_SIZE);
         for b in bunch {
-            b.serialize_compressed(&mut w)
-                .map_err(|_| io::Error::new(io::ErrorKind::Other, "oh no!"))?
+            w.write_all(&b.into_bigint().to_bytes_le()[..size])?;
         }
         Ok(())
     }
 
-    fn read(mut r: &mut impl io::Read, bunch: &mut [Self]) -> Result<(), io::Error> {
+    fn read(r: &mut impl io::Read, bunch: &mut [Self]) -> Result<(), io::Error> {
+        let size = bytes_modp(Self::MODULUS_BIT_SIZE);
+        let mut buf = vec![0u8; size];
         for b in bunch.iter_mut() {
-            let b_result = Fp::deserialize_compressed(&mut r);
-            *b = b_result.map_err(|_| {
-                io::Error::new(io::ErrorKind::Other, "Unable to deserialize into Field.")
-            })?
+            r.read_exact(&mut buf)?;
+            *b = Self::from_le_bytes_mod_order(&buf);
+            // reject encodings of integers larger than the modulus
+            if b.into_bigint().to_bytes_le()[..size] != buf {
+                return Err(io::Error::other("Non-canonical field element encoding"));
+            }
         }
         Ok(())
     }
+
+    /// Bytes are absorbed padded and packed, `(MODULUS_BIT_SIZE - 1) / 8` per field element (see [`BytePublic`]).
+    fn units_for_absorbed_bytes(count: usize) -> usize {
+        (count + 1).div_ceil(packed_bytes::<C, N>())
+    }
+
+    /// Each field element squeezed yields the bytes that are statistically close to uniform (see [`ByteChallenges`](crate::ByteChallenges)).
+    fn units_for_squeezed_bytes(count: usize) -> usize {
+        count.div_ceil(crate::plugins::random_bytes_in_random_modp(Self::MODULUS))
+    }
+}
+
+impl<C: FpConfig<N>, const N: usize> AdditiveShare for Fp<C, N> {
+    fn add_share(&self, other: &Self) -> Self {
+        *self + other
+    }
 }
 
 impl From<SerializationError> for ProofError {
@@ -40,6 +70,29 @@ impl From<SerializationError> for ProofError {
     }
 }
 
+impl ReprBytes for Vec<u8> {
+    fn repr_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+impl<C: FpConfig<N>, const N: usize> ReprBytes for Vec<Fp<C, N>> {
+    fn repr_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        Fp::<C, N>::write(self, &mut bytes).expect("Vec writes never fail");
+        bytes
+    }
+}
+
+impl<T: BytePublic> VerifyingKeyPublic for T {
+    fn add_verifying_key<VK: CanonicalSerialize>(&mut self, vk: &VK) -> ProofResult<()> {
+        let mut buf = Vec::with_capacity(vk.compressed_size());
+        vk.serialize_compressed(&mut buf)?;
+        self.public_bytes(&buf)?;
+        Ok(())
+    }
+}
+
 // Bytes <-> Field elements interactions:
 
 impl<T, G> GroupPublic<G> for T
@@ -96,31 +149,145 @@ where
     }
 }
 
-impl<H, C, const N: usize> FieldChallenges<Fp<C, N>> for Arthur<'_, H, Fp<C, N>>
+impl<F, T> DistinctChallenges<F> for T
+where
+    F: Field,
+    T: ByteChallenges,
+{
+    fn fill_challenge_scalars_distinct(&mut self, output: &mut [F]) -> ProofResult<()> {
+        // the field has at least 2^{(MODULUS_BIT_SIZE - 1) * extension_degree} elements
+        let log_size =
+            (F::BasePrimeField::MODULUS_BIT_SIZE - 1) as usize * F::extension_degree() as usize;
+        if log_size < usize::BITS as usize && output.len() > 1 << log_size {
+            return Err(
+                IOPatternError::from("Not enough field elements for distinct challenges").into(),
+            );
+        }
+
+        let seed: [u8; 32] = self.challenge_bytes()?;
+        let mut stream = crate::hash::Keccak::new(seed);
+        stream.absorb_unchecked(b"nimue/distinct-scalars");
+        let mut seen = std::collections::HashSet::with_capacity(output.len());
+        for o in output.iter_mut() {
+            *o = loop {
+                let candidate = squeeze_scalar_unchecked(&mut stream);
+                if seen.insert(candidate) {
+                    break candidate;
+                }
+            };
+        }
+        Ok(())
+    }
+}
+
+impl<F, T> DomainPointChallenges<F> for T
+where
+    F: Field,
+    T: ByteChallenges,
+{
+    fn challenge_domain_point(&mut self, domain: &CosetDomain<F>) -> ProofResult<F> {
+        // every point satisfies x^0 = g^0, so no candidate would ever be accepted
+        if domain.size == 0 {
+            return Err(IOPatternError::from("The domain has size zero").into());
+        }
+        // the multiplicative group has at least 2^{(MODULUS_BIT_SIZE - 1) * extension_degree} - 1 elements
+        let log_size =
+            (F::BasePrimeField::MODULUS_BIT_SIZE - 1) as usize * F::extension_degree() as usize;
+        if log_size < u64::BITS as usize && domain.size >= (1 << log_size) - 1 {
+            return Err(IOPatternError::from("The domain covers the whole field").into());
+        }
+
+        let seed: [u8; 32] = self.challenge_bytes()?;
+        let mut stream = crate::hash::Keccak::new(seed);
+        stream.absorb_unchecked(b"nimue/domain-point");
+        for _ in 0..MAX_DOMAIN_POINT_CANDIDATES {
+            let candidate = squeeze_scalar_unchecked(&mut stream);
+            if !domain.contains(&candidate) {
+                return Ok(candidate);
+            }
+        }
+        Err(IOPatternError::from("No out-of-domain challenge found").into())
+    }
+}
+
+/// The number of candidates sampled by [`DomainPointChallenges::challenge_domain_point`] before giving up.
+///
+/// Unless the domain covers most of the field, all of them lie in the domain with negligible probability.
+const MAX_DOMAIN_POINT_CANDIDATES: usize = 1 << 10;
+
+/// Squeeze a field element from a [`Keccak`](crate::hash::Keccak) stream, outside of any IO Pattern.
+fn squeeze_scalar_unchecked<F: Field>(stream: &mut crate::hash::Keccak) -> F {
+    let base_field_size = bytes_uniform_modp(F::BasePrimeField::MODULUS_BIT_SIZE);
+    let mut buf = vec![0u8; F::extension_degree() as usize * base_field_size];
+    stream.squeeze_unchecked(&mut buf);
+    F::from_base_prime_field_elems(
+        buf.chunks(base_field_size)
+            .map(F::BasePrimeField::from_be_bytes_mod_order),
+    )
+    .expect("Could not convert")
+}
+
+/// Squeeze `F::extension_degree()` native elements for each challenge in `output`.
+fn fill_challenge_scalars_from_units<F, T, C, const N: usize>(
+    transcript: &mut T,
+    output: &mut [F],
+) -> ProofResult<()>
+where
+    F: Field<BasePrimeField = Fp<C, N>>,
+    T: UnitTranscript<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    let mut buf = vec![Fp::<C, N>::from(0); F::extension_degree() as usize];
+    for o in output.iter_mut() {
+        transcript.fill_challenge_units(&mut buf)?;
+        *o = F::from_base_prime_field_elems(buf.iter().copied()).expect("Could not convert");
+    }
+    Ok(())
+}
+
+impl<F, H, C, const N: usize> FieldChallenges<F> for Arthur<'_, H, Fp<C, N>>
 where
+    F: Field<BasePrimeField = Fp<C, N>>,
     C: FpConfig<N>,
     H: DuplexHash<Fp<C, N>>,
 {
-    fn fill_challenge_scalars(&mut self, output: &mut [Fp<C, N>]) -> ProofResult<()> {
-        self.fill_challenge_units(output)
-            .map_err(ProofError::InvalidIO)
+    fn fill_challenge_scalars(&mut self, output: &mut [F]) -> ProofResult<()> {
+        fill_challenge_scalars_from_units(self, output)
     }
 }
 
-impl<H, C, R, const N: usize> FieldChallenges<Fp<C, N>> for Merlin<H, Fp<C, N>, R>
+impl<F, H, C, R, const N: usize> FieldChallenges<F> for Merlin<H, Fp<C, N>, R>
 where
+    F: Field<BasePrimeField = Fp<C, N>>,
     C: FpConfig<N>,
     H: DuplexHash<Fp<C, N>>,
     R: CryptoRng + RngCore,
 {
-    fn fill_challenge_scalars(&mut self, output: &mut [Fp<C, N>]) -> ProofResult<()> {
-        self.fill_challenge_units(output)
-            .map_err(ProofError::InvalidIO)
+    fn fill_challenge_scalars(&mut self, output: &mut [F]) -> ProofResult<()> {
+        fill_challenge_scalars_from_units(self, output)
     }
 }
 
 // Field <-> Field interactions:
 
+/// Absorb the coefficients of `input` over the base prime field, returning them.
+fn public_scalars_from_units<F, T, C, const N: usize>(
+    transcript: &mut T,
+    input: &[F],
+) -> ProofResult<Vec<Fp<C, N>>>
+where
+    F: Field<BasePrimeField = Fp<C, N>>,
+    T: UnitTranscript<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    let flattened: Vec<_> = input
+        .iter()
+        .flat_map(|f| f.to_base_prime_field_elements())
+        .collect();
+    transcript.public_units(&flattened)?;
+    Ok(flattened)
+}
+
 impl<F, H, R, C, const N: usize> FieldPublic<F> for Merlin<H, Fp<C, N>, R>
 where
     F: Field<BasePrimeField = Fp<C, N>>,
@@ -128,15 +295,10 @@ where
     R: RngCore + CryptoRng,
     C: FpConfig<N>,
 {
-    type Repr = ();
+    type Repr = Vec<Fp<C, N>>;
 
     fn public_scalars(&mut self, input: &[F]) -> ProofResult<Self::Repr> {
-        let flattened: Vec<_> = input
-            .iter()
-            .flat_map(|f| f.to_base_prime_field_elements())
-            .collect();
-        self.public_units(&flattened)?;
-        Ok(())
+        public_scalars_from_units(self, input)
     }
 }
 
@@ -165,33 +327,88 @@ where
     H: DuplexHash<Fp<C, N>>,
     C: FpConfig<N>,
 {
-    type Repr = ();
+    type Repr = Vec<Fp<C, N>>;
 
     fn public_scalars(&mut self, input: &[F]) -> ProofResult<Self::Repr> {
-        let flattened: Vec<_> = input
-            .iter()
-            .flat_map(|f| f.to_base_prime_field_elements())
-            .collect();
-        self.public_units(&flattened)?;
-        Ok(())
+        public_scalars_from_units(self, input)
+    }
+}
+
+/// The point at infinity is encoded as $(0, 0)$, as done by arkworks.
+/// This is unambiguous only when $(0, 0)$ is not on the curve, i.e., when $b \neq 0$:
+/// otherwise, the point at infinity cannot be encoded.
+impl<P: SWCurveConfig> AffineCoordinates for SWCurve<P> {
+    fn affine_to_coordinates(affine: &SWAffine<P>) -> ProofResult<[P::BaseField; 2]> {
+        match affine.xy() {
+            Some((x, y)) => Ok([x, y]),
+            None if !P::COEFF_B.is_zero() => Ok([P::BaseField::zero(); 2]),
+            None => Err(ProofError::SerializationError),
+        }
+    }
+
+    fn affine_from_coordinates([x, y]: [P::BaseField; 2]) -> ProofResult<SWAffine<P>> {
+        if x.is_zero() && y.is_zero() && !P::COEFF_B.is_zero() {
+            return Ok(SWAffine::identity());
+        }
+        let affine = SWAffine::new_unchecked(x, y);
+        if !affine.is_on_curve() {
+            return Err(ProofError::SerializationError);
+        }
+        Ok(affine)
+    }
+}
+
+impl<P: TECurveConfig> AffineCoordinates for EdwardsCurve<P> {
+    fn affine_to_coordinates(affine: &EdwardsAffine<P>) -> ProofResult<[P::BaseField; 2]> {
+        Ok([affine.x, affine.y])
+    }
+
+    fn affine_from_coordinates([x, y]: [P::BaseField; 2]) -> ProofResult<EdwardsAffine<P>> {
+        let affine = EdwardsAffine::new_unchecked(x, y);
+        if !affine.is_on_curve() {
+            return Err(ProofError::SerializationError);
+        }
+        Ok(affine)
     }
 }
 
+/// The affine coordinates of `input`, concatenated.
+pub(super) fn points_to_coordinates<G: AffineCoordinates>(
+    input: &[G],
+) -> ProofResult<Vec<G::BaseField>> {
+    let mut coordinates = Vec::with_capacity(2 * input.len());
+    for point in input {
+        coordinates.extend(point.to_coordinates()?);
+    }
+    Ok(coordinates)
+}
+
+/// Absorb the affine coordinates of `input`, returning them.
+fn public_points_from_units<G, T, C, const N: usize>(
+    transcript: &mut T,
+    input: &[G],
+) -> ProofResult<Vec<Fp<C, N>>>
+where
+    G: AffineCoordinates<BaseField = Fp<C, N>>,
+    T: UnitTranscript<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    let coordinates = points_to_coordinates(input)?;
+    transcript.public_units(&coordinates)?;
+    Ok(coordinates)
+}
+
 impl<H, R, C, const N: usize, G> GroupPublic<G> for Merlin<H, Fp<C, N>, R>
 where
     C: FpConfig<N>,
     R: RngCore + CryptoRng,
     H: DuplexHash<Fp<C, N>>,
-    G: CurveGroup<BaseField = Fp<C, N>>,
+    G: AffineCoordinates<BaseField = Fp<C, N>>,
 {
-    type Repr = ();
+    type Repr = Vec<Fp<C, N>>;
 
     fn public_points(&mut self, input: &[G]) -> ProofResult<Self::Repr> {
-        for point in input {
-            let (x, y) = point.into_affine().xy().unwrap();
-            self.public_units(&[x, y])?;
-        }
-        Ok(())
+        public_points_from_units(self, input)
     }
 }
 
@@ -199,31 +416,64 @@ impl<H, C, const N: usize, G> GroupPublic<G> for Arthur<'_, H, Fp<C, N>>
 where
     C: FpConfig<N>,
     H: DuplexHash<Fp<C, N>>,
-    G: CurveGroup<BaseField = Fp<C, N>>,
+    G: AffineCoordinates<BaseField = Fp<C, N>>,
 {
-    type Repr = ();
+    type Repr = Vec<Fp<C, N>>;
 
     fn public_points(&mut self, input: &[G]) -> ProofResult<Self::Repr> {
-        for point in input {
-            let (x, y) = point.into_affine().xy().unwrap();
-            self.public_units(&[x, y])?;
-        }
-        Ok(())
+        public_points_from_units(self, input)
     }
 }
 
 // Field  <-> Bytes interactions:
 
+/// The number of bytes packed in each field element, so that any packing is smaller than the modulus.
+const fn packed_bytes<C: FpConfig<N>, const N: usize>() -> usize {
+    (Fp::<C, N>::MODULUS_BIT_SIZE as usize - 1) / 8
+}
+
+/// Absorb `input` in a single operation, packed into field elements as little-endian chunks of [`packed_bytes`] bytes.
+///
+/// The input is padded with a `0x01` byte before packing.
+/// Without padding, messages differing by trailing zeros would be absorbed as the same field elements,
+/// e.g. `[1, 2, 3]` and `[1, 2, 3, 0]`, and both declared as a single unit in the IO Pattern.
+///
+/// Absorbing a byte string in several calls is **not** equivalent to absorbing it at once:
+/// each call must match a single [`ByteIOPattern::add_bytes`](crate::ByteIOPattern::add_bytes) in the IO Pattern.
+/// Shared by [`Merlin`], [`Arthur`], and [`Safe`](crate::Safe), so that the three agree on byte messages.
+fn public_bytes_packed<T, C, const N: usize>(
+    transcript: &mut T,
+    input: &[u8],
+) -> Result<(), IOPatternError>
+where
+    T: UnitTranscript<Fp<C, N>> + ?Sized,
+    C: FpConfig<N>,
+{
+    let padded = [input, &[1]].concat();
+    let units = padded
+        .chunks(packed_bytes::<C, N>())
+        .map(Fp::from_le_bytes_mod_order)
+        .collect::<Vec<_>>();
+    transcript.public_units(&units)
+}
+
 impl<H, C, const N: usize> BytePublic for Arthur<'_, H, Fp<C, N>>
 where
     C: FpConfig<N>,
     H: DuplexHash<Fp<C, N>>,
 {
     fn public_bytes(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
-        for &byte in input {
-            self.public_units(&[Fp::from(byte)])?;
-        }
-        Ok(())
+        public_bytes_packed(self, input)
+    }
+}
+
+impl<H, C, const N: usize> BytePublic for Safe<H, Fp<C, N>>
+where
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+{
+    fn public_bytes(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
+        public_bytes_packed(self, input)
     }
 }
 
@@ -234,13 +484,36 @@ where
     R: CryptoRng + rand::RngCore,
 {
     fn public_bytes(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
-        for &byte in input {
-            self.public_units(&[Fp::from(byte)])?;
-        }
-        Ok(())
+        public_bytes_packed(self, input)
     }
 }
 
+/// Fill `output` with uniformly-distributed bytes, squeezed from a transcript over field units.
+///
+/// Each unit contributes its `random_bytes_in_random_modp` least significant bytes (little-endian),
+/// which are statistically close to uniform.
+/// Shared by [`Merlin`], [`Arthur`], and [`Safe`](crate::Safe), so that the three agree on byte challenges.
+fn fill_challenge_bytes_from_units<T, C, const N: usize>(
+    transcript: &mut T,
+    output: &mut [u8],
+) -> Result<(), IOPatternError>
+where
+    T: UnitTranscript<Fp<C, N>> + ?Sized,
+    C: FpConfig<N>,
+{
+    if output.is_empty() {
+        return Ok(());
+    }
+    let len_good = crate::plugins::random_bytes_in_random_modp(Fp::<C, N>::MODULUS);
+    let mut units = vec![Fp::from(0); output.len().div_ceil(len_good)];
+    transcript.fill_challenge_units(&mut units)?;
+    for (chunk, unit) in output.chunks_mut(len_good).zip(&units) {
+        let buf = unit.into_bigint().to_bytes_le();
+        chunk.copy_from_slice(&buf[..chunk.len()]);
+    }
+    Ok(())
+}
+
 impl<H, R, C, const N: usize> ByteChallenges for Merlin<H, Fp<C, N>, R>
 where
     C: FpConfig<N>,
@@ -248,45 +521,159 @@ where
     R: CryptoRng + RngCore,
 {
     fn fill_challenge_bytes(&mut self, output: &mut [u8]) -> Result<(), IOPatternError> {
-        if output.is_empty() {
-            Ok(())
-        } else {
-            let len_good = usize::min(
-                crate::plugins::random_bytes_in_random_modp(Fp::<C, N>::MODULUS),
-                output.len(),
-            );
-            let mut tmp = [Fp::from(0); 1];
-            self.fill_challenge_units(&mut tmp)?;
-            let buf = tmp[0].into_bigint().to_bytes_le();
-            output[..len_good].copy_from_slice(&buf[..len_good]);
-
-            // recursively fill the rest of the buffer
-            self.fill_challenge_bytes(&mut output[len_good..])
-        }
+        fill_challenge_bytes_from_units(self, output)
     }
 }
 
-/// XXX. duplicate code
 impl<H, C, const N: usize> ByteChallenges for Arthur<'_, H, Fp<C, N>>
 where
     C: FpConfig<N>,
     H: DuplexHash<Fp<C, N>>,
 {
     fn fill_challenge_bytes(&mut self, output: &mut [u8]) -> Result<(), IOPatternError> {
-        if output.is_empty() {
-            Ok(())
-        } else {
-            let len_good = usize::min(
-                crate::plugins::random_bytes_in_random_modp(Fp::<C, N>::MODULUS),
-                output.len(),
-            );
-            let mut tmp = [Fp::from(0); 1];
-            self.fill_challenge_units(&mut tmp)?;
-            let buf = tmp[0].into_bigint().to_bytes_le();
-            output[..len_good].copy_from_slice(&buf[..len_good]);
+        fill_challenge_bytes_from_units(self, output)
+    }
+}
 
-            // recursively fill the rest of the buffer
-            self.fill_challenge_bytes(&mut output[len_good..])
-        }
+impl<H, C, const N: usize> ByteChallenges for Safe<H, Fp<C, N>>
+where
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+{
+    fn fill_challenge_bytes(&mut self, output: &mut [u8]) -> Result<(), IOPatternError> {
+        fill_challenge_bytes_from_units(self, output)
+    }
+}
+
+// Layered transcripts over field units, whose operations go through the middleware:
+
+impl<H, R, C, M, const N: usize> BytePublic for LayeredTranscript<Merlin<H, Fp<C, N>, R>, M>
+where
+    H: DuplexHash<Fp<C, N>>,
+    R: RngCore + CryptoRng,
+    C: FpConfig<N>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    fn public_bytes(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
+        public_bytes_packed(self, input)
+    }
+}
+
+impl<H, R, C, M, const N: usize> ByteChallenges for LayeredTranscript<Merlin<H, Fp<C, N>, R>, M>
+where
+    H: DuplexHash<Fp<C, N>>,
+    R: RngCore + CryptoRng,
+    C: FpConfig<N>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    fn fill_challenge_bytes(&mut self, output: &mut [u8]) -> Result<(), IOPatternError> {
+        fill_challenge_bytes_from_units(self, output)
+    }
+}
+
+impl<F, H, R, C, M, const N: usize> FieldPublic<F> for LayeredTranscript<Merlin<H, Fp<C, N>, R>, M>
+where
+    F: Field<BasePrimeField = Fp<C, N>>,
+    H: DuplexHash<Fp<C, N>>,
+    R: RngCore + CryptoRng,
+    C: FpConfig<N>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    type Repr = Vec<Fp<C, N>>;
+
+    fn public_scalars(&mut self, input: &[F]) -> ProofResult<Self::Repr> {
+        public_scalars_from_units(self, input)
+    }
+}
+
+impl<F, H, R, C, M, const N: usize> FieldChallenges<F>
+    for LayeredTranscript<Merlin<H, Fp<C, N>, R>, M>
+where
+    F: Field<BasePrimeField = Fp<C, N>>,
+    H: DuplexHash<Fp<C, N>>,
+    R: RngCore + CryptoRng,
+    C: FpConfig<N>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    fn fill_challenge_scalars(&mut self, output: &mut [F]) -> ProofResult<()> {
+        fill_challenge_scalars_from_units(self, output)
+    }
+}
+
+impl<G, H, R, C, M, const N: usize> GroupPublic<G> for LayeredTranscript<Merlin<H, Fp<C, N>, R>, M>
+where
+    G: AffineCoordinates<BaseField = Fp<C, N>>,
+    H: DuplexHash<Fp<C, N>>,
+    R: RngCore + CryptoRng,
+    C: FpConfig<N>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    type Repr = Vec<Fp<C, N>>;
+
+    fn public_points(&mut self, input: &[G]) -> ProofResult<Self::Repr> {
+        public_points_from_units(self, input)
+    }
+}
+
+impl<H, C, M, const N: usize> BytePublic for LayeredTranscript<Arthur<'_, H, Fp<C, N>>, M>
+where
+    H: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    fn public_bytes(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
+        public_bytes_packed(self, input)
+    }
+}
+
+impl<H, C, M, const N: usize> ByteChallenges for LayeredTranscript<Arthur<'_, H, Fp<C, N>>, M>
+where
+    H: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    fn fill_challenge_bytes(&mut self, output: &mut [u8]) -> Result<(), IOPatternError> {
+        fill_challenge_bytes_from_units(self, output)
+    }
+}
+
+impl<F, H, C, M, const N: usize> FieldPublic<F> for LayeredTranscript<Arthur<'_, H, Fp<C, N>>, M>
+where
+    F: Field<BasePrimeField = Fp<C, N>>,
+    H: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    type Repr = Vec<Fp<C, N>>;
+
+    fn public_scalars(&mut self, input: &[F]) -> ProofResult<Self::Repr> {
+        public_scalars_from_units(self, input)
+    }
+}
+
+impl<F, H, C, M, const N: usize> FieldChallenges<F>
+    for LayeredTranscript<Arthur<'_, H, Fp<C, N>>, M>
+where
+    F: Field<BasePrimeField = Fp<C, N>>,
+    H: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    fn fill_challenge_scalars(&mut self, output: &mut [F]) -> ProofResult<()> {
+        fill_challenge_scalars_from_units(self, output)
+    }
+}
+
+impl<G, H, C, M, const N: usize> GroupPublic<G> for LayeredTranscript<Arthur<'_, H, Fp<C, N>>, M>
+where
+    G: AffineCoordinates<BaseField = Fp<C, N>>,
+    H: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    type Repr = Vec<Fp<C, N>>;
+
+    fn public_points(&mut self, input: &[G]) -> ProofResult<Self::Repr> {
+        public_points_from_units(self, input)
     }
 }
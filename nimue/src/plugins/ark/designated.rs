@@ -0,0 +1,146 @@
+use ark_ec::CurveGroup;
+use ark_ff::{Field, UniformRand};
+use rand::{CryptoRng, RngCore};
+
+use super::{FieldReader, FieldWriter, GroupIOPattern, GroupReader, GroupWriter};
+use crate::designated::{DesignatedVerifier, KeccakCipher, TranscriptCipher};
+use crate::{Arthur, ByteReader, ByteWriter, DuplexHash, Merlin, ProofResult, UnitTranscript};
+
+/// IO Pattern for an ephemeral Diffie-Hellman with a designated verifier.
+pub trait DesignatedVerifierIOPattern<G: CurveGroup> {
+    /// Add the prover's ephemeral key, from which the transcript encryption key is derived.
+    ///
+    /// This should be called at statement time, before any encrypted prover message.
+    fn add_designated_verifier(self, label: &str) -> Self;
+}
+
+impl<G: CurveGroup, T: GroupIOPattern<G>> DesignatedVerifierIOPattern<G> for T {
+    fn add_designated_verifier(self, label: &str) -> Self {
+        self.add_points(1, label)
+    }
+}
+
+/// Encrypt the rest of the prover's transcript to the designated verifier with public key `verifier_pk`.
+pub trait DesignatedVerifierWriter<G: CurveGroup>: Sized {
+    /// Sample an ephemeral key, write it to the transcript, and derive the encryption key from the shared secret.
+    fn designate_verifier(self, verifier_pk: &G) -> ProofResult<DesignatedVerifier<Self>>;
+}
+
+/// Decrypt the rest of the prover's transcript as the designated verifier with secret key `verifier_sk`.
+pub trait DesignatedVerifierReader<G: CurveGroup>: Sized {
+    /// Read the prover's ephemeral key from the transcript, and derive the encryption key from the shared secret.
+    fn designated_verifier(
+        self,
+        verifier_sk: &G::ScalarField,
+    ) -> ProofResult<DesignatedVerifier<Self>>;
+}
+
+/// Domain separator for the keystream of an ephemeral Diffie-Hellman, padded to 32 bytes.
+const DH_IV: [u8; 32] = *b"nimue/designated-verifier/ark-dh";
+
+/// Derive the transcript cipher from the Diffie-Hellman `shared_secret`,
+/// bound to the prover's `ephemeral_pk` and to the designated `verifier_pk`.
+fn cipher_from_key_exchange<G: CurveGroup>(
+    shared_secret: G,
+    ephemeral_pk: G,
+    verifier_pk: G,
+) -> ProofResult<KeccakCipher> {
+    let mut parts = [Vec::new(), Vec::new(), Vec::new()];
+    shared_secret.serialize_compressed(&mut parts[0])?;
+    ephemeral_pk.serialize_compressed(&mut parts[1])?;
+    verifier_pk.serialize_compressed(&mut parts[2])?;
+    Ok(KeccakCipher::from_parts(
+        DH_IV,
+        &[&parts[0], &parts[1], &parts[2]],
+    ))
+}
+
+impl<G, H, R> DesignatedVerifierWriter<G> for Merlin<H, u8, R>
+where
+    G: CurveGroup,
+    H: DuplexHash,
+    R: RngCore + CryptoRng,
+    Merlin<H, u8, R>: GroupWriter<G>,
+{
+    fn designate_verifier(mut self, verifier_pk: &G) -> ProofResult<DesignatedVerifier<Self>> {
+        let ephemeral_sk = G::ScalarField::rand(self.rng());
+        let ephemeral_pk = G::generator() * ephemeral_sk;
+        self.add_points(&[ephemeral_pk])?;
+        let cipher =
+            cipher_from_key_exchange(*verifier_pk * ephemeral_sk, ephemeral_pk, *verifier_pk)?;
+        Ok(DesignatedVerifier::new(self, cipher))
+    }
+}
+
+impl<G, H> DesignatedVerifierReader<G> for Arthur<'_, H, u8>
+where
+    G: CurveGroup,
+    H: DuplexHash,
+{
+    fn designated_verifier(
+        mut self,
+        verifier_sk: &G::ScalarField,
+    ) -> ProofResult<DesignatedVerifier<Self>> {
+        let [ephemeral_pk]: [G; 1] = self.next_points()?;
+        let verifier_pk = G::generator() * *verifier_sk;
+        let cipher =
+            cipher_from_key_exchange(ephemeral_pk * *verifier_sk, ephemeral_pk, verifier_pk)?;
+        Ok(DesignatedVerifier::new(self, cipher))
+    }
+}
+
+impl<F, T, C> FieldWriter<F> for DesignatedVerifier<T, C>
+where
+    F: Field,
+    T: UnitTranscript<u8>,
+    C: TranscriptCipher,
+    Self: ByteWriter,
+{
+    fn add_scalars(&mut self, input: &[F]) -> ProofResult<()> {
+        let mut buf = Vec::new();
+        for i in input {
+            i.serialize_compressed(&mut buf)?;
+        }
+        Ok(self.add_bytes(&buf)?)
+    }
+}
+
+impl<G, T, C> GroupWriter<G> for DesignatedVerifier<T, C>
+where
+    G: CurveGroup,
+    T: UnitTranscript<u8>,
+    C: TranscriptCipher,
+    Self: ByteWriter,
+{
+    fn add_points(&mut self, input: &[G]) -> ProofResult<()> {
+        let mut buf = Vec::new();
+        for i in input {
+            i.serialize_compressed(&mut buf)?;
+        }
+        Ok(self.add_bytes(&buf)?)
+    }
+}
+
+impl<F, T, C> FieldReader<F> for DesignatedVerifier<T, C>
+where
+    F: Field,
+    T: UnitTranscript<u8>,
+    C: TranscriptCipher,
+    Self: ByteReader,
+{
+    fn fill_next_scalars(&mut self, output: &mut [F]) -> ProofResult<()> {
+        super::reader::read_scalars(self, output)
+    }
+}
+
+impl<G, T, C> GroupReader<G> for DesignatedVerifier<T, C>
+where
+    G: CurveGroup,
+    T: UnitTranscript<u8>,
+    C: TranscriptCipher,
+    Self: ByteReader,
+{
+    fn fill_next_points(&mut self, output: &mut [G]) -> ProofResult<()> {
+        super::reader::read_points(self, output)
+    }
+}
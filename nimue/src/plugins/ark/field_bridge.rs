@@ -0,0 +1,219 @@
+//! Moving field elements across fields.
+//!
+//! Recursive protocols constantly need to handle elements of a field $\mathbb{F}_1$
+//! (typically, the scalar field of a curve) in a protocol whose sponge works over another field $\mathbb{F}_2$
+//! (typically, its base field). This module provides:
+//! - checked embeddings of an element of $\mathbb{F}_1$ into $\mathbb{F}_2$ ([`swap_field`], [`scalar_to_base`], [`base_to_scalar`]);
+//! - lossless decomposition of an element of $\mathbb{F}_1$ into limbs of $\mathbb{F}_2$ ([`to_limbs`], [`from_limbs`]);
+//! - absorption of elements of $\mathbb{F}_1$ as limbs in a sponge over $\mathbb{F}_2$
+//!   ([`ForeignFieldIOPattern`], [`ForeignFieldPublic`]).
+//!
+//! ```
+//! use ark_bls12_381::{Fq, Fr};
+//! use nimue::plugins::ark::field_bridge::{from_limbs, to_limbs};
+//!
+//! let x = -Fq::from(1);
+//! let limbs = to_limbs::<Fr, _>(x, 64).unwrap();
+//! assert_eq!(limbs.len(), 6);
+//! assert_eq!(from_limbs::<Fq, _>(&limbs, 64).unwrap(), x);
+//! ```
+use ark_ec::CurveGroup;
+use ark_ff::{BigInteger, Fp, FpConfig, PrimeField};
+use rand::{CryptoRng, RngCore};
+
+use crate::{Arthur, DuplexHash, IOPattern, Merlin, ProofError, ProofResult, UnitTranscript};
+
+/// Move a value from prime field F1 to prime field F2.
+///
+/// Return an error if the element considered mod |F1| is different, when seen as an integer, mod |F2|.
+/// This in particular happens when element > |F2|.
+pub fn swap_field<F1: PrimeField, F2: PrimeField>(a_f1: F1) -> ProofResult<F2> {
+    let a_f2 = F2::from_le_bytes_mod_order(&a_f1.into_bigint().to_bytes_le());
+    let a_f1_control = F1::from_le_bytes_mod_order(&a_f2.into_bigint().to_bytes_le());
+    (a_f1 == a_f1_control)
+        .then_some(a_f2)
+        .ok_or(ProofError::SerializationError)
+}
+
+/// Embed a scalar of the curve `G` into its base field.
+///
+/// This never fails for curves whose scalar field is smaller than the base field (e.g. BLS12-381, BN254),
+/// and fails for scalars larger than the base field modulus otherwise (e.g. Pallas).
+pub fn scalar_to_base<G>(x: G::ScalarField) -> ProofResult<G::BaseField>
+where
+    G: CurveGroup,
+    G::BaseField: PrimeField,
+{
+    swap_field(x)
+}
+
+/// Embed an element of the base field of the curve `G` into its scalar field.
+///
+/// Fails for elements larger than the scalar field modulus.
+pub fn base_to_scalar<G>(x: G::BaseField) -> ProofResult<G::ScalarField>
+where
+    G: CurveGroup,
+    G::BaseField: PrimeField,
+{
+    swap_field(x)
+}
+
+/// The number of limbs of `limb_bits` bits needed to represent an element of `F`.
+///
+/// # Panics
+///
+/// Panics if `limb_bits` is zero.
+pub fn limbs_count<F: PrimeField>(limb_bits: usize) -> usize {
+    (F::MODULUS_BIT_SIZE as usize).div_ceil(limb_bits)
+}
+
+/// Check that limbs of `limb_bits` bits are non-empty and fit in `F2`.
+fn check_limb_bits<F2: PrimeField>(limb_bits: usize) -> ProofResult<()> {
+    if limb_bits == 0 || limb_bits >= F2::MODULUS_BIT_SIZE as usize {
+        return Err(ProofError::SerializationError);
+    }
+    Ok(())
+}
+
+/// Decompose `x` into [`limbs_count`] little-endian limbs of `limb_bits` bits, each an element of `F2`.
+///
+/// Return an error if `limb_bits` is zero or if limbs do not fit in `F2`, that is if `limb_bits >= F2::MODULUS_BIT_SIZE`.
+pub fn to_limbs<F2: PrimeField, F1: PrimeField>(x: F1, limb_bits: usize) -> ProofResult<Vec<F2>> {
+    check_limb_bits::<F2>(limb_bits)?;
+    let bits = x.into_bigint().to_bits_le();
+    bits.chunks(limb_bits)
+        .take(limbs_count::<F1>(limb_bits))
+        .map(|chunk| {
+            let mut limb = chunk.to_vec();
+            limb.reverse();
+            F2::from_bigint(F2::BigInt::from_bits_be(&limb)).ok_or(ProofError::SerializationError)
+        })
+        .collect()
+}
+
+/// Recompose an element of `F1` from little-endian limbs of `limb_bits` bits.
+///
+/// Return an error if `limb_bits` is invalid (as for [`to_limbs`]), if a limb exceeds `limb_bits` bits,
+/// if the number of limbs is not [`limbs_count`], or if the integer represented is not a canonical element of `F1`.
+pub fn from_limbs<F1: PrimeField, F2: PrimeField>(
+    limbs: &[F2],
+    limb_bits: usize,
+) -> ProofResult<F1> {
+    check_limb_bits::<F2>(limb_bits)?;
+    if limbs.len() != limbs_count::<F1>(limb_bits) {
+        return Err(ProofError::SerializationError);
+    }
+    let mut bits = Vec::with_capacity(limbs.len() * limb_bits);
+    for limb in limbs {
+        let limb_bits_le = limb.into_bigint().to_bits_le();
+        if limb_bits_le[limb_bits..].iter().any(|&b| b) {
+            return Err(ProofError::SerializationError);
+        }
+        bits.extend_from_slice(&limb_bits_le[..limb_bits]);
+    }
+    let modulus_bits = F1::MODULUS_BIT_SIZE as usize;
+    if bits[modulus_bits..].iter().any(|&b| b) {
+        return Err(ProofError::SerializationError);
+    }
+    bits.truncate(modulus_bits);
+    bits.reverse();
+    F1::from_bigint(F1::BigInt::from_bits_be(&bits)).ok_or(ProofError::SerializationError)
+}
+
+/// Declare foreign field elements, absorbed as limbs, in the IO pattern.
+pub trait ForeignFieldIOPattern<F: PrimeField> {
+    /// Absorb `count` elements of `F`, each decomposed in limbs of `limb_bits` bits.
+    ///
+    /// The limb size is recorded in the label, as `{label}.limbs{limb_bits}`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limb_bits` is invalid for the sponge field, that is where [`to_limbs`] would fail.
+    fn add_foreign_scalars(self, count: usize, limb_bits: usize, label: &str) -> Self;
+}
+
+/// Absorb foreign field elements as limbs, without adding them to the protocol transcript.
+pub trait ForeignFieldPublic<F: PrimeField> {
+    /// The native field of the sponge.
+    type Limb;
+
+    /// Absorb `input` decomposed in limbs of `limb_bits` bits, and return the limbs.
+    fn public_foreign_scalars(
+        &mut self,
+        input: &[F],
+        limb_bits: usize,
+    ) -> ProofResult<Vec<Self::Limb>>;
+}
+
+impl<F, H, C, const N: usize> ForeignFieldIOPattern<F> for IOPattern<H, Fp<C, N>>
+where
+    F: PrimeField,
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+{
+    fn add_foreign_scalars(self, count: usize, limb_bits: usize, label: &str) -> Self {
+        assert!(
+            check_limb_bits::<Fp<C, N>>(limb_bits).is_ok(),
+            "Limbs of {} bits are empty or do not fit in the sponge field.",
+            limb_bits
+        );
+        self.absorb(
+            count * limbs_count::<F>(limb_bits),
+            &format!("{}.limbs{}", label, limb_bits),
+        )
+    }
+}
+
+fn public_foreign_scalars<F, T, C, const N: usize>(
+    transcript: &mut T,
+    input: &[F],
+    limb_bits: usize,
+) -> ProofResult<Vec<Fp<C, N>>>
+where
+    F: PrimeField,
+    C: FpConfig<N>,
+    T: UnitTranscript<Fp<C, N>>,
+{
+    let limbs = input
+        .iter()
+        .map(|&x| to_limbs::<Fp<C, N>, F>(x, limb_bits))
+        .collect::<ProofResult<Vec<_>>>()?
+        .concat();
+    transcript.public_units(&limbs)?;
+    Ok(limbs)
+}
+
+impl<F, H, C, R, const N: usize> ForeignFieldPublic<F> for Merlin<H, Fp<C, N>, R>
+where
+    F: PrimeField,
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+    R: RngCore + CryptoRng,
+{
+    type Limb = Fp<C, N>;
+
+    fn public_foreign_scalars(
+        &mut self,
+        input: &[F],
+        limb_bits: usize,
+    ) -> ProofResult<Vec<Self::Limb>> {
+        public_foreign_scalars(self, input, limb_bits)
+    }
+}
+
+impl<F, H, C, const N: usize> ForeignFieldPublic<F> for Arthur<'_, H, Fp<C, N>>
+where
+    F: PrimeField,
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+{
+    type Limb = Fp<C, N>;
+
+    fn public_foreign_scalars(
+        &mut self,
+        input: &[F],
+        limb_bits: usize,
+    ) -> ProofResult<Vec<Self::Limb>> {
+        public_foreign_scalars(self, input, limb_bits)
+    }
+}
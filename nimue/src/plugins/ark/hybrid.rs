@@ -0,0 +1,486 @@
+//! Fiat-Shamir over two sponges: one over bytes, one over a prime field.
+//!
+//! Protocols often hash large amounts of data with a byte-oriented hash (e.g. [`Keccak`]),
+//! while needing algebraic challenges that are cheap to recompute in a recursive circuit.
+//! A [`HybridTranscript`] keeps one [`Safe`] sponge for each unit, and the [`HybridIOPattern`] describes
+//! the operations on both, together with the *bridges* between them.
+//!
+//! The prover state [`HybridMerlin`] and the verifier state [`HybridArthur`] build on [`Merlin`] and [`Arthur`]:
+//! prover messages of both units are encoded, in order, in a single protocol transcript.
+//!
+//! ```
+//! # #[cfg(feature = "test-utils")]
+//! # fn main() {
+//! # use nimue::*;
+//! # use nimue::plugins::ark::hybrid::HybridIOPattern;
+//! # use nimue::hash::sponge::DuplexSponge;
+//! # use nimue::testing::ToySponge;
+//! use ark_bls12_381::Fr;
+//!
+//! let io = HybridIOPattern::<DefaultHash, DuplexSponge<ToySponge<Fr>>, _, 4>::new("📝")
+//!     .bytes(|io| io.absorb(4, "data"))
+//!     .field(|io| io.absorb(1, "commitment"))
+//!     .bridge("bridge")
+//!     .field(|io| io.squeeze(1, "challenge"));
+//!
+//! let mut merlin = io.to_merlin();
+//! merlin.add_bytes(b"\x01\x02\x03\x04").unwrap();
+//! merlin.add_field_units(&[Fr::from(42u64)]).unwrap();
+//! merlin.bridge().unwrap();
+//! let mut chal = [Fr::from(0u64)];
+//! merlin.fill_challenge_units(&mut chal).unwrap();
+//!
+//! let mut arthur = io.to_arthur(merlin.transcript());
+//! assert_eq!(arthur.next_bytes().unwrap(), *b"\x01\x02\x03\x04");
+//! let mut commitment = [Fr::from(0u64)];
+//! arthur.fill_next_field_units(&mut commitment).unwrap();
+//! assert_eq!(commitment, [Fr::from(42u64)]);
+//! arthur.bridge().unwrap();
+//! let mut verifier_chal = [Fr::from(0u64)];
+//! arthur.fill_challenge_units(&mut verifier_chal).unwrap();
+//! assert_eq!(verifier_chal, chal);
+//! # }
+//! # #[cfg(not(feature = "test-utils"))]
+//! # fn main() {}
+//! ```
+//!
+//! # Security
+//!
+//! - Both sponges are initialized with the same IV, binding the two IO Patterns (and hence the domain separator).
+//! - A bridge ratchets both sponges, squeezes a digest from each, and absorbs it into the other:
+//!   32 bytes from the byte sponge are absorbed as two 128-bit field elements (an injective embedding, for fields larger than 128 bits);
+//!   one field element from the field sponge is absorbed as its canonical byte encoding.
+//!
+//! Therefore, challenges squeezed after a bridge depend on every element absorbed, in either sponge, before the bridge.
+//! Elements absorbed after the last bridge do *not* influence challenges squeezed from the other sponge.
+use ark_ff::{Fp, FpConfig, PrimeField};
+use rand::{CryptoRng, RngCore};
+
+use crate::hash::Keccak;
+use crate::merlin::ProverRng;
+use crate::{
+    Arthur, ByteReader, ByteWriter, DefaultRng, DuplexHash, IOPattern, IOPatternError, Merlin,
+    ProofResult, Safe, Unit, UnitTranscript,
+};
+
+/// Length of the digest squeezed from the byte sponge in a bridge.
+const BYTES_DIGEST_LEN: usize = 32;
+
+/// The IO Pattern of a protocol using both a byte sponge `HB` and a sponge `HF` over the field `Fp<C, N>`.
+#[derive(Clone)]
+pub struct HybridIOPattern<HB, HF, C, const N: usize>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    bytes: IOPattern<HB, u8>,
+    field: IOPattern<HF, Fp<C, N>>,
+}
+
+/// The Fiat-Shamir state of a protocol using both a byte sponge `HB` and a sponge `HF` over the field `Fp<C, N>`.
+///
+/// Implements [`UnitTranscript<u8>`] over the byte sponge and [`UnitTranscript<Fp<C, N>>`] over the field sponge.
+/// Prover and verifier share the same state: prover messages must be absorbed as public elements.
+pub struct HybridTranscript<HB, HF, C, const N: usize>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    bytes: Safe<HB, u8>,
+    field: Safe<HF, Fp<C, N>>,
+}
+
+impl<HB, HF, C, const N: usize> HybridIOPattern<HB, HF, C, N>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    /// Create a new hybrid IO Pattern with the domain separator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the modulus of the field has at most 128 bits:
+    /// bridges embed the byte digest in 128-bit chunks, which must not be reduced.
+    pub fn new(domsep: &str) -> Self {
+        assert!(
+            Fp::<C, N>::MODULUS_BIT_SIZE > 128,
+            "hybrid transcripts require a field with a modulus larger than 128 bits"
+        );
+        Self {
+            bytes: IOPattern::new(domsep),
+            field: IOPattern::new(domsep),
+        }
+    }
+
+    /// Add operations on the byte sponge.
+    pub fn bytes(self, f: impl FnOnce(IOPattern<HB, u8>) -> IOPattern<HB, u8>) -> Self {
+        Self {
+            bytes: f(self.bytes),
+            field: self.field,
+        }
+    }
+
+    /// Add operations on the field sponge.
+    pub fn field(self, f: impl FnOnce(IOPattern<HF, Fp<C, N>>) -> IOPattern<HF, Fp<C, N>>) -> Self {
+        Self {
+            bytes: self.bytes,
+            field: f(self.field),
+        }
+    }
+
+    /// Cross-absorb the state of each sponge into the other.
+    pub fn bridge(self, label: &str) -> Self {
+        let field_digest_len = Self::field_digest_len();
+        let digest_label = format!("{}.digest", label);
+        let cross_label = format!("{}.cross", label);
+        Self {
+            bytes: self
+                .bytes
+                .ratchet()
+                .squeeze(BYTES_DIGEST_LEN, &digest_label)
+                .absorb(field_digest_len, &cross_label),
+            field: self
+                .field
+                .ratchet()
+                .squeeze(1, &digest_label)
+                .absorb(BYTES_DIGEST_LEN / 16, &cross_label),
+        }
+    }
+
+    /// The IO Pattern of the byte sponge.
+    pub fn bytes_pattern(&self) -> &IOPattern<HB, u8> {
+        &self.bytes
+    }
+
+    /// The IO Pattern of the field sponge.
+    pub fn field_pattern(&self) -> &IOPattern<HF, Fp<C, N>> {
+        &self.field
+    }
+
+    /// Return a 32-byte identifier binding both IO Patterns, used as IV for both sponges.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut keccak = Keccak::default();
+        keccak.absorb_unchecked(&(self.bytes.as_bytes().len() as u64).to_le_bytes());
+        keccak.absorb_unchecked(self.bytes.as_bytes());
+        keccak.absorb_unchecked(self.field.as_bytes());
+        let mut digest = [0u8; 32];
+        keccak.squeeze_unchecked(&mut digest);
+        digest
+    }
+
+    /// Create the Fiat-Shamir state for this IO Pattern.
+    pub fn to_transcript(&self) -> HybridTranscript<HB, HF, C, N> {
+        let iv = self.digest();
+        HybridTranscript {
+            bytes: Safe::new_with_iv(&self.bytes, iv),
+            field: Safe::new_with_iv(&self.field, iv),
+        }
+    }
+
+    /// Create the prover state for this IO Pattern, with the default random number generator.
+    pub fn to_merlin(&self) -> HybridMerlin<HB, HF, C, N> {
+        self.to_merlin_with_rng(DefaultRng::default())
+    }
+
+    /// Create the prover state for this IO Pattern, seeding the prover's private coins with `csrng`.
+    pub fn to_merlin_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        csrng: R,
+    ) -> HybridMerlin<HB, HF, C, N, R> {
+        let iv = self.digest();
+        let mut sponge = Keccak::default();
+        sponge.absorb_unchecked(&iv);
+        HybridMerlin {
+            merlin: Merlin {
+                rng: ProverRng { sponge, csrng },
+                safe: Safe::new_with_iv(&self.bytes, iv),
+                transcript: Vec::new(),
+                sink: None,
+            },
+            field: Safe::new_with_iv(&self.field, iv),
+        }
+    }
+
+    /// Create the verifier state for this IO Pattern, reading the protocol `transcript`.
+    pub fn to_arthur<'a>(&self, transcript: &'a [u8]) -> HybridArthur<'a, HB, HF, C, N> {
+        let iv = self.digest();
+        HybridArthur {
+            arthur: Arthur {
+                safe: Safe::new_with_iv(&self.bytes, iv),
+                transcript: transcript.into(),
+                // trailing bytes are checked against both sponges, see [`HybridArthur::check_trailing_bytes`].
+                allow_trailing_bytes: true,
+                failure: None,
+            },
+            field: Safe::new_with_iv(&self.field, iv),
+        }
+    }
+
+    fn field_digest_len() -> usize {
+        let mut buf = Vec::new();
+        Fp::<C, N>::write(&[Fp::default()], &mut buf).expect("Vec writes never fail");
+        buf.len()
+    }
+}
+
+/// The prover state of a protocol using both a byte sponge `HB` and a sponge `HF` over the field `Fp<C, N>`.
+///
+/// Byte messages are added via [`ByteWriter`], field messages via [`HybridMerlin::add_field_units`];
+/// both are encoded, in order, in the protocol transcript.
+/// Public elements and challenges of either unit go through [`UnitTranscript`].
+pub struct HybridMerlin<HB, HF, C, const N: usize, R = DefaultRng>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+    R: RngCore + CryptoRng,
+{
+    merlin: Merlin<HB, u8, R>,
+    field: Safe<HF, Fp<C, N>>,
+}
+
+/// The verifier state of a protocol using both a byte sponge `HB` and a sponge `HF` over the field `Fp<C, N>`.
+///
+/// Byte messages are read via [`ByteReader`], field messages via [`HybridArthur::fill_next_field_units`].
+pub struct HybridArthur<'a, HB, HF, C, const N: usize>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    arthur: Arthur<'a, HB, u8>,
+    field: Safe<HF, Fp<C, N>>,
+}
+
+/// Cross-absorb the state of each sponge into the other, as declared by [`HybridIOPattern::bridge`].
+fn bridge<HB, HF, C, const N: usize>(
+    bytes: &mut Safe<HB, u8>,
+    field: &mut Safe<HF, Fp<C, N>>,
+) -> Result<(), IOPatternError>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    bytes.ratchet()?;
+    field.ratchet()?;
+
+    let mut bytes_digest = [0u8; BYTES_DIGEST_LEN];
+    bytes.squeeze(&mut bytes_digest)?;
+    let mut field_digest = [Fp::default()];
+    field.squeeze(&mut field_digest)?;
+
+    let mut field_digest_bytes = Vec::new();
+    Fp::<C, N>::write(&field_digest, &mut field_digest_bytes)?;
+    bytes.absorb(&field_digest_bytes)?;
+    let bytes_digest_elements = bytes_digest
+        .chunks(16)
+        .map(Fp::<C, N>::from_le_bytes_mod_order)
+        .collect::<Vec<_>>();
+    field.absorb(&bytes_digest_elements)
+}
+
+impl<HB, HF, C, const N: usize> HybridTranscript<HB, HF, C, N>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    /// Cross-absorb the state of each sponge into the other, as declared by [`HybridIOPattern::bridge`].
+    pub fn bridge(&mut self) -> Result<(), IOPatternError> {
+        bridge(&mut self.bytes, &mut self.field)
+    }
+}
+
+impl<HB, HF, C, R, const N: usize> HybridMerlin<HB, HF, C, N, R>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+    R: RngCore + CryptoRng,
+{
+    /// Add field elements to the protocol transcript, absorbing them into the field sponge.
+    pub fn add_field_units(&mut self, input: &[Fp<C, N>]) -> ProofResult<()> {
+        self.field.absorb(input)?;
+        let mut message = Vec::new();
+        Fp::<C, N>::write(input, &mut message).expect("Vec writes never fail");
+        self.merlin.rng.sponge.absorb_unchecked(&message);
+        self.merlin.write_message(&message);
+        Ok(())
+    }
+
+    /// Cross-absorb the state of each sponge into the other, as declared by [`HybridIOPattern::bridge`].
+    pub fn bridge(&mut self) -> Result<(), IOPatternError> {
+        bridge(&mut self.merlin.safe, &mut self.field)
+    }
+
+    /// The prover's private coins, see [`Merlin::rng`].
+    pub fn rng(&mut self) -> &mut (impl CryptoRng + RngCore) {
+        self.merlin.rng()
+    }
+
+    /// The protocol transcript, holding the prover messages of both units.
+    pub fn transcript(&self) -> &[u8] {
+        self.merlin.transcript()
+    }
+}
+
+impl<HB, HF, C, const N: usize> HybridArthur<'_, HB, HF, C, N>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    /// Read `input.len()` field elements from the protocol transcript, absorbing them into the field sponge.
+    pub fn fill_next_field_units(&mut self, input: &mut [Fp<C, N>]) -> ProofResult<()> {
+        Fp::<C, N>::read(&mut self.arthur.transcript, input).map_err(IOPatternError::from)?;
+        self.field.absorb(input)?;
+        self.check_trailing_bytes()?;
+        Ok(())
+    }
+
+    /// Cross-absorb the state of each sponge into the other, as declared by [`HybridIOPattern::bridge`].
+    pub fn bridge(&mut self) -> Result<(), IOPatternError> {
+        bridge(&mut self.arthur.safe, &mut self.field)?;
+        self.check_trailing_bytes()
+    }
+
+    /// Fail if all operations of both sponges have been performed, but the transcript has not been read entirely.
+    fn check_trailing_bytes(&mut self) -> Result<(), IOPatternError> {
+        if self.arthur.safe.remaining_ops() > 0
+            || self.field.remaining_ops() > 0
+            || self.arthur.transcript.is_empty()
+        {
+            Ok(())
+        } else {
+            Err(format!(
+                "Transcript has {} trailing bytes",
+                self.arthur.transcript.len()
+            )
+            .into())
+        }
+    }
+}
+
+impl<HB, HF, C, R, const N: usize> ByteWriter for HybridMerlin<HB, HF, C, N, R>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+    R: RngCore + CryptoRng,
+{
+    fn add_bytes(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
+        self.merlin.add_bytes(input)
+    }
+}
+
+impl<HB, HF, C, const N: usize> ByteReader for HybridArthur<'_, HB, HF, C, N>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    fn fill_next_bytes(&mut self, input: &mut [u8]) -> Result<(), IOPatternError> {
+        self.arthur.fill_next_bytes(input)?;
+        self.check_trailing_bytes()
+    }
+}
+
+impl<HB, HF, C, const N: usize> UnitTranscript<u8> for HybridTranscript<HB, HF, C, N>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    fn public_units(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
+        self.bytes.absorb(input)
+    }
+
+    fn fill_challenge_units(&mut self, output: &mut [u8]) -> Result<(), IOPatternError> {
+        self.bytes.squeeze(output)
+    }
+}
+
+impl<HB, HF, C, const N: usize> UnitTranscript<Fp<C, N>> for HybridTranscript<HB, HF, C, N>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    fn public_units(&mut self, input: &[Fp<C, N>]) -> Result<(), IOPatternError> {
+        self.field.absorb(input)
+    }
+
+    fn fill_challenge_units(&mut self, output: &mut [Fp<C, N>]) -> Result<(), IOPatternError> {
+        self.field.squeeze(output)
+    }
+}
+
+impl<HB, HF, C, R, const N: usize> UnitTranscript<u8> for HybridMerlin<HB, HF, C, N, R>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+    R: RngCore + CryptoRng,
+{
+    fn public_units(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
+        self.merlin.public_units(input)
+    }
+
+    fn fill_challenge_units(&mut self, output: &mut [u8]) -> Result<(), IOPatternError> {
+        self.merlin.fill_challenge_units(output)
+    }
+}
+
+impl<HB, HF, C, R, const N: usize> UnitTranscript<Fp<C, N>> for HybridMerlin<HB, HF, C, N, R>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+    R: RngCore + CryptoRng,
+{
+    fn public_units(&mut self, input: &[Fp<C, N>]) -> Result<(), IOPatternError> {
+        self.field.absorb(input)
+    }
+
+    fn fill_challenge_units(&mut self, output: &mut [Fp<C, N>]) -> Result<(), IOPatternError> {
+        self.field.squeeze(output)
+    }
+}
+
+impl<HB, HF, C, const N: usize> UnitTranscript<u8> for HybridArthur<'_, HB, HF, C, N>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    fn public_units(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
+        self.arthur.public_units(input)?;
+        self.check_trailing_bytes()
+    }
+
+    fn fill_challenge_units(&mut self, output: &mut [u8]) -> Result<(), IOPatternError> {
+        self.arthur.fill_challenge_units(output)?;
+        self.check_trailing_bytes()
+    }
+}
+
+impl<HB, HF, C, const N: usize> UnitTranscript<Fp<C, N>> for HybridArthur<'_, HB, HF, C, N>
+where
+    HB: DuplexHash<u8>,
+    HF: DuplexHash<Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    fn public_units(&mut self, input: &[Fp<C, N>]) -> Result<(), IOPatternError> {
+        self.field.absorb(input)?;
+        self.check_trailing_bytes()
+    }
+
+    fn fill_challenge_units(&mut self, output: &mut [Fp<C, N>]) -> Result<(), IOPatternError> {
+        self.field.squeeze(output)?;
+        self.check_trailing_bytes()
+    }
+}
@@ -43,19 +43,21 @@ where
     }
 }
 
-impl<C, H, const N: usize> ByteIOPattern for IOPattern<H, Fp<C, N>>
-where
-    C: FpConfig<N>,
-    H: DuplexHash<Fp<C, N>>,
-{
-    /// Add `count` bytes to the transcript, encoding each of them as an element of the field `Fp`.
-    fn add_bytes(self, count: usize, label: &str) -> Self {
-        self.absorb(count, label)
+impl<T: ByteIOPattern> DistinctChallengesIOPattern for T {
+    fn challenge_scalars_distinct(self, label: &str) -> Self {
+        self.challenge_bytes(32, label)
     }
+}
+
+impl<T: ByteIOPattern> DomainPointIOPattern for T {
+    fn challenge_domain_point(self, label: &str) -> Self {
+        self.challenge_bytes(32, label)
+    }
+}
 
-    fn challenge_bytes(self, count: usize, label: &str) -> Self {
-        let n = crate::plugins::random_bits_in_random_modp(Fp::<C, N>::MODULUS) / 8;
-        self.squeeze(count.div_ceil(n), label)
+impl<T: ByteIOPattern> VerifyingKeyIOPattern for T {
+    fn absorb_vk(self, size: usize, label: &str) -> Self {
+        self.add_bytes(size, label)
     }
 }
 
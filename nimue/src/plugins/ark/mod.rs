@@ -117,13 +117,37 @@
 //! ```
 //! Now the above code should work with algebraic hashes such as `PoseidonHash` just as well as [`Keccak`][`crate::hash::Keccak`].
 //!
+//! ## Protocol transcripts over field units
+//!
+//! When the hash function operates over a prime field $\mathbb{F}_p$, the protocol transcript is the concatenation
+//! of the prover messages, each field element being encoded in its canonical form (the integer in $[0, p)$),
+//! as a fixed-size, little-endian string of $\lceil \log_2 p / 8 \rceil$ bytes.
+//! This is the same as arkworks' compressed serialization.
+//! Non-canonical encodings are rejected by the verifier.
+//! Group elements are encoded as their affine coordinates $(x, y)$ (see [`AffineCoordinates`]).
+//! Implementations expecting big-endian encodings must reverse the bytes of each element.
+//!
+/// Transcripts with adversarial contents, for testing verifiers.
+pub mod adversarial;
+/// A duplex hash over bytes, from a duplex hash over a prime field.
+pub mod bytes;
 /// Add public elements (field or group elements) to the protocol transcript.
 mod common;
+/// Encryption of the protocol transcript to a designated verifier, via ephemeral Diffie-Hellman.
+pub mod designated;
+/// Bridging elements across fields, for recursion.
+pub mod field_bridge;
+/// Fiat-Shamir over a byte sponge and a field sponge, bridged together.
+pub mod hybrid;
 /// IO Pattern utilities.
 mod iopattern;
 
+/// Commonly-needed types and traits for arkworks.
+pub mod prelude;
 /// Veririfer's utilities for decoding a transcript.
 mod reader;
+/// The sponge API of arkworks, over nimue's duplex hashes.
+pub mod sponge;
 /// Prover's utilities for encoding into a transcript.
 mod writer;
 
@@ -132,43 +156,591 @@ mod writer;
 mod tests;
 
 pub use crate::traits::*;
+pub use field_bridge::swap_field;
 pub use crate::{hash::Unit, Arthur, DuplexHash, IOPattern, Merlin, ProofError, ProofResult, Safe};
 
 super::traits::field_traits!(ark_ff::Field);
 super::traits::group_traits!(ark_ec::CurveGroup, Scalar: ark_ff::PrimeField);
 
-/// Move a value from prime field F1 to prime field F2.
-///
-/// Return an error if the element considered mod |F1| is different, when seen as an integer, mod |F2|.
-/// This in particular happens when element > |F2|.
-pub fn swap_field<F1: ark_ff::PrimeField, F2: ark_ff::PrimeField>(a_f1: F1) -> ProofResult<F2> {
-    use ark_ff::BigInteger;
-    let a_f2 = F2::from_le_bytes_mod_order(&a_f1.into_bigint().to_bytes_le());
-    let a_f1_control = F1::from_le_bytes_mod_order(&a_f2.into_bigint().to_bytes_le());
-    (a_f1 == a_f1_control)
-        .then_some(a_f2)
-        .ok_or(ProofError::SerializationError)
-}
-
-// pub trait PairingReader<P: ark_ec::pairing::Pairing>: GroupReader<P::G1> + GroupReader<P::G2>  {
-//     fn fill_next_g1_points(&mut self, input: &mut [P::G1]) -> crate::ProofResult<()> {
-//         GroupReader::<P::G1>::fill_next_points(self, input)
-//     }
-
-//     fn fill_next_g2_points(&mut self, input: &mut [P::G2]) -> crate::ProofResult<()> {
-//         GroupReader::<P::G2>::fill_next_points(self, input)
-//     }
-// }
-// pub trait PairingWriter<P: ark_ec::pairing::Pairing> {
-//     fn add_g1_points(&mut self, input: &[P::G1]) -> crate::ProofResult<()> {
-//         GroupWriter::<P::G1>::add_points(self, input)
-//     }
-
-//     fn add_g2_points(&mut self, input: &[P::G2]) -> crate::ProofResult<()> {
-//         GroupWriter::<P::G2>::add_points(self, input)
-//     }
-// }
-
-// impl<'a, P: ark_ec::pairing::Pairing, H, U> PairingWriter<P> for Arthur<'a, H, U> where
-// U: Unit, H: DuplexHash<U>,
-// Arthur<'a, H, U>:  GroupWriter<P::G1> + GroupWriter<P::G2>  {}
+/// Declare a (preprocessed) verifying key in the IO pattern.
+///
+/// The verifying key is public information: it is absorbed in the sponge but never written to the protocol transcript.
+/// By convention, the verifying key is absorbed once, at statement time, and followed by a [`IOPattern::ratchet`],
+/// so that the (possibly large) key does not pollute the rate of the following rounds:
+///
+/// ```text
+///     domain-separator A1184vk R A32commitment S47challenge ...
+/// ```
+pub trait VerifyingKeyIOPattern {
+    /// Absorb a verifying key of `size` bytes (its compressed size, see [`ark_serialize::CanonicalSerialize::compressed_size`]).
+    fn absorb_vk(self, size: usize, label: &str) -> Self;
+}
+
+/// Bind a verifying key to the protocol transcript.
+///
+/// Prover and verifier call this method with the same key, which is serialized (compressed)
+/// and absorbed as public bytes. See [`VerifyingKeyIOPattern`] for the recommended pattern.
+pub trait VerifyingKeyPublic {
+    /// Serialize `vk` (compressed) and absorb it as public bytes, declared with [`VerifyingKeyIOPattern::absorb_vk`].
+    fn add_verifying_key<VK: ark_serialize::CanonicalSerialize>(
+        &mut self,
+        vk: &VK,
+    ) -> ProofResult<()>;
+}
+
+/// Declare pairwise-distinct challenges (e.g. evaluation points for interpolation) in the IO pattern.
+///
+/// Distinct challenges are derived from a 32-byte seed squeezed from the sponge,
+/// so the IO Pattern does not depend on the number of challenges, nor on the number of retries.
+pub trait DistinctChallengesIOPattern {
+    /// Squeeze the seed of pairwise-distinct challenges, see [`DistinctChallenges`].
+    fn challenge_scalars_distinct(self, label: &str) -> Self;
+}
+
+/// Squeeze pairwise-distinct challenges.
+///
+/// A 32-byte seed is squeezed from the sponge, and scalars are sampled from a [`Keccak`](crate::hash::Keccak) stream keyed with the seed,
+/// skipping the ones already sampled.
+/// Retries happen outside of the sponge: prover and verifier sample the same scalars without communicating anything.
+pub trait DistinctChallenges<F: ark_ff::Field> {
+    /// Fill `output` with pairwise-distinct challenges.
+    ///
+    /// Fails if the field has fewer than `output.len()` elements.
+    fn fill_challenge_scalars_distinct(&mut self, output: &mut [F]) -> ProofResult<()>;
+
+    /// Return `N` pairwise-distinct challenges.
+    fn challenge_scalars_distinct<const N: usize>(&mut self) -> ProofResult<[F; N]> {
+        let mut output = [F::default(); N];
+        self.fill_challenge_scalars_distinct(&mut output)
+            .map(|()| output)
+    }
+}
+
+/// A multiplicative coset $g \cdot H$ of the subgroup $H$ of order `size`, e.g. the evaluation domain of a FRI or STIR round.
+///
+/// The FFT domains of `ark-poly` are cosets of this form: `CosetDomain::new(domain.size() as u64, domain.coset_offset())`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CosetDomain<F: ark_ff::Field> {
+    /// The order of the subgroup $H$.
+    pub size: u64,
+    /// The offset $g$ of the coset (one for the subgroup itself).
+    pub offset: F,
+}
+
+impl<F: ark_ff::Field> CosetDomain<F> {
+    /// The coset $g \cdot H$ of the subgroup of order `size`, with offset $g$ = `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(size: u64, offset: F) -> Self {
+        assert!(size > 0, "the domain must have a non-zero size");
+        Self { size, offset }
+    }
+
+    /// The subgroup of order `size`.
+    pub fn subgroup(size: u64) -> Self {
+        Self::new(size, F::one())
+    }
+
+    /// Whether `x` lies in the domain, i.e. whether $x^n = g^n$, where $n$ is the size of the domain.
+    pub fn contains(&self, x: &F) -> bool {
+        x.pow([self.size]) == self.offset.pow([self.size])
+    }
+}
+
+/// Declare an out-of-domain challenge in the IO pattern.
+///
+/// As for [`DistinctChallengesIOPattern`], a 32-byte seed is squeezed from the sponge,
+/// so that the IO Pattern does not depend on the number of retries.
+pub trait DomainPointIOPattern {
+    /// Squeeze the seed of an out-of-domain challenge, see [`DomainPointChallenges`].
+    fn challenge_domain_point(self, label: &str) -> Self;
+}
+
+/// Squeeze a challenge outside of an evaluation domain, e.g. for out-of-domain sampling in DEEP-FRI and STIR.
+///
+/// A 32-byte seed is squeezed from the sponge, and candidates are sampled from a [`Keccak`](crate::hash::Keccak) stream
+/// keyed with the seed, until one lies outside of the domain.
+/// Retries happen outside of the sponge: prover and verifier resample the same candidates without communicating anything,
+/// and perform the same sponge operations whatever the number of retries.
+pub trait DomainPointChallenges<F: ark_ff::Field> {
+    /// Return a challenge outside of `domain`.
+    ///
+    /// Fails if the domain is empty or covers the whole field,
+    /// or if the first 1024 candidates all lie in the domain (which is negligible unless the domain covers most of the field).
+    fn challenge_domain_point(&mut self, domain: &CosetDomain<F>) -> ProofResult<F>;
+}
+
+/// How points read from the protocol transcript are checked against the prime-order subgroup.
+///
+/// Points are always checked to lie on the curve: over bytes, they are decoded from their compressed encoding;
+/// over field units, they are read as affine coordinates $(x, y)$ and checked against the curve equation.
+/// [`GroupReader::fill_next_points`] implements [`SubgroupPolicy::Always`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SubgroupPolicy {
+    /// Reject points outside of the prime-order subgroup with [`ProofError::SerializationError`].
+    #[default]
+    Always,
+    /// Do not check subgroup membership.
+    /// The protocol (or the curve, if it has cofactor one) must take care of small-subgroup attacks.
+    Never,
+    /// Multiply points by the cofactor, mapping them into the prime-order subgroup.
+    /// Note that the point returned differs from the one absorbed in the sponge, unless the cofactor is one.
+    CofactorClear,
+}
+
+/// The bytes of the encoding returned by [`FieldPublic::public_scalars`] or [`GroupPublic::public_points`].
+///
+/// For byte-oriented transcripts, these are the bytes absorbed.
+/// For algebraic transcripts, these are the native field elements absorbed, each encoded as in the protocol transcript
+/// (little-endian, see [`Unit::write`]), so that downstream users can audit (e.g. log, or hash) the exact statement of a proof in a uniform format.
+pub trait ReprBytes {
+    /// The encoding, as bytes.
+    fn repr_bytes(&self) -> Vec<u8>;
+}
+
+/// The affine coordinates $(x, y)$ of a point, as read and written by transcripts over field units (e.g. with algebraic hashes).
+///
+/// Implemented for short Weierstrass curves, and for twisted Edwards curves (e.g. Jubjub, Bandersnatch).
+/// On twisted Edwards curves, the identity is the point $(0, 1)$, and is encoded as any other point.
+/// On short Weierstrass curves $y^2 = x^3 + ax + b$ with $b \neq 0$, the point at infinity is encoded as $(0, 0)$.
+pub trait AffineCoordinates: ark_ec::CurveGroup {
+    /// The affine coordinates of the affine point `affine`.
+    ///
+    /// Fails with [`ProofError::SerializationError`] if the point cannot be encoded
+    /// (the point at infinity of a short Weierstrass curve with $b = 0$).
+    fn affine_to_coordinates(affine: &Self::Affine) -> ProofResult<[Self::BaseField; 2]>;
+
+    /// The affine point with affine coordinates `coordinates`.
+    ///
+    /// Fails with [`ProofError::SerializationError`] if the point is not on the curve.
+    /// Subgroup membership is not checked.
+    fn affine_from_coordinates(coordinates: [Self::BaseField; 2]) -> ProofResult<Self::Affine>;
+
+    /// The affine coordinates of the point. See [`AffineCoordinates::affine_to_coordinates`].
+    fn to_coordinates(&self) -> ProofResult<[Self::BaseField; 2]> {
+        Self::affine_to_coordinates(&self.into_affine())
+    }
+
+    /// The point with affine coordinates `coordinates`. See [`AffineCoordinates::affine_from_coordinates`].
+    fn from_coordinates(coordinates: [Self::BaseField; 2]) -> ProofResult<Self> {
+        Self::affine_from_coordinates(coordinates).map(Into::into)
+    }
+}
+
+/// Add affine points to the protocol transcript, without converting them to projective coordinates.
+///
+/// Points are encoded exactly as by [`GroupWriter::add_points`] for the group `A::Group`,
+/// and declared in the IO Pattern with [`GroupIOPattern::add_points`].
+pub trait AffineWriter<A: ark_ec::AffineRepr> {
+    /// Serialize the affine points `input` and add them to the protocol transcript.
+    fn add_affine_points(&mut self, input: &[A]) -> ProofResult<()>;
+}
+
+/// Read affine points from the protocol transcript, without converting them to projective coordinates.
+///
+/// As with [`GroupReader::fill_next_points`], points are checked to be on the curve and in the prime-order subgroup.
+pub trait AffineReader<A: ark_ec::AffineRepr> {
+    /// Deserialize affine points from the protocol transcript into `output`.
+    fn fill_next_affine_points(&mut self, output: &mut [A]) -> ProofResult<()>;
+
+    /// Deserialize affine points from the protocol transcript and return them.
+    fn next_affine_points<const N: usize>(&mut self) -> ProofResult<[A; N]> {
+        let mut output = [A::zero(); N];
+        self.fill_next_affine_points(&mut output).map(|()| output)
+    }
+}
+
+/// Receive group elements from the protocol transcript, with an explicit [`SubgroupPolicy`].
+pub trait GroupReaderWithPolicy<G: ark_ec::CurveGroup>: GroupReader<G> {
+    /// Deserialize group elements from the protocol transcript into `output`, checking them according to `policy`.
+    fn fill_next_points_with_policy(
+        &mut self,
+        output: &mut [G],
+        policy: SubgroupPolicy,
+    ) -> ProofResult<()>;
+
+    /// Deserialize group elements from the protocol transcript according to `policy`, and return them.
+    fn next_points_with_policy<const N: usize>(
+        &mut self,
+        policy: SubgroupPolicy,
+    ) -> ProofResult<[G; N]> {
+        let mut output = [G::default(); N];
+        self.fill_next_points_with_policy(&mut output, policy)
+            .map(|()| output)
+    }
+}
+
+/// Read G1 and G2 elements of a pairing-friendly curve from the protocol transcript.
+pub trait PairingReader<P: ark_ec::pairing::Pairing>:
+    GroupReader<P::G1> + GroupReader<P::G2>
+{
+    fn fill_next_g1_points(&mut self, output: &mut [P::G1]) -> crate::ProofResult<()> {
+        GroupReader::<P::G1>::fill_next_points(self, output)
+    }
+
+    fn fill_next_g2_points(&mut self, output: &mut [P::G2]) -> crate::ProofResult<()> {
+        GroupReader::<P::G2>::fill_next_points(self, output)
+    }
+}
+
+/// Add G1 and G2 elements of a pairing-friendly curve to the protocol transcript.
+pub trait PairingWriter<P: ark_ec::pairing::Pairing>:
+    GroupWriter<P::G1> + GroupWriter<P::G2>
+{
+    fn add_g1_points(&mut self, input: &[P::G1]) -> crate::ProofResult<()> {
+        GroupWriter::<P::G1>::add_points(self, input)
+    }
+
+    fn add_g2_points(&mut self, input: &[P::G2]) -> crate::ProofResult<()> {
+        GroupWriter::<P::G2>::add_points(self, input)
+    }
+}
+
+impl<P, T> PairingReader<P> for T
+where
+    P: ark_ec::pairing::Pairing,
+    T: GroupReader<P::G1> + GroupReader<P::G2>,
+{
+}
+
+impl<P, T> PairingWriter<P> for T
+where
+    P: ark_ec::pairing::Pairing,
+    T: GroupWriter<P::G1> + GroupWriter<P::G2>,
+{
+}
+
+/// The opening of a KZG commitment $C = [f(\tau)]_1$ at an evaluation point $z$:
+/// the evaluation $v = f(z)$ and the witness $W = [(f(\tau) - v) / (\tau - z)]_1$.
+///
+/// This is the (non-hiding) `Proof` of `ark_poly_commit::kzg10`, together with the claimed evaluation:
+/// commitments and witnesses are added to the protocol transcript as compressed affine points of $\mathbb{G}_1$,
+/// and evaluations as scalars.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KzgOpening<P: ark_ec::pairing::Pairing> {
+    /// The claimed evaluation $v = f(z)$.
+    pub value: P::ScalarField,
+    /// The witness $W$, a commitment to the quotient polynomial $(f(X) - v) / (X - z)$.
+    pub witness: P::G1Affine,
+}
+
+impl<P: ark_ec::pairing::Pairing> KzgOpening<P> {
+    /// Check that `commitment` opens to this opening at `point`,
+    /// given the generators $G$, $H$ and $[\tau]_2 = \tau \cdot H$ of the structured reference string:
+    /// $e(C - v \cdot G, H) = e(W, [\tau]_2 - z \cdot H)$.
+    pub fn verify(
+        &self,
+        commitment: &P::G1Affine,
+        point: P::ScalarField,
+        g: P::G1Affine,
+        h: P::G2Affine,
+        tau_h: P::G2Affine,
+    ) -> ProofResult<()> {
+        let lhs = P::pairing(*commitment - g * self.value, h);
+        let rhs = P::pairing(self.witness, tau_h - h * point);
+        if lhs == rhs {
+            Ok(())
+        } else {
+            Err(ProofError::InvalidProof)
+        }
+    }
+}
+
+/// Declare KZG commitments, evaluation points and openings in the IO pattern.
+pub trait KzgIOPattern<P: ark_ec::pairing::Pairing> {
+    /// Send a KZG commitment, one point of $\mathbb{G}_1$.
+    fn add_kzg_commitment(self, label: &str) -> Self;
+    /// Squeeze an evaluation point, one scalar.
+    fn challenge_eval_point(self, label: &str) -> Self;
+    /// Send a [`KzgOpening`]: the evaluation (one scalar) followed by the witness (one point of $\mathbb{G}_1$).
+    fn add_kzg_opening_proof(self, label: &str) -> Self;
+}
+
+impl<P, T> KzgIOPattern<P> for T
+where
+    P: ark_ec::pairing::Pairing,
+    T: GroupIOPattern<P::G1> + FieldIOPattern<P::ScalarField>,
+{
+    fn add_kzg_commitment(self, label: &str) -> Self {
+        GroupIOPattern::<P::G1>::add_points(self, 1, label)
+    }
+
+    fn challenge_eval_point(self, label: &str) -> Self {
+        FieldIOPattern::<P::ScalarField>::challenge_scalars(self, 1, label)
+    }
+
+    fn add_kzg_opening_proof(self, label: &str) -> Self {
+        let io = FieldIOPattern::<P::ScalarField>::add_scalars(self, 1, label);
+        GroupIOPattern::<P::G1>::add_points(io, 1, label)
+    }
+}
+
+/// Add KZG commitments and openings to the protocol transcript.
+pub trait KzgWriter<P: ark_ec::pairing::Pairing>:
+    AffineWriter<P::G1Affine> + FieldWriter<P::ScalarField>
+{
+    /// Add a KZG commitment (e.g. the inner point of an `ark_poly_commit::kzg10::Commitment`).
+    fn add_kzg_commitment(&mut self, commitment: &P::G1Affine) -> ProofResult<()> {
+        self.add_affine_points(&[*commitment])
+    }
+
+    /// Add the evaluation and the witness of a KZG opening.
+    fn add_kzg_opening_proof(&mut self, opening: &KzgOpening<P>) -> ProofResult<()> {
+        self.add_scalars(&[opening.value])?;
+        self.add_affine_points(&[opening.witness])
+    }
+}
+
+/// Read KZG commitments and openings from the protocol transcript.
+///
+/// Points are checked to be on the curve and in the prime-order subgroup, as for [`AffineReader`].
+/// Openings are *not* verified: see [`KzgOpening::verify`].
+pub trait KzgReader<P: ark_ec::pairing::Pairing>:
+    AffineReader<P::G1Affine> + FieldReader<P::ScalarField>
+{
+    /// Read a KZG commitment.
+    fn next_kzg_commitment(&mut self) -> ProofResult<P::G1Affine> {
+        let [commitment] = self.next_affine_points()?;
+        Ok(commitment)
+    }
+
+    /// Read the evaluation and the witness of a KZG opening.
+    fn next_kzg_opening_proof(&mut self) -> ProofResult<KzgOpening<P>> {
+        let [value] = self.next_scalars()?;
+        let [witness] = self.next_affine_points()?;
+        Ok(KzgOpening { value, witness })
+    }
+}
+
+/// Squeeze evaluation points for KZG openings.
+pub trait KzgChallenges<P: ark_ec::pairing::Pairing>: FieldChallenges<P::ScalarField> {
+    /// Squeeze an evaluation point.
+    fn challenge_eval_point(&mut self) -> ProofResult<P::ScalarField> {
+        let [point] = self.challenge_scalars()?;
+        Ok(point)
+    }
+}
+
+impl<P, T> KzgWriter<P> for T
+where
+    P: ark_ec::pairing::Pairing,
+    T: AffineWriter<P::G1Affine> + FieldWriter<P::ScalarField>,
+{
+}
+
+impl<P, T> KzgReader<P> for T
+where
+    P: ark_ec::pairing::Pairing,
+    T: AffineReader<P::G1Affine> + FieldReader<P::ScalarField>,
+{
+}
+
+impl<P, T> KzgChallenges<P> for T
+where
+    P: ark_ec::pairing::Pairing,
+    T: FieldChallenges<P::ScalarField>,
+{
+}
+
+/// The framing of the label of a commitment: its length (as a little-endian `u64`) followed by its bytes.
+fn framed_label(label: &str) -> Vec<u8> {
+    let mut framed = (label.len() as u64).to_le_bytes().to_vec();
+    framed.extend_from_slice(label.as_bytes());
+    framed
+}
+
+/// Declare labeled commitments (e.g. the `LabeledCommitment`s of `ark_poly_commit`) in the IO pattern.
+///
+/// Each commitment is preceded by its label, absorbed as a public, length-prefixed string:
+///
+/// ```text
+///     A{8 + len(labels[0])}label A{point size}label A{8 + len(labels[1])}label ...
+/// ```
+///
+/// so that the order and the naming of the commitments of a PIOP compiler are bound to the transcript.
+pub trait LabeledCommitmentIOPattern<G: ark_ec::CurveGroup> {
+    /// Add one commitment (a point of `G`) for each of `labels`.
+    fn add_labeled_commitments(self, labels: &[&str], label: &str) -> Self;
+}
+
+impl<G, T> LabeledCommitmentIOPattern<G> for T
+where
+    G: ark_ec::CurveGroup,
+    T: ByteIOPattern + GroupIOPattern<G>,
+{
+    fn add_labeled_commitments(self, labels: &[&str], label: &str) -> Self {
+        labels.iter().fold(self, |io, commitment_label| {
+            let io = io.add_bytes(framed_label(commitment_label).len(), label);
+            GroupIOPattern::<G>::add_points(io, 1, label)
+        })
+    }
+}
+
+/// Add labeled commitments to the protocol transcript.
+///
+/// Labels are absorbed as public elements, and commitments are added to the protocol transcript,
+/// in the order given.
+/// With `ark_poly_commit`, the input is obtained as
+/// `commitments.iter().map(|c| (c.label().as_str(), c.commitment().0))` for KZG commitments.
+pub trait LabeledCommitmentWriter<A: ark_ec::AffineRepr>: BytePublic + AffineWriter<A> {
+    /// Absorb the label of each commitment, and add the commitment to the protocol transcript.
+    fn add_labeled_commitments<'l>(
+        &mut self,
+        commitments: impl IntoIterator<Item = (&'l str, A)>,
+    ) -> ProofResult<()> {
+        for (label, commitment) in commitments {
+            self.public_bytes(&framed_label(label))?;
+            self.add_affine_points(&[commitment])?;
+        }
+        Ok(())
+    }
+}
+
+/// Read labeled commitments from the protocol transcript.
+pub trait LabeledCommitmentReader<A: ark_ec::AffineRepr>: BytePublic + AffineReader<A> {
+    /// Read one commitment for each of `labels`, that the verifier absorbs as public elements.
+    fn next_labeled_commitments(&mut self, labels: &[&str]) -> ProofResult<Vec<A>> {
+        labels
+            .iter()
+            .map(|label| {
+                self.public_bytes(&framed_label(label))?;
+                let [commitment] = self.next_affine_points()?;
+                Ok(commitment)
+            })
+            .collect()
+    }
+}
+
+impl<A, T> LabeledCommitmentWriter<A> for T
+where
+    A: ark_ec::AffineRepr,
+    T: BytePublic + AffineWriter<A>,
+{
+}
+
+impl<A, T> LabeledCommitmentReader<A> for T
+where
+    A: ark_ec::AffineRepr,
+    T: BytePublic + AffineReader<A>,
+{
+}
+
+/// The header of a vector of small scalars: the number of scalars and their size in bits, as little-endian `u64`s.
+fn small_scalars_header(count: usize, bits: usize) -> [u8; 16] {
+    let mut header = [0u8; 16];
+    header[..8].copy_from_slice(&(count as u64).to_le_bytes());
+    header[8..].copy_from_slice(&(bits as u64).to_le_bytes());
+    header
+}
+
+/// Check that scalars of `bits` bits are canonical elements of `F`, i.e. that $2^{bits} \leq p$.
+fn check_small_scalar_bits<F: ark_ff::PrimeField>(bits: usize) -> ProofResult<()> {
+    if bits == 0 || bits >= F::MODULUS_BIT_SIZE as usize {
+        Err(ProofError::SerializationError)
+    } else {
+        Ok(())
+    }
+}
+
+/// Declare vectors of small scalars (e.g. the multiplicities of a lookup argument) in the IO pattern.
+///
+/// A vector of `count` scalars smaller than $2^{bits}$ is sent bit-packed in $\lceil count \cdot bits / 8 \rceil$ bytes,
+/// preceded by a public header absorbing `count` and `bits`:
+///
+/// ```text
+///     A16label A{(count * bits).div_ceil(8)}label
+/// ```
+pub trait SmallScalarIOPattern<F: ark_ff::PrimeField> {
+    /// Add `count` scalars of `bits` bits.
+    ///
+    /// # Panics
+    ///
+    /// If `bits` is zero, or not smaller than the size of the modulus of `F`.
+    fn add_small_scalars(self, count: usize, bits: usize, label: &str) -> Self;
+}
+
+impl<F, T> SmallScalarIOPattern<F> for T
+where
+    F: ark_ff::PrimeField,
+    T: ByteIOPattern,
+{
+    fn add_small_scalars(self, count: usize, bits: usize, label: &str) -> Self {
+        assert!(
+            check_small_scalar_bits::<F>(bits).is_ok(),
+            "Small scalars must have between 1 and MODULUS_BIT_SIZE - 1 bits."
+        );
+        self.add_bytes(small_scalars_header(count, bits).len(), label)
+            .add_bytes((count * bits).div_ceil(8), label)
+    }
+}
+
+/// Add bit-packed small scalars to the protocol transcript.
+///
+/// Scalars are packed in little-endian order: the `i`-th scalar occupies bits $[i \cdot bits, (i + 1) \cdot bits)$,
+/// and the last byte is padded with zeros.
+pub trait SmallScalarWriter<F: ark_ff::PrimeField>: BytePublic + ByteWriter {
+    /// Fails with [`ProofError::SerializationError`] if a scalar is not smaller than $2^{bits}$.
+    fn add_small_scalars(&mut self, bits: usize, input: &[F]) -> ProofResult<()> {
+        use ark_ff::BigInteger;
+
+        check_small_scalar_bits::<F>(bits)?;
+        let mut packed = vec![0u8; (input.len() * bits).div_ceil(8)];
+        for (i, x) in input.iter().enumerate() {
+            let x = x.into_bigint();
+            if x.num_bits() as usize > bits {
+                return Err(ProofError::SerializationError);
+            }
+            for (j, bit) in x.to_bits_le().into_iter().take(bits).enumerate() {
+                packed[(i * bits + j) / 8] |= (bit as u8) << ((i * bits + j) % 8);
+            }
+        }
+        self.public_bytes(&small_scalars_header(input.len(), bits))?;
+        Ok(self.add_bytes(&packed)?)
+    }
+}
+
+/// Read bit-packed small scalars from the protocol transcript.
+pub trait SmallScalarReader<F: ark_ff::PrimeField>: BytePublic + ByteReader {
+    /// Fails with [`ProofError::SerializationError`] if the padding bits are not zero.
+    fn fill_next_small_scalars(&mut self, bits: usize, output: &mut [F]) -> ProofResult<()> {
+        use ark_ff::BigInteger;
+
+        check_small_scalar_bits::<F>(bits)?;
+        let mut packed = vec![0u8; (output.len() * bits).div_ceil(8)];
+        self.public_bytes(&small_scalars_header(output.len(), bits))?;
+        self.fill_next_bytes(&mut packed)?;
+        let bit = |k: usize| packed[k / 8] >> (k % 8) & 1 == 1;
+        if (output.len() * bits..packed.len() * 8).any(bit) {
+            return Err(ProofError::SerializationError);
+        }
+        for (i, x) in output.iter_mut().enumerate() {
+            let bits_le = (i * bits..(i + 1) * bits).map(bit).collect::<Vec<_>>();
+            *x = F::from_bigint(F::BigInt::from_bits_le(&bits_le))
+                .ok_or(ProofError::SerializationError)?;
+        }
+        Ok(())
+    }
+
+    /// Read `count` scalars of `bits` bits.
+    fn next_small_scalars(&mut self, bits: usize, count: usize) -> ProofResult<Vec<F>> {
+        let mut output = vec![F::zero(); count];
+        self.fill_next_small_scalars(bits, &mut output)
+            .map(|()| output)
+    }
+}
+
+impl<F, T> SmallScalarWriter<F> for T
+where
+    F: ark_ff::PrimeField,
+    T: BytePublic + ByteWriter,
+{
+}
+
+impl<F, T> SmallScalarReader<F> for T
+where
+    F: ark_ff::PrimeField,
+    T: BytePublic + ByteReader,
+{
+}
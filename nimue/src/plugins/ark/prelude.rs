@@ -0,0 +1,34 @@
+//! Commonly-needed types and traits for arkworks, to be glob-imported.
+//!
+//! It includes the [crate prelude](crate::prelude).
+//!
+//! ```
+//! use ark_bls12_381::{Fr, G1Projective};
+//! use ark_ec::PrimeGroup;
+//! use nimue::plugins::ark::prelude::*;
+//!
+//! let io = IOPattern::<DefaultHash>::new("📝");
+//! let io = GroupIOPattern::<G1Projective>::add_points(io, 1, "com");
+//! let io = FieldIOPattern::<Fr>::challenge_scalars(io, 1, "chal");
+//! let mut merlin = io.to_merlin();
+//! merlin.add_points(&[G1Projective::generator()]).unwrap();
+//! let [chal]: [Fr; 1] = merlin.challenge_scalars().unwrap();
+//!
+//! let mut arthur = io.to_arthur(merlin.transcript());
+//! let [point]: [G1Projective; 1] = arthur.next_points().unwrap();
+//! assert_eq!(point, G1Projective::generator());
+//! let [arthur_chal]: [Fr; 1] = arthur.challenge_scalars().unwrap();
+//! assert_eq!(arthur_chal, chal);
+//! ```
+
+pub use super::{
+    AffineCoordinates, AffineReader, AffineWriter, CosetDomain, DistinctChallenges,
+    DistinctChallengesIOPattern, DomainPointChallenges, DomainPointIOPattern, FieldChallenges,
+    FieldIOPattern, FieldPublic, FieldReader, FieldWriter, GroupIOPattern, GroupPublic,
+    GroupReader, GroupReaderWithPolicy, GroupWriter, KzgChallenges, KzgIOPattern, KzgOpening,
+    KzgReader, KzgWriter, LabeledCommitmentIOPattern, LabeledCommitmentReader,
+    LabeledCommitmentWriter, PairingReader, PairingWriter, ReprBytes, SmallScalarIOPattern,
+    SmallScalarReader, SmallScalarWriter, SubgroupPolicy, VerifyingKeyIOPattern,
+    VerifyingKeyPublic,
+};
+pub use crate::prelude::*;
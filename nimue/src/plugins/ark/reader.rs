@@ -1,13 +1,49 @@
-use ark_ec::short_weierstrass::{Affine as SWAffine, Projective as SWCurve, SWCurveConfig};
-use ark_ec::twisted_edwards::{Affine as EdwardsAffine, Projective as EdwardsCurve, TECurveConfig};
-use ark_ec::CurveGroup;
+use ark_ec::{AffineRepr, CurveGroup};
 use ark_ff::Field;
 use ark_ff::{Fp, FpConfig};
-use ark_serialize::CanonicalDeserialize;
+use ark_serialize::{CanonicalDeserialize, Compress, Valid, Validate};
 
-use super::{FieldReader, GroupReader};
+use super::{
+    AffineCoordinates, AffineReader, FieldReader, GroupReader, GroupReaderWithPolicy,
+    SubgroupPolicy,
+};
+use crate::middleware::{LayeredTranscript, TranscriptMiddleware};
 use crate::traits::*;
-use crate::{Arthur, DuplexHash, ProofResult};
+use crate::{Arthur, DuplexHash, IOPatternError, ProofResult};
+
+/// Read field elements, in compressed form, from the prover messages of a byte transcript.
+pub(super) fn read_scalars<F: Field>(
+    transcript: &mut impl ByteReader,
+    output: &mut [F],
+) -> ProofResult<()> {
+    let mut buf = vec![0u8; F::default().compressed_size()];
+    for o in output.iter_mut() {
+        transcript.fill_next_bytes(&mut buf)?;
+        *o = F::deserialize_compressed(buf.as_slice())?;
+    }
+    Ok(())
+}
+
+/// Read compressed points from the prover messages of a byte transcript all at once,
+/// and check them in a batch (in parallel, if arkworks' `parallel` feature is enabled).
+pub(super) fn read_points<G: CurveGroup>(
+    transcript: &mut impl ByteReader,
+    output: &mut [G],
+) -> ProofResult<()> {
+    let point_size = G::default().compressed_size();
+    let mut buf = vec![0u8; point_size * output.len()];
+    transcript.fill_next_bytes(&mut buf)?;
+
+    let affines = buf
+        .chunks_exact(point_size)
+        .map(|chunk| G::Affine::deserialize_with_mode(chunk, Compress::Yes, Validate::No))
+        .collect::<Result<Vec<_>, _>>()?;
+    G::Affine::batch_check(affines.iter())?;
+    for (o, affine) in output.iter_mut().zip(affines) {
+        *o = affine.into_group();
+    }
+    Ok(())
+}
 
 impl<F, H> FieldReader<F> for Arthur<'_, H>
 where
@@ -15,13 +51,18 @@ where
     H: DuplexHash,
 {
     fn fill_next_scalars(&mut self, output: &mut [F]) -> ProofResult<()> {
-        let point_size = F::default().compressed_size();
-        let mut buf = vec![0u8; point_size];
-        for o in output.iter_mut() {
-            self.fill_next_bytes(&mut buf)?;
-            *o = F::deserialize_compressed(buf.as_slice())?;
-        }
-        Ok(())
+        read_scalars(self, output)
+    }
+}
+
+impl<F, T, M> FieldReader<F> for LayeredTranscript<T, M>
+where
+    F: Field,
+    T: UnitTranscript<u8> + ByteReader,
+    M: TranscriptMiddleware<u8>,
+{
+    fn fill_next_scalars(&mut self, output: &mut [F]) -> ProofResult<()> {
+        read_scalars(self, output)
     }
 }
 
@@ -30,18 +71,159 @@ where
     G: CurveGroup,
     H: DuplexHash,
 {
+    /// Read all points at once, and check them in a batch (in parallel, if arkworks' `parallel` feature is enabled).
     fn fill_next_points(&mut self, output: &mut [G]) -> ProofResult<()> {
-        let point_size = G::default().compressed_size();
-        let mut buf = vec![0u8; point_size];
+        read_points(self, output)
+    }
+}
 
-        for o in output.iter_mut() {
-            self.fill_next_units(&mut buf)?;
-            *o = G::deserialize_compressed(buf.as_slice())?;
-        }
-        Ok(())
+impl<G, T, M> GroupReader<G> for LayeredTranscript<T, M>
+where
+    G: CurveGroup,
+    T: UnitTranscript<u8> + ByteReader,
+    M: TranscriptMiddleware<u8>,
+{
+    /// Read all points at once, and check them in a batch, as for [`Arthur`].
+    fn fill_next_points(&mut self, output: &mut [G]) -> ProofResult<()> {
+        read_points(self, output)
+    }
+}
+
+/// Read compressed affine points from the prover messages of a byte transcript all at once,
+/// and check them in a batch, as [`read_points`].
+fn read_affine_points<A: AffineRepr>(
+    transcript: &mut impl ByteReader,
+    output: &mut [A],
+) -> ProofResult<()> {
+    let point_size = A::zero().compressed_size();
+    let mut buf = vec![0u8; point_size * output.len()];
+    transcript.fill_next_bytes(&mut buf)?;
+
+    for (o, chunk) in output.iter_mut().zip(buf.chunks_exact(point_size)) {
+        *o = A::deserialize_with_mode(chunk, Compress::Yes, Validate::No)?;
+    }
+    A::batch_check(output.iter())?;
+    Ok(())
+}
+
+/// Read compressed points from the prover messages of a byte transcript, one at a time,
+/// checking each of them according to `policy`.
+fn read_points_with_policy<G: CurveGroup>(
+    transcript: &mut impl ByteReader,
+    output: &mut [G],
+    policy: SubgroupPolicy,
+) -> ProofResult<()> {
+    let point_size = G::default().compressed_size();
+    let mut buf = vec![0u8; point_size];
+
+    for o in output.iter_mut() {
+        transcript.fill_next_bytes(&mut buf)?;
+        let point = G::deserialize_with_mode(buf.as_slice(), Compress::Yes, Validate::No)?;
+        *o = apply_subgroup_policy(point, policy)?;
+    }
+    Ok(())
+}
+
+impl<A, H> AffineReader<A> for Arthur<'_, H>
+where
+    A: AffineRepr,
+    H: DuplexHash,
+{
+    /// Read all points at once, and check them in a batch, as in [`GroupReader::fill_next_points`].
+    fn fill_next_affine_points(&mut self, output: &mut [A]) -> ProofResult<()> {
+        read_affine_points(self, output)
+    }
+}
+
+impl<A, T, M> AffineReader<A> for LayeredTranscript<T, M>
+where
+    A: AffineRepr,
+    T: UnitTranscript<u8> + ByteReader,
+    M: TranscriptMiddleware<u8>,
+{
+    /// Read all points at once, and check them in a batch, as for [`Arthur`].
+    fn fill_next_affine_points(&mut self, output: &mut [A]) -> ProofResult<()> {
+        read_affine_points(self, output)
+    }
+}
+
+impl<G, H> GroupReaderWithPolicy<G> for Arthur<'_, H>
+where
+    G: CurveGroup,
+    H: DuplexHash,
+{
+    fn fill_next_points_with_policy(
+        &mut self,
+        output: &mut [G],
+        policy: SubgroupPolicy,
+    ) -> ProofResult<()> {
+        read_points_with_policy(self, output, policy)
+    }
+}
+
+impl<G, T, M> GroupReaderWithPolicy<G> for LayeredTranscript<T, M>
+where
+    G: CurveGroup,
+    T: UnitTranscript<u8> + ByteReader,
+    M: TranscriptMiddleware<u8>,
+{
+    fn fill_next_points_with_policy(
+        &mut self,
+        output: &mut [G],
+        policy: SubgroupPolicy,
+    ) -> ProofResult<()> {
+        read_points_with_policy(self, output, policy)
     }
 }
 
+/// Check (or map) a point on the curve according to the subgroup policy.
+fn apply_subgroup_policy<G: CurveGroup>(point: G, policy: SubgroupPolicy) -> ProofResult<G> {
+    match policy {
+        SubgroupPolicy::Always => point.check().map(|()| point).map_err(Into::into),
+        SubgroupPolicy::Never => Ok(point),
+        SubgroupPolicy::CofactorClear => Ok(point.into_affine().clear_cofactor().into_group()),
+    }
+}
+
+/// Read the affine coordinates $(x, y)$ of each point with `next_units`, checking them according to `policy`.
+fn read_points_from_units<G, C, const N: usize>(
+    mut next_units: impl FnMut(&mut [Fp<C, N>]) -> Result<(), IOPatternError>,
+    output: &mut [G],
+    policy: SubgroupPolicy,
+) -> ProofResult<()>
+where
+    G: AffineCoordinates<BaseField = Fp<C, N>>,
+    C: FpConfig<N>,
+{
+    for o in output.iter_mut() {
+        let mut xy = [Fp::from(0); 2];
+        next_units(&mut xy)?;
+        *o = apply_subgroup_policy(G::from_coordinates(xy)?, policy)?;
+    }
+    Ok(())
+}
+
+/// Read the affine coordinates $(x, y)$ of each affine point with `next_units`,
+/// checking that it is on the curve and in the prime-order subgroup.
+fn read_affine_points_from_units<A, C, const N: usize>(
+    mut next_units: impl FnMut(&mut [Fp<C, N>]) -> Result<(), IOPatternError>,
+    output: &mut [A],
+) -> ProofResult<()>
+where
+    A: AffineRepr<BaseField = Fp<C, N>>,
+    A::Group: AffineCoordinates,
+    C: FpConfig<N>,
+{
+    for o in output.iter_mut() {
+        let mut xy = [Fp::from(0); 2];
+        next_units(&mut xy)?;
+        let point = A::Group::affine_from_coordinates(xy)?;
+        point.check()?;
+        *o = point;
+    }
+    Ok(())
+}
+
 impl<H, C, const N: usize> FieldReader<Fp<C, N>> for Arthur<'_, H, Fp<C, N>>
 where
     C: FpConfig<N>,
@@ -53,34 +235,99 @@ where
     }
 }
 
-impl<P, H, C, const N: usize> GroupReader<EdwardsCurve<P>> for Arthur<'_, H, Fp<C, N>>
+impl<H, C, M, const N: usize> FieldReader<Fp<C, N>>
+    for LayeredTranscript<Arthur<'_, H, Fp<C, N>>, M>
 where
     C: FpConfig<N>,
     H: DuplexHash<Fp<C, N>>,
-    P: TECurveConfig<BaseField = Fp<C, N>>,
-{
-    fn fill_next_points(&mut self, output: &mut [EdwardsCurve<P>]) -> ProofResult<()> {
-        for o in output.iter_mut() {
-            let o_affine = EdwardsAffine::deserialize_compressed(&mut self.transcript)?;
-            *o = o_affine.into();
-            self.public_units(&[o.x, o.y])?;
-        }
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    fn fill_next_scalars(&mut self, output: &mut [Fp<C, N>]) -> crate::ProofResult<()> {
+        self.fill_next_units(output)?;
         Ok(())
     }
 }
 
-impl<P, H, C, const N: usize> GroupReader<SWCurve<P>> for Arthur<'_, H, Fp<C, N>>
+impl<G, H, C, const N: usize> GroupReader<G> for Arthur<'_, H, Fp<C, N>>
 where
     C: FpConfig<N>,
     H: DuplexHash<Fp<C, N>>,
-    P: SWCurveConfig<BaseField = Fp<C, N>>,
-{
-    fn fill_next_points(&mut self, output: &mut [SWCurve<P>]) -> ProofResult<()> {
-        for o in output.iter_mut() {
-            let o_affine = SWAffine::deserialize_compressed(&mut self.transcript)?;
-            *o = o_affine.into();
-            self.public_units(&[o.x, o.y])?;
-        }
-        Ok(())
+    G: AffineCoordinates<BaseField = Fp<C, N>>,
+{
+    /// Read the affine coordinates $(x, y)$ of each point, checking that it is on the curve and in the prime-order subgroup.
+    fn fill_next_points(&mut self, output: &mut [G]) -> ProofResult<()> {
+        self.fill_next_points_with_policy(output, SubgroupPolicy::Always)
+    }
+}
+
+impl<G, H, C, M, const N: usize> GroupReader<G> for LayeredTranscript<Arthur<'_, H, Fp<C, N>>, M>
+where
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+    G: AffineCoordinates<BaseField = Fp<C, N>>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    /// Read the affine coordinates $(x, y)$ of each point, as for [`Arthur`].
+    fn fill_next_points(&mut self, output: &mut [G]) -> ProofResult<()> {
+        self.fill_next_points_with_policy(output, SubgroupPolicy::Always)
+    }
+}
+
+impl<G, H, C, const N: usize> GroupReaderWithPolicy<G> for Arthur<'_, H, Fp<C, N>>
+where
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+    G: AffineCoordinates<BaseField = Fp<C, N>>,
+{
+    fn fill_next_points_with_policy(
+        &mut self,
+        output: &mut [G],
+        policy: SubgroupPolicy,
+    ) -> ProofResult<()> {
+        read_points_from_units(|xy| self.fill_next_units(xy), output, policy)
+    }
+}
+
+impl<G, H, C, M, const N: usize> GroupReaderWithPolicy<G>
+    for LayeredTranscript<Arthur<'_, H, Fp<C, N>>, M>
+where
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+    G: AffineCoordinates<BaseField = Fp<C, N>>,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    fn fill_next_points_with_policy(
+        &mut self,
+        output: &mut [G],
+        policy: SubgroupPolicy,
+    ) -> ProofResult<()> {
+        read_points_from_units(|xy| self.fill_next_units(xy), output, policy)
+    }
+}
+
+impl<A, H, C, const N: usize> AffineReader<A> for Arthur<'_, H, Fp<C, N>>
+where
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+    A: AffineRepr<BaseField = Fp<C, N>>,
+    A::Group: AffineCoordinates,
+{
+    /// Read the affine coordinates $(x, y)$ of each point, checking that it is on the curve and in the prime-order subgroup.
+    fn fill_next_affine_points(&mut self, output: &mut [A]) -> ProofResult<()> {
+        read_affine_points_from_units(|xy| self.fill_next_units(xy), output)
+    }
+}
+
+impl<A, H, C, M, const N: usize> AffineReader<A> for LayeredTranscript<Arthur<'_, H, Fp<C, N>>, M>
+where
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+    A: AffineRepr<BaseField = Fp<C, N>>,
+    A::Group: AffineCoordinates,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    /// Read the affine coordinates $(x, y)$ of each point, as for [`Arthur`].
+    fn fill_next_affine_points(&mut self, output: &mut [A]) -> ProofResult<()> {
+        read_affine_points_from_units(|xy| self.fill_next_units(xy), output)
     }
 }
@@ -0,0 +1,320 @@
+//! The sponge API of arkworks, over nimue's duplex hashes.
+//!
+//! Gadgets and native code written against the `CryptographicSponge` and `FieldBasedCryptographicSponge` traits of
+//! [`ark-crypto-primitives`](https://docs.rs/ark-crypto-primitives/latest/ark_crypto_primitives/sponge/index.html)
+//! absorb and squeeze freely, without an IO Pattern.
+//! [`ArkSponge`] provides the same operations over any [`DuplexHash`] working over a prime field
+//! (e.g. the Poseidon sponges of `nimue-poseidon`), following the conventions of arkworks' `PoseidonSponge`:
+//!
+//! - bytes are absorbed prefixed with their length as a little-endian `u64`,
+//!   and packed in field elements, as little-endian chunks of `(MODULUS_BIT_SIZE - 1) / 8` bytes;
+//! - each field element squeezed yields its `(MODULUS_BIT_SIZE - 1) / 8` least significant bytes,
+//!   or its `MODULUS_BIT_SIZE - 1` least significant bits;
+//! - elements of another field are reduced from `MODULUS_BIT_SIZE - 1` squeezed bits;
+//! - forking absorbs the domain as bytes, prefixed once with its length
+//!   (the default `CryptographicSponge::fork` prefixes the length twice, since it absorbs `len || domain` as bytes).
+//!
+//! The sponge is initialized with the IV of an [`IOPattern`], so that it shares the parameters and the domain separator
+//! of a nimue protocol.
+//!
+//! With the `ark-crypto-primitives` feature, [`ArkSponge`] implements `CryptographicSponge` and `FieldBasedCryptographicSponge`,
+//! and, in the other direction, [`FromArkSponge`] is a [`DuplexHash`] over any arkworks sponge.
+//! Both traits and types being foreign to downstream crates, these implementations can only live here.
+//!
+//! ```
+//! # #[cfg(feature = "test-utils")]
+//! # fn main() {
+//! use ark_bls12_381::{Fq, Fr};
+//! use nimue::plugins::ark::sponge::ArkSponge;
+//! # use nimue::IOPattern;
+//! # type Poseidon = nimue::hash::sponge::DuplexSponge<nimue::testing::ToySponge<Fr>>;
+//!
+//! let io = IOPattern::<Poseidon, Fr>::new("my-gadget");
+//! let mut sponge = ArkSponge::from_io_pattern(&io);
+//! sponge.absorb_bytes(b"statement");
+//! sponge.absorb_native_field_elements(&[Fr::from(42)]);
+//! let mut fork = sponge.fork(b"round 1");
+//! let bytes = fork.squeeze_bytes(40);
+//! let bits = fork.squeeze_bits(300);
+//! let [challenge] = sponge.squeeze_field_elements::<Fq>(1).try_into().unwrap();
+//! assert_eq!((bytes.len(), bits.len()), (40, 300));
+//! # }
+//! # #[cfg(not(feature = "test-utils"))]
+//! # fn main() {}
+//! ```
+use core::marker::PhantomData;
+
+#[cfg(feature = "ark-crypto-primitives")]
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge, FieldBasedCryptographicSponge};
+use ark_ff::{BigInteger, PrimeField};
+
+use crate::{DuplexHash, IOPattern, Unit};
+
+/// A sponge over the prime field `F`, with the API of arkworks' `CryptographicSponge`.
+#[derive(Clone)]
+pub struct ArkSponge<H, F>
+where
+    F: PrimeField + Unit,
+    H: DuplexHash<F>,
+{
+    sponge: H,
+    _field: PhantomData<F>,
+}
+
+impl<H, F> ArkSponge<H, F>
+where
+    F: PrimeField + Unit,
+    H: DuplexHash<F>,
+{
+    /// Initialize the sponge from a 32-byte `iv`.
+    pub fn new(iv: [u8; 32]) -> Self {
+        Self {
+            sponge: H::new(iv),
+            _field: PhantomData,
+        }
+    }
+
+    /// Initialize the sponge with the IV of `io_pattern` (see [`IOPattern::digest`]).
+    pub fn from_io_pattern(io_pattern: &IOPattern<H, F>) -> Self {
+        Self::new(io_pattern.digest())
+    }
+
+    /// The number of bytes packed in, or squeezed from, each field element.
+    fn usable_bytes() -> usize {
+        (F::MODULUS_BIT_SIZE as usize - 1) / 8
+    }
+
+    /// Absorb `input`, prefixed with its length and packed into field elements.
+    pub fn absorb_bytes(&mut self, input: &[u8]) {
+        let mut bytes = (input.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(input);
+        let elements = bytes
+            .chunks(Self::usable_bytes())
+            .map(F::from_le_bytes_mod_order)
+            .collect::<Vec<_>>();
+        self.sponge.absorb_unchecked(&elements);
+    }
+
+    /// Absorb field elements of `F`.
+    pub fn absorb_native_field_elements(&mut self, input: &[F]) {
+        self.sponge.absorb_unchecked(input);
+    }
+
+    /// Squeeze `num_elements` field elements of `F`.
+    pub fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<F> {
+        let mut output = vec![F::default(); num_elements];
+        self.sponge.squeeze_unchecked(&mut output);
+        output
+    }
+
+    /// Squeeze `num_bytes` bytes.
+    pub fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let usable_bytes = Self::usable_bytes();
+        let mut bytes = self
+            .squeeze_native_field_elements(num_bytes.div_ceil(usable_bytes))
+            .into_iter()
+            .flat_map(|x| x.into_bigint().to_bytes_le()[..usable_bytes].to_vec())
+            .collect::<Vec<_>>();
+        bytes.truncate(num_bytes);
+        bytes
+    }
+
+    /// Squeeze `num_bits` bits, in little-endian order.
+    pub fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let usable_bits = F::MODULUS_BIT_SIZE as usize - 1;
+        let mut bits = self
+            .squeeze_native_field_elements(num_bits.div_ceil(usable_bits))
+            .into_iter()
+            .flat_map(|x| x.into_bigint().to_bits_le()[..usable_bits].to_vec())
+            .collect::<Vec<_>>();
+        bits.truncate(num_bits);
+        bits
+    }
+
+    /// Squeeze `num_elements` elements of the prime field `F2`.
+    ///
+    /// Elements of a field with the same modulus as `F` are squeezed natively,
+    /// others are reduced from `F2::MODULUS_BIT_SIZE - 1` bits each.
+    pub fn squeeze_field_elements<F2: PrimeField>(&mut self, num_elements: usize) -> Vec<F2> {
+        if F2::MODULUS.to_bytes_le() == F::MODULUS.to_bytes_le() {
+            return self
+                .squeeze_native_field_elements(num_elements)
+                .into_iter()
+                .map(|x| F2::from_le_bytes_mod_order(&x.into_bigint().to_bytes_le()))
+                .collect();
+        }
+        let num_bits = F2::MODULUS_BIT_SIZE as usize - 1;
+        let bits = self.squeeze_bits(num_elements * num_bits);
+        bits.chunks(num_bits)
+            .map(|bits| {
+                let bytes = bits
+                    .chunks(8)
+                    .map(|bits| {
+                        bits.iter()
+                            .rev()
+                            .fold(0u8, |byte, &bit| byte << 1 | bit as u8)
+                    })
+                    .collect::<Vec<_>>();
+                F2::from_le_bytes_mod_order(&bytes)
+            })
+            .collect()
+    }
+
+    /// Return a copy of the sponge, separated by `domain`.
+    pub fn fork(&self, domain: &[u8]) -> Self {
+        let mut fork = self.clone();
+        fork.absorb_bytes(domain);
+        fork
+    }
+
+    /// Return the underlying duplex hash.
+    pub fn into_inner(self) -> H {
+        self.sponge
+    }
+}
+
+impl<H, F> core::fmt::Debug for ArkSponge<H, F>
+where
+    F: PrimeField + Unit,
+    H: DuplexHash<F>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Ensure that the state isn't accidentally logged
+        write!(f, "ArkSponge: STATE OMITTED")
+    }
+}
+
+#[cfg(feature = "ark-crypto-primitives")]
+impl<H, F> CryptographicSponge for ArkSponge<H, F>
+where
+    F: PrimeField + Unit,
+    H: DuplexHash<F>,
+{
+    /// The IV of the sponge.
+    type Config = [u8; 32];
+
+    fn new(iv: &Self::Config) -> Self {
+        Self::new(*iv)
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        self.absorb_native_field_elements(&input.to_sponge_field_elements_as_vec::<F>());
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        self.squeeze_bytes(num_bytes)
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        self.squeeze_bits(num_bits)
+    }
+
+    fn squeeze_field_elements<F2: PrimeField>(&mut self, num_elements: usize) -> Vec<F2> {
+        self.squeeze_field_elements(num_elements)
+    }
+
+    fn fork(&self, domain: &[u8]) -> Self {
+        self.fork(domain)
+    }
+}
+
+#[cfg(feature = "ark-crypto-primitives")]
+impl<H, F> FieldBasedCryptographicSponge<F> for ArkSponge<H, F>
+where
+    F: PrimeField + Unit,
+    H: DuplexHash<F>,
+{
+    fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<F> {
+        self.squeeze_native_field_elements(num_elements)
+    }
+}
+
+/// The configuration of an arkworks sponge `S`, fixed at the type level.
+///
+/// [`DuplexHash::new`] takes no parameters: implement this trait on a (local) marker type
+/// to use `S` as a [`DuplexHash`] via [`FromArkSponge`].
+#[cfg(feature = "ark-crypto-primitives")]
+pub trait ArkSpongeConfig<S: CryptographicSponge> {
+    /// The parameters of the sponge, e.g. the round constants of Poseidon.
+    fn config() -> S::Config;
+}
+
+/// A [`DuplexHash`] over the arkworks sponge `S`, configured by `C`.
+///
+/// - the IV is absorbed as bytes, packed in field elements;
+/// - absorptions and squeezes are native field elements, and are assumed to stream as in arkworks' `PoseidonSponge`;
+/// - arkworks sponges cannot ratchet: ratcheting squeezes one element, and absorbs it into a fresh sponge.
+///
+/// arkworks sponges do not implement [`zeroize::Zeroize`]: zeroizing replaces the state with a fresh sponge.
+#[cfg(feature = "ark-crypto-primitives")]
+pub struct FromArkSponge<S, C> {
+    sponge: S,
+    _config: PhantomData<C>,
+}
+
+#[cfg(feature = "ark-crypto-primitives")]
+impl<S: CryptographicSponge, C: ArkSpongeConfig<S>> Default for FromArkSponge<S, C> {
+    fn default() -> Self {
+        Self {
+            sponge: S::new(&C::config()),
+            _config: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "ark-crypto-primitives")]
+impl<S: CryptographicSponge, C> Clone for FromArkSponge<S, C> {
+    fn clone(&self) -> Self {
+        Self {
+            sponge: self.sponge.clone(),
+            _config: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "ark-crypto-primitives")]
+impl<S: CryptographicSponge, C: ArkSpongeConfig<S>> zeroize::Zeroize for FromArkSponge<S, C> {
+    fn zeroize(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(feature = "ark-crypto-primitives")]
+impl<S, C, F> DuplexHash<F> for FromArkSponge<S, C>
+where
+    F: PrimeField + Unit + Absorb,
+    S: FieldBasedCryptographicSponge<F>,
+    C: ArkSpongeConfig<S>,
+{
+    fn new(iv: [u8; 32]) -> Self {
+        let mut hash = Self::default();
+        hash.sponge.absorb(&iv.as_slice());
+        hash
+    }
+
+    fn absorb_unchecked(&mut self, input: &[F]) -> &mut Self {
+        self.sponge.absorb(&input);
+        self
+    }
+
+    fn squeeze_unchecked(&mut self, output: &mut [F]) -> &mut Self {
+        let squeezed = self.sponge.squeeze_native_field_elements(output.len());
+        output.copy_from_slice(&squeezed);
+        self
+    }
+
+    fn ratchet_unchecked(&mut self) -> &mut Self {
+        let digest = self.sponge.squeeze_native_field_elements(1);
+        *self = Self::default();
+        self.sponge.absorb(&digest);
+        self
+    }
+}
+
+#[cfg(feature = "ark-crypto-primitives")]
+impl<S, C> core::fmt::Debug for FromArkSponge<S, C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Ensure that the state isn't accidentally logged
+        write!(f, "FromArkSponge: STATE OMITTED")
+    }
+}
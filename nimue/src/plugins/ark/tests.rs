@@ -97,3 +97,1637 @@ fn test_arkworks() {
     test_arkworks_end_to_end::<F, DefaultHash>().unwrap();
     test_arkworks_end_to_end::<F2, DefaultHash>().unwrap();
 }
+
+#[test]
+fn test_arkworks_extension_challenges() {
+    use ark_bls12_381::{Fq12, Fq6};
+
+    test_arkworks_end_to_end::<Fq6, DefaultHash>().unwrap();
+    test_arkworks_end_to_end::<Fq12, DefaultHash>().unwrap();
+}
+
+/// A toy sponge over a prime field (by default, the BLS12-381 base field), not to be used outside of tests.
+pub(super) type ToySponge<F = ark_bls12_381::Fq> = crate::testing::ToySponge<F>;
+
+pub(super) type ToyHash = crate::hash::sponge::DuplexSponge<ToySponge>;
+
+/// Algebraic hashes are initialized from the Keccak digest of the IO Pattern.
+#[test]
+fn test_algebraic_hash_iv() {
+    use ark_bls12_381::Fq;
+
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue").squeeze(2, "chal");
+    let mut merlin = io.to_merlin();
+    let mut chal = [Fq::default(); 2];
+    merlin.fill_challenge_units(&mut chal).unwrap();
+
+    let mut expected = [Fq::default(); 2];
+    ToyHash::new(io.digest()).squeeze_unchecked(&mut expected);
+    assert_eq!(chal, expected);
+}
+
+/// Hashes over field elements cannot derive the IV themselves.
+#[test]
+fn test_algebraic_hash_protocol_iv() {
+    use crate::hash::IvHash;
+    use ark_bls12_381::Fq;
+
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue")
+        .with_iv_hash(IvHash::Protocol)
+        .squeeze(2, "chal");
+    assert!(io.try_digest().is_err());
+    assert!(crate::Safe::try_new(&io).is_err());
+}
+
+#[test]
+fn test_extension_challenges_over_field_units() {
+    use super::{FieldChallenges, FieldIOPattern};
+    use ark_bls12_381::{Fq, Fq2, Fq6};
+
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue").absorb(1, "in");
+    let io = FieldIOPattern::<Fq2>::challenge_scalars(io, 2, "chal");
+    let io = FieldIOPattern::<Fq6>::challenge_scalars(io, 1, "chal");
+    // 2 Fq2 challenges and 1 Fq6 challenge.
+    assert!(io.as_bytes().ends_with(b"S4chal\0S6chal"));
+
+    let mut merlin = io.to_merlin();
+    merlin.add_units(&[Fq::from(0x42)]).unwrap();
+    let merlin_chal: [Fq2; 2] = merlin.challenge_scalars().unwrap();
+    let [merlin_chal6]: [Fq6; 1] = merlin.challenge_scalars().unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.fill_next_units(&mut [Fq::from(0)]).unwrap();
+    let arthur_chal: [Fq2; 2] = arthur.challenge_scalars().unwrap();
+    let [arthur_chal6]: [Fq6; 1] = arthur.challenge_scalars().unwrap();
+
+    assert_eq!(merlin_chal, arthur_chal);
+    assert_eq!(merlin_chal6, arthur_chal6);
+    assert_ne!(merlin_chal[0], merlin_chal[1]);
+}
+
+/// Large byte challenges over field units are filled without recursion.
+#[test]
+fn test_large_challenge_bytes_over_field_units() {
+    use ark_bls12_381::Fq;
+
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue")
+        .absorb(1, "in")
+        .challenge_bytes(1 << 16, "chal");
+
+    let mut merlin = io.to_merlin();
+    merlin.add_units(&[Fq::from(0x42)]).unwrap();
+    let mut merlin_chal = vec![0u8; 1 << 16];
+    merlin.fill_challenge_bytes(&mut merlin_chal).unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.fill_next_units(&mut [Fq::from(0)]).unwrap();
+    let mut arthur_chal = vec![0u8; 1 << 16];
+    arthur.fill_challenge_bytes(&mut arthur_chal).unwrap();
+
+    assert_eq!(merlin_chal, arthur_chal);
+    assert_ne!(merlin_chal[(1 << 16) - 32..], [0u8; 32]);
+}
+
+/// Byte counts in the IO Pattern are translated into field units.
+#[test]
+fn test_byte_iopattern_over_field_units() {
+    use ark_bls12_381::Fq;
+    use ark_ff::PrimeField;
+
+    // bytes are padded and packed 47 per field element,
+    // and 47 bytes are squeezed from two field elements of 31 useful bytes each
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue")
+        .add_bytes(3, "in")
+        .challenge_bytes(47, "chal")
+        .add_bytes(100, "more");
+    assert_eq!(
+        io.as_bytes(),
+        b"github.com/mmaker/nimue\0A1in\0S2chal\0A3more".as_slice()
+    );
+
+    let more = [0x42u8; 100];
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1, 2, 3]).unwrap();
+    let chal: [u8; 47] = merlin.challenge_bytes().unwrap();
+    merlin.add_bytes(&more).unwrap();
+    assert_eq!(merlin.transcript(), [[1, 2, 3].as_slice(), &more].concat());
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert_eq!(arthur.next_bytes::<3>().unwrap(), [1, 2, 3]);
+    assert_eq!(arthur.challenge_bytes::<47>().unwrap(), chal);
+    assert_eq!(arthur.next_bytes::<100>().unwrap(), more);
+
+    // the same as absorbing the little-endian packing, padded with a 0x01 byte
+    let mut control = io.to_merlin();
+    control
+        .public_units(&[Fq::from_le_bytes_mod_order(&[1, 2, 3, 1])])
+        .unwrap();
+    assert_eq!(control.challenge_bytes::<47>().unwrap(), chal);
+
+    // messages of 3 and 4 bytes are both declared as one unit, but trailing zeros are not lost
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue")
+        .add_bytes(3, "in")
+        .challenge_bytes(16, "chal");
+    let other_io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue")
+        .add_bytes(4, "in")
+        .challenge_bytes(16, "chal");
+    assert_eq!(io.as_bytes(), other_io.as_bytes());
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1, 2, 3]).unwrap();
+    let mut other_merlin = other_io.to_merlin();
+    other_merlin.add_bytes(&[1, 2, 3, 0]).unwrap();
+    assert_ne!(
+        merlin.challenge_bytes::<16>().unwrap(),
+        other_merlin.challenge_bytes::<16>().unwrap()
+    );
+    // the padding byte takes a unit when the message fills the last one
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue")
+        .add_bytes(46, "fits")
+        .add_bytes(47, "spills");
+    assert_eq!(
+        io.as_bytes(),
+        b"github.com/mmaker/nimue\0A1fits\0A2spills".as_slice()
+    );
+}
+
+/// Field units are encoded as fixed-size, canonical, little-endian strings.
+#[test]
+fn test_field_unit_encoding() {
+    use ark_bls12_381::Fq;
+    use ark_ff::One;
+    use ark_serialize::CanonicalSerialize;
+
+    let units = [Fq::from(0x0102u64), -Fq::one()];
+    let mut encoding = Vec::new();
+    Fq::write(&units, &mut encoding).unwrap();
+    let expected = hex::decode(concat!(
+        "0201000000000000000000000000000000000000000000000000000000000000",
+        "00000000000000000000000000000000",
+        "aaaafffffffffeb9ffff53b1feffab1e24f6b0f6a0d23067bf1285f3844b7764",
+        "d7ac4b43b6a71b4b9ae67f39ea11011a",
+    ))
+    .unwrap();
+    assert_eq!(encoding, expected);
+    // arkworks' compressed serialization agrees
+    let mut compressed = Vec::new();
+    units.serialize_compressed(&mut compressed).unwrap();
+    assert_eq!(compressed, encoding);
+
+    let mut decoded = [Fq::from(0); 2];
+    Fq::read(&mut encoding.as_slice(), &mut decoded).unwrap();
+    assert_eq!(decoded, units);
+
+    // the modulus is not a canonical encoding
+    let mut modulus = expected[48..].to_vec();
+    modulus[0] += 1;
+    assert!(Fq::read(&mut modulus.as_slice(), &mut decoded[..1]).is_err());
+    // short reads fail
+    assert!(Fq::read(&mut &expected[..47], &mut decoded[..1]).is_err());
+    assert_eq!(<Fq as Unit>::SIZE, Some(48));
+}
+
+/// Public elements return the exact encoding absorbed, also over field units.
+#[test]
+fn test_public_repr_over_field_units() {
+    use super::{FieldPublic, GroupPublic};
+    use ark_bls12_381::{Fq, Fq2, G1Projective};
+    use ark_ec::{CurveGroup, PrimeGroup};
+
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue")
+        .absorb(2, "scalar")
+        .absorb(2, "point")
+        .squeeze(1, "chal");
+    let scalar = Fq2::new(Fq::from(1), Fq::from(2));
+    let point = G1Projective::generator();
+
+    let mut merlin = io.to_merlin();
+    let scalar_repr = merlin.public_scalars(&[scalar]).unwrap();
+    let point_repr = merlin.public_points(&[point]).unwrap();
+    assert_eq!(scalar_repr, [Fq::from(1), Fq::from(2)]);
+    let affine = point.into_affine();
+    assert_eq!(point_repr, [affine.x, affine.y]);
+
+    // the bytes of the encoding are the compressed field elements absorbed.
+    use super::ReprBytes;
+    use ark_serialize::CanonicalSerialize;
+    let mut expected = Vec::new();
+    (Fq::from(1), Fq::from(2))
+        .serialize_compressed(&mut expected)
+        .unwrap();
+    assert_eq!(scalar_repr.repr_bytes(), expected);
+    let mut merlin_bytes = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue")
+        .absorb(96, "scalars")
+        .to_merlin();
+    let bytes_repr = FieldPublic::<Fq2>::public_scalars(&mut merlin_bytes, &[scalar]).unwrap();
+    assert_eq!(bytes_repr.repr_bytes(), bytes_repr);
+
+    // Absorbing the representation directly yields the same state.
+    let mut another_merlin = io.to_merlin();
+    another_merlin.public_units(&scalar_repr).unwrap();
+    another_merlin.public_units(&point_repr).unwrap();
+    let mut chal = [Fq::from(0); 2];
+    merlin.fill_challenge_units(&mut chal[..1]).unwrap();
+    another_merlin.fill_challenge_units(&mut chal[1..]).unwrap();
+    assert_eq!(chal[0], chal[1]);
+}
+
+/// Over field units, points are written and read as affine coordinates, and checked to be on the curve.
+#[test]
+fn test_points_over_field_units() {
+    use super::{GroupIOPattern, GroupReader, GroupReaderWithPolicy, GroupWriter, SubgroupPolicy};
+    use ark_bls12_381::{Fq, Fr, G1Affine, G1Projective};
+    use ark_ec::{AffineRepr, CurveGroup, PrimeGroup};
+    use ark_ff::{PrimeField, Zero};
+    use ark_serialize::CanonicalSerialize;
+
+    let io = GroupIOPattern::<G1Projective>::add_points(
+        IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue"),
+        2,
+        "points",
+    );
+    let points = [
+        G1Projective::generator(),
+        G1Projective::generator() * Fr::from(3),
+    ];
+    let mut merlin = io.to_merlin();
+    merlin.add_points(&points).unwrap();
+    let affine = points[1].into_affine();
+    let mut expected = Vec::new();
+    (affine.x, affine.y)
+        .serialize_compressed(&mut expected)
+        .unwrap();
+    assert_eq!(&merlin.transcript()[96..], expected);
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read: [G1Projective; 2] = arthur.next_points().unwrap();
+    assert_eq!(read, points);
+
+    // (x, y + 1) is not on the curve.
+    let mut transcript = merlin.transcript().to_vec();
+    transcript.truncate(144);
+    (affine.y + Fq::from(1))
+        .serialize_compressed(&mut transcript)
+        .unwrap();
+    let mut arthur = io.to_arthur(&transcript);
+    assert!(GroupReader::<G1Projective>::next_points::<2>(&mut arthur).is_err());
+
+    // Points outside of the prime-order subgroup are rejected, unless the policy says otherwise.
+    let low_order_point = (0u64..)
+        .filter_map(|x| G1Affine::get_point_from_x_unchecked(Fq::from(x), false))
+        .map(|point| point.mul_bigint(Fr::MODULUS))
+        .find(|point| !point.is_zero())
+        .unwrap();
+    let mut merlin = io.to_merlin();
+    merlin.add_points(&[points[0], low_order_point]).unwrap();
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert!(GroupReader::<G1Projective>::next_points::<2>(&mut arthur).is_err());
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read: [G1Projective; 2] = arthur
+        .next_points_with_policy(SubgroupPolicy::Never)
+        .unwrap();
+    assert_eq!(read[1], low_order_point);
+}
+
+/// Twisted Edwards points are read and written as affine coordinates, the identity being $(0, 1)$.
+#[test]
+fn test_edwards_points_over_field_units() {
+    use super::{AffineCoordinates, GroupIOPattern, GroupReader, GroupWriter};
+    use ark_curve25519::{EdwardsProjective, Fq, Fr};
+    use ark_ec::PrimeGroup;
+    use ark_ff::{One, Zero};
+
+    type H = crate::hash::sponge::DuplexSponge<ToySponge<Fq>>;
+
+    let io = GroupIOPattern::<EdwardsProjective>::add_points(
+        IOPattern::<H, Fq>::new("github.com/mmaker/nimue"),
+        3,
+        "points",
+    );
+    let points = [
+        EdwardsProjective::generator(),
+        EdwardsProjective::zero(),
+        EdwardsProjective::generator() * Fr::from(3),
+    ];
+    assert_eq!(points[1].to_coordinates().unwrap(), [Fq::zero(), Fq::one()]);
+
+    let mut merlin = io.to_merlin();
+    merlin.add_points(&points).unwrap();
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read: [EdwardsProjective; 3] = arthur.next_points().unwrap();
+    assert_eq!(read, points);
+
+    // (0, 2) is not on the curve.
+    assert!(EdwardsProjective::from_coordinates([Fq::zero(), Fq::from(2)]).is_err());
+    let mut merlin = io.to_merlin();
+    merlin.add_units(&[Fq::zero(), Fq::from(2)]).unwrap();
+    merlin.add_points(&points[1..]).unwrap();
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert!(GroupReader::<EdwardsProjective>::next_points::<3>(&mut arthur).is_err());
+}
+
+/// The point at infinity of a short Weierstrass curve is encoded as $(0, 0)$ over field units.
+#[test]
+fn test_identity_over_field_units() {
+    use super::{AffineCoordinates, GroupIOPattern, GroupReader, GroupWriter};
+    use ark_bls12_381::{Fq, G1Projective};
+    use ark_ec::PrimeGroup;
+    use ark_ff::Zero;
+
+    let io = GroupIOPattern::<G1Projective>::add_points(
+        IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue"),
+        2,
+        "points",
+    );
+    let points = [G1Projective::zero(), G1Projective::generator()];
+    assert_eq!(points[0].to_coordinates().unwrap(), [Fq::zero(); 2]);
+
+    let mut merlin = io.to_merlin();
+    merlin.add_points(&points).unwrap();
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read: [G1Projective; 2] = arthur.next_points().unwrap();
+    assert_eq!(read, points);
+}
+
+/// Affine points are encoded as projective points, both over bytes and over field units.
+#[test]
+fn test_affine_points() {
+    use super::{AffineReader, AffineWriter, GroupIOPattern, GroupReader, GroupWriter};
+    use ark_bls12_381::{Fq, Fr, G1Affine, G1Projective};
+    use ark_ec::{CurveGroup, PrimeGroup};
+    use ark_ff::Zero;
+
+    let points = [
+        G1Projective::generator(),
+        G1Projective::zero(),
+        G1Projective::generator() * Fr::from(3),
+    ];
+    let affines = G1Projective::normalize_batch(&points);
+
+    let io = GroupIOPattern::<G1Projective>::add_points(
+        IOPattern::<DefaultHash>::new("github.com/mmaker/nimue"),
+        3,
+        "points",
+    );
+    let mut merlin = io.to_merlin();
+    merlin.add_points(&points).unwrap();
+    let mut affine_merlin = io.to_merlin();
+    affine_merlin.add_affine_points(&affines).unwrap();
+    assert_eq!(merlin.transcript(), affine_merlin.transcript());
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read: [G1Affine; 3] = arthur.next_affine_points().unwrap();
+    assert_eq!(read.as_slice(), affines);
+    // flip a bit in the x-coordinate of the last point
+    let mut transcript = merlin.transcript().to_vec();
+    *transcript.last_mut().unwrap() ^= 1;
+    let mut arthur = io.to_arthur(&transcript);
+    assert!(AffineReader::<G1Affine>::next_affine_points::<3>(&mut arthur).is_err());
+
+    let io = GroupIOPattern::<G1Projective>::add_points(
+        IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue"),
+        3,
+        "points",
+    );
+    let mut merlin = io.to_merlin();
+    merlin.add_points(&points).unwrap();
+    let mut affine_merlin = io.to_merlin();
+    affine_merlin.add_affine_points(&affines).unwrap();
+    assert_eq!(merlin.transcript(), affine_merlin.transcript());
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read: [G1Affine; 3] = arthur.next_affine_points().unwrap();
+    assert_eq!(read.as_slice(), affines);
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read: [G1Projective; 3] = arthur.next_points().unwrap();
+    assert_eq!(read, points);
+}
+
+/// Distinct challenges agree between prover and verifier, also over field units, and fail for tiny fields.
+#[test]
+fn test_challenge_scalars_distinct() {
+    use super::{DistinctChallenges, DistinctChallengesIOPattern};
+    use ark_bls12_381::{Fq, Fr};
+    use ark_ff::{Fp64, MontBackend, MontConfig};
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue")
+        .absorb(1, "in")
+        .challenge_scalars_distinct("points");
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[0x42]).unwrap();
+    let merlin_points: [Fr; 16] = merlin.challenge_scalars_distinct().unwrap();
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.next_bytes::<1>().unwrap();
+    let arthur_points: [Fr; 16] = arthur.challenge_scalars_distinct().unwrap();
+    assert_eq!(merlin_points, arthur_points);
+
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue")
+        .absorb(1, "in")
+        .challenge_scalars_distinct("points");
+    let mut merlin = io.to_merlin();
+    merlin.add_units(&[Fq::from(0x42)]).unwrap();
+    let merlin_points: [Fr; 4] = merlin.challenge_scalars_distinct().unwrap();
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.fill_next_units(&mut [Fq::from(0)]).unwrap();
+    let arthur_points: [Fr; 4] = arthur.challenge_scalars_distinct().unwrap();
+    assert_eq!(merlin_points, arthur_points);
+
+    // the sponge only squeezes the declared seed, whatever the number of challenges
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue")
+        .challenge_scalars_distinct("points")
+        .challenge_bytes(16, "next");
+    let mut merlin = io.to_merlin();
+    let _: [Fr; 16] = merlin.challenge_scalars_distinct().unwrap();
+    let mut control = io.to_merlin();
+    control.challenge_bytes::<32>().unwrap();
+    assert_eq!(
+        merlin.challenge_bytes::<16>().unwrap(),
+        control.challenge_bytes::<16>().unwrap()
+    );
+
+    // the size of a field with 17 elements is conservatively bounded by 16.
+    #[derive(MontConfig)]
+    #[modulus = "17"]
+    #[generator = "3"]
+    struct F17Config;
+    type F17 = Fp64<MontBackend<F17Config, 1>>;
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue")
+        .challenge_scalars_distinct("points");
+    let mut points: [F17; 16] = io.to_merlin().challenge_scalars_distinct().unwrap();
+    points.sort();
+    assert!(points.windows(2).all(|w| w[0] != w[1]));
+    assert!(
+        DistinctChallenges::<F17>::challenge_scalars_distinct::<17>(&mut io.to_merlin()).is_err()
+    );
+}
+
+/// Many points are read and checked in a batch, and a single invalid point is rejected.
+#[test]
+fn test_batch_point_reads() {
+    use super::{GroupIOPattern, GroupReader, GroupWriter};
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_ec::PrimeGroup;
+
+    let io = GroupIOPattern::<G1Projective>::add_points(
+        IOPattern::<DefaultHash>::new("github.com/mmaker/nimue"),
+        256,
+        "points",
+    );
+    let points = (0..256u64)
+        .map(|i| G1Projective::generator() * Fr::from(i))
+        .collect::<Vec<_>>();
+    let mut merlin = io.to_merlin();
+    merlin.add_points(&points).unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read: Vec<G1Projective> = arthur.next_points_vec(256).unwrap();
+    assert_eq!(read, points);
+
+    // flip a bit in the x-coordinate of the last point
+    let mut transcript = merlin.transcript().to_vec();
+    *transcript.last_mut().unwrap() ^= 1;
+    let mut arthur = io.to_arthur(&transcript);
+    assert!(GroupReader::<G1Projective>::next_points_vec(&mut arthur, 256).is_err());
+}
+
+/// Points outside of the prime-order subgroup are handled according to the subgroup policy.
+#[test]
+fn test_subgroup_policy() {
+    use super::{GroupIOPattern, GroupReaderWithPolicy, GroupWriter, SubgroupPolicy};
+    use crate::ProofError;
+    use ark_bls12_381::{Fq, Fr, G1Affine, G1Projective};
+    use ark_ec::{AffineRepr, CurveGroup, PrimeGroup};
+    use ark_ff::{PrimeField, Zero};
+
+    // find a point of small order, outside of the prime-order subgroup
+    let low_order_point = (0u64..)
+        .filter_map(|x| G1Affine::get_point_from_x_unchecked(Fq::from(x), false))
+        .map(|point| point.mul_bigint(Fr::MODULUS))
+        .find(|point| !point.is_zero())
+        .unwrap();
+    assert!(!low_order_point
+        .into_affine()
+        .is_in_correct_subgroup_assuming_on_curve());
+
+    let io = GroupIOPattern::<G1Projective>::add_points(
+        IOPattern::<DefaultHash>::new("github.com/mmaker/nimue"),
+        1,
+        "point",
+    );
+    let mut merlin = io.to_merlin();
+    merlin.add_points(&[low_order_point]).unwrap();
+
+    let read = |policy| -> ProofResult<[G1Projective; 1]> {
+        io.to_arthur(merlin.transcript())
+            .next_points_with_policy(policy)
+    };
+    assert!(matches!(
+        read(SubgroupPolicy::Always),
+        Err(ProofError::SerializationError)
+    ));
+    let [read_point] = read(SubgroupPolicy::Never).unwrap();
+    assert_eq!(read_point, low_order_point);
+    let [cleared] = read(SubgroupPolicy::CofactorClear).unwrap();
+    assert!(cleared
+        .into_affine()
+        .is_in_correct_subgroup_assuming_on_curve());
+
+    // valid points are read as with `next_points`
+    let mut merlin = io.to_merlin();
+    let point = G1Projective::generator();
+    merlin.add_points(&[point]).unwrap();
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let [read_point]: [G1Projective; 1] = arthur
+        .next_points_with_policy(SubgroupPolicy::Always)
+        .unwrap();
+    assert_eq!(read_point, point);
+}
+
+#[test]
+fn test_field_bridge() {
+    use super::field_bridge::*;
+    use ark_bls12_381::{Fq, Fr, G1Projective};
+    use ark_ff::{BigInteger, PrimeField};
+
+    // Fr embeds into Fq, but not the other way around.
+    let x = -Fr::from(1);
+    let y = scalar_to_base::<G1Projective>(x).unwrap();
+    assert_eq!(
+        y.into_bigint().to_bytes_le()[..32],
+        x.into_bigint().to_bytes_le()
+    );
+    assert!(base_to_scalar::<G1Projective>(-Fq::from(1)).is_err());
+    assert_eq!(base_to_scalar::<G1Projective>(y).unwrap(), x);
+
+    for limb_bits in [1, 8, 64, 100, 254] {
+        let x = -Fq::from(42);
+        let limbs = to_limbs::<Fr, _>(x, limb_bits).unwrap();
+        assert_eq!(limbs.len(), limbs_count::<Fq>(limb_bits));
+        assert_eq!(from_limbs::<Fq, _>(&limbs, limb_bits).unwrap(), x);
+    }
+    // non-canonical limbs are rejected
+    let mut limbs = to_limbs::<Fr, _>(Fq::from(1), 64).unwrap();
+    limbs[0] = Fr::from(u64::MAX) + Fr::from(1);
+    assert!(from_limbs::<Fq, Fr>(&limbs, 64).is_err());
+    assert!(from_limbs::<Fq, Fr>(&limbs[1..], 64).is_err());
+    let modulus = to_limbs::<Fr, _>(Fq::from(0), 64)
+        .unwrap()
+        .iter()
+        .zip(Fq::MODULUS.0)
+        .map(|(&zero, limb)| zero + Fr::from(limb))
+        .collect::<Vec<_>>();
+    assert!(from_limbs::<Fq, Fr>(&modulus, 64).is_err());
+    // limbs must be non-empty, and fit in the limb field
+    for limb_bits in [0, 255, 1000] {
+        assert!(to_limbs::<Fr, _>(Fq::from(1), limb_bits).is_err());
+        assert!(from_limbs::<Fq, Fr>(&[Fr::from(1)], limb_bits).is_err());
+    }
+}
+
+/// Foreign field elements are absorbed as limbs, whose count is declared in the IO pattern.
+#[test]
+fn test_foreign_field_absorption() {
+    use super::field_bridge::{ForeignFieldIOPattern, ForeignFieldPublic};
+    use ark_bls12_381::{Fq, Fr};
+
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue");
+    let io =
+        ForeignFieldIOPattern::<Fr>::add_foreign_scalars(io, 2, 64, "foreign").squeeze(1, "chal");
+    assert!(io.as_bytes().ends_with(b"A8foreign.limbs64\0S1chal"));
+
+    let input = [Fr::from(1), -Fr::from(1)];
+    let mut merlin = io.to_merlin();
+    let limbs = merlin.public_foreign_scalars(&input, 64).unwrap();
+    assert_eq!(limbs.len(), 8);
+    let mut chal = [Fq::from(0); 2];
+    merlin.fill_challenge_units(&mut chal[..1]).unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.public_foreign_scalars(&input, 64).unwrap();
+    arthur.fill_challenge_units(&mut chal[1..]).unwrap();
+    assert_eq!(chal[0], chal[1]);
+}
+
+/// Invalid limb sizes are rejected when declaring the IO pattern, as when decomposing.
+#[test]
+#[should_panic(expected = "Limbs of 0 bits")]
+fn test_foreign_field_invalid_limbs() {
+    use super::field_bridge::ForeignFieldIOPattern;
+    use ark_bls12_381::{Fq, Fr};
+
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue");
+    ForeignFieldIOPattern::<Fr>::add_foreign_scalars(io, 2, 0, "foreign");
+}
+
+/// Runtime-length reads are equivalent to the const-generic ones.
+#[test]
+fn test_runtime_length_reads() {
+    use super::{
+        FieldChallenges, FieldIOPattern, FieldReader, FieldWriter, GroupIOPattern, GroupReader,
+        GroupWriter,
+    };
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_ec::PrimeGroup;
+
+    let count = 3;
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = GroupIOPattern::<G1Projective>::add_points(io, count, "points");
+    let io = FieldIOPattern::<Fr>::add_scalars(io, count, "scalars");
+    let io = FieldIOPattern::<Fr>::challenge_scalars(io, count, "chal");
+
+    let points = vec![G1Projective::generator(); count];
+    let scalars = (0..count as u64).map(Fr::from).collect::<Vec<_>>();
+    let mut merlin = io.to_merlin();
+    merlin.add_points(&points).unwrap();
+    merlin.add_scalars(&scalars).unwrap();
+    let merlin_chal: [Fr; 3] = merlin.challenge_scalars().unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert_eq!(
+        GroupReader::<G1Projective>::next_points_vec(&mut arthur, count).unwrap(),
+        points
+    );
+    assert_eq!(
+        FieldReader::<Fr>::next_scalars_vec(&mut arthur, count).unwrap(),
+        scalars
+    );
+    let arthur_chal: Vec<Fr> = arthur.challenge_scalars_vec(count).unwrap();
+    assert_eq!(arthur_chal, merlin_chal);
+}
+
+/// Challenges of either sponge depend on what has been absorbed in the other one before a bridge.
+#[test]
+fn test_hybrid_transcript() {
+    use super::hybrid::HybridIOPattern;
+    use ark_bls12_381::Fq;
+
+    let io = HybridIOPattern::<DefaultHash, ToyHash, _, 6>::new("github.com/mmaker/nimue")
+        .bytes(|io| io.absorb(1000, "data"))
+        .field(|io| io.absorb(1, "commitment"))
+        .bridge("bridge")
+        .field(|io| io.squeeze(1, "challenge"))
+        .bytes(|io| io.squeeze(16, "challenge"));
+    assert_ne!(io.digest(), io.bytes_pattern().digest());
+
+    let challenges = |data: &[u8], commitment: Fq| {
+        let mut transcript = io.to_transcript();
+        transcript.public_units(data).unwrap();
+        transcript.public_units(&[commitment]).unwrap();
+        transcript.bridge().unwrap();
+        let mut field_chal = [Fq::from(0u64)];
+        transcript.fill_challenge_units(&mut field_chal).unwrap();
+        let mut bytes_chal = [0u8; 16];
+        transcript.fill_challenge_units(&mut bytes_chal).unwrap();
+        (field_chal[0], bytes_chal)
+    };
+
+    let (field_chal, bytes_chal) = challenges(&[0u8; 1000], Fq::from(1u64));
+    assert_eq!(
+        challenges(&[0u8; 1000], Fq::from(1u64)),
+        (field_chal, bytes_chal)
+    );
+    let (other_field_chal, other_bytes_chal) = challenges(&[1u8; 1000], Fq::from(1u64));
+    assert_ne!(other_field_chal, field_chal);
+    assert_ne!(other_bytes_chal, bytes_chal);
+    let (other_field_chal, other_bytes_chal) = challenges(&[0u8; 1000], Fq::from(2u64));
+    assert_ne!(other_field_chal, field_chal);
+    assert_ne!(other_bytes_chal, bytes_chal);
+
+    // bridging is part of the IO Pattern.
+    let mut transcript = io.to_transcript();
+    transcript.public_units(&[0u8; 1000]).unwrap();
+    transcript.public_units(&[Fq::from(1u64)]).unwrap();
+    assert!(transcript.fill_challenge_units(&mut [0u8; 16]).is_err());
+}
+
+/// Prover messages of both units are encoded in a single transcript, and read back by the verifier.
+#[test]
+fn test_hybrid_merlin_arthur() {
+    use super::hybrid::HybridIOPattern;
+    use ark_bls12_381::Fq;
+
+    let io = HybridIOPattern::<DefaultHash, ToyHash, _, 6>::new("github.com/mmaker/nimue")
+        .bytes(|io| io.absorb(4, "data"))
+        .field(|io| io.absorb(2, "commitment"))
+        .bridge("bridge")
+        .field(|io| io.squeeze(1, "challenge"))
+        .bytes(|io| io.absorb(1, "response").squeeze(16, "challenge"));
+
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1, 2, 3, 4]).unwrap();
+    merlin
+        .add_field_units(&[Fq::from(5u64), Fq::from(6u64)])
+        .unwrap();
+    merlin.bridge().unwrap();
+    let mut field_chal = [Fq::from(0u64)];
+    merlin.fill_challenge_units(&mut field_chal).unwrap();
+    merlin.add_bytes(&[7]).unwrap();
+    let bytes_chal: [u8; 16] = merlin.challenge_bytes().unwrap();
+
+    let transcript = merlin.transcript();
+    let mut arthur = io.to_arthur(transcript);
+    assert_eq!(arthur.next_bytes().unwrap(), [1, 2, 3, 4]);
+    let mut commitment = [Fq::from(0u64); 2];
+    arthur.fill_next_field_units(&mut commitment).unwrap();
+    assert_eq!(commitment, [Fq::from(5u64), Fq::from(6u64)]);
+    arthur.bridge().unwrap();
+    let mut arthur_field_chal = [Fq::from(0u64)];
+    arthur.fill_challenge_units(&mut arthur_field_chal).unwrap();
+    assert_eq!(arthur_field_chal, field_chal);
+    assert_eq!(arthur.next_bytes().unwrap(), [7]);
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), bytes_chal);
+
+    // the field message changes the challenges squeezed after the bridge.
+    let mut tampered = transcript.to_vec();
+    tampered[4] ^= 1;
+    let mut arthur = io.to_arthur(&tampered);
+    arthur.next_bytes::<4>().unwrap();
+    arthur.fill_next_field_units(&mut commitment).unwrap();
+    arthur.bridge().unwrap();
+    arthur.fill_challenge_units(&mut arthur_field_chal).unwrap();
+    assert_ne!(arthur_field_chal, field_chal);
+
+    // trailing bytes are rejected once both sponges are done, and not before.
+    let io = HybridIOPattern::<DefaultHash, ToyHash, _, 6>::new("github.com/mmaker/nimue")
+        .bytes(|io| io.absorb(1, "data"))
+        .field(|io| io.absorb(1, "commitment"));
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1]).unwrap();
+    merlin.add_field_units(&[Fq::from(2u64)]).unwrap();
+    let mut trailing = merlin.transcript().to_vec();
+    trailing.push(0);
+    let mut arthur = io.to_arthur(&trailing);
+    assert_eq!(arthur.next_bytes().unwrap(), [1]);
+    assert!(arthur.fill_next_field_units(&mut [Fq::from(0u64)]).is_err());
+}
+
+/// Trailing bytes are rejected also when the protocol ends with a squeeze or a bridge.
+#[test]
+fn test_hybrid_trailing_bytes_after_squeeze() {
+    use super::hybrid::HybridIOPattern;
+    use ark_bls12_381::Fq;
+
+    let io = HybridIOPattern::<DefaultHash, ToyHash, _, 6>::new("github.com/mmaker/nimue")
+        .bytes(|io| io.absorb(4, "data"))
+        .field(|io| io.absorb(1, "commitment"))
+        .bridge("bridge")
+        .field(|io| io.squeeze(1, "challenge"));
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1, 2, 3, 4]).unwrap();
+    merlin.add_field_units(&[Fq::from(42u64)]).unwrap();
+    merlin.bridge().unwrap();
+    merlin.fill_challenge_units(&mut [Fq::from(0u64)]).unwrap();
+    let mut trailing = merlin.transcript().to_vec();
+    trailing.extend_from_slice(b"garbage");
+
+    let mut arthur = io.to_arthur(&trailing);
+    arthur.next_bytes::<4>().unwrap();
+    arthur.fill_next_field_units(&mut [Fq::from(0u64)]).unwrap();
+    arthur.bridge().unwrap();
+    let err = arthur
+        .fill_challenge_units(&mut [Fq::from(0u64)])
+        .unwrap_err();
+    assert!(err.to_string().contains("Transcript has 7 trailing bytes"));
+
+    // same for a protocol ending with a bridge.
+    let io = HybridIOPattern::<DefaultHash, ToyHash, _, 6>::new("github.com/mmaker/nimue")
+        .bytes(|io| io.absorb(1, "data"))
+        .bridge("bridge");
+    let mut arthur = io.to_arthur(&[1, 0]);
+    arthur.next_bytes::<1>().unwrap();
+    assert!(arthur.bridge().is_err());
+}
+
+/// Field sponges run over bytes are streamable, and pack absorbs with a padding.
+#[test]
+fn test_bytes_over_field() {
+    use super::bytes::BytesOverField;
+    use ark_bls12_381::Fq;
+    use ark_ff::{BigInteger, PrimeField};
+
+    type H = BytesOverField<ToyHash, Fq>;
+    crate::tests::test_streaming_absorb_and_squeeze::<H>();
+
+    let challenge = |message: &[u8]| {
+        let mut sponge = H::new([0; 32]);
+        sponge.absorb_unchecked(message);
+        let mut chal = [0u8; 64];
+        sponge.squeeze_unchecked(&mut chal);
+        chal
+    };
+    // messages differing by trailing zeros, or filling an element exactly, are absorbed differently.
+    assert_ne!(challenge(&[1, 2, 3]), challenge(&[1, 2, 3, 0]));
+    assert_ne!(challenge(&[7; 47]), challenge(&[7; 48]));
+
+    // the same as absorbing the packed and padded message over field units.
+    let mut sponge = ToyHash::new([0; 32]);
+    sponge.absorb_unchecked(&[Fq::from(0x01030201u64)]);
+    let mut expected = [Fq::from(0u64)];
+    sponge.squeeze_unchecked(&mut expected);
+    let expected = expected[0].into_bigint().to_bytes_le();
+    assert_eq!(challenge(&[1, 2, 3])[..16], expected[..16]);
+}
+
+/// Bridges embed 128-bit chunks of the byte digest, which small fields would reduce.
+#[test]
+#[should_panic(expected = "larger than 128 bits")]
+fn test_hybrid_small_field() {
+    use super::hybrid::HybridIOPattern;
+    use ark_ff::{Fp64, MontBackend, MontConfig};
+
+    #[derive(MontConfig)]
+    #[modulus = "18446744069414584321"]
+    #[generator = "7"]
+    struct GoldilocksConfig;
+    type Goldilocks = Fp64<MontBackend<GoldilocksConfig, 1>>;
+
+    HybridIOPattern::<DefaultHash, crate::hash::sponge::DuplexSponge<ToySponge<Goldilocks>>, _, 1>::new(
+        "github.com/mmaker/nimue",
+    );
+}
+
+/// Prover messages are encrypted to the designated verifier, while challenges are unchanged.
+#[test]
+fn test_designated_verifier() {
+    use super::designated::*;
+    use super::{FieldChallenges, FieldIOPattern, FieldReader, FieldWriter};
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_ec::PrimeGroup;
+    use ark_std::UniformRand;
+
+    let sk = Fr::rand(&mut ark_std::test_rng());
+    let pk = G1Projective::generator() * sk;
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = DesignatedVerifierIOPattern::<G1Projective>::add_designated_verifier(io, "ephemeral");
+    let io = FieldIOPattern::<Fr>::add_scalars(io, 1, "secret");
+    let io = FieldIOPattern::<Fr>::challenge_scalars(io, 1, "chal");
+
+    let message = Fr::from(42u64);
+    let mut merlin = io.to_merlin().designate_verifier(&pk).unwrap();
+    merlin.add_scalars(&[message]).unwrap();
+    let [merlin_chal]: [Fr; 1] = merlin.challenge_scalars().unwrap();
+    let transcript = merlin.transcript().to_vec();
+
+    // the plaintext does not appear in the transcript.
+    let mut plaintext = Vec::new();
+    ark_serialize::CanonicalSerialize::serialize_compressed(&message, &mut plaintext).unwrap();
+    assert!(!transcript.ends_with(&plaintext));
+
+    let arthur = io.to_arthur(&transcript);
+    let mut arthur =
+        DesignatedVerifierReader::<G1Projective>::designated_verifier(arthur, &sk).unwrap();
+    let [arthur_message]: [Fr; 1] = arthur.next_scalars().unwrap();
+    let [arthur_chal]: [Fr; 1] = arthur.challenge_scalars().unwrap();
+    assert_eq!(arthur_message, message);
+    assert_eq!(arthur_chal, merlin_chal);
+
+    // any other verifier decrypts garbage, and derives a different challenge.
+    let arthur = io.to_arthur(&transcript);
+    let mut arthur = DesignatedVerifierReader::<G1Projective>::designated_verifier(
+        arthur,
+        &(sk + Fr::from(1u64)),
+    )
+    .unwrap();
+    let decrypted: ProofResult<[Fr; 1]> = arthur.next_scalars();
+    if decrypted.is_ok() {
+        let [wrong_chal]: [Fr; 1] = arthur.challenge_scalars().unwrap();
+        assert_ne!(wrong_chal, merlin_chal);
+    }
+
+    // points outside of the prime-order subgroup are rejected, as by Arthur.
+    use super::{GroupIOPattern, GroupReader, GroupWriter};
+    use ark_bls12_381::{Fq, G1Affine};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_ff::{PrimeField, Zero};
+    let low_order_point = (0u64..)
+        .filter_map(|x| G1Affine::get_point_from_x_unchecked(Fq::from(x), false))
+        .map(|point| point.mul_bigint(Fr::MODULUS))
+        .find(|point| !point.is_zero())
+        .unwrap();
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = DesignatedVerifierIOPattern::<G1Projective>::add_designated_verifier(io, "ephemeral");
+    let io = GroupIOPattern::<G1Projective>::add_points(io, 2, "points");
+    let mut merlin = io.to_merlin().designate_verifier(&pk).unwrap();
+    merlin.add_points(&[pk, low_order_point]).unwrap();
+    let arthur = io.to_arthur(merlin.transcript());
+    let mut arthur =
+        DesignatedVerifierReader::<G1Projective>::designated_verifier(arthur, &sk).unwrap();
+    assert!(GroupReader::<G1Projective>::next_points::<2>(&mut arthur).is_err());
+    assert!(!low_order_point
+        .into_affine()
+        .is_in_correct_subgroup_assuming_on_curve());
+}
+
+#[test]
+fn test_verifying_key_absorption() {
+    use super::{VerifyingKeyIOPattern, VerifyingKeyPublic};
+    use ark_bls12_381::G1Affine;
+    use ark_ec::AffineRepr;
+    use ark_serialize::CanonicalSerialize;
+
+    // A toy verifying key: a handful of commitments to selector polynomials.
+    let vk = vec![G1Affine::generator(); 4];
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue")
+        .absorb_vk(vk.compressed_size(), "vk")
+        .ratchet()
+        .challenge_bytes(16, "chal");
+
+    let mut merlin = io.to_merlin();
+    merlin.add_verifying_key(&vk).unwrap();
+    merlin.ratchet().unwrap();
+    let merlin_chal = merlin.challenge_bytes::<16>().unwrap();
+    // The verifying key is public and not part of the transcript.
+    assert_eq!(merlin.transcript(), b"");
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.add_verifying_key(&vk).unwrap();
+    arthur.ratchet().unwrap();
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), merlin_chal);
+
+    // A different verifying key leads to different challenges.
+    let other_vk = vec![G1Affine::zero(); 4];
+    let mut arthur = io.to_arthur(b"");
+    arthur.add_verifying_key(&other_vk).unwrap();
+    arthur.ratchet().unwrap();
+    assert_ne!(arthur.challenge_bytes::<16>().unwrap(), merlin_chal);
+}
+
+/// Blinded values are committed to, and their openings revealed and checked later.
+#[test]
+fn test_pedersen_commit_then_reveal() {
+    use super::{
+        GroupReader, PedersenIOPattern, PedersenOpeningWriter, PedersenReader, PedersenWriter,
+    };
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_ec::PrimeGroup;
+
+    let generators = [
+        G1Projective::generator(),
+        G1Projective::generator() * Fr::from(42u64),
+    ];
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = PedersenIOPattern::<G1Projective, Fr>::add_pedersen_commitments(io, 2, "commit");
+    let io = ByteIOPattern::challenge_bytes(io, 16, "chal");
+    let io = PedersenIOPattern::<G1Projective, Fr>::add_pedersen_openings(io, 2, "reveal");
+
+    let values = [Fr::from(1u64), Fr::from(2u64)];
+    let mut merlin = io.to_merlin();
+    let openings = merlin
+        .add_pedersen_commitments(&generators, &values)
+        .unwrap();
+    assert_ne!(openings[0].blinder, openings[1].blinder);
+    let chal: [u8; 16] = merlin.challenge_bytes().unwrap();
+    merlin.add_pedersen_openings(&openings).unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let commitments: [G1Projective; 2] = arthur.next_points().unwrap();
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+    let revealed: Vec<Fr> = arthur
+        .next_pedersen_openings(&generators, &commitments)
+        .unwrap();
+    assert_eq!(revealed, values);
+
+    // a wrong opening is rejected
+    let mut wrong = merlin.transcript().to_vec();
+    let last = wrong.len() - 1;
+    wrong[last] ^= 1;
+    let mut arthur = io.to_arthur(&wrong);
+    let commitments: [G1Projective; 2] = arthur.next_points().unwrap();
+    arthur.challenge_bytes::<16>().unwrap();
+    let revealed: ProofResult<Vec<Fr>> = arthur.next_pedersen_openings(&generators, &commitments);
+    assert!(revealed.is_err());
+}
+
+/// KZG commitments, evaluation points and openings go through the transcript and verify.
+#[test]
+fn test_kzg_opening() {
+    use super::{KzgChallenges, KzgIOPattern, KzgOpening, KzgReader, KzgWriter};
+    use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_ff::UniformRand;
+
+    let mut rng = ark_std::test_rng();
+    let (g, h) = (G1Affine::generator(), G2Affine::generator());
+    let tau = Fr::rand(&mut rng);
+    let tau_h = (h * tau).into_affine();
+    // f(X) = a + b X + c X^2, and (f(X) - f(z)) / (X - z) = b + c (X + z)
+    let [a, b, c] = [Fr::from(3u64), Fr::from(5u64), Fr::from(7u64)];
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = KzgIOPattern::<Bls12_381>::add_kzg_commitment(io, "f");
+    let io = KzgIOPattern::<Bls12_381>::challenge_eval_point(io, "z");
+    let io = KzgIOPattern::<Bls12_381>::add_kzg_opening_proof(io, "f(z)");
+
+    let mut merlin = io.to_merlin();
+    let commitment = (g * (a + b * tau + c * tau * tau)).into_affine();
+    KzgWriter::<Bls12_381>::add_kzg_commitment(&mut merlin, &commitment).unwrap();
+    let z = KzgChallenges::<Bls12_381>::challenge_eval_point(&mut merlin).unwrap();
+    let opening = KzgOpening::<Bls12_381> {
+        value: a + b * z + c * z * z,
+        witness: (g * (b + c * (tau + z))).into_affine(),
+    };
+    merlin.add_kzg_opening_proof(&opening).unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read_commitment = KzgReader::<Bls12_381>::next_kzg_commitment(&mut arthur).unwrap();
+    assert_eq!(read_commitment, commitment);
+    let read_z = KzgChallenges::<Bls12_381>::challenge_eval_point(&mut arthur).unwrap();
+    assert_eq!(read_z, z);
+    let read_opening: KzgOpening<Bls12_381> = arthur.next_kzg_opening_proof().unwrap();
+    assert_eq!(read_opening, opening);
+    assert!(read_opening.verify(&commitment, z, g, h, tau_h).is_ok());
+
+    // a wrong evaluation is rejected
+    let wrong = KzgOpening::<Bls12_381> {
+        value: opening.value + Fr::from(1u64),
+        ..opening
+    };
+    assert!(wrong.verify(&commitment, z, g, h, tau_h).is_err());
+}
+
+/// Labels bind the order and naming of commitments to the transcript.
+#[test]
+fn test_labeled_commitments() {
+    use super::{LabeledCommitmentIOPattern, LabeledCommitmentReader, LabeledCommitmentWriter};
+    use ark_bls12_381::{Fr, G1Affine, G1Projective};
+    use ark_ec::{AffineRepr, CurveGroup};
+
+    let labels = ["witness", "quotient"];
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io =
+        LabeledCommitmentIOPattern::<G1Projective>::add_labeled_commitments(io, &labels, "com");
+    let io = ByteIOPattern::challenge_bytes(io, 16, "chal");
+
+    let commitments = [
+        G1Affine::generator(),
+        (G1Affine::generator() * Fr::from(2u64)).into_affine(),
+    ];
+    let mut merlin = io.to_merlin();
+    merlin
+        .add_labeled_commitments(labels.into_iter().zip(commitments))
+        .unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    assert_eq!(merlin.transcript().len(), 2 * 48);
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read: Vec<G1Affine> = arthur.next_labeled_commitments(&labels).unwrap();
+    assert_eq!(read, commitments);
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+
+    // swapping the labels changes the challenges
+    let swapped = ["quotient", "witness"];
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io =
+        LabeledCommitmentIOPattern::<G1Projective>::add_labeled_commitments(io, &swapped, "com");
+    let io = ByteIOPattern::challenge_bytes(io, 16, "chal");
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let _: Vec<G1Affine> = arthur.next_labeled_commitments(&swapped).unwrap();
+    assert_ne!(arthur.challenge_bytes::<16>().unwrap(), chal);
+}
+
+/// Small scalars are bit-packed on the wire.
+#[test]
+fn test_small_scalars() {
+    use super::{SmallScalarIOPattern, SmallScalarReader, SmallScalarWriter};
+    use ark_bls12_381::Fr;
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = SmallScalarIOPattern::<Fr>::add_small_scalars(io, 10, 3, "multiplicities");
+    let io = ByteIOPattern::challenge_bytes(io, 16, "chal");
+
+    let scalars = (0..10u64).map(|i| Fr::from(i % 8)).collect::<Vec<_>>();
+    let mut merlin = io.to_merlin();
+    assert!(merlin.add_small_scalars(3, &[Fr::from(8u64)]).is_err());
+    let mut merlin = io.to_merlin();
+    merlin.add_small_scalars(3, &scalars).unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    // 10 scalars of 3 bits fit in 4 bytes
+    assert_eq!(merlin.transcript(), [0x88, 0xc6, 0xfa, 0x08]);
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read: Vec<Fr> = arthur.next_small_scalars(3, 10).unwrap();
+    assert_eq!(read, scalars);
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+
+    // non-zero padding is rejected
+    let mut transcript = merlin.transcript().to_vec();
+    transcript[3] |= 0x80;
+    let mut arthur = io.to_arthur(&transcript);
+    assert!(SmallScalarReader::<Fr>::next_small_scalars(&mut arthur, 3, 10).is_err());
+
+    // the number of scalars and their size are bound to the challenges
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = SmallScalarIOPattern::<Fr>::add_small_scalars(io, 6, 5, "multiplicities");
+    let io = ByteIOPattern::challenge_bytes(io, 16, "chal");
+    let mut arthur = io.to_arthur(merlin.transcript());
+    SmallScalarReader::<Fr>::next_small_scalars(&mut arthur, 5, 6).unwrap();
+    assert_ne!(arthur.challenge_bytes::<16>().unwrap(), chal);
+}
+
+/// Byte challenges over field units are the low bytes of the units squeezed, and empty challenges squeeze nothing.
+#[test]
+fn test_challenge_bytes_from_units() {
+    use ark_bls12_381::Fq;
+    use ark_ff::{BigInteger, PrimeField};
+
+    let len_good = crate::plugins::random_bytes_in_random_modp(Fq::MODULUS);
+    let units = 2048usize.div_ceil(len_good);
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue").squeeze(units, "chal");
+
+    let mut merlin = io.to_merlin();
+    merlin.fill_challenge_bytes(&mut []).unwrap();
+    let mut chal = vec![0u8; 2048];
+    merlin.fill_challenge_bytes(&mut chal).unwrap();
+
+    let mut another_merlin = io.to_merlin();
+    let mut expected = vec![Fq::from(0); units];
+    another_merlin.fill_challenge_units(&mut expected).unwrap();
+    let expected = expected
+        .iter()
+        .flat_map(|u| u.into_bigint().to_bytes_le()[..len_good].to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(chal, expected[..2048]);
+
+    // the SAFE sponge alone squeezes the same byte challenges
+    let mut safe = crate::Safe::new(&io);
+    let mut safe_chal = vec![0u8; 2048];
+    safe.fill_challenge_bytes(&mut safe_chal).unwrap();
+    assert_eq!(safe_chal, chal);
+}
+
+/// Adversarial transcripts keep the layout of the honest one, and cover every element of the targeted messages.
+#[test]
+fn test_adversarial_transcripts() {
+    use super::adversarial::{AdversarialTranscripts, MutationKind};
+    use super::{BytePublic, FieldIOPattern, FieldWriter, GroupIOPattern, GroupWriter};
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_ec::PrimeGroup;
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue").add_bytes(4, "public");
+    let io = GroupIOPattern::<G1Projective>::add_points(io, 2, "points");
+    let io = FieldIOPattern::<Fr>::add_scalars(io, 1, "scalar");
+    let mut merlin = io.to_merlin();
+    merlin.public_bytes(b"stmt").unwrap();
+    merlin.add_points(&[G1Projective::generator(); 2]).unwrap();
+    merlin.add_scalars(&[Fr::from(42)]).unwrap();
+    let transcript = merlin.transcript();
+
+    assert!(AdversarialTranscripts::new(&io, transcript, &[]).is_err());
+    let adversary = AdversarialTranscripts::new(&io, transcript, &["public"]).unwrap();
+
+    let points = adversary.points::<G1Projective>("points");
+    assert_eq!(points.len(), 2 * 3);
+    assert!(points
+        .iter()
+        .any(|m| m.index == 1 && m.kind == MutationKind::InvalidPoint));
+    let scalars = adversary.scalars::<Fr>("scalar");
+    assert_eq!(scalars.len(), 5);
+    assert_eq!(scalars[0].transcript[..96], transcript[..96]);
+    for mutation in points.iter().chain(&scalars) {
+        assert_eq!(mutation.transcript.len(), transcript.len());
+        assert_ne!(mutation.transcript, transcript);
+    }
+    assert!(adversary.scalars::<Fr>("unknown").is_empty());
+}
+
+/// The arkworks sponge API follows the conventions of arkworks' Poseidon sponge.
+#[test]
+fn test_ark_sponge() {
+    use super::sponge::ArkSponge;
+    use ark_bls12_381::{Fq, Fr};
+    use ark_ff::{BigInteger, PrimeField};
+
+    let io = IOPattern::<crate::hash::sponge::DuplexSponge<ToySponge<Fr>>, Fr>::new(
+        "github.com/mmaker/nimue",
+    );
+    let sponge = ArkSponge::from_io_pattern(&io);
+
+    // bytes are prefixed with their length, and packed in 31-byte little-endian chunks
+    let mut packed = sponge.clone();
+    let chunk = [&32u64.to_le_bytes()[..], &[1; 23]].concat();
+    packed.absorb_native_field_elements(&[
+        Fr::from_le_bytes_mod_order(&chunk),
+        Fr::from_le_bytes_mod_order(&[1; 9]),
+    ]);
+    let mut bytes = sponge.clone();
+    bytes.absorb_bytes(&[1; 32]);
+    assert_eq!(
+        packed.squeeze_native_field_elements(1),
+        bytes.squeeze_native_field_elements(1)
+    );
+
+    // squeezed bytes and bits are the low bytes and bits of squeezed elements
+    let elements = sponge.clone().squeeze_native_field_elements(2);
+    let expected_bytes = elements
+        .iter()
+        .flat_map(|x| x.into_bigint().to_bytes_le()[..31].to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(sponge.clone().squeeze_bytes(40), expected_bytes[..40]);
+    let expected_bits = elements
+        .iter()
+        .flat_map(|x| x.into_bigint().to_bits_le()[..254].to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(sponge.clone().squeeze_bits(300), expected_bits[..300]);
+    assert_eq!(sponge.clone().squeeze_field_elements::<Fr>(2), elements);
+
+    // foreign elements are reduced from 380 bits
+    let [x] = sponge
+        .clone()
+        .squeeze_field_elements::<Fq>(1)
+        .try_into()
+        .unwrap();
+    let bits = sponge.clone().squeeze_bits(380);
+    assert_eq!(x.into_bigint().to_bits_le()[..380], bits);
+
+    let mut fork = sponge.fork(b"domain");
+    let mut expected = sponge.clone();
+    expected.absorb_bytes(b"domain");
+    assert_eq!(
+        fork.squeeze_native_field_elements(1),
+        expected.squeeze_native_field_elements(1)
+    );
+    assert_ne!(
+        sponge.fork(b"other").squeeze_bytes(32),
+        sponge.clone().squeeze_bytes(32)
+    );
+}
+
+/// With `ark-crypto-primitives`, nimue's sponges and arkworks' sponges can be used in place of each other.
+#[cfg(feature = "ark-crypto-primitives")]
+#[test]
+fn test_ark_crypto_primitives_sponges() {
+    use super::sponge::{ArkSponge, ArkSpongeConfig, FromArkSponge};
+    use super::{FieldChallenges, FieldIOPattern, FieldWriter};
+    use ark_bls12_381::Fr;
+    use ark_crypto_primitives::sponge::poseidon::{
+        find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge,
+    };
+    use ark_crypto_primitives::sponge::{CryptographicSponge, FieldBasedCryptographicSponge};
+
+    /// Poseidon over BLS12-381's scalar field, with rate 2 and capacity 1.
+    struct Poseidon2;
+    impl ArkSpongeConfig<PoseidonSponge<Fr>> for Poseidon2 {
+        fn config() -> PoseidonConfig<Fr> {
+            let (ark, mds) = find_poseidon_ark_and_mds::<Fr>(255, 2, 8, 57, 0);
+            PoseidonConfig::new(8, 57, 5, mds, ark, 2, 1)
+        }
+    }
+    type Poseidon = FromArkSponge<PoseidonSponge<Fr>, Poseidon2>;
+
+    /// Code written against arkworks' traits.
+    fn transcript<S: FieldBasedCryptographicSponge<Fr>>(sponge: &mut S) -> (Vec<u8>, Vec<Fr>) {
+        sponge.absorb(&b"statement".as_slice());
+        sponge.absorb(&Fr::from(42));
+        let bytes = sponge.squeeze_bytes(40);
+        (bytes, sponge.squeeze_native_field_elements(2))
+    }
+
+    // the traits forward to the inherent methods
+    let iv = [0x42; 32];
+    let mut sponge = ArkSponge::<crate::hash::sponge::DuplexSponge<ToySponge<Fr>>, Fr>::new(iv);
+    let (bytes, elements) = transcript(&mut sponge);
+    let mut expected = ArkSponge::<crate::hash::sponge::DuplexSponge<ToySponge<Fr>>, Fr>::new(iv);
+    expected.absorb_bytes(b"statement");
+    expected.absorb_native_field_elements(&[Fr::from(42)]);
+    assert_eq!(bytes, expected.squeeze_bytes(40));
+    assert_eq!(elements, expected.squeeze_native_field_elements(2));
+
+    // an arkworks sponge, wrapped as a duplex hash and back, is unchanged
+    let mut poseidon = PoseidonSponge::new(&Poseidon2::config());
+    poseidon.absorb(&iv.as_slice());
+    let sponge = ArkSponge::<Poseidon, Fr>::new(iv);
+    // forking absorbs the domain once, with a single length prefix
+    let mut fork = poseidon.clone();
+    fork.absorb(&b"domain".as_slice());
+    assert_eq!(
+        transcript(&mut fork),
+        transcript(&mut sponge.fork(b"domain"))
+    );
+    assert_ne!(
+        transcript(&mut poseidon.fork(b"domain")),
+        transcript(&mut sponge.fork(b"domain"))
+    );
+    assert_eq!(
+        poseidon
+            .clone()
+            .squeeze_field_elements::<ark_bls12_381::Fq>(2),
+        sponge
+            .clone()
+            .squeeze_field_elements::<ark_bls12_381::Fq>(2)
+    );
+    assert_eq!(transcript(&mut poseidon), transcript(&mut sponge.clone()));
+
+    // and can be used in a protocol
+    let io = IOPattern::<Poseidon, Fr>::new("github.com/mmaker/nimue");
+    let io = FieldIOPattern::<Fr>::add_scalars(io, 1, "message");
+    let io = FieldIOPattern::<Fr>::challenge_scalars(io, 1, "challenge");
+    let mut merlin = io.to_merlin();
+    merlin.add_scalars(&[Fr::from(42)]).unwrap();
+    let [challenge]: [Fr; 1] = merlin.challenge_scalars().unwrap();
+    let mut poseidon = PoseidonSponge::new(&Poseidon2::config());
+    poseidon.absorb(&io.digest().as_slice());
+    poseidon.absorb(&Fr::from(42));
+    assert_eq!(poseidon.squeeze_native_field_elements(1), [challenge]);
+}
+
+#[test]
+fn test_layered_transcript_points_and_scalars() {
+    use crate::middleware::LayeredTranscript;
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_ec::PrimeGroup;
+
+    use super::{
+        FieldIOPattern, FieldReader, FieldWriter, GroupIOPattern, GroupReader, GroupWriter,
+    };
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = GroupIOPattern::<G1Projective>::add_points(io, 1, "point");
+    let io = FieldIOPattern::<Fr>::add_scalars(io, 1, "scalar");
+    let (point, scalar) = (G1Projective::generator(), Fr::from(42u64));
+
+    let mut merlin = LayeredTranscript::new(io.to_merlin(), ());
+    merlin.add_points(&[point]).unwrap();
+    merlin.add_scalars(&[scalar]).unwrap();
+    let mut control = io.to_merlin();
+    control.add_points(&[point]).unwrap();
+    control.add_scalars(&[scalar]).unwrap();
+    assert_eq!(merlin.transcript(), control.transcript());
+
+    let mut arthur = LayeredTranscript::new(io.to_arthur(merlin.transcript()), ());
+    let [read_point]: [G1Projective; 1] = arthur.next_points().unwrap();
+    let [read_scalar]: [Fr; 1] = arthur.next_scalars().unwrap();
+    assert_eq!((read_point, read_scalar), (point, scalar));
+
+    /// Record the length of each prover message.
+    struct Messages(Vec<usize>);
+    impl crate::middleware::TranscriptMiddleware for Messages {
+        fn on_message(&mut self, message: &[u8]) -> Result<(), crate::IOPatternError> {
+            self.0.push(message.len());
+            Ok(())
+        }
+    }
+
+    // points are read at once, and checked in a batch, as by Arthur
+    let io = GroupIOPattern::<G1Projective>::add_points(
+        IOPattern::<DefaultHash>::new("github.com/mmaker/nimue"),
+        2,
+        "points",
+    );
+    let mut merlin = io.to_merlin();
+    merlin.add_points(&[point, point + point]).unwrap();
+    let mut arthur = LayeredTranscript::new(io.to_arthur(merlin.transcript()), Messages(vec![]));
+    let points: [G1Projective; 2] = arthur.next_points().unwrap();
+    assert_eq!(points, [point, point + point]);
+    assert_eq!(arthur.middleware().0, [96]);
+    let mut invalid = merlin.transcript().to_vec();
+    invalid[48..].fill(0xff);
+    let mut arthur = LayeredTranscript::new(io.to_arthur(&invalid), ());
+    assert!(GroupReader::<G1Projective>::next_points::<2>(&mut arthur).is_err());
+}
+
+/// Affine points, Pedersen commitments, markers, and transcripts over field units go through the middleware.
+#[test]
+fn test_layered_transcript_plugins() {
+    use crate::middleware::{LayeredTranscript, TranscriptMiddleware};
+    use crate::{IOPatternError, Unit};
+    use ark_bls12_381::{Fq, Fr, G1Affine, G1Projective};
+    use ark_ec::{AffineRepr, PrimeGroup};
+
+    use super::{
+        AffineReader, AffineWriter, FieldChallenges, FieldReader, FieldWriter, GroupIOPattern,
+        GroupReader, GroupWriter, PedersenIOPattern, PedersenWriter,
+    };
+
+    /// Record the length of each prover message, and the markers crossed.
+    #[derive(Default)]
+    struct Trace {
+        messages: Vec<usize>,
+        markers: Vec<String>,
+    }
+
+    impl<U: Unit> TranscriptMiddleware<U> for Trace {
+        fn on_message(&mut self, message: &[U]) -> Result<(), IOPatternError> {
+            self.messages.push(message.len());
+            Ok(())
+        }
+
+        fn on_marker(&mut self, label: &str) -> Result<(), IOPatternError> {
+            self.markers.push(label.to_string());
+            Ok(())
+        }
+    }
+
+    /// Refuse every prover message.
+    struct Deny;
+
+    impl TranscriptMiddleware for Deny {
+        fn on_message(&mut self, _message: &[u8]) -> Result<(), IOPatternError> {
+            Err("no messages".to_string().into())
+        }
+    }
+
+    let generators = [
+        G1Projective::generator(),
+        G1Projective::generator() * Fr::from(42u64),
+    ];
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue").marker("commit");
+    let io = GroupIOPattern::<G1Projective>::add_points(io, 1, "affine");
+    let io = PedersenIOPattern::<G1Projective, Fr>::add_pedersen_commitments(io, 1, "pedersen");
+
+    let mut merlin = LayeredTranscript::new(io.to_merlin(), Trace::default());
+    merlin.marker("commit").unwrap();
+    merlin.add_affine_points(&[G1Affine::generator()]).unwrap();
+    merlin
+        .add_pedersen_commitments(&generators, &[Fr::from(1u64)])
+        .unwrap();
+    let (merlin, trace) = merlin.into_parts();
+    assert_eq!(
+        (trace.messages, trace.markers),
+        (vec![48, 48], vec!["commit".to_string()])
+    );
+
+    let mut arthur = LayeredTranscript::new(io.to_arthur(merlin.transcript()), Trace::default());
+    arthur.marker("commit").unwrap();
+    let [point]: [G1Affine; 1] = arthur.next_affine_points().unwrap();
+    assert_eq!(point, G1Affine::generator());
+    let _: [G1Projective; 1] = arthur.next_points().unwrap();
+    assert_eq!(arthur.middleware().messages, [48, 48]);
+
+    // a policy rejecting messages cannot be bypassed by the plugins
+    let mut merlin = LayeredTranscript::new(io.to_merlin(), Deny);
+    merlin.marker("commit").unwrap();
+    assert!(merlin.add_affine_points(&[G1Affine::generator()]).is_err());
+    let mut merlin = LayeredTranscript::new(io.to_merlin(), Deny);
+    merlin.marker("commit").unwrap();
+    merlin
+        .inner
+        .add_affine_points(&[G1Affine::generator()])
+        .unwrap();
+    assert!(merlin
+        .add_pedersen_commitments(&generators, &[Fr::from(1u64)])
+        .is_err());
+
+    // over field units, messages are seen as the units written
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue")
+        .absorb(2, "point")
+        .absorb(1, "scalar")
+        .squeeze(1, "chal");
+    let (point, scalar) = (G1Projective::generator(), Fq::from(42u64));
+    let mut control = io.to_merlin();
+    control.add_points(&[point]).unwrap();
+    control.add_scalars(&[scalar]).unwrap();
+    let [chal]: [Fq; 1] = control.challenge_scalars().unwrap();
+
+    let mut merlin = LayeredTranscript::new(io.to_merlin(), Trace::default());
+    merlin.add_points(&[point]).unwrap();
+    merlin.add_scalars(&[scalar]).unwrap();
+    let merlin_chal: [Fq; 1] = merlin.challenge_scalars().unwrap();
+    assert_eq!(merlin_chal, [chal]);
+    assert_eq!(merlin.transcript(), control.transcript());
+    assert_eq!(merlin.middleware().messages, [2, 1]);
+
+    let mut arthur = LayeredTranscript::new(io.to_arthur(control.transcript()), Trace::default());
+    let read_point: [G1Projective; 1] = arthur.next_points().unwrap();
+    let read_scalar: [Fq; 1] = arthur.next_scalars().unwrap();
+    let arthur_chal: [Fq; 1] = arthur.challenge_scalars().unwrap();
+    assert_eq!(
+        (read_point, read_scalar, arthur_chal),
+        ([point], [scalar], [chal])
+    );
+    assert_eq!(arthur.middleware().messages, [2, 1]);
+}
+
+/// Out-of-domain challenges agree between prover and verifier, and are resampled inside of the domain.
+#[test]
+fn test_challenge_domain_point() {
+    use super::{CosetDomain, DomainPointChallenges, DomainPointIOPattern};
+    use ark_bls12_381::{Fq, Fr};
+    use ark_ff::{FftField, Fp64, MontBackend, MontConfig};
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue")
+        .absorb(1, "in")
+        .challenge_domain_point("ood");
+    let domain = CosetDomain::new(1 << 10, Fr::GENERATOR);
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[0x42]).unwrap();
+    let merlin_point: Fr = merlin.challenge_domain_point(&domain).unwrap();
+    assert!(!domain.contains(&merlin_point));
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.next_bytes::<1>().unwrap();
+    assert_eq!(
+        arthur.challenge_domain_point(&domain).unwrap(),
+        merlin_point
+    );
+
+    // the sponge only squeezes the declared seed, whatever the number of retries
+    let next_io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue")
+        .challenge_domain_point("ood")
+        .challenge_bytes(16, "next");
+    let mut merlin = next_io.to_merlin();
+    merlin.challenge_domain_point(&domain).unwrap();
+    let mut control = next_io.to_merlin();
+    control.challenge_bytes::<32>().unwrap();
+    assert_eq!(
+        merlin.challenge_bytes::<16>().unwrap(),
+        control.challenge_bytes::<16>().unwrap()
+    );
+
+    // the seed is squeezed from the sponge of transcripts over field units, too
+    let io = IOPattern::<ToyHash, Fq>::new("github.com/mmaker/nimue")
+        .absorb(1, "in")
+        .challenge_domain_point("ood");
+    let mut merlin = io.to_merlin();
+    merlin.add_units(&[Fq::from(0x42)]).unwrap();
+    let merlin_point: Fr = merlin.challenge_domain_point(&domain).unwrap();
+    assert!(!domain.contains(&merlin_point));
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.fill_next_units(&mut [Fq::from(0)]).unwrap();
+    assert_eq!(
+        arthur.challenge_domain_point(&domain).unwrap(),
+        merlin_point
+    );
+
+    // over a field with 17 elements, a domain of size 8 is hit half of the time
+    #[derive(MontConfig)]
+    #[modulus = "17"]
+    #[generator = "3"]
+    struct F17Config;
+    type F17 = Fp64<MontBackend<F17Config, 1>>;
+
+    let domain = CosetDomain::<F17>::subgroup(8);
+    assert!(domain.contains(&F17::from(2)) && !domain.contains(&F17::from(3)));
+    for i in 0u8..32 {
+        let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue")
+            .absorb(1, "in")
+            .challenge_domain_point("ood");
+        let mut merlin = io.to_merlin();
+        merlin.add_bytes(&[i]).unwrap();
+        let point = merlin.challenge_domain_point(&domain).unwrap();
+        assert!(!domain.contains(&point));
+        assert_eq!(merlin.remaining_ops(), 0);
+    }
+    let whole = CosetDomain::<F17>::subgroup(16);
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue").challenge_domain_point("ood");
+    assert!(io.to_merlin().challenge_domain_point(&whole).is_err());
+    let empty = CosetDomain::<F17> {
+        size: 0,
+        offset: F17::from(1),
+    };
+    assert!(io.to_merlin().challenge_domain_point(&empty).is_err());
+}
+
+/// Domains of size zero are rejected.
+#[test]
+#[should_panic(expected = "the domain must have a non-zero size")]
+fn test_coset_domain_empty() {
+    super::CosetDomain::<ark_bls12_381::Fr>::subgroup(0);
+}
+
+/// Serializing straight into the prover state matches `add_points`.
+#[test]
+fn test_serialize_into_merlin() {
+    use super::{GroupIOPattern, GroupWriter};
+    use ark_bls12_381::G1Projective;
+    use ark_ec::PrimeGroup;
+    use ark_serialize::CanonicalSerialize;
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = GroupIOPattern::<G1Projective>::add_points(io, 2, "points");
+    let points = [
+        G1Projective::generator(),
+        G1Projective::generator() * ark_bls12_381::Fr::from(2u64),
+    ];
+
+    let mut merlin = io.to_merlin();
+    points[0].serialize_compressed(&mut merlin).unwrap();
+    points[1].serialize_compressed(&mut merlin).unwrap();
+    let mut control = io.to_merlin();
+    control.add_points(&points).unwrap();
+    assert_eq!(merlin.transcript(), control.transcript());
+
+    // a third point does not fit in the IO Pattern
+    assert!(points[0].serialize_compressed(&mut merlin).is_err());
+}
+
+/// Deserializing straight from the verifier state matches `next_points`.
+#[test]
+fn test_deserialize_from_arthur() {
+    use super::{GroupIOPattern, GroupWriter};
+    use ark_bls12_381::G1Projective;
+    use ark_ec::PrimeGroup;
+    use ark_serialize::CanonicalDeserialize;
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = GroupIOPattern::<G1Projective>::add_points(io, 1, "point");
+    let mut merlin = io.to_merlin();
+    merlin.add_points(&[G1Projective::generator()]).unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let point = G1Projective::deserialize_compressed(&mut arthur).unwrap();
+    assert_eq!(point, G1Projective::generator());
+    assert_eq!(arthur.remaining_ops(), 0);
+
+    // the point cannot be read past the end of the message
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue").absorb(47, "short");
+    let mut arthur = io.to_arthur(&merlin.transcript()[..47]);
+    assert!(G1Projective::deserialize_compressed(&mut arthur).is_err());
+}
@@ -1,9 +1,14 @@
-use ark_ec::CurveGroup;
-use ark_ff::{Field, Fp, FpConfig};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{Field, Fp, FpConfig, UniformRand};
 use ark_serialize::CanonicalSerialize;
 use rand::{CryptoRng, RngCore};
 
-use super::{FieldPublic, FieldWriter, GroupPublic, GroupWriter};
+use super::common::points_to_coordinates;
+use super::{
+    AffineCoordinates, AffineWriter, FieldPublic, FieldWriter, GroupPublic, GroupWriter,
+    PedersenOpening, PedersenWriter,
+};
+use crate::middleware::{LayeredTranscript, TranscriptMiddleware};
 use crate::{
     Arthur, BytePublic, ByteReader, ByteWriter, DuplexHash, IOPatternError, Merlin, ProofResult,
     Unit, UnitTranscript,
@@ -11,8 +16,8 @@ use crate::{
 
 impl<F: Field, H: DuplexHash, R: RngCore + CryptoRng> FieldWriter<F> for Merlin<H, u8, R> {
     fn add_scalars(&mut self, input: &[F]) -> ProofResult<()> {
-        let serialized = self.public_scalars(input);
-        self.transcript.extend(serialized?);
+        let serialized = self.public_scalars(input)?;
+        self.write_message(&serialized);
         Ok(())
     }
 }
@@ -22,13 +27,30 @@ impl<C: FpConfig<N>, H: DuplexHash<Fp<C, N>>, R: RngCore + CryptoRng, const N: u
 {
     fn add_scalars(&mut self, input: &[Fp<C, N>]) -> ProofResult<()> {
         self.public_units(input)?;
+        let start = self.transcript.len();
         for i in input {
             i.serialize_compressed(&mut self.transcript)?;
         }
+        self.flush_message(start);
         Ok(())
     }
 }
 
+impl<F, T, M> FieldWriter<F> for LayeredTranscript<T, M>
+where
+    F: Field,
+    T: UnitTranscript<u8> + ByteWriter,
+    M: TranscriptMiddleware<u8>,
+{
+    fn add_scalars(&mut self, input: &[F]) -> ProofResult<()> {
+        let mut buf = Vec::new();
+        for i in input {
+            i.serialize_compressed(&mut buf)?;
+        }
+        Ok(self.add_bytes(&buf)?)
+    }
+}
+
 impl<G, H, R> GroupWriter<G> for Merlin<H, u8, R>
 where
     G: CurveGroup,
@@ -38,30 +60,213 @@ where
 {
     #[inline(always)]
     fn add_points(&mut self, input: &[G]) -> ProofResult<()> {
-        let serialized = self.public_points(input);
-        self.transcript.extend(serialized?);
+        let serialized = self.public_points(input)?;
+        self.write_message(&serialized);
         Ok(())
     }
 }
 
-impl<G, H, R, C: FpConfig<N>, C2: FpConfig<N>, const N: usize> GroupWriter<G>
-    for Merlin<H, Fp<C, N>, R>
+impl<G, T, M> GroupWriter<G> for LayeredTranscript<T, M>
+where
+    G: CurveGroup,
+    T: UnitTranscript<u8> + ByteWriter,
+    M: TranscriptMiddleware<u8>,
+{
+    fn add_points(&mut self, input: &[G]) -> ProofResult<()> {
+        let mut buf = Vec::new();
+        for i in input {
+            i.serialize_compressed(&mut buf)?;
+        }
+        Ok(self.add_bytes(&buf)?)
+    }
+}
+
+impl<G, H, R, C: FpConfig<N>, const N: usize> GroupWriter<G> for Merlin<H, Fp<C, N>, R>
 where
-    G: CurveGroup<BaseField = Fp<C2, N>>,
+    G: CurveGroup<BaseField = Fp<C, N>>,
     H: DuplexHash<Fp<C, N>>,
     R: RngCore + CryptoRng,
-    Merlin<H, Fp<C, N>, R>: GroupPublic<G> + FieldWriter<G::BaseField>,
+    Merlin<H, Fp<C, N>, R>: GroupPublic<G, Repr = Vec<Fp<C, N>>>,
 {
+    /// Write the affine coordinates $(x, y)$ of each point, as field elements.
     #[inline(always)]
     fn add_points(&mut self, input: &[G]) -> ProofResult<()> {
-        self.public_points(input).map(|_| ())?;
-        for i in input {
-            i.serialize_compressed(&mut self.transcript)?;
+        let coordinates = self.public_points(input)?;
+        let start = self.transcript.len();
+        for c in coordinates {
+            c.serialize_compressed(&mut self.transcript)?;
+        }
+        self.flush_message(start);
+        Ok(())
+    }
+}
+
+/// Write compressed affine points as a single prover message of a byte transcript.
+fn write_affine_points<A: AffineRepr>(
+    transcript: &mut impl ByteWriter,
+    input: &[A],
+) -> ProofResult<()> {
+    let mut buf = Vec::with_capacity(input.len() * A::zero().compressed_size());
+    for point in input {
+        point.serialize_compressed(&mut buf)?;
+    }
+    transcript.add_bytes(&buf)?;
+    Ok(())
+}
+
+impl<A, H, R> AffineWriter<A> for Merlin<H, u8, R>
+where
+    A: AffineRepr,
+    H: DuplexHash,
+    R: RngCore + CryptoRng,
+{
+    fn add_affine_points(&mut self, input: &[A]) -> ProofResult<()> {
+        write_affine_points(self, input)
+    }
+}
+
+impl<A, T, M> AffineWriter<A> for LayeredTranscript<T, M>
+where
+    A: AffineRepr,
+    T: UnitTranscript<u8> + ByteWriter,
+    M: TranscriptMiddleware<u8>,
+{
+    fn add_affine_points(&mut self, input: &[A]) -> ProofResult<()> {
+        write_affine_points(self, input)
+    }
+}
+
+impl<A, H, R, C: FpConfig<N>, const N: usize> AffineWriter<A> for Merlin<H, Fp<C, N>, R>
+where
+    A: AffineRepr<BaseField = Fp<C, N>>,
+    A::Group: AffineCoordinates,
+    H: DuplexHash<Fp<C, N>>,
+    R: RngCore + CryptoRng,
+{
+    /// Write the affine coordinates $(x, y)$ of each point, as field elements.
+    fn add_affine_points(&mut self, input: &[A]) -> ProofResult<()> {
+        let mut coordinates = Vec::with_capacity(2 * input.len());
+        for point in input {
+            coordinates.extend(A::Group::affine_to_coordinates(point)?);
         }
+        self.public_units(&coordinates)?;
+        let start = self.transcript.len();
+        for c in coordinates {
+            c.serialize_compressed(&mut self.transcript)?;
+        }
+        self.flush_message(start);
         Ok(())
     }
 }
 
+/// Open each of `values` with a fresh blinder from `rng`, and commit to the openings.
+fn pedersen_openings<G: CurveGroup>(
+    rng: &mut (impl RngCore + CryptoRng),
+    generators: &[G; 2],
+    values: &[G::ScalarField],
+) -> (Vec<PedersenOpening<G::ScalarField>>, Vec<G>) {
+    let openings = values
+        .iter()
+        .map(|&value| PedersenOpening {
+            value,
+            blinder: G::ScalarField::rand(rng),
+        })
+        .collect::<Vec<_>>();
+    let commitments = openings
+        .iter()
+        .map(|opening| opening.commit(generators))
+        .collect();
+    (openings, commitments)
+}
+
+impl<C, H, R, M, const N: usize> FieldWriter<Fp<C, N>>
+    for LayeredTranscript<Merlin<H, Fp<C, N>, R>, M>
+where
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+    R: RngCore + CryptoRng,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    fn add_scalars(&mut self, input: &[Fp<C, N>]) -> ProofResult<()> {
+        self.middleware.on_message(input)?;
+        self.inner.add_scalars(input)
+    }
+}
+
+impl<G, C, H, R, M, const N: usize> GroupWriter<G> for LayeredTranscript<Merlin<H, Fp<C, N>, R>, M>
+where
+    G: AffineCoordinates<BaseField = Fp<C, N>>,
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+    R: RngCore + CryptoRng,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    /// Write the affine coordinates $(x, y)$ of each point, as field elements.
+    fn add_points(&mut self, input: &[G]) -> ProofResult<()> {
+        self.middleware.on_message(&points_to_coordinates(input)?)?;
+        self.inner.add_points(input)
+    }
+}
+
+impl<A, C, H, R, M, const N: usize> AffineWriter<A> for LayeredTranscript<Merlin<H, Fp<C, N>, R>, M>
+where
+    A: AffineRepr<BaseField = Fp<C, N>>,
+    A::Group: AffineCoordinates,
+    C: FpConfig<N>,
+    H: DuplexHash<Fp<C, N>>,
+    R: RngCore + CryptoRng,
+    M: TranscriptMiddleware<Fp<C, N>>,
+{
+    /// Write the affine coordinates $(x, y)$ of each point, as field elements.
+    fn add_affine_points(&mut self, input: &[A]) -> ProofResult<()> {
+        let mut coordinates = Vec::with_capacity(2 * input.len());
+        for point in input {
+            coordinates.extend(A::Group::affine_to_coordinates(point)?);
+        }
+        self.middleware.on_message(&coordinates)?;
+        self.inner.add_affine_points(input)
+    }
+}
+
+impl<G, H, U, R> PedersenWriter<G, G::ScalarField> for Merlin<H, U, R>
+where
+    G: CurveGroup,
+    U: Unit,
+    H: DuplexHash<U>,
+    R: RngCore + CryptoRng,
+    Merlin<H, U, R>: GroupWriter<G> + FieldWriter<G::ScalarField>,
+{
+    fn add_pedersen_commitments(
+        &mut self,
+        generators: &[G; 2],
+        values: &[G::ScalarField],
+    ) -> ProofResult<Vec<PedersenOpening<G::ScalarField>>> {
+        let (openings, commitments) = pedersen_openings(self.rng(), generators, values);
+        GroupWriter::<G>::add_points(self, &commitments)?;
+        Ok(openings)
+    }
+}
+
+impl<G, H, U, R, M> PedersenWriter<G, G::ScalarField> for LayeredTranscript<Merlin<H, U, R>, M>
+where
+    G: CurveGroup,
+    U: Unit,
+    H: DuplexHash<U>,
+    R: RngCore + CryptoRng,
+    M: TranscriptMiddleware<U>,
+    Self: GroupWriter<G> + FieldWriter<G::ScalarField>,
+{
+    fn add_pedersen_commitments(
+        &mut self,
+        generators: &[G; 2],
+        values: &[G::ScalarField],
+    ) -> ProofResult<Vec<PedersenOpening<G::ScalarField>>> {
+        let (openings, commitments) = pedersen_openings(self.rng(), generators, values);
+        GroupWriter::<G>::add_points(self, &commitments)?;
+        Ok(openings)
+    }
+}
+
 impl<H, R, C, const N: usize> ByteWriter for Merlin<H, Fp<C, N>, R>
 where
     H: DuplexHash<Fp<C, N>>,
@@ -70,7 +275,7 @@ where
 {
     fn add_bytes(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
         self.public_bytes(input)?;
-        self.transcript.extend(input);
+        self.write_message(input);
         Ok(())
     }
 }
@@ -1,6 +1,8 @@
 //! This adds support also for [curve25519-dalek](https://github.com/dalek-cryptography/curve25519-dalek) with feature flag `group`.
 mod common;
 mod iopattern;
+/// Commonly-needed types and traits for group.
+pub mod prelude;
 mod reader;
 mod writer;
 
@@ -0,0 +1,9 @@
+//! Commonly-needed types and traits for [group](https://github.com/zkcrypto/group), to be glob-imported.
+//!
+//! It includes the [crate prelude](crate::prelude).
+
+pub use super::{
+    FieldChallenges, FieldIOPattern, FieldPublic, FieldReader, FieldWriter, GroupIOPattern,
+    GroupPublic, GroupReader, GroupWriter,
+};
+pub use crate::prelude::*;
@@ -1,18 +1,41 @@
 use super::FieldReader;
-use crate::{Arthur, ByteReader, DuplexHash, ProofError};
+use crate::middleware::{LayeredTranscript, TranscriptMiddleware};
+use crate::{Arthur, ByteReader, DuplexHash, ProofError, UnitTranscript};
 use group::ff::PrimeField;
 
+/// Read field elements, in their canonical encoding, from the prover messages of a byte transcript.
+fn read_scalars<F, const N: usize>(
+    transcript: &mut impl ByteReader,
+    output: &mut [F],
+) -> crate::ProofResult<()>
+where
+    F: PrimeField<Repr = [u8; N]>,
+{
+    let mut buf = [0u8; N];
+    for o in output.iter_mut() {
+        transcript.fill_next_bytes(&mut buf)?;
+        *o = F::from_repr_vartime(buf).ok_or(ProofError::SerializationError)?;
+    }
+    Ok(())
+}
+
 impl<'a, F, H, const N: usize> FieldReader<F> for Arthur<'a, H>
 where
     H: DuplexHash,
     F: PrimeField<Repr = [u8; N]>,
 {
     fn fill_next_scalars(&mut self, output: &mut [F]) -> crate::ProofResult<()> {
-        let mut buf = [0u8; N];
-        for o in output.iter_mut() {
-            self.fill_next_bytes(&mut buf)?;
-            *o = F::from_repr_vartime(buf).ok_or(ProofError::SerializationError)?;
-        }
-        Ok(())
+        read_scalars(self, output)
+    }
+}
+
+impl<F, T, M, const N: usize> FieldReader<F> for LayeredTranscript<T, M>
+where
+    F: PrimeField<Repr = [u8; N]>,
+    T: UnitTranscript<u8> + ByteReader,
+    M: TranscriptMiddleware<u8>,
+{
+    fn fill_next_scalars(&mut self, output: &mut [F]) -> crate::ProofResult<()> {
+        read_scalars(self, output)
     }
 }
@@ -1,8 +1,12 @@
-use group::{ff::PrimeField, Group, GroupEncoding};
+use group::{
+    ff::{Field, PrimeField},
+    Group, GroupEncoding,
+};
 use rand::{CryptoRng, RngCore};
 
-use super::{FieldPublic, FieldWriter, GroupPublic, GroupWriter};
-use crate::{ByteWriter, DuplexHash, Merlin, ProofResult};
+use super::{FieldPublic, FieldWriter, GroupPublic, GroupWriter, PedersenOpening, PedersenWriter};
+use crate::middleware::{LayeredTranscript, TranscriptMiddleware};
+use crate::{BytePublic, ByteWriter, DuplexHash, Merlin, ProofResult, UnitTranscript};
 
 impl<F, H, R> FieldWriter<F> for Merlin<H, u8, R>
 where
@@ -11,8 +15,8 @@ where
     R: RngCore + CryptoRng,
 {
     fn add_scalars(&mut self, input: &[F]) -> ProofResult<()> {
-        let serialized = self.public_scalars(input);
-        self.transcript.extend(serialized?);
+        let serialized = self.public_scalars(input)?;
+        self.write_message(&serialized);
         Ok(())
     }
 }
@@ -26,10 +30,7 @@ where
 {
     type Repr = Vec<u8>;
     fn public_points(&mut self, input: &[G]) -> crate::ProofResult<Self::Repr> {
-        let mut buf = Vec::new();
-        for p in input.iter() {
-            buf.extend_from_slice(&<G as GroupEncoding>::to_bytes(p).as_ref());
-        }
+        let buf = points_to_bytes(input);
         self.add_bytes(&buf)?;
         Ok(buf)
     }
@@ -43,8 +44,125 @@ where
     R: RngCore + CryptoRng,
 {
     fn add_points(&mut self, input: &[G]) -> crate::ProofResult<()> {
-        let serialized = self.public_points(input);
-        self.transcript.extend(serialized?);
+        let serialized = self.public_points(input)?;
+        self.write_message(&serialized);
         Ok(())
     }
 }
+
+/// The encodings of `input`, concatenated.
+fn points_to_bytes<G>(input: &[G]) -> Vec<u8>
+where
+    G: GroupEncoding,
+    G::Repr: AsRef<[u8]>,
+{
+    let mut buf = Vec::new();
+    for p in input.iter() {
+        buf.extend_from_slice(<G as GroupEncoding>::to_bytes(p).as_ref());
+    }
+    buf
+}
+
+/// Open each of `values` with a fresh blinder from `rng`, and commit to the openings.
+fn pedersen_openings<G>(
+    rng: &mut (impl RngCore + CryptoRng),
+    generators: &[G; 2],
+    values: &[G::Scalar],
+) -> (Vec<PedersenOpening<G::Scalar>>, Vec<G>)
+where
+    G: Group,
+    G::Scalar: PrimeField,
+{
+    let openings = values
+        .iter()
+        .map(|&value| PedersenOpening {
+            value,
+            blinder: G::Scalar::random(&mut *rng),
+        })
+        .collect::<Vec<_>>();
+    let commitments = openings
+        .iter()
+        .map(|opening| opening.commit(generators))
+        .collect();
+    (openings, commitments)
+}
+
+impl<G, H, R> PedersenWriter<G, G::Scalar> for Merlin<H, u8, R>
+where
+    G: Group + GroupEncoding,
+    G::Repr: AsRef<[u8]>,
+    G::Scalar: PrimeField,
+    H: DuplexHash,
+    R: RngCore + CryptoRng,
+{
+    fn add_pedersen_commitments(
+        &mut self,
+        generators: &[G; 2],
+        values: &[G::Scalar],
+    ) -> ProofResult<Vec<PedersenOpening<G::Scalar>>> {
+        let (openings, commitments) = pedersen_openings(self.rng(), generators, values);
+        GroupWriter::<G>::add_points(self, &commitments)?;
+        Ok(openings)
+    }
+}
+
+impl<F, T, M> FieldWriter<F> for LayeredTranscript<T, M>
+where
+    F: PrimeField,
+    T: UnitTranscript<u8> + ByteWriter,
+    M: TranscriptMiddleware<u8>,
+{
+    fn add_scalars(&mut self, input: &[F]) -> ProofResult<()> {
+        let mut buf = Vec::new();
+        input.iter().for_each(|i| buf.extend(i.to_repr().as_ref()));
+        Ok(self.add_bytes(&buf)?)
+    }
+}
+
+impl<G, T, M> GroupPublic<G> for LayeredTranscript<T, M>
+where
+    G: Group + GroupEncoding,
+    G::Repr: AsRef<[u8]>,
+    T: UnitTranscript<u8>,
+    M: TranscriptMiddleware<u8>,
+{
+    type Repr = Vec<u8>;
+
+    fn public_points(&mut self, input: &[G]) -> ProofResult<Self::Repr> {
+        let buf = points_to_bytes(input);
+        self.public_bytes(&buf)?;
+        Ok(buf)
+    }
+}
+
+impl<G, T, M> GroupWriter<G> for LayeredTranscript<T, M>
+where
+    G: Group + GroupEncoding,
+    G::Repr: AsRef<[u8]>,
+    T: UnitTranscript<u8> + ByteWriter,
+    M: TranscriptMiddleware<u8>,
+{
+    fn add_points(&mut self, input: &[G]) -> ProofResult<()> {
+        Ok(self.add_bytes(&points_to_bytes(input))?)
+    }
+}
+
+impl<G, H, R, M> PedersenWriter<G, G::Scalar> for LayeredTranscript<Merlin<H, u8, R>, M>
+where
+    G: Group + GroupEncoding,
+    G::Repr: AsRef<[u8]>,
+    G::Scalar: PrimeField,
+    H: DuplexHash,
+    R: RngCore + CryptoRng,
+    M: TranscriptMiddleware<u8>,
+{
+    fn add_pedersen_commitments(
+        &mut self,
+        generators: &[G; 2],
+        values: &[G::Scalar],
+    ) -> ProofResult<Vec<PedersenOpening<G::Scalar>>> {
+        let (openings, commitments) = pedersen_openings(self.rng(), generators, values);
+        GroupWriter::<G>::add_points(self, &commitments)?;
+        Ok(openings)
+    }
+}
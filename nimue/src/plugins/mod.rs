@@ -13,6 +13,10 @@ pub mod ark;
 /// This plugin is experimental and has not yet been thoroughly tested.
 pub mod group;
 
+#[cfg(feature = "p3")]
+/// [plonky3](https://github.com/Plonky3/Plonky3) 31-bit fields and their extensions.
+pub mod p3;
+
 /// Bits needed in order to obtain a uniformly distributed random element of `modulus_bits`
 #[allow(unused)]
 pub(super) const fn bytes_uniform_modp(modulus_bits: u32) -> usize {
@@ -54,7 +58,7 @@ pub(super) fn random_bytes_in_random_modp<const N: usize>(modulus: ark_ff::BigIn
 /// Bits needed in order to encode an element of F.
 #[allow(unused)]
 pub(super) const fn bytes_modp(modulus_bits: u32) -> usize {
-    (modulus_bits as usize + 7) / 8
+    (modulus_bits as usize).div_ceil(8)
 }
 
 /// Unit-tests for inter-operability among libraries.
@@ -0,0 +1,221 @@
+//! Interoperability with [plonky3](https://github.com/Plonky3/Plonky3) 31-bit fields, with feature flag `p3`.
+//!
+//! Without further features, the plugin does not depend on plonky3: elements are exchanged as their canonical `u32` representation
+//! (`PrimeField32::as_canonical_u32` in plonky3),
+//! and elements of a `BinomialExtensionField<F, D>` as their `D` coefficients over the base field
+//! (`as_basis_coefficients_slice`).
+//! With the `plonky3` feature, the `plonky3` module exchanges the plonky3 types themselves
+//! (any `PrimeField32`, its extensions, and the Merkle caps committed to by its MMCS) in the same encoding.
+//! This allows to Fiat-Shamir a stack mixing plonky3 and arkworks components over a single byte transcript:
+//!
+//! - field elements are encoded as 4-byte little-endian integers, and non-canonical encodings are rejected by the verifier;
+//! - commitment digests (e.g. the `[F; 8]` roots of a Poseidon2 Merkle tree MMCS) are sequences of field elements;
+//! - challenges are sampled as in the [`ark`](crate::plugins::ark) plugin: each coefficient is reduced from
+//!   128 bits more than the size of the modulus, so that it is statistically close to uniform.
+//!
+//! ```
+//! use nimue::{DefaultHash, IOPattern};
+//! use nimue::plugins::p3::{BabyBear, P3Challenges, P3IOPattern, P3Reader, P3Writer};
+//!
+//! let io = IOPattern::<DefaultHash>::new("📝");
+//! let io = P3IOPattern::<BabyBear>::add_p3_elements(io, 8, "commitment");
+//! let io = P3IOPattern::<BabyBear>::challenge_p3_extension(io, 4, "alpha");
+//!
+//! let root = [1, 2, 3, 4, 5, 6, 7, 8];
+//! let mut merlin = io.to_merlin();
+//! P3Writer::<BabyBear>::add_p3_elements(&mut merlin, &root).unwrap();
+//! let alpha: [u32; 4] = P3Challenges::<BabyBear>::challenge_p3_extension(&mut merlin).unwrap();
+//!
+//! let mut arthur = io.to_arthur(merlin.transcript());
+//! let read: [u32; 8] = P3Reader::<BabyBear>::next_p3_elements(&mut arthur).unwrap();
+//! assert_eq!(read, root);
+//! assert_eq!(P3Challenges::<BabyBear>::challenge_p3_extension(&mut arthur).unwrap(), alpha);
+//! ```
+use super::bytes_uniform_modp;
+use crate::{
+    ByteChallenges, ByteIOPattern, ByteReader, ByteWriter, IOPatternError, ProofError, ProofResult,
+};
+
+/// Codecs for the field and commitment types of plonky3.
+#[cfg(feature = "plonky3")]
+pub mod plonky3;
+
+/// A 31-bit prime field of plonky3, identified by its modulus.
+pub trait Field31 {
+    /// The modulus of the field, smaller than $2^{31}$.
+    const MODULUS: u32;
+}
+
+/// The BabyBear field, $p = 2^{31} - 2^{27} + 1$.
+///
+/// A marker selecting the modulus, not plonky3's `p3_baby_bear::BabyBear`: elements are passed as their canonical `u32`.
+#[derive(Clone, Copy, Debug)]
+pub struct BabyBear;
+
+/// The KoalaBear field, $p = 2^{31} - 2^{24} + 1$.
+///
+/// A marker selecting the modulus, not plonky3's `p3_koala_bear::KoalaBear`: elements are passed as their canonical `u32`.
+#[derive(Clone, Copy, Debug)]
+pub struct KoalaBear;
+
+/// The Mersenne-31 field, $p = 2^{31} - 1$.
+///
+/// A marker selecting the modulus, not plonky3's `p3_mersenne_31::Mersenne31`: elements are passed as their canonical `u32`.
+#[derive(Clone, Copy, Debug)]
+pub struct Mersenne31;
+
+impl Field31 for BabyBear {
+    const MODULUS: u32 = 0x7800_0001;
+}
+
+impl Field31 for KoalaBear {
+    const MODULUS: u32 = 0x7f00_0001;
+}
+
+impl Field31 for Mersenne31 {
+    const MODULUS: u32 = 0x7fff_ffff;
+}
+
+/// The size in bytes of an element on the wire.
+const ELEMENT_BYTES: usize = 4;
+
+/// The number of bytes squeezed for each challenge coefficient.
+const CHALLENGE_BYTES: usize = bytes_uniform_modp(31);
+
+/// Declare plonky3 field elements and challenges in the IO pattern.
+pub trait P3IOPattern<F: Field31> {
+    /// Send `count` field elements (e.g. 8 for a Poseidon2 digest).
+    fn add_p3_elements(self, count: usize, label: &str) -> Self;
+    /// Squeeze a challenge in the extension of degree `degree` (1 for the base field).
+    fn challenge_p3_extension(self, degree: usize, label: &str) -> Self;
+}
+
+impl<F: Field31, T: ByteIOPattern> P3IOPattern<F> for T {
+    fn add_p3_elements(self, count: usize, label: &str) -> Self {
+        self.add_bytes(count * ELEMENT_BYTES, label)
+    }
+
+    fn challenge_p3_extension(self, degree: usize, label: &str) -> Self {
+        self.challenge_bytes(degree * CHALLENGE_BYTES, label)
+    }
+}
+
+/// Add field elements modulo `modulus`, given in canonical form, to the protocol transcript.
+fn write_elements<W: ByteWriter + ?Sized>(
+    writer: &mut W,
+    input: &[u32],
+    modulus: u32,
+) -> ProofResult<()> {
+    if input.iter().any(|&x| x >= modulus) {
+        return Err(ProofError::SerializationError);
+    }
+    let bytes = input
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<_>>();
+    Ok(writer.add_bytes(&bytes)?)
+}
+
+/// Read field elements modulo `modulus` from the protocol transcript, rejecting non-canonical encodings.
+fn read_elements<R: ByteReader + ?Sized>(
+    reader: &mut R,
+    output: &mut [u32],
+    modulus: u32,
+) -> ProofResult<()> {
+    let mut bytes = vec![0u8; output.len() * ELEMENT_BYTES];
+    reader.fill_next_bytes(&mut bytes)?;
+    for (x, chunk) in output.iter_mut().zip(bytes.chunks_exact(ELEMENT_BYTES)) {
+        *x = u32::from_le_bytes(chunk.try_into().unwrap());
+        if *x >= modulus {
+            return Err(ProofError::SerializationError);
+        }
+    }
+    Ok(())
+}
+
+/// Squeeze field elements modulo `modulus`, each reduced from [`CHALLENGE_BYTES`] bytes.
+fn challenge_elements<C: ByteChallenges + ?Sized>(
+    challenger: &mut C,
+    output: &mut [u32],
+    modulus: u32,
+) -> Result<(), IOPatternError> {
+    let mut bytes = vec![0u8; output.len() * CHALLENGE_BYTES];
+    challenger.fill_challenge_bytes(&mut bytes)?;
+    for (x, chunk) in output.iter_mut().zip(bytes.chunks_exact(CHALLENGE_BYTES)) {
+        // little-endian integer, reduced modulo p
+        *x = chunk
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &b| ((acc << 8) | b as u64) % modulus as u64) as u32;
+    }
+    Ok(())
+}
+
+/// Add plonky3 field elements, in canonical form, to the protocol transcript.
+pub trait P3Writer<F: Field31>: ByteWriter {
+    /// Fails with [`ProofError::SerializationError`] if an element is not canonical.
+    fn add_p3_elements(&mut self, input: &[u32]) -> ProofResult<()> {
+        write_elements(self, input, F::MODULUS)
+    }
+}
+
+/// Read plonky3 field elements from the protocol transcript.
+pub trait P3Reader<F: Field31>: ByteReader {
+    /// Fails with [`ProofError::SerializationError`] if an element is not canonical.
+    fn fill_next_p3_elements(&mut self, output: &mut [u32]) -> ProofResult<()> {
+        read_elements(self, output, F::MODULUS)
+    }
+
+    /// Read `N` field elements, see [`P3Reader::fill_next_p3_elements`].
+    fn next_p3_elements<const N: usize>(&mut self) -> ProofResult<[u32; N]> {
+        let mut output = [0u32; N];
+        self.fill_next_p3_elements(&mut output).map(|()| output)
+    }
+}
+
+/// Squeeze challenges in a plonky3 field, or in one of its binomial extensions.
+pub trait P3Challenges<F: Field31>: ByteChallenges {
+    /// Fill `output` with the coefficients of a challenge in the extension of degree `output.len()`.
+    fn fill_challenge_p3_extension(&mut self, output: &mut [u32]) -> Result<(), IOPatternError> {
+        challenge_elements(self, output, F::MODULUS)
+    }
+
+    /// Return the `D` coefficients of a challenge in the extension of degree `D` (1 for the base field).
+    fn challenge_p3_extension<const D: usize>(&mut self) -> Result<[u32; D], IOPatternError> {
+        let mut output = [0u32; D];
+        self.fill_challenge_p3_extension(&mut output)
+            .map(|()| output)
+    }
+}
+
+impl<F: Field31, T: ByteWriter> P3Writer<F> for T {}
+impl<F: Field31, T: ByteReader> P3Reader<F> for T {}
+impl<F: Field31, T: ByteChallenges> P3Challenges<F> for T {}
+
+#[test]
+fn test_p3_elements() {
+    use crate::{DefaultHash, IOPattern};
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = P3IOPattern::<BabyBear>::add_p3_elements(io, 2, "elements");
+    let io = P3IOPattern::<BabyBear>::challenge_p3_extension(io, 4, "chal");
+
+    let mut merlin = io.to_merlin();
+    assert!(P3Writer::<BabyBear>::add_p3_elements(&mut merlin, &[BabyBear::MODULUS, 0]).is_err());
+    let mut merlin = io.to_merlin();
+    P3Writer::<BabyBear>::add_p3_elements(&mut merlin, &[BabyBear::MODULUS - 1, 0]).unwrap();
+    let chal: [u32; 4] = P3Challenges::<BabyBear>::challenge_p3_extension(&mut merlin).unwrap();
+    assert!(chal.iter().all(|&x| x < BabyBear::MODULUS));
+    assert_eq!(
+        merlin.transcript(),
+        [0x00, 0x00, 0x00, 0x78, 0x00, 0x00, 0x00, 0x00]
+    );
+
+    // BabyBear's modulus is a canonical element of Mersenne-31, but not of BabyBear
+    let mut transcript = merlin.transcript().to_vec();
+    transcript[0] = 1;
+    let mut arthur = io.to_arthur(&transcript);
+    assert!(P3Reader::<BabyBear>::next_p3_elements::<2>(&mut arthur).is_err());
+    let mut arthur = io.to_arthur(&transcript);
+    assert!(P3Reader::<Mersenne31>::next_p3_elements::<2>(&mut arthur).is_ok());
+}
@@ -0,0 +1,274 @@
+//! Elements of plonky3 fields and MMCS commitments, with feature flag `plonky3`.
+//!
+//! The encoding is that of the parent module, so that the two APIs can be mixed within a protocol:
+//! an element of a `PrimeField32` is sent as its canonical `u32`, and an element of an extension
+//! (e.g. a `BinomialExtensionField<F, D>`) as its `D` coefficients over `F`.
+//! A commitment of a Merkle tree MMCS over `F` (a [`MerkleCap`] of `[F; DIGEST_ELEMS]` digests)
+//! is sent as the sequence of the field elements of its roots.
+//!
+//! As for the parent module, traits are parametrized by the field, and are called with the fully-qualified syntax:
+//!
+//! ```
+//! use nimue::{DefaultHash, IOPattern};
+//! use nimue::plugins::p3::plonky3::{P3FieldChallenges, P3FieldIOPattern, P3FieldReader, P3FieldWriter};
+//! use p3_baby_bear::BabyBear;
+//! use p3_field::extension::BinomialExtensionField;
+//! use p3_field::PrimeCharacteristicRing;
+//! use p3_symmetric::MerkleCap;
+//!
+//! type EF = BinomialExtensionField<BabyBear, 4>;
+//!
+//! let io = IOPattern::<DefaultHash>::new("📝");
+//! let io = P3FieldIOPattern::<BabyBear>::add_p3_commitment(io, 1, 8, "commitment");
+//! let io = P3FieldIOPattern::<BabyBear, EF>::challenge_p3_fields(io, 1, "alpha");
+//! let io = P3FieldIOPattern::<BabyBear, EF>::add_p3_fields(io, 1, "evaluation");
+//!
+//! let root = MerkleCap::new(vec![[BabyBear::ONE; 8]]);
+//! let mut merlin = io.to_merlin();
+//! P3FieldWriter::<BabyBear>::add_p3_commitment(&mut merlin, &root).unwrap();
+//! let [alpha]: [EF; 1] = P3FieldChallenges::<BabyBear, EF>::challenge_p3_fields(&mut merlin).unwrap();
+//! P3FieldWriter::<BabyBear, EF>::add_p3_fields(&mut merlin, &[alpha.square()]).unwrap();
+//!
+//! let mut arthur = io.to_arthur(merlin.transcript());
+//! let commitment = P3FieldReader::<BabyBear>::next_p3_commitment::<8>(&mut arthur, 1).unwrap();
+//! assert_eq!(commitment, root);
+//! let [verifier_alpha]: [EF; 1] = P3FieldChallenges::<BabyBear, EF>::challenge_p3_fields(&mut arthur).unwrap();
+//! let [evaluation]: [EF; 1] = P3FieldReader::<BabyBear, EF>::next_p3_fields(&mut arthur).unwrap();
+//! assert_eq!(evaluation, verifier_alpha.square());
+//! ```
+use p3_field::{ExtensionField, PrimeField32};
+use p3_symmetric::MerkleCap;
+
+use super::{challenge_elements, read_elements, write_elements, CHALLENGE_BYTES, ELEMENT_BYTES};
+use crate::{
+    ByteChallenges, ByteIOPattern, ByteReader, ByteWriter, IOPatternError, ProofError, ProofResult,
+};
+
+/// Map canonical `u32`s, already checked to be smaller than the modulus, to field elements.
+fn from_canonical<F: PrimeField32>(elements: &[u32]) -> ProofResult<Vec<F>> {
+    elements
+        .iter()
+        .map(|&x| F::from_canonical_checked(x).ok_or(ProofError::SerializationError))
+        .collect()
+}
+
+/// Declare elements of a plonky3 field `F` (or of its extension `EF`), and MMCS commitments, in the IO pattern.
+pub trait P3FieldIOPattern<F: PrimeField32, EF: ExtensionField<F> = F> {
+    /// Send `count` elements of `EF`.
+    fn add_p3_fields(self, count: usize, label: &str) -> Self;
+    /// Squeeze `count` challenges in `EF`.
+    fn challenge_p3_fields(self, count: usize, label: &str) -> Self;
+    /// Send a Merkle cap of `roots` digests, each of `digest_elems` elements of `F`.
+    fn add_p3_commitment(self, roots: usize, digest_elems: usize, label: &str) -> Self;
+}
+
+impl<F, EF, T> P3FieldIOPattern<F, EF> for T
+where
+    F: PrimeField32,
+    EF: ExtensionField<F>,
+    T: ByteIOPattern,
+{
+    fn add_p3_fields(self, count: usize, label: &str) -> Self {
+        self.add_bytes(count * EF::DIMENSION * ELEMENT_BYTES, label)
+    }
+
+    fn challenge_p3_fields(self, count: usize, label: &str) -> Self {
+        self.challenge_bytes(count * EF::DIMENSION * CHALLENGE_BYTES, label)
+    }
+
+    fn add_p3_commitment(self, roots: usize, digest_elems: usize, label: &str) -> Self {
+        self.add_bytes(roots * digest_elems * ELEMENT_BYTES, label)
+    }
+}
+
+/// Add elements of a plonky3 field `F` (or of its extension `EF`), and MMCS commitments, to the protocol transcript.
+pub trait P3FieldWriter<F: PrimeField32, EF: ExtensionField<F> = F>: ByteWriter {
+    /// Add the coefficients over `F` of each element of `input`.
+    fn add_p3_fields(&mut self, input: &[EF]) -> ProofResult<()> {
+        let coefficients = input
+            .iter()
+            .flat_map(|x| x.as_basis_coefficients_slice())
+            .map(F::as_canonical_u32)
+            .collect::<Vec<_>>();
+        write_elements(self, &coefficients, F::ORDER_U32)
+    }
+
+    /// Add the roots of a Merkle cap, e.g. the commitment of a `MerkleTreeMmcs`.
+    fn add_p3_commitment<const DIGEST_ELEMS: usize>(
+        &mut self,
+        commitment: &MerkleCap<F, [F; DIGEST_ELEMS]>,
+    ) -> ProofResult<()> {
+        let elements = commitment
+            .roots()
+            .iter()
+            .flatten()
+            .map(F::as_canonical_u32)
+            .collect::<Vec<_>>();
+        write_elements(self, &elements, F::ORDER_U32)
+    }
+}
+
+/// Read elements of a plonky3 field `F` (or of its extension `EF`), and MMCS commitments, from the protocol transcript.
+pub trait P3FieldReader<F: PrimeField32, EF: ExtensionField<F> = F>: ByteReader {
+    /// Fails with [`ProofError::SerializationError`] if a coefficient is not canonical.
+    fn fill_next_p3_fields(&mut self, output: &mut [EF]) -> ProofResult<()> {
+        let mut coefficients = vec![0u32; output.len() * EF::DIMENSION];
+        read_elements(self, &mut coefficients, F::ORDER_U32)?;
+        let coefficients = from_canonical::<F>(&coefficients)?;
+        for (x, chunk) in output
+            .iter_mut()
+            .zip(coefficients.chunks_exact(EF::DIMENSION))
+        {
+            // guaranteed never to fail, since the chunk has exactly `EF::DIMENSION` coefficients
+            *x = EF::from_basis_coefficients_slice(chunk).unwrap();
+        }
+        Ok(())
+    }
+
+    /// Read `N` elements of `EF`, see [`P3FieldReader::fill_next_p3_fields`].
+    fn next_p3_fields<const N: usize>(&mut self) -> ProofResult<[EF; N]> {
+        let mut output = [EF::ZERO; N];
+        self.fill_next_p3_fields(&mut output).map(|()| output)
+    }
+
+    /// Read a Merkle cap of `roots` digests.
+    ///
+    /// Fails with [`ProofError::SerializationError`] if an element is not canonical,
+    /// or if `roots` is not a power of two.
+    fn next_p3_commitment<const DIGEST_ELEMS: usize>(
+        &mut self,
+        roots: usize,
+    ) -> ProofResult<MerkleCap<F, [F; DIGEST_ELEMS]>> {
+        if !roots.is_power_of_two() {
+            return Err(ProofError::SerializationError);
+        }
+        let mut elements = vec![0u32; roots * DIGEST_ELEMS];
+        read_elements(self, &mut elements, F::ORDER_U32)?;
+        let elements = from_canonical::<F>(&elements)?;
+        let digests = elements
+            .chunks_exact(DIGEST_ELEMS)
+            // guaranteed never to fail, since the chunk has exactly `DIGEST_ELEMS` elements
+            .map(|chunk| <[F; DIGEST_ELEMS]>::try_from(chunk).unwrap())
+            .collect();
+        Ok(MerkleCap::new(digests))
+    }
+}
+
+/// Squeeze challenges in a plonky3 field `F`, or in its extension `EF`.
+///
+/// Each coefficient over `F` is sampled as in [`P3Challenges`](super::P3Challenges).
+pub trait P3FieldChallenges<F: PrimeField32, EF: ExtensionField<F> = F>: ByteChallenges {
+    /// Fill `output` with challenges in `EF`.
+    fn fill_challenge_p3_fields(&mut self, output: &mut [EF]) -> Result<(), IOPatternError> {
+        let mut coefficients = vec![0u32; output.len() * EF::DIMENSION];
+        challenge_elements(self, &mut coefficients, F::ORDER_U32)?;
+        for (x, chunk) in output
+            .iter_mut()
+            .zip(coefficients.chunks_exact(EF::DIMENSION))
+        {
+            // coefficients are reduced modulo the order of `F`
+            *x = EF::from_basis_coefficients_fn(|i| F::from_int(chunk[i]));
+        }
+        Ok(())
+    }
+
+    /// Return `N` challenges in `EF`.
+    fn challenge_p3_fields<const N: usize>(&mut self) -> Result<[EF; N], IOPatternError> {
+        let mut output = [EF::ZERO; N];
+        self.fill_challenge_p3_fields(&mut output).map(|()| output)
+    }
+}
+
+impl<F: PrimeField32, EF: ExtensionField<F>, T: ByteWriter> P3FieldWriter<F, EF> for T {}
+impl<F: PrimeField32, EF: ExtensionField<F>, T: ByteReader> P3FieldReader<F, EF> for T {}
+impl<F: PrimeField32, EF: ExtensionField<F>, T: ByteChallenges> P3FieldChallenges<F, EF> for T {}
+
+/// The plonky3 types are encoded as their canonical `u32` representation, and sample the same challenges.
+#[test]
+fn test_p3_fields() {
+    use super::{Field31, P3Challenges, P3Writer};
+    use crate::{DefaultHash, IOPattern};
+    use p3_baby_bear::BabyBear;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_field::{BasedVectorSpace, PrimeCharacteristicRing};
+
+    type EF = BinomialExtensionField<BabyBear, 4>;
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = P3FieldIOPattern::<BabyBear, EF>::add_p3_fields(io, 1, "elements");
+    let io = P3FieldIOPattern::<BabyBear, EF>::challenge_p3_fields(io, 1, "chal");
+    let io = P3FieldIOPattern::<BabyBear>::add_p3_commitment(io, 2, 4, "commitment");
+
+    let element = EF::from_basis_coefficients_fn(|i| BabyBear::from_u32(i as u32) - BabyBear::ONE);
+    let coefficients = [super::BabyBear::MODULUS - 1, 0, 1, 2];
+    let cap = MerkleCap::new(vec![[BabyBear::ONE; 4], [BabyBear::TWO; 4]]);
+
+    let mut merlin = io.to_merlin();
+    P3FieldWriter::<BabyBear, EF>::add_p3_fields(&mut merlin, &[element]).unwrap();
+    let [chal]: [EF; 1] =
+        P3FieldChallenges::<BabyBear, EF>::challenge_p3_fields(&mut merlin).unwrap();
+    P3FieldWriter::<BabyBear>::add_p3_commitment(&mut merlin, &cap).unwrap();
+
+    // the same protocol, with canonical `u32`s
+    let mut u32_merlin = io.to_merlin();
+    P3Writer::<super::BabyBear>::add_p3_elements(&mut u32_merlin, &coefficients).unwrap();
+    let u32_chal: [u32; 4] =
+        P3Challenges::<super::BabyBear>::challenge_p3_extension(&mut u32_merlin).unwrap();
+    P3Writer::<super::BabyBear>::add_p3_elements(&mut u32_merlin, &[1, 1, 1, 1, 2, 2, 2, 2])
+        .unwrap();
+    assert_eq!(merlin.transcript(), u32_merlin.transcript());
+    assert_eq!(
+        BasedVectorSpace::<BabyBear>::as_basis_coefficients_slice(&chal)
+            .iter()
+            .map(PrimeField32::as_canonical_u32)
+            .collect::<Vec<_>>(),
+        u32_chal
+    );
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let [read]: [EF; 1] = P3FieldReader::<BabyBear, EF>::next_p3_fields(&mut arthur).unwrap();
+    assert_eq!(read, element);
+    let [verifier_chal]: [EF; 1] =
+        P3FieldChallenges::<BabyBear, EF>::challenge_p3_fields(&mut arthur).unwrap();
+    assert_eq!(verifier_chal, chal);
+    let read = P3FieldReader::<BabyBear>::next_p3_commitment::<4>(&mut arthur, 2).unwrap();
+    assert_eq!(read, cap);
+
+    // non-canonical coefficients, and caps that are not a full layer of a tree, are rejected
+    let mut transcript = merlin.transcript().to_vec();
+    transcript[..4].copy_from_slice(&super::BabyBear::MODULUS.to_le_bytes());
+    let mut arthur = io.to_arthur(&transcript);
+    assert!(P3FieldReader::<BabyBear, EF>::next_p3_fields::<1>(&mut arthur).is_err());
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let _ = P3FieldReader::<BabyBear, EF>::next_p3_fields::<1>(&mut arthur).unwrap();
+    let _ = P3FieldChallenges::<BabyBear, EF>::challenge_p3_fields::<1>(&mut arthur).unwrap();
+    assert!(P3FieldReader::<BabyBear>::next_p3_commitment::<4>(&mut arthur, 3).is_err());
+}
+
+/// The `BabyBear` marker agrees with plonky3's field, also when challenges are in the base field.
+#[test]
+fn test_p3_base_field() {
+    use super::{Field31, P3Challenges, P3IOPattern};
+    use crate::{DefaultHash, IOPattern};
+    use p3_baby_bear::BabyBear;
+    use p3_field::PrimeCharacteristicRing;
+
+    assert_eq!(super::BabyBear::MODULUS, BabyBear::ORDER_U32);
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue");
+    let io = P3FieldIOPattern::<BabyBear>::add_p3_fields(io, 2, "elements");
+    let io = P3IOPattern::<super::BabyBear>::challenge_p3_extension(io, 3, "chal");
+
+    let elements = [BabyBear::NEG_ONE, BabyBear::from_u32(42)];
+    let mut merlin = io.to_merlin();
+    P3FieldWriter::<BabyBear>::add_p3_fields(&mut merlin, &elements).unwrap();
+    let chal: [BabyBear; 3] =
+        P3FieldChallenges::<BabyBear>::challenge_p3_fields(&mut merlin).unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let read: [BabyBear; 2] = P3FieldReader::<BabyBear>::next_p3_fields(&mut arthur).unwrap();
+    assert_eq!(read, elements);
+    let u32_chal: [u32; 3] =
+        P3Challenges::<super::BabyBear>::challenge_p3_extension(&mut arthur).unwrap();
+    assert_eq!(chal.map(|x| x.as_canonical_u32()), u32_chal);
+}
@@ -17,11 +17,23 @@ macro_rules! field_traits {
                 let mut output = [F::default(); N];
                 self.fill_challenge_scalars(&mut output).map(|()| output)
             }
+
+            /// Squeeze `count` field elements, when `count` is known only at runtime.
+            fn challenge_scalars_vec(&mut self, count: usize) -> crate::ProofResult<Vec<F>> {
+                let mut output = vec![F::default(); count];
+                self.fill_challenge_scalars(&mut output).map(|()| output)
+            }
         }
 
         /// Add field elements as shared public information.
         pub trait FieldPublic<F: $Field> {
+            /// The encoding of the field elements, exactly as absorbed in the sponge.
+            /// For byte-oriented transcripts, this is the serialized bytes;
+            /// for algebraic transcripts, this is the sequence of native field elements.
             type Repr;
+
+            /// Absorb field elements without adding them to the protocol transcript.
+            /// Returns the encoding absorbed, so that it can be re-used (e.g. for auditing) without serializing twice.
             fn public_scalars(&mut self, input: &[F]) -> crate::ProofResult<Self::Repr>;
         }
 
@@ -41,6 +53,12 @@ macro_rules! field_traits {
                 let mut output = [F::default(); N];
                 self.fill_next_scalars(&mut output).map(|()| output)
             }
+
+            /// Read `count` field elements, when `count` is known only at runtime.
+            fn next_scalars_vec(&mut self, count: usize) -> crate::ProofResult<Vec<F>> {
+                let mut output = vec![F::default(); count];
+                self.fill_next_scalars(&mut output).map(|()| output)
+            }
         }
     };
 }
@@ -71,6 +89,12 @@ macro_rules! group_traits {
                 let mut output = [G::default(); N];
                 self.fill_next_points(&mut output).map(|()| output)
             }
+
+            /// Deserialize `count` group elements from the protocol transcript, when `count` is known only at runtime.
+            fn next_points_vec(&mut self, count: usize) -> $crate::ProofResult<Vec<G>> {
+                let mut output = vec![G::default(); count];
+                self.fill_next_points(&mut output).map(|()| output)
+            }
         }
 
         /// Add group elements to the protocol transcript.
@@ -81,8 +105,136 @@ macro_rules! group_traits {
             type Repr;
 
             /// Incorporate group elements into the proof without adding them to the final protocol transcript.
+            /// Returns the encoding absorbed in the sponge.
             fn public_points(&mut self, input: &[G]) -> $crate::ProofResult<Self::Repr>;
         }
+
+        /// The opening of a Pedersen commitment $C = v \cdot G + r \cdot H$:
+        /// the committed value $v$ and the blinder $r$.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct PedersenOpening<F> {
+            /// The committed value $v$, multiplying the generator $G$.
+            pub value: F,
+            /// The blinder $r$, multiplying the generator $H$. It must be uniformly random for the commitment to be hiding.
+            pub blinder: F,
+        }
+
+        impl<F: $Field> PedersenOpening<F> {
+            /// Compute the commitment to this opening under the generators $(G, H)$.
+            pub fn commit<G>(&self, generators: &[G; 2]) -> G
+            where
+                G: $Group + core::ops::Mul<F, Output = G>,
+            {
+                generators[0] * self.value + generators[1] * self.blinder
+            }
+
+            /// Check that `commitment` opens to this opening under the generators $(G, H)$.
+            pub fn verify<G>(&self, generators: &[G; 2], commitment: &G) -> $crate::ProofResult<()>
+            where
+                G: $Group + core::ops::Mul<F, Output = G>,
+            {
+                if self.commit(generators) == *commitment {
+                    Ok(())
+                } else {
+                    Err($crate::ProofError::InvalidProof)
+                }
+            }
+        }
+
+        /// Commit-then-reveal with Pedersen commitments in the IO pattern.
+        pub trait PedersenIOPattern<G: $Group, F: $Field> {
+            /// Send `count` Pedersen commitments, one group element each.
+            fn add_pedersen_commitments(self, count: usize, label: &str) -> Self;
+            /// Reveal the openings of `count` Pedersen commitments, two scalars each.
+            fn add_pedersen_openings(self, count: usize, label: &str) -> Self;
+        }
+
+        impl<G, F, T> PedersenIOPattern<G, F> for T
+        where
+            G: $Group,
+            F: $Field,
+            T: GroupIOPattern<G> + FieldIOPattern<F>,
+        {
+            fn add_pedersen_commitments(self, count: usize, label: &str) -> Self {
+                GroupIOPattern::<G>::add_points(self, count, label)
+            }
+
+            fn add_pedersen_openings(self, count: usize, label: &str) -> Self {
+                FieldIOPattern::<F>::add_scalars(self, 2 * count, label)
+            }
+        }
+
+        /// Add blinded (Pedersen-masked) values to the protocol transcript.
+        /// The openings are revealed later with [`PedersenOpeningWriter`].
+        pub trait PedersenWriter<G, F>: GroupWriter<G> + FieldWriter<F>
+        where
+            G: $Group + core::ops::Mul<F, Output = G>,
+            F: $Field,
+        {
+            /// Commit to each of `values` with a fresh blinder from the prover's private randomness,
+            /// and add the commitments to the protocol transcript.
+            /// Returns the openings, to be kept by the prover as hints.
+            fn add_pedersen_commitments(
+                &mut self,
+                generators: &[G; 2],
+                values: &[F],
+            ) -> $crate::ProofResult<Vec<PedersenOpening<F>>>;
+        }
+
+        /// Reveal the openings of Pedersen commitments in the protocol transcript.
+        pub trait PedersenOpeningWriter<F: $Field>: FieldWriter<F> {
+            /// Reveal the openings of previously added commitments.
+            fn add_pedersen_openings(
+                &mut self,
+                openings: &[PedersenOpening<F>],
+            ) -> $crate::ProofResult<()> {
+                let scalars = openings
+                    .iter()
+                    .flat_map(|opening| [opening.value, opening.blinder])
+                    .collect::<Vec<_>>();
+                self.add_scalars(&scalars)
+            }
+        }
+
+        impl<F: $Field, T: FieldWriter<F>> PedersenOpeningWriter<F> for T {}
+
+        /// Read the openings of Pedersen commitments from the protocol transcript.
+        pub trait PedersenReader<G, F>: GroupReader<G> + FieldReader<F>
+        where
+            G: $Group + Default + core::ops::Mul<F, Output = G>,
+            F: $Field,
+        {
+            /// Read the openings of `commitments`, check them against the generators $(G, H)$,
+            /// and return the committed values.
+            fn next_pedersen_openings(
+                &mut self,
+                generators: &[G; 2],
+                commitments: &[G],
+            ) -> $crate::ProofResult<Vec<F>> {
+                let scalars = self.next_scalars_vec(2 * commitments.len())?;
+                scalars
+                    .chunks_exact(2)
+                    .zip(commitments)
+                    .map(|(opening, commitment)| {
+                        let opening = PedersenOpening {
+                            value: opening[0],
+                            blinder: opening[1],
+                        };
+                        opening
+                            .verify(generators, commitment)
+                            .map(|()| opening.value)
+                    })
+                    .collect()
+            }
+        }
+
+        impl<G, F, T> PedersenReader<G, F> for T
+        where
+            G: $Group + Default + core::ops::Mul<F, Output = G>,
+            F: $Field,
+            T: GroupReader<G> + FieldReader<F>,
+        {
+        }
     };
 }
 
@@ -0,0 +1,23 @@
+//! Commonly-needed types and traits, to be glob-imported.
+//!
+//! ```
+//! use nimue::prelude::*;
+//!
+//! let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").squeeze(16, "🍷");
+//! let mut merlin = io.to_merlin();
+//! merlin.add_bytes(&[0x42]).unwrap();
+//! let challenge: [u8; 16] = merlin.challenge_bytes().unwrap();
+//!
+//! let mut arthur = io.to_arthur(merlin.transcript());
+//! assert_eq!(arthur.next_bytes().unwrap(), [0x42]);
+//! assert_eq!(arthur.challenge_bytes::<16>().unwrap(), challenge);
+//! ```
+//!
+//! Plugins have their own prelude, that also includes this one
+//! (e.g. [`plugins::ark::prelude`](crate::plugins::ark::prelude), with the `ark` feature).
+
+pub use crate::traits::*;
+pub use crate::{
+    Arthur, DefaultHash, DefaultRng, DuplexHash, IOPattern, IOPatternError, Merlin, Proof,
+    ProofError, ProofResult, Unit,
+};
@@ -0,0 +1,75 @@
+use rand::{CryptoRng, RngCore};
+
+use crate::hash::Unit;
+use crate::{Arthur, DuplexHash, IOPattern, IOPatternError, Merlin};
+
+/// A finished protocol transcript, exported from the prover.
+///
+/// Converting a [`Merlin`] into a [`Proof`] consumes the prover state, destroying its secret coins,
+/// so that no more messages can be appended to the protocol transcript once exported.
+///
+/// ```
+/// # use nimue::*;
+///
+/// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").squeeze(16, "🍷");
+/// let mut merlin = io.to_merlin();
+/// merlin.add_bytes(&[0x42]).unwrap();
+/// let challenge = merlin.challenge_bytes::<16>().unwrap();
+///
+/// let proof = Proof::try_from(merlin).unwrap();
+/// let mut arthur = proof.verifier(&io);
+/// assert_eq!(arthur.next_bytes().unwrap(), [0x42]);
+/// assert_eq!(arthur.challenge_bytes::<16>().unwrap(), challenge);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Proof(Vec<u8>);
+
+impl Proof {
+    /// Create a [`Arthur`] instance verifying this proof against the IO Pattern `io_pattern`.
+    pub fn verifier<'a, H, U>(&'a self, io_pattern: &IOPattern<H, U>) -> Arthur<'a, H, U>
+    where
+        U: Unit,
+        H: DuplexHash<U>,
+    {
+        io_pattern.to_arthur(&self.0)
+    }
+
+    /// Return the protocol transcript.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Return the protocol transcript, consuming the proof.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl<H, U, R> TryFrom<Merlin<H, U, R>> for Proof
+where
+    U: Unit,
+    H: DuplexHash<U>,
+    R: RngCore + CryptoRng,
+{
+    type Error = IOPatternError;
+
+    /// Export the protocol transcript of `merlin`, destroying the prover state.
+    ///
+    /// Fails if the prover messages are written to a sink (see [`Merlin::with_sink`]).
+    fn try_from(merlin: Merlin<H, U, R>) -> Result<Self, Self::Error> {
+        merlin.try_transcript()?;
+        Ok(Self(merlin.transcript))
+    }
+}
+
+impl From<Vec<u8>> for Proof {
+    fn from(transcript: Vec<u8>) -> Self {
+        Self(transcript)
+    }
+}
+
+impl AsRef<[u8]> for Proof {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
@@ -0,0 +1,123 @@
+//! Proofs of possession for BLS public keys.
+//!
+//! In BLS signature schemes, public keys $X = x \cdot Q$ live in $\mathbb{G}_2$.
+//! To prevent rogue-key attacks when aggregating signatures, each signer publishes together with its public key
+//! a proof of knowledge of the secret key $x$.
+//! This module implements such a proof as a Schnorr protocol over $\mathbb{G}_2$:
+//!
+//! - P -> V: $K = k \cdot Q$, a commitment (point in $\mathbb{G}_2$);
+//! - V -> P: $c$, a challenge (scalar);
+//! - P -> V: $r = k + c x$, a response (scalar).
+//!
+//! The verifier checks that $r \cdot Q = K + c \cdot X$.
+//!
+//! ```
+//! use ark_bls12_381::Bls12_381;
+//! use nimue::{DefaultHash, IOPattern};
+//! use nimue::protocols::bls_pop::{self, BlsPopIOPattern};
+//!
+//! let io: IOPattern<DefaultHash> = BlsPopIOPattern::<Bls12_381>::new_bls_pop("nimue::bls_pop");
+//! let (sk, pk) = bls_pop::keygen::<Bls12_381>(&mut rand::rngs::OsRng);
+//!
+//! let mut merlin = io.to_merlin();
+//! let proof = bls_pop::prove::<Bls12_381, _>(&mut merlin, sk, pk).unwrap();
+//!
+//! let mut arthur = io.to_arthur(proof);
+//! assert!(bls_pop::verify::<Bls12_381, _>(&mut arthur, pk).is_ok());
+//! ```
+use ark_ec::pairing::Pairing;
+use ark_ec::PrimeGroup;
+use ark_ff::UniformRand;
+use rand::{CryptoRng, RngCore};
+
+use crate::plugins::ark::{
+    FieldChallenges, FieldIOPattern, FieldReader, FieldWriter, GroupIOPattern, GroupPublic,
+    PairingReader, PairingWriter,
+};
+use crate::{Arthur, DuplexHash, IOPattern, Merlin, ProofError, ProofResult};
+
+/// Extend the IO pattern with a BLS proof of possession.
+pub trait BlsPopIOPattern<P: Pairing> {
+    /// Shortcut: create a new proof of possession with statement + proof.
+    fn new_bls_pop(domsep: &str) -> Self;
+
+    /// Add the statement: the public key in $\mathbb{G}_2$.
+    fn add_bls_pop_statement(self) -> Self;
+
+    /// Add the Schnorr protocol over $\mathbb{G}_2$.
+    fn add_bls_pop_io(self) -> Self;
+}
+
+impl<P, H> BlsPopIOPattern<P> for IOPattern<H>
+where
+    P: Pairing,
+    H: DuplexHash,
+    IOPattern<H>: GroupIOPattern<P::G2> + FieldIOPattern<P::ScalarField>,
+{
+    fn new_bls_pop(domsep: &str) -> Self {
+        let io = BlsPopIOPattern::<P>::add_bls_pop_statement(IOPattern::new(domsep));
+        BlsPopIOPattern::<P>::add_bls_pop_io(io)
+    }
+
+    fn add_bls_pop_statement(self) -> Self {
+        GroupIOPattern::<P::G2>::add_points(self, 1, "public key (X)").ratchet()
+    }
+
+    fn add_bls_pop_io(self) -> Self {
+        let io = GroupIOPattern::<P::G2>::add_points(self, 1, "commitment (K)");
+        let io = FieldIOPattern::<P::ScalarField>::challenge_scalars(io, 1, "challenge (c)");
+        FieldIOPattern::<P::ScalarField>::add_scalars(io, 1, "response (r)")
+    }
+}
+
+/// Generate a BLS key pair: a secret key $x$ and the public key $x \cdot Q$ in $\mathbb{G}_2$.
+pub fn keygen<P: Pairing>(rng: &mut (impl RngCore + CryptoRng)) -> (P::ScalarField, P::G2) {
+    let sk = P::ScalarField::rand(rng);
+    (sk, P::G2::generator() * sk)
+}
+
+/// Prove possession of the secret key `sk` for the public key `pk`.
+///
+/// The public key is absorbed (and the state ratcheted) before running the protocol.
+#[allow(non_snake_case)]
+pub fn prove<P, H>(merlin: &mut Merlin<H>, sk: P::ScalarField, pk: P::G2) -> ProofResult<&[u8]>
+where
+    P: Pairing,
+    H: DuplexHash,
+    Merlin<H>: PairingWriter<P> + FieldWriter<P::ScalarField> + FieldChallenges<P::ScalarField>,
+{
+    GroupPublic::<P::G2>::public_points(merlin, &[pk])?;
+    merlin.ratchet()?;
+
+    let k = P::ScalarField::rand(merlin.rng());
+    let K = P::G2::generator() * k;
+    merlin.add_g2_points(&[K])?;
+    let [c] = merlin.challenge_scalars()?;
+    merlin.add_scalars(&[k + c * sk])?;
+
+    Ok(merlin.transcript())
+}
+
+/// Verify a proof of possession for the public key `pk`.
+#[allow(non_snake_case)]
+pub fn verify<P, H>(arthur: &mut Arthur<H>, pk: P::G2) -> ProofResult<()>
+where
+    P: Pairing,
+    H: DuplexHash,
+    for<'a> Arthur<'a, H>:
+        PairingReader<P> + FieldReader<P::ScalarField> + FieldChallenges<P::ScalarField>,
+{
+    GroupPublic::<P::G2>::public_points(arthur, &[pk])?;
+    arthur.ratchet()?;
+
+    let mut K = [P::G2::default()];
+    arthur.fill_next_g2_points(&mut K)?;
+    let [c] = arthur.challenge_scalars()?;
+    let [r] = arthur.next_scalars()?;
+
+    if P::G2::generator() * r == K[0] + pk * c {
+        Ok(())
+    } else {
+        Err(ProofError::InvalidProof)
+    }
+}
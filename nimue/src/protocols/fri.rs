@@ -0,0 +1,197 @@
+//! FRI (and STIR, WHIR) folding verifier building blocks.
+//!
+//! This module contains the transcript plumbing of a FRI-like low-degree test,
+//! leaving the polynomial arithmetic and the Merkle openings to the caller:
+//!
+//! - commit phase, repeated for each round: P -> V: the Merkle root of the folded codeword; V -> P: the folding randomness $\alpha_i$ (scalar);
+//! - P -> V: the coefficients of the final polynomial (scalars);
+//! - V -> P: the query indices, uniformly distributed over the initial evaluation domain.
+//!
+//! The Merkle hash `D` is any [`digest::Digest`]: roots are absorbed as its raw output bytes.
+//!
+//! ```
+//! use ark_bls12_381::Fr;
+//! use nimue::{DefaultHash, IOPattern};
+//! use nimue::protocols::fri::{self, FriIOPattern, FriParameters};
+//!
+//! let params = FriParameters::new(10, 1, 3, 4, 8);
+//! let io: IOPattern<DefaultHash> = FriIOPattern::<Fr, sha2::Sha256>::new_fri("nimue::fri", &params);
+//!
+//! let mut merlin = io.to_merlin();
+//! for i in 0..params.num_rounds {
+//!     // in practice, the root of the Merkle tree over the i-th folded codeword.
+//!     let root = <sha2::Sha256 as sha2::Digest>::digest([i as u8]);
+//!     let _alpha: Fr = fri::commit_round::<Fr, sha2::Sha256, _>(&mut merlin, &root).unwrap();
+//! }
+//! fri::add_final_poly(&mut merlin, &[Fr::from(1); 8]).unwrap();
+//! let queries = fri::challenge_queries(&mut merlin, &params).unwrap();
+//!
+//! let mut arthur = io.to_arthur(merlin.transcript());
+//! let commitments = fri::read_commitments::<Fr, sha2::Sha256, _>(&mut arthur, &params).unwrap();
+//! assert_eq!(commitments.queries, queries);
+//! ```
+use ark_ff::Field;
+use digest::{Digest, Output};
+
+use crate::plugins::ark::{
+    ByteIOPattern, ByteReader, ByteWriter, FieldChallenges, FieldIOPattern, FieldReader,
+    FieldWriter,
+};
+use crate::{ChallengeMod, ChallengeModIOPattern, DuplexHash, IOPattern, ProofResult};
+
+/// The shape of a FRI proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FriParameters {
+    /// The initial evaluation domain has size $2^{\text{log\_domain\_size}}$.
+    pub log_domain_size: usize,
+    /// Each round folds the domain by a factor $2^{\text{log\_folding\_factor}}$.
+    pub log_folding_factor: usize,
+    /// The number of folding rounds, that is, the number of Merkle roots sent by the prover.
+    pub num_rounds: usize,
+    /// The number of query indices squeezed by the verifier.
+    pub num_queries: usize,
+    /// The number of coefficients of the final polynomial.
+    pub final_poly_len: usize,
+}
+
+impl FriParameters {
+    /// Create new FRI parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the domain is too large to be indexed with a `u64`,
+    /// or if it is too small for `num_rounds` foldings.
+    pub fn new(
+        log_domain_size: usize,
+        log_folding_factor: usize,
+        num_rounds: usize,
+        num_queries: usize,
+        final_poly_len: usize,
+    ) -> Self {
+        assert!(log_domain_size < 64);
+        assert!(num_rounds * log_folding_factor <= log_domain_size);
+        Self {
+            log_domain_size,
+            log_folding_factor,
+            num_rounds,
+            num_queries,
+            final_poly_len,
+        }
+    }
+
+    /// The size of the evaluation domain after `round` foldings.
+    pub fn domain_size(&self, round: usize) -> usize {
+        1 << (self.log_domain_size - round * self.log_folding_factor)
+    }
+}
+
+/// The prover messages and verifier challenges of a FRI proof, as read by the verifier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FriCommitments<F: Field, D: Digest> {
+    /// The Merkle root of each folded codeword.
+    pub roots: Vec<Output<D>>,
+    /// The folding randomness of each round.
+    pub folding_randomness: Vec<F>,
+    /// The coefficients of the final polynomial.
+    pub final_poly: Vec<F>,
+    /// The query indices, over the initial evaluation domain.
+    pub queries: Vec<usize>,
+}
+
+/// Extend the IO pattern with the FRI commit and query phases.
+pub trait FriIOPattern<F: Field, D: Digest> {
+    /// Shortcut: create a new FRI proof with the given parameters.
+    fn new_fri(domsep: &str, params: &FriParameters) -> Self;
+
+    /// Add the commit phase, the final polynomial, and the query phase.
+    fn add_fri(self, params: &FriParameters) -> Self;
+}
+
+impl<F, D, H> FriIOPattern<F, D> for IOPattern<H>
+where
+    F: Field,
+    D: Digest,
+    H: DuplexHash,
+    IOPattern<H>: FieldIOPattern<F>,
+{
+    fn new_fri(domsep: &str, params: &FriParameters) -> Self {
+        FriIOPattern::<F, D>::add_fri(IOPattern::new(domsep), params)
+    }
+
+    fn add_fri(mut self, params: &FriParameters) -> Self {
+        for _ in 0..params.num_rounds {
+            self = self.add_bytes(<D as Digest>::output_size(), "merkle-root");
+            self = FieldIOPattern::<F>::challenge_scalars(self, 1, "folding-randomness");
+        }
+        self = FieldIOPattern::<F>::add_scalars(self, params.final_poly_len, "final-poly");
+        for _ in 0..params.num_queries {
+            self = self.challenge_mod(params.domain_size(0) as u64, "query");
+        }
+        self
+    }
+}
+
+/// Commit phase (prover): absorb the Merkle root of the folded codeword and return the folding randomness.
+pub fn commit_round<F, D, T>(merlin: &mut T, root: &Output<D>) -> ProofResult<F>
+where
+    F: Field,
+    D: Digest,
+    T: ByteWriter + FieldChallenges<F>,
+{
+    merlin.add_bytes(root)?;
+    let [alpha] = merlin.challenge_scalars()?;
+    Ok(alpha)
+}
+
+/// Send the coefficients of the final polynomial.
+pub fn add_final_poly<F, T>(merlin: &mut T, coeffs: &[F]) -> ProofResult<()>
+where
+    F: Field,
+    T: FieldWriter<F>,
+{
+    merlin.add_scalars(coeffs)
+}
+
+/// Query phase: squeeze `params.num_queries` indices over the initial evaluation domain.
+///
+/// The indices are statistically close to uniform, and may repeat.
+pub fn challenge_queries<T: ChallengeMod>(
+    transcript: &mut T,
+    params: &FriParameters,
+) -> ProofResult<Vec<usize>> {
+    let modulus = params.domain_size(0) as u64;
+    (0..params.num_queries)
+        .map(|_| Ok(transcript.challenge_mod(modulus)? as usize))
+        .collect()
+}
+
+/// Read the roots and the final polynomial of a FRI proof, and compute the verifier's challenges.
+///
+/// The Merkle openings at the query indices are left to the caller.
+pub fn read_commitments<F, D, T>(
+    arthur: &mut T,
+    params: &FriParameters,
+) -> ProofResult<FriCommitments<F, D>>
+where
+    F: Field,
+    D: Digest,
+    T: ByteReader + FieldReader<F> + FieldChallenges<F> + ChallengeMod,
+{
+    let mut roots = Vec::with_capacity(params.num_rounds);
+    let mut folding_randomness = Vec::with_capacity(params.num_rounds);
+    for _ in 0..params.num_rounds {
+        let mut root = Output::<D>::default();
+        arthur.fill_next_bytes(&mut root)?;
+        let [alpha] = arthur.challenge_scalars()?;
+        roots.push(root);
+        folding_randomness.push(alpha);
+    }
+    let final_poly = arthur.next_scalars_vec(params.final_poly_len)?;
+    let queries = challenge_queries(arthur, params)?;
+    Ok(FriCommitments {
+        roots,
+        folding_randomness,
+        final_poly,
+        queries,
+    })
+}
@@ -0,0 +1,248 @@
+//! Bulletproofs inner-product argument.
+//!
+//! Bulletproofs allow to prove that a vector commitment has the following form
+//!
+//! $$
+//! C = \langle a, G \rangle + \langle b, H \rangle + \langle a, b \rangle U
+//! $$
+//!
+//! for vectors $a, b$ of length $n$, a power of two.
+//! The proof consists of $2 \log_2 n$ group elements and 2 scalars.
+//! Prover and verifier fold the vectors iteratively: the stack does not grow with $n$.
+//!
+//! ```
+//! use ark_bls12_381::{Fr, G1Projective as G};
+//! use nimue::{DefaultHash, IOPattern};
+//! use nimue::protocols::ipa::{self, Generators, IpaIOPattern};
+//!
+//! let size = 8;
+//! let io: IOPattern<DefaultHash> = IpaIOPattern::<G>::new_ipa("nimue::ipa", size);
+//! let generators = Generators::<G>::rand(size, &mut rand::rngs::OsRng);
+//!
+//! let a = (0..size as u64).map(Fr::from).collect::<Vec<_>>();
+//! let b = (0..size as u64).map(|i| Fr::from(i + 42)).collect::<Vec<_>>();
+//! let statement = generators.commit(&a, &b);
+//!
+//! let mut merlin = io.to_merlin();
+//! let proof = ipa::prove(&mut merlin, &generators, &statement, (&a, &b)).unwrap();
+//!
+//! let mut arthur = io.to_arthur(proof);
+//! assert!(ipa::verify(&mut arthur, &generators, &statement).is_ok());
+//! ```
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{Field, UniformRand};
+use rand::{CryptoRng, RngCore};
+
+use crate::plugins::ark::{
+    FieldChallenges, FieldIOPattern, FieldReader, FieldWriter, GroupIOPattern, GroupPublic,
+    GroupReader, GroupWriter,
+};
+use crate::{Arthur, DuplexHash, IOPattern, Merlin, ProofError, ProofResult};
+
+/// The generators for the vectors $a$, $b$, and for their inner product.
+#[derive(Clone, Debug)]
+pub struct Generators<G: CurveGroup> {
+    /// The generators $G$ committing to $a$, one per element.
+    pub g: Vec<G::Affine>,
+    /// The generators $H$ committing to $b$, one per element, as many as in [`Generators::g`].
+    pub h: Vec<G::Affine>,
+    /// The generator $U$ committing to the inner product $\langle a, b \rangle$.
+    pub u: G::Affine,
+}
+
+impl<G: CurveGroup> Generators<G> {
+    /// Create a new set of generators.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `g` and `h` have different lengths, or if their length is not a power of two.
+    pub fn new(g: Vec<G::Affine>, h: Vec<G::Affine>, u: G::Affine) -> Self {
+        assert_eq!(g.len(), h.len());
+        assert!(g.len().is_power_of_two());
+        Self { g, h, u }
+    }
+
+    /// Sample random generators for vectors of length `len`.
+    pub fn rand(len: usize, rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        let g = (0..len).map(|_| G::Affine::rand(rng)).collect();
+        let h = (0..len).map(|_| G::Affine::rand(rng)).collect();
+        Self::new(g, h, G::Affine::rand(rng))
+    }
+
+    /// The length of the vectors committed.
+    pub fn len(&self) -> usize {
+        self.g.len()
+    }
+
+    /// Whether there are no generators (never the case for generators built with [`Generators::new`]).
+    pub fn is_empty(&self) -> bool {
+        self.g.is_empty()
+    }
+
+    /// Compute the commitment $\langle a, G \rangle + \langle b, H \rangle + \langle a, b \rangle U$.
+    pub fn commit(&self, a: &[G::ScalarField], b: &[G::ScalarField]) -> G {
+        G::msm_unchecked(&self.g, a) + G::msm_unchecked(&self.h, b) + self.u * dot_prod(a, b)
+    }
+}
+
+/// Extend the IO pattern with the inner-product argument.
+pub trait IpaIOPattern<G: CurveGroup> {
+    /// Shortcut: create a new inner-product argument with statement + proof for vectors of length `len`.
+    fn new_ipa(domsep: &str, len: usize) -> Self;
+
+    /// Add the statement: the commitment $C$.
+    fn add_ipa_statement(self) -> Self;
+
+    /// Add the inner-product argument for vectors of length `len`.
+    fn add_ipa(self, len: usize) -> Self;
+}
+
+impl<G, H> IpaIOPattern<G> for IOPattern<H>
+where
+    G: CurveGroup,
+    H: DuplexHash,
+    IOPattern<H>: GroupIOPattern<G> + FieldIOPattern<G::ScalarField>,
+{
+    fn new_ipa(domsep: &str, len: usize) -> Self {
+        let io = IpaIOPattern::<G>::add_ipa_statement(IOPattern::new(domsep));
+        IpaIOPattern::<G>::add_ipa(io, len)
+    }
+
+    fn add_ipa_statement(self) -> Self {
+        GroupIOPattern::<G>::add_points(self, 1, "Pedersen commitment").ratchet()
+    }
+
+    fn add_ipa(mut self, len: usize) -> Self {
+        for _ in 0..len.ilog2() {
+            self = GroupIOPattern::<G>::add_points(self, 2, "round-message");
+            self = FieldIOPattern::<G::ScalarField>::challenge_scalars(self, 1, "challenge");
+        }
+        FieldIOPattern::<G::ScalarField>::add_scalars(self, 2, "final-message")
+    }
+}
+
+/// Prove that `statement` is a commitment to `witness` $(a, b)$ with respect to `generators`.
+///
+/// The statement is absorbed (and the state ratcheted) before running the protocol.
+///
+/// # Panics
+///
+/// Panics if the witness vectors do not have the same length as the generators.
+pub fn prove<'a, G, H>(
+    merlin: &'a mut Merlin<H>,
+    generators: &Generators<G>,
+    statement: &G,
+    witness: (&[G::ScalarField], &[G::ScalarField]),
+) -> ProofResult<&'a [u8]>
+where
+    G: CurveGroup,
+    H: DuplexHash,
+    Merlin<H>: GroupWriter<G> + FieldWriter<G::ScalarField> + FieldChallenges<G::ScalarField>,
+{
+    assert_eq!(witness.0.len(), generators.len());
+    assert_eq!(witness.1.len(), generators.len());
+
+    merlin.public_points(&[*statement])?;
+    merlin.ratchet()?;
+
+    let mut g = generators.g.clone();
+    let mut h = generators.h.clone();
+    let mut a = witness.0.to_vec();
+    let mut b = witness.1.to_vec();
+    let u = generators.u;
+
+    while a.len() != 1 {
+        let n = a.len() / 2;
+        let (a_left, a_right) = a.split_at(n);
+        let (b_left, b_right) = b.split_at(n);
+        let (g_left, g_right) = g.split_at(n);
+        let (h_left, h_right) = h.split_at(n);
+
+        let left = u * dot_prod(a_left, b_right)
+            + G::msm_unchecked(g_right, a_left)
+            + G::msm_unchecked(h_left, b_right);
+        let right = u * dot_prod(a_right, b_left)
+            + G::msm_unchecked(g_left, a_right)
+            + G::msm_unchecked(h_right, b_left);
+
+        merlin.add_points(&[left, right])?;
+        let [x]: [G::ScalarField; 1] = merlin.challenge_scalars()?;
+        let x_inv = x.inverse().expect("You just won the lottery!");
+
+        g = fold_generators(g_left, g_right, &x_inv, &x);
+        h = fold_generators(h_left, h_right, &x, &x_inv);
+        a = fold(a_left, a_right, &x, &x_inv);
+        b = fold(b_left, b_right, &x_inv, &x);
+    }
+
+    merlin.add_scalars(&[a[0], b[0]])?;
+    Ok(merlin.transcript())
+}
+
+/// Verify an inner-product argument for `statement` with respect to `generators`.
+pub fn verify<G, H>(
+    arthur: &mut Arthur<H>,
+    generators: &Generators<G>,
+    statement: &G,
+) -> ProofResult<()>
+where
+    G: CurveGroup,
+    H: DuplexHash,
+    for<'a> Arthur<'a, H>:
+        GroupReader<G> + FieldReader<G::ScalarField> + FieldChallenges<G::ScalarField>,
+{
+    arthur.public_points(&[*statement])?;
+    arthur.ratchet()?;
+
+    let mut g = generators.g.clone();
+    let mut h = generators.h.clone();
+    let u = generators.u;
+    let mut statement = *statement;
+
+    while g.len() != 1 {
+        let [left, right]: [G; 2] = arthur.next_points()?;
+        let n = g.len() / 2;
+        let (g_left, g_right) = g.split_at(n);
+        let (h_left, h_right) = h.split_at(n);
+        let [x]: [G::ScalarField; 1] = arthur.challenge_scalars()?;
+        let x_inv = x.inverse().ok_or(ProofError::InvalidProof)?;
+
+        g = fold_generators(g_left, g_right, &x_inv, &x);
+        h = fold_generators(h_left, h_right, &x, &x_inv);
+        statement += left * x.square() + right * x_inv.square();
+    }
+    let [a, b]: [G::ScalarField; 2] = arthur.next_scalars()?;
+
+    if (g[0] * a + h[0] * b + u * (a * b) - statement).is_zero() {
+        Ok(())
+    } else {
+        Err(ProofError::InvalidProof)
+    }
+}
+
+fn fold_generators<A: AffineRepr>(
+    a: &[A],
+    b: &[A],
+    x: &A::ScalarField,
+    y: &A::ScalarField,
+) -> Vec<A> {
+    let folded = a
+        .iter()
+        .zip(b.iter())
+        .map(|(&a, &b)| a * x + b * y)
+        .collect::<Vec<_>>();
+    A::Group::normalize_batch(&folded)
+}
+
+/// Computes the inner product of vectors `a` and `b`.
+fn dot_prod<F: Field>(a: &[F], b: &[F]) -> F {
+    a.iter().zip(b.iter()).map(|(&a, &b)| a * b).sum()
+}
+
+/// Folds together `(a, b)` using challenges `x` and `y`.
+fn fold<F: Field>(a: &[F], b: &[F], x: &F, y: &F) -> Vec<F> {
+    a.iter()
+        .zip(b.iter())
+        .map(|(&a, &b)| a * x + b * y)
+        .collect()
+}
@@ -0,0 +1,20 @@
+//! Fiat-Shamir protocols, ready to be used and composed.
+//!
+//! Each protocol provides an extension trait for [`IOPattern`](crate::IOPattern) describing its messages,
+//! and a prover and verifier working respectively over [`Merlin`](crate::Merlin) and [`Arthur`](crate::Arthur).
+//! The statement is absorbed by the prover and verifier functions themselves, so that the resulting transcript
+//! is bound to it.
+//! They are meant both as building blocks for larger protocols and as documentation for the [`plugins::ark`](crate::plugins::ark) traits.
+
+/// Schnorr proofs of possession for BLS public keys.
+pub mod bls_pop;
+/// FRI folding verifier building blocks.
+#[cfg(feature = "legacy")]
+pub mod fri;
+/// Bulletproofs inner-product argument.
+pub mod ipa;
+/// Schnorr signatures.
+pub mod schnorr;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,147 @@
+//! Schnorr signatures.
+//!
+//! A signature on a message $m$ under the public key $X = x \cdot G$ is a Schnorr proof of knowledge of $x$,
+//! computed over a fresh transcript that absorbs the public key and the message:
+//!
+//! - public: $X$, $m$;
+//! - P -> V: $K = k \cdot G$, a commitment (point);
+//! - V -> P: $c$, a challenge (scalar);
+//! - P -> V: $r = k + c x$, a response (scalar).
+//!
+//! The signature is the protocol transcript, that is: the compressed encoding of $K$ followed by the compressed encoding of $r$.
+//! Its size is fixed, and depends only on the group.
+//!
+//! ```
+//! use ark_bls12_381::G1Projective as G;
+//! use nimue::DefaultHash;
+//! use nimue::protocols::schnorr;
+//!
+//! let (sk, pk) = schnorr::keygen::<G>(&mut rand::rngs::OsRng);
+//! let signature = schnorr::sign::<G, DefaultHash>("my-app", sk, b"hello").unwrap();
+//! assert!(schnorr::verify::<G, DefaultHash>("my-app", pk, b"hello", &signature).is_ok());
+//! assert!(schnorr::verify::<G, DefaultHash>("my-app", pk, b"hell0", &signature).is_err());
+//! ```
+use ark_ec::CurveGroup;
+use ark_ff::UniformRand;
+use rand::{CryptoRng, RngCore};
+
+use crate::plugins::ark::{
+    ByteIOPattern, BytePublic, FieldChallenges, FieldIOPattern, FieldReader, FieldWriter,
+    GroupIOPattern, GroupPublic, GroupReader, GroupWriter,
+};
+use crate::{DefaultRng, DuplexHash, IOPattern, Merlin, ProofError, ProofResult};
+
+/// Extend the IO pattern with a Schnorr signature.
+pub trait SchnorrIOPattern<G: CurveGroup> {
+    /// Shortcut: create a new Schnorr signature for messages of `message_len` bytes.
+    fn new_schnorr_signature(domsep: &str, message_len: usize) -> Self;
+
+    /// Add the statement: the public key and the message of `message_len` bytes.
+    fn add_schnorr_statement(self, message_len: usize) -> Self;
+
+    /// Add the Schnorr protocol to the IO pattern.
+    fn add_schnorr_io(self) -> Self;
+}
+
+impl<G, H> SchnorrIOPattern<G> for IOPattern<H>
+where
+    G: CurveGroup,
+    H: DuplexHash,
+    IOPattern<H>: GroupIOPattern<G> + FieldIOPattern<G::ScalarField>,
+{
+    fn new_schnorr_signature(domsep: &str, message_len: usize) -> Self {
+        let io = SchnorrIOPattern::<G>::add_schnorr_statement(IOPattern::new(domsep), message_len);
+        SchnorrIOPattern::<G>::add_schnorr_io(io)
+    }
+
+    fn add_schnorr_statement(self, message_len: usize) -> Self {
+        GroupIOPattern::<G>::add_points(self, 1, "public key (X)")
+            .add_bytes(message_len, "message")
+            .ratchet()
+    }
+
+    fn add_schnorr_io(self) -> Self {
+        let io = GroupIOPattern::<G>::add_points(self, 1, "commitment (K)");
+        let io = FieldIOPattern::<G::ScalarField>::challenge_scalars(io, 1, "challenge (c)");
+        FieldIOPattern::<G::ScalarField>::add_scalars(io, 1, "response (r)")
+    }
+}
+
+/// Generate a key pair: a secret key $x$ and the public key $x \cdot G$.
+pub fn keygen<G: CurveGroup>(rng: &mut (impl RngCore + CryptoRng)) -> (G::ScalarField, G) {
+    let sk = G::ScalarField::rand(rng);
+    (sk, G::generator() * sk)
+}
+
+/// Sign `message` with the secret key `sk`, under the domain separator `domsep`.
+pub fn sign<G, H>(domsep: &str, sk: G::ScalarField, message: &[u8]) -> ProofResult<Vec<u8>>
+where
+    G: CurveGroup,
+    H: DuplexHash,
+    IOPattern<H>: GroupIOPattern<G> + FieldIOPattern<G::ScalarField>,
+    Merlin<H>: GroupWriter<G> + FieldWriter<G::ScalarField> + FieldChallenges<G::ScalarField>,
+{
+    sign_with_rng::<G, H, _>(domsep, sk, message, DefaultRng::default())
+}
+
+/// Sign `message` with the secret key `sk`, seeding the prover's randomness with `csrng`.
+///
+/// The nonce is derived from `csrng` together with the statement,
+/// so the signature is deterministic if `csrng` is.
+#[allow(non_snake_case)]
+pub fn sign_with_rng<G, H, R>(
+    domsep: &str,
+    sk: G::ScalarField,
+    message: &[u8],
+    csrng: R,
+) -> ProofResult<Vec<u8>>
+where
+    G: CurveGroup,
+    H: DuplexHash,
+    R: RngCore + CryptoRng,
+    IOPattern<H>: GroupIOPattern<G> + FieldIOPattern<G::ScalarField>,
+    Merlin<H, u8, R>:
+        GroupWriter<G> + FieldWriter<G::ScalarField> + FieldChallenges<G::ScalarField>,
+{
+    let io: IOPattern<H> = SchnorrIOPattern::<G>::new_schnorr_signature(domsep, message.len());
+    let mut merlin = Merlin::new(&io, csrng);
+    merlin.public_points(&[G::generator() * sk])?;
+    merlin.public_bytes(message)?;
+    merlin.ratchet()?;
+
+    let k = G::ScalarField::rand(merlin.rng());
+    merlin.add_points(&[G::generator() * k])?;
+    let [c] = merlin.challenge_scalars()?;
+    merlin.add_scalars(&[k + c * sk])?;
+
+    Ok(merlin.transcript().to_vec())
+}
+
+/// Verify the signature `signature` on `message` under the public key `pk`.
+///
+/// Signatures with trailing bytes are rejected, as for any protocol transcript read by [`Arthur`](crate::Arthur).
+#[allow(non_snake_case)]
+pub fn verify<G, H>(domsep: &str, pk: G, message: &[u8], signature: &[u8]) -> ProofResult<()>
+where
+    G: CurveGroup,
+    H: DuplexHash,
+    IOPattern<H>: GroupIOPattern<G> + FieldIOPattern<G::ScalarField>,
+    for<'a> crate::Arthur<'a, H>:
+        GroupReader<G> + FieldReader<G::ScalarField> + FieldChallenges<G::ScalarField>,
+{
+    let io: IOPattern<H> = SchnorrIOPattern::<G>::new_schnorr_signature(domsep, message.len());
+    let mut arthur = io.to_arthur(signature);
+    arthur.public_points(&[pk])?;
+    arthur.public_bytes(message)?;
+    arthur.ratchet()?;
+
+    let [K]: [G; 1] = arthur.next_points()?;
+    let [c] = arthur.challenge_scalars()?;
+    let [r] = arthur.next_scalars()?;
+
+    if G::generator() * r == K + pk * c {
+        Ok(())
+    } else {
+        Err(ProofError::InvalidProof)
+    }
+}
@@ -0,0 +1,201 @@
+use ark_bls12_381::{Bls12_381, Fr, G1Projective};
+use rand::rngs::OsRng;
+
+use super::bls_pop::{self, BlsPopIOPattern};
+#[cfg(feature = "legacy")]
+use super::fri::{self, FriIOPattern, FriParameters};
+use super::ipa::{self, Generators, IpaIOPattern};
+use super::schnorr;
+use crate::{DefaultHash, IOPattern, ProofError};
+
+#[test]
+fn test_bls_pop() {
+    let io: IOPattern<DefaultHash> = BlsPopIOPattern::<Bls12_381>::new_bls_pop("nimue::bls_pop");
+    let (sk, pk) = bls_pop::keygen::<Bls12_381>(&mut OsRng);
+    let (_, another_pk) = bls_pop::keygen::<Bls12_381>(&mut OsRng);
+
+    let mut merlin = io.to_merlin();
+    let proof = bls_pop::prove::<Bls12_381, _>(&mut merlin, sk, pk).unwrap();
+
+    let mut arthur = io.to_arthur(proof);
+    assert!(bls_pop::verify::<Bls12_381, _>(&mut arthur, pk).is_ok());
+
+    // The proof is bound to the public key.
+    let mut arthur = io.to_arthur(proof);
+    assert!(matches!(
+        bls_pop::verify::<Bls12_381, _>(&mut arthur, another_pk),
+        Err(ProofError::InvalidProof)
+    ));
+}
+
+#[test]
+fn test_ipa() {
+    for size in [1, 2, 16] {
+        let io: IOPattern<DefaultHash> = IpaIOPattern::<G1Projective>::new_ipa("nimue::ipa", size);
+        let generators = Generators::<G1Projective>::rand(size, &mut OsRng);
+        let a = (0..size as u64).map(Fr::from).collect::<Vec<_>>();
+        let b = (0..size as u64)
+            .map(|i| Fr::from(i + 42))
+            .collect::<Vec<_>>();
+        let statement = generators.commit(&a, &b);
+
+        let mut merlin = io.to_merlin();
+        let proof = ipa::prove(&mut merlin, &generators, &statement, (&a, &b)).unwrap();
+
+        let mut arthur = io.to_arthur(proof);
+        assert!(ipa::verify(&mut arthur, &generators, &statement).is_ok());
+
+        // A commitment to different vectors does not verify.
+        let another_statement = statement + generators.u;
+        let mut arthur = io.to_arthur(proof);
+        assert!(ipa::verify(&mut arthur, &generators, &another_statement).is_err());
+    }
+}
+
+#[test]
+fn test_schnorr_signature() {
+    let (sk, pk) = schnorr::keygen::<G1Projective>(&mut OsRng);
+    let signature =
+        schnorr::sign::<G1Projective, DefaultHash>("nimue::schnorr", sk, b"msg").unwrap();
+    // compressed point (48 bytes) followed by a compressed scalar (32 bytes).
+    assert_eq!(signature.len(), 48 + 32);
+
+    let verify = |pk, msg: &[u8], sig: &[u8]| {
+        schnorr::verify::<G1Projective, DefaultHash>("nimue::schnorr", pk, msg, sig)
+    };
+    assert!(verify(pk, b"msg", &signature).is_ok());
+    assert!(verify(pk, b"msh", &signature).is_err());
+    assert!(verify(pk, b"message", &signature).is_err());
+    assert!(verify(pk + pk, b"msg", &signature).is_err());
+    assert!(verify(pk, b"msg", &[&signature[..], &[0]].concat()).is_err());
+    assert!(verify(pk, b"msg", &signature[..79]).is_err());
+}
+
+/// Batch Schnorr proofs of knowledge, with a single challenge shared by all instances.
+#[test]
+#[allow(non_snake_case)]
+fn test_batched_schnorr() {
+    use crate::plugins::ark::prelude::*;
+    use crate::BatchedStatement;
+    use ark_ec::PrimeGroup;
+    use ark_ff::UniformRand;
+
+    let batch = BatchedStatement::new("schnorr", 3);
+    let io = batch.io_pattern(
+        IOPattern::<DefaultHash>::new("nimue::batched-schnorr"),
+        |io, _| {
+            let io = GroupIOPattern::<G1Projective>::add_points(io, 1, "public key (X)");
+            GroupIOPattern::<G1Projective>::add_points(io, 1, "commitment (K)")
+        },
+        |io| FieldIOPattern::<Fr>::challenge_scalars(io, 1, "challenge (c)"),
+        |io, _| FieldIOPattern::<Fr>::add_scalars(io, 1, "response (r)"),
+    );
+    let G = G1Projective::generator();
+    let keys = (0..3)
+        .map(|_| schnorr::keygen::<G1Projective>(&mut OsRng))
+        .collect::<Vec<_>>();
+
+    let mut merlin = io.to_merlin();
+    batch
+        .run(
+            &mut merlin,
+            &keys,
+            |merlin, &(_, X)| {
+                merlin.public_points(&[X])?;
+                let k = Fr::rand(merlin.rng());
+                merlin.add_points(&[G * k])?;
+                Ok(k)
+            },
+            |merlin| Ok(merlin.challenge_scalars::<1>()?[0]),
+            |merlin, &(x, _), k, c: &Fr| merlin.add_scalars(&[k + *c * x]),
+        )
+        .unwrap();
+
+    let verify = |keys: &[(Fr, G1Projective)], transcript: &[u8]| {
+        batch.run(
+            &mut io.to_arthur(transcript),
+            keys,
+            |arthur, &(_, X)| {
+                arthur.public_points(&[X])?;
+                let [K]: [G1Projective; 1] = arthur.next_points()?;
+                Ok(K)
+            },
+            |arthur| Ok(arthur.challenge_scalars::<1>()?[0]),
+            |arthur, &(_, X), K, c: &Fr| {
+                let [r]: [Fr; 1] = arthur.next_scalars()?;
+                match G * r == K + X * c {
+                    true => Ok(()),
+                    false => Err(ProofError::InvalidProof),
+                }
+            },
+        )
+    };
+    assert!(verify(&keys, merlin.transcript()).is_ok());
+    let mut swapped = keys.clone();
+    swapped.swap(0, 1);
+    assert!(verify(&swapped, merlin.transcript()).is_err());
+    assert!(verify(&keys[..2], merlin.transcript()).is_err());
+}
+
+/// Known-answer test: the signature layout and the transcript are stable.
+#[test]
+fn test_schnorr_signature_vector() {
+    use ark_ec::PrimeGroup;
+    use rand::SeedableRng;
+
+    let sk = Fr::from(42u64);
+    let rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+    let signature =
+        schnorr::sign_with_rng::<G1Projective, DefaultHash, _>("nimue::schnorr", sk, b"msg", rng)
+            .unwrap();
+    assert_eq!(
+        hex::encode(&signature),
+        concat!(
+            "8c766decd5794750a6f0fe195ba8842a48ea1dc0294513408a3b831553f71bee741969b3c59a2f4e10c52935e88fd478",
+            "331d94a68fc3c89be239f7ff20b7f999a985397f98b789b8c566b064d0b1a06e"
+        )
+    );
+    let pk = G1Projective::generator() * sk;
+    assert!(
+        schnorr::verify::<G1Projective, DefaultHash>("nimue::schnorr", pk, b"msg", &signature)
+            .is_ok()
+    );
+}
+
+#[cfg(feature = "legacy")]
+#[test]
+fn test_fri_transcript() {
+    use sha2::{Digest, Sha256};
+
+    let params = FriParameters::new(12, 2, 4, 16, 4);
+    let io: IOPattern<DefaultHash> = FriIOPattern::<Fr, Sha256>::new_fri("nimue::fri", &params);
+    let roots = (0..params.num_rounds as u8)
+        .map(|i| Sha256::digest([i]))
+        .collect::<Vec<_>>();
+    let final_poly = [Fr::from(1), Fr::from(2), Fr::from(3), Fr::from(4)];
+
+    let mut merlin = io.to_merlin();
+    let folding_randomness = roots
+        .iter()
+        .map(|root| fri::commit_round::<Fr, Sha256, _>(&mut merlin, root).unwrap())
+        .collect::<Vec<_>>();
+    fri::add_final_poly(&mut merlin, &final_poly).unwrap();
+    let queries = fri::challenge_queries(&mut merlin, &params).unwrap();
+    assert!(queries.iter().all(|&q| q < params.domain_size(0)));
+    assert_eq!(params.domain_size(params.num_rounds), 16);
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let commitments = fri::read_commitments::<Fr, Sha256, _>(&mut arthur, &params).unwrap();
+    assert_eq!(commitments.roots, roots);
+    assert_eq!(commitments.folding_randomness, folding_randomness);
+    assert_eq!(commitments.final_poly, final_poly);
+    assert_eq!(commitments.queries, queries);
+
+    // Tampering with a root changes all subsequent challenges.
+    let mut transcript = merlin.transcript().to_vec();
+    transcript[0] ^= 1;
+    let mut arthur = io.to_arthur(&transcript);
+    let commitments = fri::read_commitments::<Fr, Sha256, _>(&mut arthur, &params).unwrap();
+    assert_ne!(commitments.folding_randomness[0], folding_randomness[0]);
+    assert_ne!(commitments.queries, queries);
+}
@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How much of the prover and verifier states is shown by their [`Debug`](core::fmt::Debug) implementations,
+/// and by the errors and logs they produce.
+///
+/// The policy is crate-wide and applies to [`Safe`](crate::Safe), and thus to [`Merlin`](crate::Merlin),
+/// [`Arthur`](crate::Arthur), and the other states wrapping a [`Safe`](crate::Safe) sponge, and to [`IOPattern`](crate::IOPattern).
+/// Errors raised when the protocol deviates from the IO Pattern (see [`IOPatternError`](crate::IOPatternError)),
+/// the failures reported by verifiers (see [`OpFailure`](crate::OpFailure)), and logs follow the same level.
+/// The sponge state and the protocol transcript are never shown, whatever the level.
+///
+/// The default level is [`RedactionLevel::Labels`] in debug builds (e.g. in tests),
+/// and [`RedactionLevel::Progress`] in release builds, so that production logs do not leak the naming of the protocol.
+///
+/// ```
+/// # use nimue::*;
+///
+/// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").squeeze(16, "🍷");
+/// let mut merlin = io.to_merlin();
+/// merlin.add_bytes(&[0x42]).unwrap();
+///
+/// RedactionLevel::Labels.set_global();
+/// assert_eq!(format!("{:?}", merlin), r#"SAFE sponge at op 1/2, after "🍝", with IO: [Squeeze(16) "🍷"]"#);
+/// RedactionLevel::Progress.set_global();
+/// assert_eq!(format!("{:?}", merlin), "SAFE sponge at op 1/2, with IO: [Squeeze(16)]");
+/// RedactionLevel::Opaque.set_global();
+/// assert_eq!(format!("{:?}", merlin), "SAFE sponge");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum RedactionLevel {
+    /// Show nothing but the type.
+    Opaque = 0,
+    /// Show the progress through the IO Pattern: the operations completed, and the remaining ones without their labels.
+    /// IO Patterns are shown by their digest.
+    Progress = 1,
+    /// Also show the labels of the IO Pattern.
+    Labels = 2,
+}
+
+const DEFAULT_LEVEL: RedactionLevel = if cfg!(debug_assertions) {
+    RedactionLevel::Labels
+} else {
+    RedactionLevel::Progress
+};
+
+static LEVEL: AtomicU8 = AtomicU8::new(DEFAULT_LEVEL as u8);
+
+impl RedactionLevel {
+    /// The current crate-wide level.
+    pub fn global() -> Self {
+        match LEVEL.load(Ordering::Relaxed) {
+            0 => Self::Opaque,
+            1 => Self::Progress,
+            _ => Self::Labels,
+        }
+    }
+
+    /// Set the crate-wide level, e.g. once at the startup of a service.
+    pub fn set_global(self) {
+        LEVEL.store(self as u8, Ordering::Relaxed);
+    }
+}
+
+impl Default for RedactionLevel {
+    fn default() -> Self {
+        DEFAULT_LEVEL
+    }
+}
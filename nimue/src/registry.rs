@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+
+use crate::errors::IOPatternError;
+use crate::hash::{DuplexHash, Unit};
+use crate::{Arthur, IOPattern};
+
+/// A family of related protocols of the same application, each with its own domain separator.
+///
+/// The domain separator of a protocol is derived from the application label, the protocol identifier, and its version,
+/// as `{application}/{protocol}/v{version}`.
+/// Distinct protocols have distinct domain separators, hence distinct IO Patterns and IVs.
+///
+/// ```
+/// # use nimue::*;
+///
+/// let mut registry = ProtocolRegistry::<DefaultHash>::new("zkapp.com");
+/// registry.register("transfer", 1, |io| io.absorb(32, "commitment").squeeze(16, "challenge")).unwrap();
+/// registry.register("transfer", 2, |io| io.absorb(48, "commitment").squeeze(16, "challenge")).unwrap();
+/// assert!(registry.register("transfer", 2, |io| io).is_err());
+///
+/// let io = registry.get("transfer", 1).unwrap();
+/// assert!(io.as_bytes().starts_with(b"zkapp.com/transfer/v1\0"));
+/// let mut merlin = io.to_merlin();
+/// merlin.add_bytes(&[0u8; 32]).unwrap();
+///
+/// // the verifier looks up the protocol by its identifier.
+/// let mut arthur = registry.to_arthur("transfer", 1, merlin.transcript()).unwrap();
+/// assert_eq!(arthur.next_bytes().unwrap(), [0u8; 32]);
+/// ```
+#[derive(Clone)]
+pub struct ProtocolRegistry<H = crate::DefaultHash, U = u8>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+{
+    application: String,
+    protocols: BTreeMap<(String, u32), IOPattern<H, U>>,
+}
+
+impl<H, U> ProtocolRegistry<H, U>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+{
+    /// Create an empty registry for the application `application`.
+    ///
+    /// # Panics
+    ///
+    /// If `application` contains `/` or a NUL byte.
+    pub fn new(application: &str) -> Self {
+        assert!(
+            !application.contains(['/', '\0']),
+            "Application label cannot contain '/' or NUL."
+        );
+        Self {
+            application: application.to_string(),
+            protocols: BTreeMap::new(),
+        }
+    }
+
+    /// The domain separator of the protocol `protocol` at version `version`.
+    ///
+    /// # Panics
+    ///
+    /// If `protocol` contains `/`.
+    pub fn domain_separator(&self, protocol: &str, version: u32) -> String {
+        assert!(
+            !protocol.contains('/'),
+            "Protocol identifier cannot contain '/'."
+        );
+        format!("{}/{}/v{}", self.application, protocol, version)
+    }
+
+    /// Register the protocol `protocol` at version `version`,
+    /// whose IO Pattern is built by `f` from the (empty) IO Pattern with the derived domain separator.
+    ///
+    /// Fails if `protocol` contains `/` or a NUL byte, if the protocol is already registered at the same version,
+    /// or if `f` changes the domain separator.
+    pub fn register(
+        &mut self,
+        protocol: &str,
+        version: u32,
+        f: impl FnOnce(IOPattern<H, U>) -> IOPattern<H, U>,
+    ) -> Result<&IOPattern<H, U>, IOPatternError> {
+        if protocol.contains(['/', '\0']) {
+            return Err(format!(
+                "Protocol identifier {:?} cannot contain '/' or NUL",
+                protocol
+            )
+            .into());
+        }
+        let key = (protocol.to_string(), version);
+        if self.protocols.contains_key(&key) {
+            return Err(format!(
+                "Protocol {:?} already registered at version {}",
+                protocol, version
+            )
+            .into());
+        }
+        let domsep = self.domain_separator(protocol, version);
+        let io = f(IOPattern::new(&domsep));
+        if io.as_bytes().split(|&b| b == 0).next() != Some(domsep.as_bytes()) {
+            return Err("The domain separator of a registered protocol cannot be changed".into());
+        }
+        Ok(self.protocols.entry(key).or_insert(io))
+    }
+
+    /// The IO Pattern of the protocol `protocol` at version `version`, if registered.
+    pub fn get(&self, protocol: &str, version: u32) -> Option<&IOPattern<H, U>> {
+        self.protocols.get(&(protocol.to_string(), version))
+    }
+
+    /// Iterate over the registered protocols, as `(protocol, version, IO Pattern)`.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32, &IOPattern<H, U>)> {
+        self.protocols
+            .iter()
+            .map(|((protocol, version), io)| (protocol.as_str(), *version, io))
+    }
+
+    /// Create an [`Arthur`] instance verifying `transcript` for the protocol `protocol` at version `version`.
+    pub fn to_arthur<'a>(
+        &self,
+        protocol: &str,
+        version: u32,
+        transcript: &'a [u8],
+    ) -> Result<Arthur<'a, H, U>, IOPatternError> {
+        self.get(protocol, version)
+            .map(|io| io.to_arthur(transcript))
+            .ok_or_else(|| format!("Unknown protocol {:?} at version {}", protocol, version).into())
+    }
+}
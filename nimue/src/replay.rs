@@ -0,0 +1,239 @@
+//! Replay a protocol transcript against its IO Pattern.
+//!
+//! [`describe`] walks the IO Pattern over a proof (the non-interactive argument, or NARG)
+//! and returns, for each operation, the bytes read from the proof (or given as public inputs) or squeezed from the sponge,
+//! together with a running digest of the sponge state.
+//! Comparing the output with the one of another implementation pinpoints the first operation where they disagree.
+//!
+//! ```
+//! # use nimue::*;
+//!
+//! let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "🍝").squeeze(2, "🍷");
+//! let mut merlin = io.to_merlin();
+//! merlin.add_bytes(&[0x42]).unwrap();
+//! let challenge = merlin.challenge_bytes::<2>().unwrap();
+//!
+//! let events = replay::describe(io.as_bytes(), merlin.transcript()).unwrap();
+//! assert_eq!(events[0].bytes(), [0x42]);
+//! assert_eq!(events[1].bytes(), challenge);
+//! for event in events {
+//!     println!("{event}");
+//! }
+//! ```
+//!
+//! The binary `nimue-replay` prints the same events given the files containing the IO Pattern and the proof.
+use core::fmt;
+
+use crate::errors::IOPatternError;
+use crate::hash::DuplexHash;
+use crate::iopattern::{IOPattern, Op};
+use crate::safe::Safe;
+use crate::DefaultHash;
+
+/// An operation performed while replaying a transcript.
+///
+/// Each event carries the digest of the sponge state right after the operation,
+/// computed by ratcheting and squeezing 32 bytes from a copy of the sponge.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A prover message of the IO Pattern was read from the proof and absorbed.
+    Message {
+        label: String,
+        bytes: Vec<u8>,
+        digest: [u8; 32],
+    },
+    /// A public input was absorbed, see [`describe_public`].
+    Public {
+        label: String,
+        bytes: Vec<u8>,
+        digest: [u8; 32],
+    },
+    /// A verifier challenge was squeezed.
+    Challenge {
+        label: String,
+        bytes: Vec<u8>,
+        digest: [u8; 32],
+    },
+    /// The sponge was ratcheted.
+    Ratchet { digest: [u8; 32] },
+    /// A segment was sealed, with the given chaining digest.
+    Segment { digest: [u8; 32] },
+    /// A phase boundary was crossed.
+    Marker { label: String, digest: [u8; 32] },
+}
+
+impl Event {
+    /// The bytes read, absorbed or squeezed, empty for ratchets, segment boundaries and markers.
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            Event::Message { bytes, .. }
+            | Event::Public { bytes, .. }
+            | Event::Challenge { bytes, .. } => bytes,
+            Event::Ratchet { .. } | Event::Segment { .. } | Event::Marker { .. } => &[],
+        }
+    }
+
+    /// The running digest of the sponge state after this event.
+    pub fn digest(&self) -> &[u8; 32] {
+        match self {
+            Event::Message { digest, .. }
+            | Event::Public { digest, .. }
+            | Event::Challenge { digest, .. }
+            | Event::Ratchet { digest }
+            | Event::Segment { digest }
+            | Event::Marker { digest, .. } => digest,
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Message { label, bytes, .. } => {
+                write!(f, "message   {:?}: {}", label, hex::encode(bytes))?
+            }
+            Event::Public { label, bytes, .. } => {
+                write!(f, "public    {:?}: {}", label, hex::encode(bytes))?
+            }
+            Event::Challenge { label, bytes, .. } => {
+                write!(f, "challenge {:?}: {}", label, hex::encode(bytes))?
+            }
+            Event::Ratchet { .. } => write!(f, "ratchet")?,
+            Event::Segment { .. } => write!(f, "segment")?,
+            Event::Marker { label, .. } => write!(f, "marker    {:?}", label)?,
+        }
+        write!(f, " [state {}]", hex::encode(self.digest()))
+    }
+}
+
+/// Replay the proof `narg` against the IO Pattern `domsep` (as bytes), using the [`DefaultHash`].
+///
+/// Every absorb is read from the proof: see [`describe_public`] for protocols with public inputs.
+pub fn describe(domsep: &[u8], narg: &[u8]) -> Result<Vec<Event>, IOPatternError> {
+    describe_with::<DefaultHash>(domsep, narg)
+}
+
+/// Replay the proof `narg` against the IO Pattern `domsep` (as bytes), using the hash function `H`.
+///
+/// Every absorb is read from the proof: see [`describe_public`] for protocols with public inputs.
+pub fn describe_with<H: DuplexHash>(
+    domsep: &[u8],
+    narg: &[u8],
+) -> Result<Vec<Event>, IOPatternError> {
+    describe_public::<H>(domsep, &[], narg)
+}
+
+/// Replay the proof `narg` against the IO Pattern `domsep` (as bytes), using the hash function `H`,
+/// given the `public` inputs absorbed by the protocol, as pairs of labels and bytes.
+///
+/// The IO Pattern does not tell public inputs from prover messages.
+/// Public inputs are matched, in order, with the absorbs of the same label, and read from `public` instead of the proof.
+///
+/// Fails if the IO Pattern is malformed, if the proof is too short, or if it has trailing bytes,
+/// and if a public input has the wrong length or is not absorbed.
+///
+/// ```
+/// # use nimue::*;
+///
+/// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "statement").absorb(1, "🍝");
+/// let mut merlin = io.to_merlin();
+/// merlin.public_bytes(&[0x01]).unwrap();
+/// merlin.add_bytes(&[0x42]).unwrap();
+///
+/// let public: &[(&str, &[u8])] = &[("statement", &[0x01])];
+/// let events = replay::describe_public::<DefaultHash>(io.as_bytes(), public, merlin.transcript()).unwrap();
+/// assert!(matches!(&events[0], replay::Event::Public { bytes, .. } if bytes == &[0x01]));
+/// assert_eq!(events[1].bytes(), [0x42]);
+/// ```
+pub fn describe_public<H: DuplexHash>(
+    domsep: &[u8],
+    public: &[(&str, &[u8])],
+    mut narg: &[u8],
+) -> Result<Vec<Event>, IOPatternError> {
+    let io = IOPattern::<H>::from_bytes(domsep)?;
+    let mut sponge = H::new_for_io_pattern(io.as_bytes(), io.digest(), io.digest())?;
+    let running_digest = |sponge: &H| {
+        let mut sponge = sponge.clone();
+        let mut digest = [0u8; 32];
+        sponge.ratchet_unchecked().squeeze_unchecked(&mut digest);
+        digest
+    };
+
+    let mut public = public.iter().peekable();
+    let mut events = Vec::new();
+    for (position, op, label) in IOPattern::<H>::parse_ops(io.as_bytes())? {
+        let label = core::str::from_utf8(label)
+            .map_err(|_| format!("Label at position {} is not valid UTF-8", position))?
+            .to_owned();
+        match op {
+            Op::Absorb(length) => match public.next_if(|(name, _)| *name == label) {
+                Some((_, bytes)) => {
+                    if bytes.len() != length {
+                        return Err(format!(
+                            "Public input {:?} has {} bytes, expected {}",
+                            label,
+                            bytes.len(),
+                            length
+                        )
+                        .into());
+                    }
+                    sponge.absorb_unchecked(bytes);
+                    events.push(Event::Public {
+                        label,
+                        bytes: bytes.to_vec(),
+                        digest: running_digest(&sponge),
+                    });
+                }
+                None => {
+                    if narg.len() < length {
+                        return Err(format!("Proof too short: cannot read {:?}", label).into());
+                    }
+                    let (bytes, rest) = narg.split_at(length);
+                    narg = rest;
+                    sponge.absorb_unchecked(bytes);
+                    events.push(Event::Message {
+                        label,
+                        bytes: bytes.to_vec(),
+                        digest: running_digest(&sponge),
+                    });
+                }
+            },
+            Op::Squeeze(length) => {
+                let mut bytes = vec![0u8; length];
+                sponge.squeeze_unchecked(&mut bytes);
+                events.push(Event::Challenge {
+                    label,
+                    bytes,
+                    digest: running_digest(&sponge),
+                });
+            }
+            Op::Ratchet => {
+                sponge.ratchet_unchecked();
+                events.push(Event::Ratchet {
+                    digest: running_digest(&sponge),
+                });
+            }
+            Op::Segment => {
+                let mut digest = [0u8; 32];
+                sponge.ratchet_unchecked().squeeze_unchecked(&mut digest);
+                sponge = sponge.new_like(digest);
+                events.push(Event::Segment { digest });
+            }
+            Op::Marker => {
+                sponge.absorb_unchecked(&[Safe::<H>::marker_unit(&label)]);
+                events.push(Event::Marker {
+                    digest: running_digest(&sponge),
+                    label,
+                });
+            }
+        }
+    }
+
+    if let Some((label, _)) = public.next() {
+        return Err(format!("Public input {:?} is not absorbed by the IO Pattern", label).into());
+    }
+    if !narg.is_empty() {
+        return Err(format!("Proof has {} trailing bytes", narg.len()).into());
+    }
+    Ok(events)
+}
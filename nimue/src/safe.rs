@@ -1,11 +1,33 @@
 use core::fmt;
 use core::marker::PhantomData;
+use core::mem::MaybeUninit;
 use std::collections::vec_deque::VecDeque;
+use std::sync::Arc;
 
-use super::errors::IOPatternError;
+use super::errors::{IOPatternError, OpParseError};
+use super::hash::DuplexHash;
 use super::hash::Unit;
-use super::hash::{DuplexHash, Keccak};
-use super::iopattern::{IOPattern, Op};
+use super::iopattern::{IOPattern, LabelSpan, Op};
+use super::redaction::RedactionLevel;
+use super::traits::UnitTranscript;
+
+/// The progress of a [`Safe`] sponge through its IO Pattern, reported after each completed operation.
+///
+/// Consecutive absorbs (resp. squeezes) in the IO Pattern count as a single operation, labeled as the first one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpProgress<'a> {
+    /// The label of the operation just completed, empty for ratchets and segment boundaries.
+    pub label: &'a str,
+    /// The labels of the absorbs (resp. squeezes) merged into the operation, and their units (see [`IOPattern::label_spans`]).
+    pub spans: &'a [LabelSpan],
+    /// The number of operations completed so far.
+    pub completed: usize,
+    /// The total number of operations in the IO Pattern.
+    pub total: usize,
+}
+
+/// A callback invoked after each completed operation.
+type ProgressCallback = Arc<dyn Fn(OpProgress<'_>) + Send + Sync>;
 
 /// A (slightly modified) SAFE API for sponge functions.
 ///
@@ -18,24 +40,182 @@ where
 {
     sponge: H,
     stack: VecDeque<Op>,
+    labels: VecDeque<Vec<LabelSpan>>,
+    /// The label of the last operation completed.
+    last_label: String,
+    total_ops: usize,
+    on_op_completed: Option<ProgressCallback>,
     _unit: PhantomData<U>,
 }
 
 impl<U: Unit, H: DuplexHash<U>> Safe<H, U> {
     /// Initialise a SAFE sponge,
     /// setting up the state of the sponge function and parsing the tag string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the IO Pattern is malformed, which can only happen for patterns that
+    /// were not built with the [`IOPattern`] builder methods. See [`Safe::try_new`].
     pub fn new(io_pattern: &IOPattern<H, U>) -> Self {
-        let stack = io_pattern.finalize();
-        let tag = Self::generate_tag(io_pattern.as_bytes());
-        Self::unchecked_load_with_stack(tag, stack)
+        Self::try_new(io_pattern).expect("Malformed IO Pattern")
+    }
+
+    /// Initialise a SAFE sponge, returning an error if the IO Pattern is malformed.
+    pub fn try_new(io_pattern: &IOPattern<H, U>) -> Result<Self, OpParseError> {
+        let stack = io_pattern.finalize()?;
+        let digest = io_pattern.try_digest()?;
+        let sponge = H::new_for_io_pattern(io_pattern.as_bytes(), digest, digest)?;
+        let mut safe = Self::unchecked_load_with_stack(sponge, stack);
+        safe.labels = io_pattern.label_spans().into();
+        Ok(safe)
+    }
+
+    /// Initialise a SAFE sponge with a custom `iv`, for instance one binding several IO Patterns together.
+    #[cfg(feature = "ark")]
+    pub(crate) fn new_with_iv(io_pattern: &IOPattern<H, U>, iv: [u8; 32]) -> Self {
+        let stack = io_pattern.finalize().expect("Malformed IO Pattern");
+        let sponge = H::new_for_io_pattern(io_pattern.as_bytes(), io_pattern.digest(), iv)
+            .expect("Malformed IO Pattern");
+        let mut safe = Self::unchecked_load_with_stack(sponge, stack);
+        safe.labels = io_pattern.label_spans().into();
+        safe
+    }
+
+    /// The total number of operations in the IO Pattern.
+    ///
+    /// Consecutive absorbs (resp. squeezes) count as a single operation.
+    pub fn total_ops(&self) -> usize {
+        self.total_ops
+    }
+
+    /// The number of operations not completed yet, including a partially-performed absorb or squeeze.
+    pub fn remaining_ops(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// The number of units left in the absorb at the top of the stack, if the next operation is an absorb.
+    pub(crate) fn pending_absorb(&self) -> Option<usize> {
+        match self.stack.front() {
+            Some(&Op::Absorb(length)) => Some(length),
+            _ => None,
+        }
+    }
+
+    /// Invoke `callback` after each completed operation, e.g. to display a progress bar.
+    ///
+    /// ```
+    /// # use nimue::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let io = IOPattern::<DefaultHash>::new("📝").absorb(2, "🍝").squeeze(1, "🍷");
+    /// let mut merlin = io.to_merlin();
+    /// let labels = Arc::new(Mutex::new(Vec::new()));
+    /// let log = labels.clone();
+    /// merlin.on_op_completed(move |progress| log.lock().unwrap().push(progress.label.to_string()));
+    /// merlin.add_bytes(&[1]).unwrap();
+    /// assert!(labels.lock().unwrap().is_empty());
+    /// merlin.add_bytes(&[2]).unwrap();
+    /// merlin.challenge_bytes::<1>().unwrap();
+    /// assert_eq!(*labels.lock().unwrap(), ["🍝", "🍷"]);
+    /// ```
+    pub fn on_op_completed(&mut self, callback: impl Fn(OpProgress<'_>) + Send + Sync + 'static) {
+        self.on_op_completed = Some(Arc::new(callback));
+    }
+
+    /// Drop the operations left, e.g. for snapshots whose remaining operations are performed elsewhere.
+    pub(crate) fn clear_ops(&mut self) {
+        self.stack.clear();
+    }
+
+    #[cfg(test)]
+    pub(crate) fn sponge(&self) -> &H {
+        &self.sponge
+    }
+
+    /// Record the completion of the operation at the top of the stack, which has just been popped.
+    fn complete_op(&mut self) {
+        let spans = self.labels.pop_front().unwrap_or_default();
+        let label = spans
+            .first()
+            .map(|span| span.label.clone())
+            .unwrap_or_default();
+        if let Some(callback) = &self.on_op_completed {
+            callback(OpProgress {
+                label: &label,
+                spans: &spans,
+                completed: self.total_ops - self.stack.len(),
+                total: self.total_ops,
+            });
+        }
+        self.last_label = label;
+    }
+
+    /// The number of operations completed so far, and the label of the last one.
+    pub(crate) fn last_op(&self) -> (usize, &str) {
+        (self.total_ops - self.stack.len(), &self.last_label)
+    }
+
+    /// Cross a phase boundary declared with [`IOPattern::marker`], checking that its label is `label`.
+    ///
+    /// The sponge absorbs a single unit derived from the label, and nothing from the transcript.
+    pub fn marker(&mut self, label: &str) -> Result<(), IOPatternError>
+    where
+        U: Default,
+    {
+        if self.stack.front() != Some(&Op::Marker) {
+            self.stack.clear();
+            return Err(match RedactionLevel::global() {
+                RedactionLevel::Labels => format!("Invalid tag. Expected marker {:?}", label).into(),
+                _ => "Invalid tag. Expected a marker".into(),
+            });
+        }
+        let expected = self
+            .labels
+            .front()
+            .and_then(|spans| spans.first())
+            .map_or("", |span| span.label.as_str());
+        if expected != label {
+            let error = match RedactionLevel::global() {
+                RedactionLevel::Labels => {
+                    format!("Invalid marker: expected {:?}, got {:?}", expected, label).into()
+                }
+                _ => "Invalid marker".into(),
+            };
+            self.stack.clear();
+            return Err(error);
+        }
+        self.stack.pop_front();
+        self.sponge.absorb_unchecked(&[Self::marker_unit(label)]);
+        self.complete_op();
+        Ok(())
+    }
+
+    /// The domain-separating constant absorbed by a marker labeled `label`.
+    ///
+    /// The unit is squeezed from a sponge initialized with the [`Keccak`](crate::hash::Keccak) digest of the label,
+    /// so that it is a constant of the protocol, and never depends on the transcript.
+    pub(crate) fn marker_unit(label: &str) -> U
+    where
+        U: Default,
+    {
+        let mut keccak = crate::hash::Keccak::default();
+        keccak.absorb_unchecked(label.as_bytes());
+        let mut iv = [0u8; 32];
+        keccak.squeeze_unchecked(&mut iv);
+        let mut unit = [U::default()];
+        H::new(iv).squeeze_unchecked(&mut unit);
+        let [unit] = unit;
+        unit
     }
 
     /// Finish the block and compress the state.
     pub fn ratchet(&mut self) -> Result<(), IOPatternError> {
-        if self.stack.pop_front().unwrap() != Op::Ratchet {
+        if self.stack.pop_front() != Some(Op::Ratchet) {
+            self.stack.clear();
             Err("Invalid tag".into())
         } else {
             self.sponge.ratchet_unchecked();
+            self.complete_op();
             Ok(())
         }
     }
@@ -50,31 +230,25 @@ impl<U: Unit, H: DuplexHash<U>> Safe<H, U> {
     /// Perform secure absorption of the elements in `input`.
     ///
     /// Absorb calls can be batched together, or provided separately for streaming-friendly protocols.
+    /// Absorbing an empty slice is a no-op, whatever the next operation.
     pub fn absorb(&mut self, input: &[U]) -> Result<(), IOPatternError> {
+        if input.is_empty() {
+            return Ok(());
+        }
         match self.stack.pop_front() {
             Some(Op::Absorb(length)) if length >= input.len() => {
+                self.sponge.absorb_unchecked(input);
                 if length > input.len() {
                     self.stack.push_front(Op::Absorb(length - input.len()));
+                } else {
+                    self.complete_op();
                 }
-                self.sponge.absorb_unchecked(input);
                 Ok(())
             }
-            None => {
-                self.stack.clear();
-                Err(format!(
-                    "Invalid tag. Stack empty, got {:?}",
-                    Op::Absorb(input.len())
-                )
-                .into())
-            }
-            Some(op) => {
+            next => {
+                let error = Self::mismatch(Op::Absorb(input.len()), next, &self.stack);
                 self.stack.clear();
-                Err(format!(
-                    "Invalid tag. Got {:?}, expected {:?}",
-                    Op::Absorb(input.len()),
-                    op
-                )
-                .into())
+                Err(error)
             }
         }
     }
@@ -84,53 +258,155 @@ impl<U: Unit, H: DuplexHash<U>> Safe<H, U> {
     /// For byte-oriented sponges, this operation is equivalent to the squeeze operation.
     /// However, for algebraic hashes, this operation is non-trivial.
     /// This function provides no guarantee of streaming-friendliness.
+    /// Squeezing into an empty slice is a no-op, whatever the next operation.
     pub fn squeeze(&mut self, output: &mut [U]) -> Result<(), IOPatternError> {
+        self.pop_squeeze(output.len())?;
+        self.sponge.squeeze_unchecked(output);
+        Ok(())
+    }
+
+    /// Perform a secure squeeze operation into an uninitialized buffer, returning the initialized slice.
+    ///
+    /// This is equivalent to [`Safe::squeeze`], without requiring the caller to initialize `output` first.
+    pub fn squeeze_uninit<'a>(
+        &mut self,
+        output: &'a mut [MaybeUninit<U>],
+    ) -> Result<&'a mut [U], IOPatternError>
+    where
+        U: Default,
+    {
+        self.pop_squeeze(output.len())?;
+        Ok(self.sponge.squeeze_uninit_unchecked(output))
+    }
+
+    /// Consume `len` units from the squeeze operation at the top of the stack.
+    fn pop_squeeze(&mut self, len: usize) -> Result<(), IOPatternError> {
+        if len == 0 {
+            return Ok(());
+        }
         match self.stack.pop_front() {
-            Some(Op::Squeeze(length)) if output.len() <= length => {
-                self.sponge.squeeze_unchecked(output);
-                if length != output.len() {
-                    self.stack.push_front(Op::Squeeze(length - output.len()));
+            Some(Op::Squeeze(length)) if len <= length => {
+                if length != len {
+                    self.stack.push_front(Op::Squeeze(length - len));
+                } else {
+                    self.complete_op();
                 }
                 Ok(())
             }
-            None => {
+            next => {
+                let error = Self::mismatch(Op::Squeeze(len), next, &self.stack);
                 self.stack.clear();
-                Err(format!(
-                    "Invalid tag. Stack empty, got {:?}",
-                    Op::Squeeze(output.len())
-                )
-                .into())
+                Err(error)
             }
-            Some(op) => {
-                self.stack.clear();
-                Err(format!(
-                    "Invalid tag. Got {:?}, expected {:?}. The stack remaining is: {:?}",
-                    Op::Squeeze(output.len()),
-                    op,
-                    self.stack
-                )
-                .into())
+        }
+    }
+
+    /// The error for an operation `got` that does not match the `next` operation of the IO Pattern,
+    /// followed by the operations in `remaining`.
+    ///
+    /// Operations are shown only if the [`RedactionLevel`] shows the progress through the IO Pattern:
+    /// error messages often end up in production logs.
+    fn mismatch(got: Op, next: Option<Op>, remaining: &VecDeque<Op>) -> IOPatternError {
+        if RedactionLevel::global() == RedactionLevel::Opaque {
+            return "Invalid tag".into();
+        }
+        match next {
+            None => format!("Invalid tag. Stack empty, got {:?}", got).into(),
+            Some(op) if remaining.is_empty() => {
+                format!("Invalid tag. Got {:?}, expected {:?}", got, op).into()
             }
+            Some(op) => format!(
+                "Invalid tag. Got {:?}, expected {:?}. The stack remaining is: {:?}",
+                got, op, remaining
+            )
+            .into(),
         }
     }
 
-    fn generate_tag(iop_bytes: &[u8]) -> [u8; 32] {
-        let mut keccak = Keccak::default();
-        keccak.absorb_unchecked(iop_bytes);
-        let mut tag = [0u8; 32];
-        keccak.squeeze_unchecked(&mut tag);
-        tag
+    /// Initialise a SAFE sponge from `sponge`, that will execute the operations in `stack`,
+    /// whose labels are `labels` (see [`IOPattern::label_spans`]).
+    pub(crate) fn from_stack(
+        sponge: H,
+        stack: VecDeque<Op>,
+        labels: VecDeque<Vec<LabelSpan>>,
+    ) -> Self {
+        let mut safe = Self::unchecked_load_with_stack(sponge, stack);
+        safe.labels = labels;
+        safe
     }
 
-    fn unchecked_load_with_stack(tag: [u8; 32], stack: VecDeque<Op>) -> Self {
+    fn unchecked_load_with_stack(sponge: H, stack: VecDeque<Op>) -> Self {
         Self {
-            sponge: H::new(tag),
+            sponge,
+            total_ops: stack.len(),
             stack,
+            labels: VecDeque::new(),
+            last_label: String::new(),
+            on_op_completed: None,
             _unit: PhantomData,
         }
     }
 }
 
+impl<H: DuplexHash<u8>> Safe<H, u8> {
+    /// Ratchet the sponge after the last operation of the IO Pattern, and squeeze a 32-byte digest of its state.
+    pub(crate) fn final_digest(&mut self) -> Result<[u8; 32], IOPatternError> {
+        if !self.stack.is_empty() {
+            return Err(match RedactionLevel::global() {
+                RedactionLevel::Opaque => "Unfinished operations".into(),
+                _ => format!("Unfinished operations: {:?}", self.stack).into(),
+            });
+        }
+        let mut digest = [0u8; 32];
+        self.sponge
+            .ratchet_unchecked()
+            .squeeze_unchecked(&mut digest);
+        Ok(digest)
+    }
+
+    /// End the current segment, returning the chaining digest.
+    ///
+    /// The sponge is ratcheted, a 32-byte digest is squeezed, and the sponge is re-initialized with the digest as IV.
+    pub(crate) fn seal_segment(&mut self) -> Result<[u8; 32], IOPatternError> {
+        if self.stack.pop_front() != Some(Op::Segment) {
+            self.stack.clear();
+            return Err("Invalid tag. Expected a segment boundary".into());
+        }
+        let mut digest = [0u8; 32];
+        self.sponge.ratchet_unchecked();
+        self.sponge.squeeze_unchecked(&mut digest);
+        let sponge = self.sponge.new_like(digest);
+        self.sponge.zeroize();
+        self.sponge = sponge;
+        self.complete_op();
+        Ok(digest)
+    }
+}
+
+/// The SAFE sponge alone, with no protocol transcript: public elements are absorbed and challenges squeezed.
+///
+/// This gives [`Safe`] the same byte (and, with the plugins, field and group) challenges as [`Merlin`](crate::Merlin)
+/// and [`Arthur`](crate::Arthur).
+impl<U: Unit, H: DuplexHash<U>> UnitTranscript<U> for Safe<H, U> {
+    fn public_units(&mut self, input: &[U]) -> Result<(), IOPatternError> {
+        self.absorb(input)
+    }
+
+    fn fill_challenge_units(&mut self, output: &mut [U]) -> Result<(), IOPatternError> {
+        self.squeeze(output)
+    }
+
+    fn fill_challenge_units_uninit<'a>(
+        &mut self,
+        output: &'a mut [MaybeUninit<U>],
+    ) -> Result<&'a mut [U], IOPatternError>
+    where
+        U: Default,
+    {
+        self.squeeze_uninit(output)
+    }
+}
+
 impl<U: Unit, H: DuplexHash<U>> Drop for Safe<H, U> {
     /// Destroy the sponge state.
     fn drop(&mut self) {
@@ -139,7 +415,10 @@ impl<U: Unit, H: DuplexHash<U>> Drop for Safe<H, U> {
         // (like another panic) will pollute the traceback.
         // debug_assert!(self.stack.is_empty());
         if !self.stack.is_empty() {
-            log::error!("Unfinished operations:\n {:?}", self.stack)
+            match RedactionLevel::global() {
+                RedactionLevel::Opaque => log::error!("Unfinished operations"),
+                _ => log::error!("Unfinished operations:\n {:?}", self.stack),
+            }
         }
         // XXX. is the compiler going to optimize this out?
         self.sponge.zeroize();
@@ -147,10 +426,34 @@ impl<U: Unit, H: DuplexHash<U>> Drop for Safe<H, U> {
 }
 
 impl<U: Unit, H: DuplexHash<U>> fmt::Debug for Safe<H, U> {
+    /// Ensure that the state isn't accidentally logged,
+    /// but provide the remaining IO Pattern for debugging, as allowed by the [`RedactionLevel`].
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Ensure that the state isn't accidentally logged,
-        // but provide the remaining IO Pattern for debugging.
-        write!(f, "SAFE sponge with IO: {:?}", self.stack)
+        let level = RedactionLevel::global();
+        write!(f, "SAFE sponge")?;
+        if level == RedactionLevel::Opaque {
+            return Ok(());
+        }
+        let (completed, last_label) = self.last_op();
+        write!(f, " at op {}/{}", completed, self.total_ops)?;
+        if level == RedactionLevel::Progress {
+            return write!(f, ", with IO: {:?}", self.stack);
+        }
+        if completed > 0 {
+            write!(f, ", after {:?}", last_label)?;
+        }
+        write!(f, ", with IO: [")?;
+        for (i, op) in self.stack.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:?}", op)?;
+            let labels = self.labels.get(i).map_or(&[][..], Vec::as_slice);
+            for span in labels.iter().filter(|span| !span.label.is_empty()) {
+                write!(f, " {:?}", span.label)?;
+            }
+        }
+        write!(f, "]")
     }
 }
 
@@ -0,0 +1,259 @@
+use rand::{CryptoRng, RngCore};
+
+use crate::hash::{DuplexHash, Keccak, Unit};
+use crate::merlin::ProverRng;
+use crate::{
+    Arthur, DefaultHash, DefaultRng, IOPattern, IOPatternError, Merlin, ProofResult, Safe,
+    UnitTranscript,
+};
+
+/// Public elements absorbed once, at the beginning of many protocol transcripts.
+///
+/// Large public parameters (e.g. the digest of a structured reference string, or vectors of generators)
+/// are often absorbed identically in the statement of every proof.
+/// A [`SharedStatement`] absorbs them once, following the IO Pattern,
+/// and keeps a snapshot of the sponges from which each [`Merlin`] (or [`Arthur`]) continues.
+/// Transcripts are the same as if the public elements had been absorbed by each prover and verifier.
+/// The statement is [`Send`] and [`Sync`] (for thread-safe hashes) and can be shared behind an [`Arc`](std::sync::Arc)
+/// by parallel verifiers, see [`Arthur::from_statement`].
+///
+/// ```
+/// # use nimue::*;
+///
+/// let io = IOPattern::<DefaultHash>::new("📝").absorb(4096, "generators").absorb(1, "🍝").squeeze(16, "🍷");
+/// let generators = [0x42u8; 4096];
+/// let mut params = SharedStatement::new(&io);
+/// params.public_bytes(&generators).unwrap();
+///
+/// for pasta in 0..4u8 {
+///     let mut merlin = params.to_merlin();
+///     merlin.add_bytes(&[pasta]).unwrap();
+///     let chal = merlin.challenge_bytes::<16>().unwrap();
+///
+///     let mut arthur = params.to_arthur(merlin.transcript());
+///     assert_eq!(arthur.next_bytes().unwrap(), [pasta]);
+///     assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SharedStatement<H = DefaultHash, U = u8>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+{
+    /// The public coins after absorbing the statement.
+    safe: Safe<H, U>,
+    /// The state of the prover's sponge after absorbing the statement (see [`ProverRng`]).
+    rng_sponge: Keccak,
+}
+
+impl<H, U> SharedStatement<H, U>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+{
+    /// Start a statement following `io_pattern`. Public elements are then absorbed via [`UnitTranscript`].
+    pub fn new(io_pattern: &IOPattern<H, U>) -> Self {
+        let mut rng_sponge = Keccak::default();
+        rng_sponge.absorb_unchecked(io_pattern.as_bytes());
+        Self {
+            safe: Safe::new(io_pattern),
+            rng_sponge,
+        }
+    }
+
+    /// Create a [`Merlin`] continuing from the statement, with the default random number generator.
+    pub fn to_merlin(&self) -> Merlin<H, U, DefaultRng> {
+        self.to_merlin_with_rng(DefaultRng::default())
+    }
+
+    /// Create a [`Merlin`] continuing from the statement, seeding the prover's private coins with `csrng`.
+    pub fn to_merlin_with_rng<R: RngCore + CryptoRng>(&self, csrng: R) -> Merlin<H, U, R> {
+        Merlin {
+            rng: ProverRng {
+                sponge: self.rng_sponge.clone(),
+                csrng,
+            },
+            safe: self.safe.clone(),
+            transcript: Vec::new(),
+            sink: None,
+        }
+    }
+
+    /// Create an [`Arthur`] continuing from the statement, reading the protocol `transcript`.
+    pub fn to_arthur<'a>(&self, transcript: &'a [u8]) -> Arthur<'a, H, U> {
+        Arthur {
+            safe: self.safe.clone(),
+            transcript: transcript.into(),
+            allow_trailing_bytes: false,
+            failure: None,
+        }
+    }
+}
+
+impl<H, U> UnitTranscript<U> for SharedStatement<H, U>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+{
+    /// Absorb public elements in the statement.
+    fn public_units(&mut self, input: &[U]) -> Result<(), IOPatternError> {
+        self.safe.absorb(input)?;
+        let mut encoded = Vec::new();
+        // write never fails on Vec<u8>
+        U::write(input, &mut encoded).unwrap();
+        self.rng_sponge.absorb_unchecked(&encoded);
+        Ok(())
+    }
+
+    /// Squeeze challenges in the statement, shared by all transcripts.
+    fn fill_challenge_units(&mut self, output: &mut [U]) -> Result<(), IOPatternError> {
+        self.safe.squeeze(output)
+    }
+}
+
+impl<H, U> Drop for SharedStatement<H, U>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+{
+    /// The operations left are performed by the transcripts continuing from the statement, not by the statement itself.
+    fn drop(&mut self) {
+        self.safe.clear_ops();
+    }
+}
+
+impl<H, U> core::fmt::Debug for SharedStatement<H, U>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "SharedStatement: ")?;
+        self.safe.fmt(f)
+    }
+}
+
+/// Many statements proven with shared challenges.
+///
+/// A common trick batches `count` instances of a protocol (e.g. Schnorr proofs of knowledge):
+/// the statement and the first prover message of each instance are absorbed,
+/// then a single set of challenges is squeezed and reused by the `count` responses.
+/// [`BatchedStatement::io_pattern`] generates the IO Pattern of the batch, namespacing the labels of the `i`-th instance
+/// as `label[i].` (see [`IOPattern::rounds`]), and [`BatchedStatement::run`] performs the operations in the same order,
+/// for the prover and for the verifier.
+///
+/// ```
+/// # use nimue::*;
+///
+/// let batch = BatchedStatement::new("instance", 3);
+/// let io = batch.io_pattern(
+///     IOPattern::<DefaultHash>::new("📝"),
+///     |io, _| io.absorb(1, "statement").absorb(1, "commitment"),
+///     |io| io.squeeze(16, "challenge"),
+///     |io, _| io.absorb(1, "response"),
+/// );
+/// assert!(String::from_utf8_lossy(io.as_bytes()).contains("A1instance[2].statement"));
+///
+/// let statements = [1u8, 2, 3];
+/// let mut merlin = io.to_merlin();
+/// let responses = batch.run(
+///     &mut merlin,
+///     &statements,
+///     |merlin, &x| {
+///         merlin.public_bytes(&[x])?;
+///         Ok(merlin.add_bytes(&[x + 1])?)
+///     },
+///     |merlin| Ok(merlin.challenge_bytes::<16>()?),
+///     |merlin, &x, (), chal| Ok(merlin.add_bytes(&[x ^ chal[0]])?),
+/// ).unwrap();
+/// assert_eq!(responses.len(), 3);
+///
+/// let mut arthur = io.to_arthur(merlin.transcript());
+/// batch.run(
+///     &mut arthur,
+///     &statements,
+///     |arthur, &x| {
+///         arthur.public_bytes(&[x])?;
+///         Ok(arthur.next_bytes::<1>()?[0])
+///     },
+///     |arthur| Ok(arthur.challenge_bytes::<16>()?),
+///     |arthur, &x, commitment, chal| match arthur.next_bytes::<1>()? {
+///         [r] if commitment == x + 1 && r == x ^ chal[0] => Ok(()),
+///         _ => Err(ProofError::InvalidProof),
+///     },
+/// ).unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchedStatement {
+    label: String,
+    count: usize,
+}
+
+impl BatchedStatement {
+    /// A batch of `count` instances, namespaced by `label`.
+    pub fn new(label: &str, count: usize) -> Self {
+        Self {
+            label: label.to_string(),
+            count,
+        }
+    }
+
+    /// The number of instances in the batch.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Extend `io_pattern` with the batch:
+    /// the operations added by `statement(io, i)` for each instance, the shared challenges added by `challenges`,
+    /// then the operations added by `response(io, i)` for each instance.
+    pub fn io_pattern<H, U>(
+        &self,
+        io_pattern: IOPattern<H, U>,
+        statement: impl Fn(IOPattern<H, U>, usize) -> IOPattern<H, U>,
+        challenges: impl FnOnce(IOPattern<H, U>) -> IOPattern<H, U>,
+        response: impl Fn(IOPattern<H, U>, usize) -> IOPattern<H, U>,
+    ) -> IOPattern<H, U>
+    where
+        U: Unit,
+        H: DuplexHash<U>,
+    {
+        let io_pattern = io_pattern.rounds(&self.label, self.count, statement);
+        challenges(io_pattern).rounds(&self.label, self.count, response)
+    }
+
+    /// Run the batch over `transcript` (a [`Merlin`] or an [`Arthur`]):
+    /// `absorb` each of the `statements`, squeeze the shared challenges with `challenge`,
+    /// and `respond` for each of the `statements`, given the result of `absorb` (e.g. the prover's nonce, or its commitment)
+    /// and the shared challenges.
+    ///
+    /// Returns the results of `respond`, in the order of `statements`.
+    /// Fails if the number of statements does not match the batch.
+    pub fn run<T, S, A, C, R>(
+        &self,
+        transcript: &mut T,
+        statements: &[S],
+        mut absorb: impl FnMut(&mut T, &S) -> ProofResult<A>,
+        challenge: impl FnOnce(&mut T) -> ProofResult<C>,
+        mut respond: impl FnMut(&mut T, &S, A, &C) -> ProofResult<R>,
+    ) -> ProofResult<Vec<R>> {
+        if statements.len() != self.count {
+            return Err(IOPatternError::from(format!(
+                "Expected {} statements in the batch, got {}",
+                self.count,
+                statements.len()
+            ))
+            .into());
+        }
+        let absorbed = statements
+            .iter()
+            .map(|statement| absorb(transcript, statement))
+            .collect::<ProofResult<Vec<_>>>()?;
+        let challenges = challenge(transcript)?;
+        statements
+            .iter()
+            .zip(absorbed)
+            .map(|(statement, a)| respond(transcript, statement, a, &challenges))
+            .collect()
+    }
+}
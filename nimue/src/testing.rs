@@ -0,0 +1,345 @@
+//! Negative tests for protocols built on the top of nimue.
+//!
+//! Protocol code is expected to propagate the errors of the transcript when it deviates from the IO Pattern,
+//! rather than panicking, ignoring them, or accepting a transcript before all operations have been performed.
+//! [`expect_mismatch!`] asserts that a single call fails,
+//! while [`check_misuse`] runs a prover and a verifier against a set of canned [`Misuse`] scenarios:
+//!
+//! - [`Misuse::WrongOrder`]: two consecutive operations are performed in the wrong order;
+//! - [`Misuse::WrongCount`]: an operation absorbs (resp. squeezes) one unit more, or one unit less, than declared;
+//! - [`Misuse::WrongUnit`]: the length of an operation is counted in the wrong unit, e.g. in bits instead of bytes;
+//! - [`Misuse::DoubleRead`]: an operation is performed twice, e.g. a prover message is read twice.
+//!
+//! Each scenario runs the unmodified code against the operations of the IO Pattern, modified accordingly,
+//! with the IV of the original IO Pattern, so that only the transcript checks can catch the misuse.
+//!
+//! ```
+//! use nimue::testing::{assert_misuse_rejected, check_misuse};
+//! use nimue::*;
+//!
+//! let io = IOPattern::<DefaultHash>::new("📝").absorb(4, "commitment").squeeze(16, "chal").absorb(2, "response");
+//! let prove = |merlin: &mut Merlin| -> ProofResult<()> {
+//!     merlin.add_bytes(&[1, 2, 3, 4])?;
+//!     let [chal] = merlin.challenge_bytes::<1>()?;
+//!     merlin.fill_challenge_bytes(&mut [0u8; 15])?;
+//!     merlin.add_bytes(&[chal, chal])?;
+//!     Ok(())
+//! };
+//! let verify = |arthur: &mut Arthur| -> ProofResult<()> {
+//!     let _commitment: [u8; 4] = arthur.next_bytes()?;
+//!     let chal: [u8; 16] = arthur.challenge_bytes()?;
+//!     let response: [u8; 2] = arthur.next_bytes()?;
+//!     if response != [chal[0]; 2] || arthur.remaining_ops() != 0 {
+//!         return Err(ProofError::InvalidProof);
+//!     }
+//!     Ok(())
+//! };
+//! assert_misuse_rejected(&io, prove, verify);
+//!
+//! // a verifier that does not check that the whole IO Pattern was followed is caught
+//! let sloppy = |arthur: &mut Arthur| -> ProofResult<()> {
+//!     let _ = arthur.next_bytes::<4>();
+//!     Ok(())
+//! };
+//! assert!(check_misuse(&io, prove, sloppy).iter().any(|report| !report.is_caught()));
+//! ```
+//!
+//! With the `ark` and `test-utils` features, `ToySponge` provides a cheap sponge over any prime field,
+//! for tests of algebraic transcripts (including those of downstream crates, as a dev-dependency).
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::hash::Keccak;
+use crate::iopattern::{LabelSpan, Op};
+use crate::merlin::ProverRng;
+use crate::{Arthur, DefaultRng, DuplexHash, IOPattern, Merlin, ProofResult, Safe, Unit};
+
+/// Assert that the result of a transcript operation (or of a whole prover or verifier) is an error,
+/// optionally matching a pattern, and return the error.
+///
+/// ```
+/// # use nimue::*;
+/// let io = IOPattern::<DefaultHash>::new("📝").absorb(1, "a").squeeze(1, "b");
+/// let mut arthur = io.to_arthur(&[0x42]);
+/// // the challenge is squeezed before reading the prover message
+/// nimue::expect_mismatch!(arthur.challenge_bytes::<1>());
+/// // the operations are cleared after a mismatch
+/// let mut read = || -> ProofResult<[u8; 1]> { Ok(arthur.next_bytes()?) };
+/// nimue::expect_mismatch!(read(), ProofError::InvalidIO(_));
+/// ```
+#[macro_export]
+macro_rules! expect_mismatch {
+    ($result:expr $(,)?) => {
+        match $result {
+            Ok(_) => panic!(
+                "expected a transcript mismatch, but `{}` succeeded",
+                stringify!($result)
+            ),
+            Err(err) => err,
+        }
+    };
+    ($result:expr, $pattern:pat $(,)?) => {
+        match $result {
+            Ok(_) => panic!(
+                "expected a transcript mismatch, but `{}` succeeded",
+                stringify!($result)
+            ),
+            Err(err @ $pattern) => err,
+            Err(err) => panic!(
+                "expected `{}` to fail with `{}`, got {:?}",
+                stringify!($result),
+                stringify!($pattern),
+                err
+            ),
+        }
+    };
+}
+
+pub use crate::expect_mismatch;
+
+/// A misuse of the transcript, at the operation of index `op` in the IO Pattern (after merging).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Misuse {
+    /// The operations `op` and `op + 1` are swapped.
+    WrongOrder { op: usize },
+    /// The operation `op` absorbs (resp. squeezes) `count` units, one more or one less than declared.
+    WrongCount { op: usize, count: usize },
+    /// The length of the operation `op` is counted in bits instead of bytes, i.e. it is `count` units.
+    WrongUnit { op: usize, count: usize },
+    /// The operation `op` is performed twice.
+    DoubleRead { op: usize },
+}
+
+/// How the prover (or the verifier) behaved under a [`Misuse`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The code returned an error.
+    Rejected,
+    /// The code returned successfully, leaving the given number of operations of the IO Pattern unperformed.
+    Incomplete(usize),
+    /// The code panicked.
+    Panicked,
+    /// The code returned successfully, after performing all the operations of the IO Pattern.
+    Accepted,
+}
+
+/// The outcome of a [`Misuse`] scenario, for the prover and for the verifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MisuseReport {
+    pub misuse: Misuse,
+    pub prover: Outcome,
+    pub verifier: Outcome,
+}
+
+impl MisuseReport {
+    /// Whether the misuse was caught.
+    ///
+    /// The prover must fail, or leave operations unperformed (its transcript is then rejected by an honest verifier).
+    /// The verifier must fail: verifiers should check that they performed all the operations of the IO Pattern,
+    /// e.g. via [`Arthur::remaining_ops`] or [`Arthur::final_digest`].
+    pub fn is_caught(&self) -> bool {
+        matches!(self.prover, Outcome::Rejected | Outcome::Incomplete(_))
+            && self.verifier == Outcome::Rejected
+    }
+}
+
+/// An operation of the IO Pattern, with its labels (see [`IOPattern::label_spans`]).
+type LabeledOp = (Op, Vec<LabelSpan>);
+
+/// The operations of the IO Pattern, modified according to each canned [`Misuse`].
+///
+/// Labels are moved (resp. dropped) together with their operation, so that markers are still checked.
+fn misuse_scenarios(ops: &[LabeledOp]) -> Vec<(Misuse, Vec<LabeledOp>)> {
+    let mut scenarios = Vec::new();
+    for (i, (op, _)) in ops.iter().enumerate() {
+        if ops.get(i + 1).is_some_and(|(next, _)| next != op) {
+            let mut stack = ops.to_vec();
+            stack.swap(i, i + 1);
+            scenarios.push((Misuse::WrongOrder { op: i }, stack));
+        }
+        let with_count = |count| {
+            let mut stack = ops.to_vec();
+            stack[i].0 = match op {
+                Op::Absorb(_) => Op::Absorb(count),
+                _ => Op::Squeeze(count),
+            };
+            stack
+        };
+        if let &Op::Absorb(length) | &Op::Squeeze(length) = op {
+            for count in [length - 1, length + 1] {
+                if count > 0 {
+                    scenarios.push((Misuse::WrongCount { op: i, count }, with_count(count)));
+                }
+            }
+            let count = length * 8;
+            scenarios.push((Misuse::WrongUnit { op: i, count }, with_count(count)));
+            // performing the operation twice against the IO Pattern
+            // is the same as performing it once against the IO Pattern without it
+            let mut stack = ops.to_vec();
+            stack.remove(i);
+            scenarios.push((Misuse::DoubleRead { op: i }, stack));
+        }
+    }
+    scenarios
+}
+
+/// Run `f`, classifying its result and the operations of the IO Pattern left in `state`.
+fn outcome<T>(
+    f: impl FnOnce(&mut T) -> ProofResult<()>,
+    state: &mut T,
+    remaining_ops: impl Fn(&T) -> usize,
+) -> Outcome {
+    match catch_unwind(AssertUnwindSafe(|| f(state))) {
+        Err(_) => Outcome::Panicked,
+        Ok(Err(_)) => Outcome::Rejected,
+        Ok(Ok(())) => match remaining_ops(state) {
+            0 => Outcome::Accepted,
+            n => Outcome::Incomplete(n),
+        },
+    }
+}
+
+/// Run `prove` and `verify` against each canned [`Misuse`] of `io_pattern`, and report how they behaved.
+///
+/// # Panics
+///
+/// Panics if the honest prover fails, or if the verifier rejects its transcript.
+pub fn check_misuse<H, U>(
+    io_pattern: &IOPattern<H, U>,
+    prove: impl Fn(&mut Merlin<H, U>) -> ProofResult<()>,
+    verify: impl Fn(&mut Arthur<H, U>) -> ProofResult<()>,
+) -> Vec<MisuseReport>
+where
+    U: Unit,
+    H: DuplexHash<U>,
+{
+    let mut merlin = io_pattern.to_merlin();
+    prove(&mut merlin).expect("The honest prover failed");
+    let transcript = merlin.transcript().to_vec();
+    verify(&mut io_pattern.to_arthur(&transcript)).expect("The honest transcript was rejected");
+
+    let ops = io_pattern
+        .finalize()
+        .expect("Malformed IO Pattern")
+        .into_iter()
+        .zip(io_pattern.label_spans())
+        .collect::<Vec<_>>();
+    let iv = io_pattern.digest();
+    misuse_scenarios(&ops)
+        .into_iter()
+        .map(|(misuse, scenario)| {
+            let (stack, labels): (VecDeque<_>, VecDeque<_>) = scenario.into_iter().unzip();
+            let mut sponge = Keccak::default();
+            sponge.absorb_unchecked(io_pattern.as_bytes());
+            let mut merlin = Merlin {
+                rng: ProverRng {
+                    sponge,
+                    csrng: DefaultRng::default(),
+                },
+                safe: Safe::from_stack(
+                    H::new_for_io_pattern(io_pattern.as_bytes(), iv, iv)
+                        .expect("Malformed IO Pattern"),
+                    stack.clone(),
+                    labels.clone(),
+                ),
+                transcript: Vec::new(),
+                sink: None,
+            };
+            let prover = outcome(&prove, &mut merlin, Merlin::remaining_ops);
+            merlin.safe.clear_ops();
+
+            let mut arthur = Arthur {
+                safe: Safe::from_stack(
+                    H::new_for_io_pattern(io_pattern.as_bytes(), iv, iv)
+                        .expect("Malformed IO Pattern"),
+                    stack,
+                    labels,
+                ),
+                transcript: transcript.as_slice().into(),
+                allow_trailing_bytes: false,
+                failure: None,
+            };
+            let verifier = outcome(&verify, &mut arthur, Arthur::remaining_ops);
+            arthur.safe.clear_ops();
+
+            MisuseReport {
+                misuse,
+                prover,
+                verifier,
+            }
+        })
+        .collect()
+}
+
+/// Assert that `prove` and `verify` catch every canned [`Misuse`] of `io_pattern` (see [`MisuseReport::is_caught`]).
+///
+/// # Panics
+///
+/// Panics listing the scenarios that were not caught.
+pub fn assert_misuse_rejected<H, U>(
+    io_pattern: &IOPattern<H, U>,
+    prove: impl Fn(&mut Merlin<H, U>) -> ProofResult<()>,
+    verify: impl Fn(&mut Arthur<H, U>) -> ProofResult<()>,
+) where
+    U: Unit,
+    H: DuplexHash<U>,
+{
+    let missed = check_misuse(io_pattern, prove, verify)
+        .into_iter()
+        .filter(|report| !report.is_caught())
+        .collect::<Vec<_>>();
+    assert!(
+        missed.is_empty(),
+        "Transcript misuse not caught: {:#?}",
+        missed
+    );
+}
+
+/// A toy sponge over the prime field `F`, not to be used outside of tests.
+///
+/// Its permutation is cheap and has no security whatsoever.
+#[doc(hidden)]
+#[cfg(all(feature = "ark", any(test, feature = "test-utils")))]
+#[derive(Clone, Default)]
+pub struct ToySponge<F>([F; 3]);
+
+#[cfg(all(feature = "ark", any(test, feature = "test-utils")))]
+impl<F: zeroize::Zeroize> zeroize::Zeroize for ToySponge<F> {
+    fn zeroize(&mut self) {
+        self.0.zeroize()
+    }
+}
+
+#[cfg(all(feature = "ark", any(test, feature = "test-utils")))]
+impl<F> AsRef<[F]> for ToySponge<F> {
+    fn as_ref(&self) -> &[F] {
+        &self.0
+    }
+}
+
+#[cfg(all(feature = "ark", any(test, feature = "test-utils")))]
+impl<F> AsMut<[F]> for ToySponge<F> {
+    fn as_mut(&mut self) -> &mut [F] {
+        &mut self.0
+    }
+}
+
+#[cfg(all(feature = "ark", any(test, feature = "test-utils")))]
+impl<F: ark_ff::PrimeField + Unit> crate::hash::sponge::Sponge for ToySponge<F> {
+    type U = F;
+    const N: usize = 3;
+    const R: usize = 2;
+
+    fn new(iv: [u8; 32]) -> Self {
+        let mut sponge = Self::default();
+        sponge.0[Self::R] = F::from_le_bytes_mod_order(&iv);
+        sponge
+    }
+
+    fn permute(&mut self) {
+        for _ in 0..8 {
+            let [a, b, c] = self.0;
+            self.0 = [b + c.pow([5]), c + a + F::ONE, a + b.square()];
+        }
+    }
+}
@@ -1,13 +1,22 @@
 use rand::RngCore;
 
+#[cfg(feature = "strobe")]
+use crate::hash::compat::Strobe128;
 use crate::hash::keccak::Keccak;
+#[cfg(feature = "legacy")]
 use crate::hash::legacy::DigestBridge;
 use crate::{
-    ByteChallenges, BytePublic, ByteReader, ByteWriter, DuplexHash, IOPattern, Merlin, Safe,
+    Arthur, ByteChallenges, ByteIOPattern, BytePublic, ByteReader, ByteWriter, ChallengeMod,
+    ChallengeModIOPattern, DuplexHash, IOPattern, IntegerIOPattern, IntegerPublic, IntegerReader,
+    IntegerWriter, Merlin, OpParseError, OpParseErrorKind, Proof, ProofError, ProofResult,
+    ProtocolRegistry, Safe, UnitTranscript,
 };
 
+#[cfg(feature = "sha2")]
 type Sha2 = DigestBridge<sha2::Sha256>;
+#[cfg(feature = "legacy")]
 type Blake2b512 = DigestBridge<blake2::Blake2b512>;
+#[cfg(feature = "legacy")]
 type Blake2s256 = DigestBridge<blake2::Blake2s256>;
 
 /// How should a protocol without actual IO be handled?
@@ -18,6 +27,326 @@ fn test_iopattern() {
     assert!(iop.as_bytes().starts_with(b"example.com"));
 }
 
+/// The IO Pattern digest identifies the protocol and is checked upon request.
+#[test]
+fn test_iopattern_digest() {
+    let iop = IOPattern::<Keccak>::new("example.com").absorb(1, "in");
+    let other_iop = IOPattern::<Keccak>::new("example.com").absorb(2, "in");
+    assert_eq!(iop.digest(), iop.clone().digest());
+    assert_ne!(iop.digest(), other_iop.digest());
+
+    let mut arthur = iop.to_arthur_checked(b"\x42", &iop.digest()).unwrap();
+    assert_eq!(arthur.next_bytes().unwrap(), [0x42]);
+    assert!(iop.to_arthur_checked(b"\x42", &other_iop.digest()).is_err());
+
+    // the cached digest is not carried over when the pattern is extended
+    let extended = iop.clone().absorb(1, "in");
+    let fresh = IOPattern::<Keccak>::new("example.com")
+        .absorb(1, "in")
+        .absorb(1, "in");
+    assert_eq!(extended.digest(), fresh.digest());
+    assert_ne!(iop.digest(), extended.digest());
+}
+
+/// The hash computing the IV is recorded in the header of the IO Pattern.
+#[test]
+fn test_iopattern_iv_hash() {
+    use crate::hash::{HashBackend, IvHash};
+
+    let io = IOPattern::<Keccak>::new("example.com").absorb(1, "in");
+    let keccak = IOPattern::<Keccak>::new("example.com")
+        .with_iv_hash(HashBackend::Keccak)
+        .absorb(1, "in");
+    assert_eq!(io.iv_hash(), Ok(IvHash::Backend(HashBackend::Keccak)));
+    assert_eq!(keccak.as_bytes(), b"example.com\0Ikeccak\0A1in");
+    // the header is part of the IV
+    assert_ne!(io.digest(), keccak.digest());
+    // and can be set after the operations
+    let late = IOPattern::<Keccak>::new("example.com")
+        .absorb(1, "in")
+        .with_iv_hash(HashBackend::Keccak);
+    assert_eq!(late.as_bytes(), keccak.as_bytes());
+
+    // domain separators cannot select the IV hash
+    let io = IOPattern::<Keccak>::new("example.com{iv=shake128}").absorb(1, "in");
+    assert_eq!(io.iv_hash(), Ok(IvHash::Backend(HashBackend::Keccak)));
+    assert_eq!(io.as_bytes(), b"example.com{iv=shake128}\0A1in");
+
+    #[cfg(feature = "shake")]
+    {
+        use crate::hash::Shake128;
+
+        let io = IOPattern::<Keccak>::new("example.com")
+            .with_iv_hash(HashBackend::Shake128)
+            .absorb(1, "in")
+            .squeeze(16, "out");
+        assert_eq!(io.iv_hash(), Ok(IvHash::Backend(HashBackend::Shake128)));
+        let mut expected = [0u8; 32];
+        Shake128::default()
+            .absorb_unchecked(io.as_bytes())
+            .squeeze_unchecked(&mut expected);
+        assert_eq!(io.digest(), expected);
+
+        let mut merlin = io.to_merlin();
+        merlin.add_bytes(&[0x42]).unwrap();
+        let chal = merlin.challenge_bytes::<16>().unwrap();
+        let mut arthur = io.to_arthur(merlin.transcript());
+        assert_eq!(arthur.next_bytes().unwrap(), [0x42]);
+        assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+
+        // the IV can be derived with the protocol hash itself
+        let io = IOPattern::<Shake128>::new("example.com")
+            .with_iv_hash(IvHash::Protocol)
+            .absorb(1, "in");
+        assert_eq!(io.iv_hash(), Ok(IvHash::Protocol));
+        let mut expected = [0u8; 32];
+        Shake128::default()
+            .absorb_unchecked(io.as_bytes())
+            .squeeze_unchecked(&mut expected);
+        assert_eq!(io.try_digest(), Ok(expected));
+    }
+
+    // unknown hashes are rejected
+    let err = IOPattern::<Keccak>::from_bytes(b"example.com\0Imd5\0A1in").unwrap_err();
+    assert_eq!(
+        err,
+        OpParseError {
+            position: 12,
+            kind: OpParseErrorKind::UnknownBackend
+        }
+    );
+    let io = IOPattern::<Keccak>::from_string("example.com\0Imd5".to_string());
+    assert!(io.iv_hash().is_err());
+    assert!(io.try_digest().is_err());
+    assert!(Safe::<Keccak>::try_new(&io).is_err());
+}
+
+/// Malformed IO Patterns are rejected, and not a cause of panic.
+#[test]
+fn test_malformed_iopattern() {
+    use crate::fuzzing::{parse_iopattern, run_arthur};
+
+    assert!(parse_iopattern(b"domsep\0").is_err());
+    assert!(parse_iopattern(b"domsep\0A1\0\0S1").is_err());
+    assert!(parse_iopattern(b"domsep\0A99999999999999999999999").is_err());
+    assert!(parse_iopattern(b"domsep\0A18446744073709551615\0A1").is_err());
+    assert!(parse_iopattern(b"domsep\0R1").is_err());
+    assert_eq!(parse_iopattern(b"domsep\0A1\0A2\0R\0S3").unwrap(), 3);
+
+    let error = |position, kind| Err(OpParseError { position, kind });
+    let parse = |io: &[u8]| IOPattern::<Keccak>::from_bytes(io).map(|io| io.finalize().unwrap());
+    assert_eq!(parse(b"ds\0A1\0"), error(6, OpParseErrorKind::EmptyOp));
+    assert_eq!(
+        parse(b"ds\0A1\0X2"),
+        error(6, OpParseErrorKind::UnknownOp(b'X'))
+    );
+    assert_eq!(
+        parse(b"ds\0R\0S"),
+        error(5, OpParseErrorKind::InvalidLength)
+    );
+    assert_eq!(
+        parse(b"ds\0A1\xff"),
+        error(5, OpParseErrorKind::InvalidUtf8)
+    );
+    assert_eq!(
+        parse(b"ds\0A18446744073709551615\0A1"),
+        error(25, OpParseErrorKind::LengthOverflow)
+    );
+    assert!(Arthur::try_new(&IOPattern::<Keccak>::from_string("ds\0A".into()), b"").is_err());
+
+    // ratcheting past the end of the pattern, reading past the end of the transcript.
+    assert_eq!(run_arthur(b"domsep\0A1\0R", &[3, 3, 4, 3], b""), 0);
+    assert_eq!(run_arthur(b"domsep\0A1\0R\0S1", &[4, 3, 6], b"x"), 3);
+    assert_eq!(run_arthur(b"\xff\0A1", &[4], b"x"), 0);
+}
+
+/// Static IO Patterns are built once, with their digest, and shared across threads.
+#[test]
+fn test_static_io_pattern() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<IOPattern<Keccak>>();
+    #[cfg(feature = "strobe")]
+    assert_send_sync::<IOPattern<Strobe128>>();
+
+    crate::static_io_pattern! {
+        fn io_pattern() -> IOPattern<Keccak> = IOPattern::new("example.com").absorb(1, "in");
+    }
+    let digests = (0..4)
+        .map(|_| std::thread::spawn(|| (io_pattern() as *const _ as usize, io_pattern().digest())))
+        .map(|handle| handle.join().unwrap())
+        .collect::<std::collections::BTreeSet<_>>();
+    assert_eq!(digests.len(), 1);
+    assert_eq!(
+        io_pattern().digest(),
+        IOPattern::<Keccak>::new("example.com")
+            .absorb(1, "in")
+            .digest()
+    );
+}
+
+/// Canonical IO Patterns drop cosmetic differences, and merge consecutive absorbs (resp. squeezes).
+#[test]
+fn test_iopattern_canonicalize() {
+    let canonical = |io: &str| {
+        IOPattern::<Keccak>::from_string(io.into())
+            .canonicalize()
+            .as_bytes()
+            .to_vec()
+    };
+    assert_eq!(canonical("ds\0A1a\0A02b\0S1c"), b"ds\0A3a, b\0S1c");
+    assert_eq!(canonical("ds\0A1a\0S0x\0A2b"), b"ds\0A3a, b");
+    assert_eq!(canonical("ds\0A1a\0R0\0A2b\0C"), b"ds\0A1a\0R\0A2b\0C");
+    // the labels of markers are kept as they are
+    assert_eq!(canonical("ds\0A1a\0M q \0A1b"), b"ds\0A1a\0M q \0A1b");
+    // trimming does not make a label start with a digit
+    assert_eq!(canonical("ds\0A1 2x \0S1 c"), b"ds\0A1 2x\0S1c");
+    // malformed operations are kept as they are
+    assert_eq!(canonical("ds\0\0X1"), b"ds\0\0X1");
+    // ratchets, segment boundaries and markers are never merged
+    assert_eq!(canonical("ds\0R\0R\0C\0C"), b"ds\0R\0R\0C\0C");
+    assert_eq!(canonical("ds\0Mq\0Mq"), b"ds\0Mq\0Mq");
+
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(1, "in")
+        .absorb(1, "in")
+        .squeeze(0, "comment")
+        .squeeze(16, "out");
+    let other_io = IOPattern::<Keccak>::new("example.com")
+        .absorb(1, " in")
+        .absorb(1, "in ")
+        .squeeze(16, "out");
+    assert_eq!(
+        io.canonicalize().canonicalize().as_bytes(),
+        io.canonicalize().as_bytes()
+    );
+    // the IV binds the canonical form of the IO Pattern
+    assert_eq!(io.digest(), other_io.digest());
+    assert_eq!(io.digest(), io.canonicalize().digest());
+    assert_ne!(io.as_bytes(), other_io.as_bytes());
+
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1, 2]).unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    let mut arthur = other_io.to_arthur(merlin.transcript());
+    arthur.next_bytes::<2>().unwrap();
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+}
+
+/// Rewriting the labels of malformed IO Patterns never panics.
+#[test]
+fn test_iopattern_malformed_labels() {
+    for malformed in ["ds\0", "ds\0A1a\0\0S1b", "ds\0🍝1", "ds\0A1a\0é"] {
+        let io = IOPattern::<Keccak>::from_string(malformed.into());
+        assert_eq!(io.redact_labels(b"salt").as_bytes(), malformed.as_bytes());
+        assert_eq!(io.canonicalize().as_bytes(), malformed.as_bytes());
+
+        let io = IOPattern::<Keccak>::new("ds")
+            .absorb(1, "a")
+            .rounds("round", 2, |_, _| IOPattern::from_string(malformed.into()));
+        assert!(io.finalize().is_err());
+    }
+}
+
+/// Protocols of a registry have pairwise distinct IVs, and are looked up by identifier and version.
+#[test]
+fn test_protocol_registry() {
+    let mut registry = ProtocolRegistry::<Keccak>::new("example.com");
+    for (protocol, version) in [("a", 1), ("a", 2), ("b", 1), ("ab", 1)] {
+        registry
+            .register(protocol, version, |io| io.absorb(1, "in"))
+            .unwrap();
+    }
+    assert!(registry.register("a", 1, |io| io).is_err());
+    assert!(registry.register("a/b", 1, |io| io).is_err());
+    assert!(registry.register("a\0b", 1, |io| io).is_err());
+    assert!(registry
+        .register("c", 1, |_| IOPattern::new("example.com/a/v1"))
+        .is_err());
+
+    let digests = registry
+        .iter()
+        .map(|(_, _, io)| io.digest())
+        .collect::<std::collections::BTreeSet<_>>();
+    assert_eq!(digests.len(), 4);
+    assert_eq!(
+        registry.get("b", 1).unwrap().as_bytes(),
+        IOPattern::<Keccak>::new("example.com/b/v1")
+            .absorb(1, "in")
+            .as_bytes()
+    );
+
+    assert!(registry.to_arthur("a", 2, b"x").is_ok());
+    assert!(registry.to_arthur("a", 3, b"x").is_err());
+    assert!(registry.to_arthur("c", 1, b"x").is_err());
+}
+
+/// Repeated rounds are labeled with their index, and behave as if written by hand.
+#[test]
+fn test_iopattern_rounds() {
+    let io = IOPattern::<Keccak>::new("example.com").rounds("fold", 3, |io, i| {
+        io.absorb(2, "com").squeeze(1 + i, "chal").ratchet()
+    });
+    let expected = IOPattern::<Keccak>::new("example.com")
+        .absorb(2, "fold[0].com")
+        .squeeze(1, "fold[0].chal")
+        .ratchet()
+        .absorb(2, "fold[1].com")
+        .squeeze(2, "fold[1].chal")
+        .ratchet()
+        .absorb(2, "fold[2].com")
+        .squeeze(3, "fold[2].chal")
+        .ratchet();
+    assert_eq!(io.as_bytes(), expected.as_bytes());
+    assert_eq!(io.finalize().unwrap(), expected.finalize().unwrap());
+
+    let io = IOPattern::<Keccak>::new("example.com")
+        .repeat(2, |io| io.repeat(2, |io| io.absorb(1, "in")))
+        .repeat(0, |io| io.absorb(1, "never"));
+    let expected = IOPattern::<Keccak>::new("example.com")
+        .absorb(1, "[0].[0].in")
+        .absorb(1, "[0].[1].in")
+        .absorb(1, "[1].[0].in")
+        .absorb(1, "[1].[1].in");
+    assert_eq!(io.as_bytes(), expected.as_bytes());
+}
+
+/// Squeezing into uninitialized buffers is equivalent to squeezing.
+#[test]
+fn test_challenge_units_uninit() {
+    use core::mem::MaybeUninit;
+
+    fn check_uninit<H: DuplexHash<u8>>() {
+        let io = IOPattern::<H>::new("example.com")
+            .absorb(1, "in")
+            .squeeze(1000, "chal");
+        let mut expected = [0u8; 1000];
+        let mut merlin = io.to_merlin();
+        merlin.add_bytes(&[0x42]).unwrap();
+        merlin.fill_challenge_bytes(&mut expected[..3]).unwrap();
+        merlin.fill_challenge_bytes(&mut expected[3..]).unwrap();
+
+        let mut arthur = io.to_arthur(merlin.transcript());
+        arthur.fill_next_bytes(&mut [0u8]).unwrap();
+        let mut output = vec![MaybeUninit::uninit(); 1000];
+        let (first, rest) = output.split_at_mut(3);
+        assert_eq!(
+            arthur.fill_challenge_units_uninit(first).unwrap(),
+            &expected[..3]
+        );
+        assert_eq!(
+            arthur.fill_challenge_units_uninit(rest).unwrap(),
+            &expected[3..]
+        );
+        assert!(arthur
+            .fill_challenge_units_uninit(&mut [MaybeUninit::uninit()])
+            .is_err());
+    }
+
+    check_uninit::<Keccak>();
+    #[cfg(feature = "sha2")]
+    check_uninit::<DigestBridge<sha2::Sha256>>();
+}
+
 /// Test Merlin's rng is not doing completely stupid things.
 #[test]
 fn test_merlin_rng_basic() {
@@ -35,6 +364,24 @@ fn test_merlin_rng_basic() {
     assert!(random_bytes.iter().any(|&x| x != random_bytes[0]));
 }
 
+/// Deterministic prover coins depend on the secret and on the protocol transcript only.
+#[test]
+fn test_deterministic_prover_rng() {
+    use crate::DeterministicProverRng;
+
+    let iop = IOPattern::<Keccak>::new("example.com").absorb(4, "statement");
+    let coins = |secret: &[u8], statement: &[u8]| {
+        let mut merlin = Merlin::new(&iop, DeterministicProverRng::from_secret(secret));
+        merlin.public_bytes(statement).unwrap();
+        let mut coins = [0u8; 32];
+        merlin.rng().fill_bytes(&mut coins);
+        coins
+    };
+    assert_eq!(coins(b"sk", b"1234"), coins(b"sk", b"1234"));
+    assert_ne!(coins(b"sk", b"1234"), coins(b"sk", b"1235"));
+    assert_ne!(coins(b"sk", b"1234"), coins(b"another sk", b"1234"));
+}
+
 /// Test adding of public bytes and non-public elements to the transcript.
 #[test]
 fn test_merlin_bytewriter() {
@@ -53,6 +400,19 @@ fn test_merlin_bytewriter() {
     assert_eq!(merlin.transcript(), b"");
 }
 
+/// Any function filling buffers seeds the prover's coins.
+#[test]
+fn test_csprng_fn() {
+    let io = IOPattern::<Keccak>::new("example.com").absorb(1, "msg");
+    let coins = |seed: u8| {
+        let mut merlin = Merlin::new(&io, crate::CsprngFn(|buf: &mut [u8]| buf.fill(seed)));
+        merlin.add_bytes(&[0x42]).unwrap();
+        merlin.rng().next_u64()
+    };
+    assert_eq!(coins(1), coins(1));
+    assert_ne!(coins(1), coins(2));
+}
+
 /// A protocol flow that does not match the IOPattern should fail.
 #[test]
 fn test_invalid_io_sequence() {
@@ -90,6 +450,21 @@ fn test_deterministic() {
     assert_eq!(first, second);
 }
 
+/// The SAFE sponge offers the same challenges as the prover and the verifier.
+#[test]
+fn test_safe_challenges() {
+    let iop = IOPattern::<Keccak>::new("example.com")
+        .absorb(3, "elt")
+        .squeeze(16, "chal");
+    let mut merlin = iop.to_merlin();
+    merlin.add_bytes(b"123").unwrap();
+    let expected = merlin.challenge_bytes::<16>().unwrap();
+
+    let mut safe = Safe::<Keccak>::new(&iop);
+    safe.public_bytes(b"123").unwrap();
+    assert_eq!(safe.challenge_bytes::<16>().unwrap(), expected);
+}
+
 /// Basic scatistical test to check that the squeezed output looks random.
 #[test]
 fn test_statistics() {
@@ -131,6 +506,193 @@ fn test_transcript_readwrite() {
     assert_eq!(arthur_challenges, merlin_challenges);
 }
 
+/// Integers are encoded with the endianness in the method name, and their type in the label.
+#[test]
+fn test_integer_encoding() {
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .add_u64_be("epoch")
+        .add_u32_le("slot")
+        .squeeze(16, "chal");
+    assert_eq!(
+        io.as_bytes(),
+        b"domain separator\0A8epoch.u64_be\0A4slot.u32_le\0S16chal"
+    );
+
+    let mut merlin = io.to_merlin();
+    merlin.public_u64_be(0x0102030405060708).unwrap();
+    merlin.add_u32_le(0x01020304).unwrap();
+    assert_eq!(merlin.transcript(), [4, 3, 2, 1]);
+    let merlin_challenges = merlin.challenge_bytes::<16>().unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.public_u64_be(0x0102030405060708).unwrap();
+    assert_eq!(arthur.next_u32_le().unwrap(), 0x01020304);
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), merlin_challenges);
+}
+
+/// Signed integers are ZigZag-encoded, and slices of integers share the pattern of single ones.
+#[test]
+fn test_integer_slices_encoding() {
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .add_i64s(4, "digits")
+        .add_u128s(1, "balance")
+        .squeeze(16, "chal");
+    assert_eq!(
+        io.as_bytes(),
+        b"domain separator\0A32digits.i64_zigzag_le\0A16balance.u128_le\0S16chal"
+    );
+    assert_eq!(
+        IOPattern::<Keccak>::new("").add_u128s(1, "x").as_bytes(),
+        IOPattern::<Keccak>::new("").add_u128_le("x").as_bytes()
+    );
+
+    let digits = [0, -1, 1, i64::MIN];
+    let mut merlin = io.to_merlin();
+    merlin.add_i64s(&digits).unwrap();
+    merlin.public_u128s(&[u128::MAX - 1]).unwrap();
+    assert_eq!(
+        &merlin.transcript()[..24],
+        [[0; 8], [1, 0, 0, 0, 0, 0, 0, 0], [2, 0, 0, 0, 0, 0, 0, 0]].concat()
+    );
+    assert_eq!(&merlin.transcript()[24..], [0xff; 8]);
+    let merlin_challenges = merlin.challenge_bytes::<16>().unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert_eq!(arthur.next_i64s::<4>().unwrap(), digits);
+    arthur.public_u128s(&[u128::MAX - 1]).unwrap();
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), merlin_challenges);
+}
+
+/// Challenges modulo an integer record the modulus in the pattern, and are reduced the same way by prover and verifier.
+#[test]
+fn test_challenge_mod() {
+    assert_eq!(crate::challenge_mod_bytes(1), 16);
+    assert_eq!(crate::challenge_mod_bytes(256), 17);
+    assert_eq!(crate::challenge_mod_bytes(257), 18);
+    assert_eq!(crate::challenge_mod_bytes(u64::MAX), 24);
+
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .challenge_mod(17, "index")
+        .challenge_mod(u64::MAX, "large");
+    assert_eq!(
+        io.as_bytes(),
+        b"domain separator\0S17index.mod17\0S24large.mod18446744073709551615"
+    );
+
+    let mut merlin = io.to_merlin();
+    let (index, large) = (
+        merlin.challenge_mod(17).unwrap(),
+        merlin.challenge_mod(u64::MAX).unwrap(),
+    );
+    assert!(index < 17 && large < u64::MAX);
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert_eq!(arthur.challenge_mod(17).unwrap(), index);
+    assert_eq!(arthur.challenge_mod(u64::MAX).unwrap(), large);
+
+    // the number of bytes squeezed must match the declared modulus.
+    let io = IOPattern::<Keccak>::new("domain separator").challenge_mod(1 << 20, "index");
+    assert!(io.to_merlin().challenge_mod(1 << 30).is_err());
+    // the modulus itself is not checked: only the number of bytes squeezed is.
+    assert!(io.to_merlin().challenge_mod(1 << 21).is_ok());
+    let io = IOPattern::<Keccak>::new("domain separator").challenge_bytes(18, "index");
+    assert!(io.to_merlin().challenge_mod(257).is_ok());
+    // a zero modulus is an error, not a panic.
+    assert!(io.to_merlin().challenge_mod(0).is_err());
+}
+
+#[test]
+fn test_challenge_queries() {
+    use crate::{ChallengeQueries, ChallengeQueriesIOPattern};
+
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .challenge_queries(8, 4, true, "queries")
+        .challenge_queries(3, 1 << 20, false, "more");
+    assert_eq!(
+        io.as_bytes(),
+        b"domain separator\0S136queries.mod4\0A8queries.count.u64_le\0S57more.mod1048576"
+    );
+
+    let mut merlin = io.to_merlin();
+    let queries = merlin.challenge_queries(8, 4, true).unwrap();
+    let more = merlin.challenge_queries(3, 1 << 20, false).unwrap();
+    assert!(!queries.is_empty() && queries.windows(2).all(|w| w[0] < w[1] && w[1] < 4));
+    assert_eq!(more.len(), 3);
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert_eq!(arthur.challenge_queries(8, 4, true).unwrap(), queries);
+    assert_eq!(arthur.challenge_queries(3, 1 << 20, false).unwrap(), more);
+
+    // the count is part of the IO Pattern: skipping deduplication is a mismatch
+    assert!(io
+        .to_merlin()
+        .challenge_queries(8, 4, false)
+        .is_ok_and(|q| q.len() == 8));
+    let mut merlin = io.to_merlin();
+    merlin.challenge_queries(8, 4, false).unwrap();
+    assert!(merlin.challenge_queries(3, 1 << 20, false).is_err());
+    // an empty domain is an error, not a panic
+    assert!(io.to_merlin().challenge_queries(8, 0, true).is_err());
+}
+
+/// Segments can be verified sequentially, or independently given the previous chaining digest.
+#[test]
+fn test_segments() {
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .absorb(2, "first")
+        .squeeze(16, "chal")
+        .segment()
+        .absorb(2, "second")
+        .segment()
+        .absorb(2, "third")
+        .squeeze(16, "chal");
+    assert_eq!(
+        io.as_bytes(),
+        b"domain separator\0A2first\0S16chal\0C\0A2second\0C\0A2third\0S16chal"
+    );
+
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(b"ab").unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    let (first, first_digest) = merlin.seal_segment().unwrap();
+    merlin.add_bytes(b"cd").unwrap();
+    let (second, second_digest) = merlin.seal_segment().unwrap();
+    merlin.add_bytes(b"ef").unwrap();
+    let final_chal = merlin.challenge_bytes::<16>().unwrap();
+    let third = merlin.transcript().to_vec();
+    assert_eq!(
+        (first.as_slice(), second.as_slice(), third.as_slice()),
+        (&b"ab"[..], &b"cd"[..], &b"ef"[..])
+    );
+    assert_ne!(first_digest, second_digest);
+
+    // sequential verification
+    let mut arthur = io.to_arthur(&first);
+    assert_eq!(&arthur.next_bytes().unwrap(), b"ab");
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+    arthur.open_segment(&first_digest, &second).unwrap();
+    assert_eq!(&arthur.next_bytes().unwrap(), b"cd");
+    arthur.open_segment(&second_digest, &third).unwrap();
+    assert_eq!(&arthur.next_bytes().unwrap(), b"ef");
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), final_chal);
+
+    // independent verification of the last segment
+    let mut arthur = io.to_arthur_segment(2, &second_digest, &third).unwrap();
+    assert_eq!(&arthur.next_bytes().unwrap(), b"ef");
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), final_chal);
+    assert!(io.to_arthur_segment(3, &second_digest, &third).is_err());
+
+    // the chaining digest binds the previous segments
+    let mut arthur = io.to_arthur(b"ba");
+    arthur.next_bytes::<2>().unwrap();
+    arthur.challenge_bytes::<16>().unwrap();
+    assert!(arthur.open_segment(&first_digest, &second).is_err());
+    // segments must be read entirely
+    let mut arthur = io.to_arthur_segment(1, &first_digest, b"cdx").unwrap();
+    arthur.next_bytes::<2>().unwrap();
+    assert!(arthur.open_segment(&second_digest, &third).is_err());
+}
+
 /// An IO that is not fully finished should fail.
 #[test]
 #[should_panic]
@@ -155,8 +717,1066 @@ fn test_merlin_empty_absorb() {
     assert!(io.to_arthur(b"").next_bytes::<1>().is_err());
 }
 
+/// Zero-length operations are no-ops, both in the IO Pattern and at runtime.
+#[test]
+fn test_zero_length_ops() {
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .absorb(1, "in")
+        .absorb(0, "comment")
+        .squeeze(0, "nothing")
+        .absorb(1, "in")
+        .squeeze(1, "out");
+    assert_eq!(
+        io.finalize().unwrap(),
+        IOPattern::<Keccak>::new("domain separator")
+            .absorb(2, "in")
+            .squeeze(1, "out")
+            .finalize()
+            .unwrap()
+    );
+
+    let mut merlin = io.to_merlin();
+    merlin.fill_challenge_bytes(&mut []).unwrap();
+    merlin.add_bytes(&[1]).unwrap();
+    merlin.fill_challenge_bytes(&mut []).unwrap();
+    merlin.add_bytes(&[]).unwrap();
+    merlin.add_bytes(&[2]).unwrap();
+    merlin.add_bytes(&[]).unwrap();
+    let chal = merlin.challenge_bytes::<1>().unwrap();
+    // empty absorbs and squeezes are no-ops also after the end of the IO Pattern
+    merlin.add_bytes(&[]).unwrap();
+    merlin.fill_challenge_bytes(&mut []).unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.fill_next_bytes(&mut []).unwrap();
+    assert_eq!(arthur.next_bytes().unwrap(), [1, 2]);
+    arthur.public_bytes(&[]).unwrap();
+    assert_eq!(arthur.challenge_bytes::<1>().unwrap(), chal);
+}
+
+/// The progress of Merlin and Arthur is reported after each completed operation.
+#[test]
+fn test_op_progress() {
+    use std::sync::{Arc, Mutex};
+
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .absorb(1, "a")
+        .absorb(2, "b")
+        .squeeze(0, "comment")
+        .ratchet()
+        .squeeze(4, "c");
+    let mut merlin = io.to_merlin();
+    assert_eq!((merlin.total_ops(), merlin.remaining_ops()), (3, 3));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let log = events.clone();
+    merlin.on_op_completed(move |p| {
+        log.lock()
+            .unwrap()
+            .push((p.label.to_string(), p.completed, p.total))
+    });
+    merlin.add_bytes(&[1, 2]).unwrap();
+    assert_eq!(merlin.remaining_ops(), 3);
+    merlin.add_bytes(&[3]).unwrap();
+    assert_eq!(merlin.remaining_ops(), 2);
+    merlin.ratchet().unwrap();
+    merlin.challenge_bytes::<4>().unwrap();
+    assert_eq!(merlin.remaining_ops(), 0);
+    let expected = [
+        ("a".to_string(), 1, 3),
+        (String::new(), 2, 3),
+        ("c".to_string(), 3, 3),
+    ];
+    assert_eq!(*events.lock().unwrap(), expected);
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let log = events.clone();
+    arthur.on_op_completed(move |p| {
+        log.lock()
+            .unwrap()
+            .push((p.label.to_string(), p.completed, p.total))
+    });
+    arthur.next_bytes::<3>().unwrap();
+    arthur.ratchet().unwrap();
+    arthur.challenge_bytes::<4>().unwrap();
+    assert_eq!(*events.lock().unwrap(), expected);
+}
+
+/// Merged operations keep the label of each of their components.
+#[test]
+fn test_label_spans() {
+    use crate::LabelSpan;
+    use std::sync::{Arc, Mutex};
+
+    let span = |label: &str, range| LabelSpan {
+        label: label.to_string(),
+        range,
+    };
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .squeeze(16, "a")
+        .squeeze(0, "comment")
+        .squeeze(8, "b")
+        .marker("phase")
+        .absorb(2, "c")
+        .segment()
+        .absorb(1, "d");
+    let expected = vec![
+        vec![span("a", 0..16), span("b", 16..24)],
+        vec![span("phase", 0..0)],
+        vec![span("c", 0..2)],
+        vec![],
+        vec![span("d", 0..1)],
+    ];
+    assert_eq!(io.label_spans(), expected);
+
+    let mut merlin = io.to_merlin();
+    let spans = Arc::new(Mutex::new(Vec::new()));
+    let log = spans.clone();
+    merlin.on_op_completed(move |p| log.lock().unwrap().push(p.spans.to_vec()));
+    merlin.challenge_bytes::<24>().unwrap();
+    merlin.marker("phase").unwrap();
+    merlin.add_bytes(&[1, 2]).unwrap();
+    merlin.seal_segment().unwrap();
+    merlin.add_bytes(&[3]).unwrap();
+    assert_eq!(*spans.lock().unwrap(), expected);
+}
+
+/// The static permutation count matches the permutations performed by Merlin and Arthur.
+#[test]
+fn test_permutation_count() {
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .absorb(100, "a")
+        .absorb(100, "b")
+        .squeeze(300, "c")
+        .ratchet()
+        .absorb(136, "d")
+        .squeeze(1, "e")
+        .squeeze(136, "f");
+    let count = io.permutation_count();
+    assert_eq!(count, 7);
+
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[0u8; 200]).unwrap();
+    merlin.fill_challenge_bytes(&mut [0u8; 300]).unwrap();
+    merlin.ratchet().unwrap();
+    merlin.add_bytes(&[0u8; 136]).unwrap();
+    merlin.fill_challenge_bytes(&mut [0u8; 137]).unwrap();
+    assert_eq!(merlin.safe.sponge().permutations(), count);
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.skip_bytes(200).unwrap();
+    arthur.fill_challenge_bytes(&mut [0u8; 300]).unwrap();
+    arthur.ratchet().unwrap();
+    arthur.skip_bytes(136).unwrap();
+    arthur.fill_challenge_bytes(&mut [0u8; 137]).unwrap();
+    assert_eq!(arthur.safe.sponge().permutations(), count);
+
+    // the count carries over segment boundaries
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .absorb(200, "a")
+        .segment()
+        .absorb(10, "b")
+        .squeeze(16, "c");
+    let count = io.permutation_count();
+    assert_eq!(count, 4);
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[0u8; 200]).unwrap();
+    let (first, digest) = merlin.seal_segment().unwrap();
+    merlin.add_bytes(&[0u8; 10]).unwrap();
+    merlin.challenge_bytes::<16>().unwrap();
+    assert_eq!(merlin.safe.sponge().permutations(), count);
+
+    let mut arthur = io.to_arthur(&first);
+    arthur.skip_bytes(200).unwrap();
+    arthur.open_segment(&digest, merlin.transcript()).unwrap();
+    arthur.skip_bytes(10).unwrap();
+    arthur.challenge_bytes::<16>().unwrap();
+    assert_eq!(arthur.safe.sponge().permutations(), count);
+}
+
+/// Prover messages are handed to the sink one by one, public messages are not.
+#[test]
+fn test_transcript_sink() {
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Messages(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    impl crate::TranscriptSink for Messages {
+        fn write_message(&mut self, message: &[u8]) {
+            self.0.lock().unwrap().push(message.to_vec());
+        }
+    }
+
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(1, "first")
+        .absorb(2, "public")
+        .squeeze(4, "chal")
+        .absorb(3, "second");
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1]).unwrap();
+    let messages = Messages::default();
+    let mut merlin = merlin.with_sink(messages.clone());
+    merlin.public_bytes(&[2, 3]).unwrap();
+    let chal = merlin.challenge_bytes::<4>().unwrap();
+    merlin.add_bytes(&[4, 5, 6]).unwrap();
+
+    // messages written before the sink is set are handed to it first.
+    assert_eq!(*messages.0.lock().unwrap(), [vec![1], vec![4, 5, 6]]);
+
+    let mut arthur = io.to_arthur(&[1, 4, 5, 6]);
+    arthur.next_bytes::<1>().unwrap();
+    arthur.public_bytes(&[2, 3]).unwrap();
+    assert_eq!(arthur.challenge_bytes::<4>().unwrap(), chal);
+    assert_eq!(arthur.next_bytes::<3>().unwrap(), [4, 5, 6]);
+}
+
+/// With a sink, the prover state does not hold the protocol transcript.
+#[test]
+fn test_transcript_sink_no_transcript() {
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(1, "first")
+        .segment()
+        .absorb(1, "second");
+    let mut merlin = io.to_merlin().with_sink(Vec::new());
+    merlin.add_bytes(&[1]).unwrap();
+    assert!(merlin.seal_segment().is_err());
+    assert!(merlin.try_transcript().is_err());
+    // the prover state is not exported into an empty proof
+    assert!(Proof::try_from(merlin).is_err());
+
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1]).unwrap();
+    assert_eq!(merlin.try_transcript().unwrap(), [1]);
+}
+
+/// With a sink, asking for the protocol transcript is a bug of the caller.
+#[test]
+#[should_panic(expected = "The protocol transcript is written to a sink")]
+fn test_transcript_sink_panics() {
+    let io = IOPattern::<Keccak>::new("example.com").absorb(1, "first");
+    let mut merlin = io.to_merlin().with_sink(Vec::new());
+    merlin.add_bytes(&[1]).unwrap();
+    merlin.transcript();
+}
+
+/// Prover messages pulled from a source are read as if concatenated after the transcript.
+#[test]
+fn test_transcript_source() {
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(3, "first")
+        .squeeze(4, "chal")
+        .absorb(4, "second");
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1, 2, 3]).unwrap();
+    let chal = merlin.challenge_bytes::<4>().unwrap();
+    merlin.add_bytes(&[4, 5, 6, 7]).unwrap();
+
+    // message boundaries need not match the IO Pattern
+    let messages = vec![vec![2], vec![], vec![3, 4], vec![5, 6, 7]];
+    let mut arthur = io.to_arthur(&[1]).with_source(messages.clone().into_iter());
+    assert_eq!(arthur.next_bytes().unwrap(), [1, 2, 3]);
+    assert_eq!(arthur.challenge_bytes::<4>().unwrap(), chal);
+    arthur.skip_bytes(4).unwrap();
+
+    // short and long sources are detected
+    let short = messages.clone().into_iter().take(3);
+    let mut arthur = io.to_arthur(&[1]).with_source(short);
+    arthur.next_bytes::<3>().unwrap();
+    arthur.challenge_bytes::<4>().unwrap();
+    assert!(arthur.skip_bytes(4).is_err());
+    let long = messages.into_iter().chain([vec![], vec![8]]);
+    let mut arthur = io.to_arthur(&[1]).with_source(long);
+    arthur.next_bytes::<3>().unwrap();
+    arthur.challenge_bytes::<4>().unwrap();
+    assert!(arthur.next_bytes::<4>().is_err());
+}
+
+/// Transcripts continuing from a shared statement are the same as if they absorbed it.
+#[test]
+fn test_shared_statement() {
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(300, "params")
+        .absorb(2, "msg")
+        .squeeze(16, "chal");
+    let params = [7u8; 300];
+
+    let mut merlin = Merlin::new(&io, crate::DeterministicProverRng::from_secret(b"seed"));
+    merlin.public_bytes(&params).unwrap();
+    merlin.add_bytes(&[1, 2]).unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    let coins = merlin.rng().next_u64();
+
+    let mut statement = crate::SharedStatement::new(&io);
+    statement.public_bytes(&params).unwrap();
+    for _ in 0..2 {
+        let csrng = crate::DeterministicProverRng::from_secret(b"seed");
+        let mut shared = statement.to_merlin_with_rng(csrng);
+        shared.add_bytes(&[1, 2]).unwrap();
+        assert_eq!(shared.challenge_bytes::<16>().unwrap(), chal);
+        assert_eq!(shared.rng().next_u64(), coins);
+        assert_eq!(shared.transcript(), merlin.transcript());
+    }
+
+    let mut arthur = statement.to_arthur(merlin.transcript());
+    assert_eq!(arthur.next_bytes().unwrap(), [1, 2]);
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+
+    // the statement follows the IO Pattern
+    let mut statement = crate::SharedStatement::new(&io);
+    assert!(statement.public_bytes(&[0u8; 303]).is_err());
+}
+
+/// A prepared statement is shared behind an `Arc` by verifiers running in parallel.
+#[test]
+fn test_shared_statement_arc() {
+    use std::sync::Arc;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<crate::SharedStatement<Keccak>>();
+
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(300, "params")
+        .absorb(1, "msg")
+        .squeeze(16, "chal");
+    let mut statement = crate::SharedStatement::new(&io);
+    statement.public_bytes(&[7u8; 300]).unwrap();
+    let statement = Arc::new(statement);
+
+    let proofs = (0..8u8)
+        .map(|i| {
+            let mut merlin = statement.to_merlin();
+            merlin.add_bytes(&[i]).unwrap();
+            let chal = merlin.challenge_bytes::<16>().unwrap();
+            (merlin.transcript().to_vec(), chal)
+        })
+        .collect::<Vec<_>>();
+    std::thread::scope(|scope| {
+        for (proof, chal) in &proofs {
+            let statement = Arc::clone(&statement);
+            scope.spawn(move || {
+                let mut arthur = Arthur::from_statement(&statement, proof);
+                arthur.next_bytes::<1>().unwrap();
+                assert_eq!(&arthur.challenge_bytes::<16>().unwrap(), chal);
+            });
+        }
+    });
+    assert_eq!(Arc::strong_count(&statement), 1);
+}
+
+/// Writing to a prover state as a byte stream is the same as adding the bytes, however they are split.
+#[test]
+fn test_merlin_io_write() {
+    use std::io::Write;
+
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(10, "message")
+        .squeeze(16, "chal")
+        .absorb(2, "response");
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(b"0123456789").unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+
+    let mut writer = io.to_merlin();
+    for chunk in [&b"0"[..], b"", b"12345", b"6789"] {
+        writer.write_all(chunk).unwrap();
+    }
+    writer.flush().unwrap();
+    assert_eq!(writer.challenge_bytes::<16>().unwrap(), chal);
+    assert_eq!(writer.transcript(), merlin.transcript());
+
+    // the response cannot be written before the challenge is squeezed
+    let mut writer = io.to_merlin();
+    writer.write_all(b"0123456789").unwrap();
+    let err = writer.write_all(b"ab").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = crate::IOPatternError::from(err);
+    assert!(err.to_string().contains("Invalid tag"), "{}", err);
+}
+
+/// Reading from a verifier state as a byte stream stops at the end of each prover message.
+#[test]
+fn test_arthur_io_read() {
+    use std::io::Read;
+
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(10, "message")
+        .squeeze(16, "chal")
+        .absorb(2, "response");
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(b"0123456789").unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    merlin.add_bytes(b"ab").unwrap();
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    let mut buf = [0u8; 4];
+    arthur.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"0123");
+    // reads are capped at the end of the message
+    assert_eq!(arthur.read(&mut [0u8; 16]).unwrap(), 6);
+    assert_eq!(arthur.read(&mut buf).unwrap(), 0);
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+    let mut response = Vec::new();
+    arthur.read_to_end(&mut response).unwrap();
+    assert_eq!(response, b"ab");
+
+    // truncated transcripts are invalid data
+    let mut arthur = io.to_arthur(&merlin.transcript()[..5]);
+    let err = arthur.read_exact(&mut [0u8; 10]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+/// Simulated transcripts have the size of real ones, and are read by the verifier.
+#[test]
+fn test_simulate() {
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .absorb(3, "a")
+        .squeeze(16, "b")
+        .ratchet()
+        .absorb(5, "c");
+
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1, 2, 3]).unwrap();
+    merlin.fill_challenge_bytes(&mut [0u8; 16]).unwrap();
+    merlin.ratchet().unwrap();
+    merlin.add_bytes(&[4, 5, 6, 7, 8]).unwrap();
+
+    let transcript = io.simulate(&mut rand::rngs::OsRng);
+    assert_eq!(transcript.len(), merlin.transcript().len());
+    assert_ne!(transcript, io.simulate(&mut rand::rngs::OsRng));
+
+    let mut arthur = io.to_arthur(&transcript);
+    assert_eq!(arthur.next_bytes::<3>().unwrap(), transcript[..3]);
+    arthur.fill_challenge_bytes(&mut [0u8; 16]).unwrap();
+    arthur.ratchet().unwrap();
+    assert_eq!(arthur.next_bytes::<5>().unwrap(), transcript[3..]);
+}
+
+/// Transcripts of the wrong length are rejected before any sponge work.
+#[test]
+fn test_try_to_arthur() {
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .absorb(3, "a")
+        .squeeze(16, "b")
+        .absorb(5, "c");
+    let transcript = io.simulate(&mut rand::rngs::OsRng);
+
+    let mut arthur = io.try_to_arthur(&transcript).unwrap();
+    arthur.next_bytes::<3>().unwrap();
+    assert!(io.try_to_arthur(&transcript[..7]).is_err());
+    assert!(io
+        .try_to_arthur(&[transcript.as_slice(), &[0]].concat())
+        .is_err());
+
+    let malformed = IOPattern::<Keccak>::from_string("domain separator\0Zx".into());
+    assert!(malformed.try_to_arthur(&[]).is_err());
+}
+
+/// Parties of a distributed prover agree on the public transcript, but not on their private coins.
+#[test]
+fn test_collaborative_merlin() {
+    use crate::collaborative::CollaborativeMerlin;
+
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(3, "shared")
+        .squeeze(16, "chal")
+        .absorb(1, "clear");
+    let shares = [vec![1u8, 2, 250], vec![3, 4, 10], vec![5, 6, 7]];
+
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[9, 12, 11]).unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    merlin.add_bytes(&[0x42]).unwrap();
+
+    let coins = (0..3)
+        .map(|party| {
+            let csrng = crate::DeterministicProverRng::from_secret(b"same seed");
+            let mut party = CollaborativeMerlin::new(&io, party, csrng);
+            party.add_shares(&shares).unwrap();
+            assert_eq!(party.challenge_bytes::<16>().unwrap(), chal);
+            party.add_bytes(&[0x42]).unwrap();
+            assert_eq!(party.transcript(), merlin.transcript());
+            party.rng().next_u64()
+        })
+        .collect::<std::collections::HashSet<_>>();
+    assert_eq!(coins.len(), 3);
+
+    // shares must be consistent
+    let mut party = CollaborativeMerlin::new(&io, 0, rand::rngs::OsRng);
+    assert!(party.add_shares(&[vec![1u8, 2, 3], vec![4, 5]]).is_err());
+    assert!(party.add_shares::<Vec<u8>>(&[]).is_err());
+    // a custom combiner, e.g. for XOR-sharing
+    party
+        .add_shares_with(&shares, |shares| {
+            (0..3)
+                .map(|i| shares.iter().fold(0, |acc, share| acc ^ share[i]))
+                .collect()
+        })
+        .unwrap();
+    assert_eq!(party.transcript(), [1 ^ 3 ^ 5, 2 ^ 4 ^ 6, 250 ^ 10 ^ 7]);
+}
+
+/// Redacted IO Patterns have the same operations, but different labels and IV.
+#[test]
+fn test_redact_labels() {
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(2, "internal-name")
+        .ratchet()
+        .squeeze(16, "challenge")
+        .segment()
+        .absorb(1, "");
+    let redacted = io.redact_labels(b"salt");
+    assert_eq!(redacted.finalize().unwrap(), io.finalize().unwrap());
+    assert_ne!(redacted.digest(), io.digest());
+    assert_ne!(redacted.digest(), io.redact_labels(b"pepper").digest());
+    assert_eq!(redacted.as_bytes(), io.redact_labels(b"salt").as_bytes());
+    let redacted_str = String::from_utf8(redacted.as_bytes().to_vec()).unwrap();
+    assert!(!redacted_str.contains("example.com"));
+    assert!(!redacted_str.contains("internal-name"));
+    assert!(IOPattern::<Keccak>::from_bytes(redacted.as_bytes()).is_ok());
+
+    let mut merlin = redacted.to_merlin();
+    merlin.add_bytes(&[1, 2]).unwrap();
+    merlin.ratchet().unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+
+    let mut arthur = redacted.to_arthur(merlin.transcript());
+    arthur.next_bytes::<2>().unwrap();
+    arthur.ratchet().unwrap();
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+
+    // the IV hash is kept
+    let io = IOPattern::<Keccak>::new("example.com")
+        .with_iv_hash(crate::hash::IvHash::Protocol)
+        .absorb(1, "internal-name");
+    let redacted = io.redact_labels(b"salt");
+    assert_eq!(redacted.iv_hash(), io.iv_hash());
+    assert_eq!(redacted.finalize().unwrap(), io.finalize().unwrap());
+    assert!(!String::from_utf8_lossy(redacted.as_bytes()).contains("example.com"));
+}
+
+/// Replaying a transcript yields the messages and challenges seen by prover and verifier.
+#[test]
+fn test_replay() {
+    use crate::replay::{self, Event};
+
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(2, "msg")
+        .ratchet()
+        .squeeze(16, "chal")
+        .segment()
+        .absorb(1, "last");
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1, 2]).unwrap();
+    merlin.ratchet().unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    let (mut transcript, digest) = merlin.seal_segment().unwrap();
+    merlin.add_bytes(&[3]).unwrap();
+    transcript.extend_from_slice(merlin.transcript());
+
+    let events = replay::describe(io.as_bytes(), &transcript).unwrap();
+    assert_eq!(events.len(), 5);
+    assert!(
+        matches!(&events[0], Event::Message { label, bytes, .. } if label == "msg" && bytes == &[1, 2])
+    );
+    assert!(matches!(events[1], Event::Ratchet { .. }));
+    assert_eq!(events[2].bytes(), chal);
+    assert_eq!(events[3], Event::Segment { digest });
+    assert_eq!(events[4].bytes(), [3]);
+    // the running digests differ at each step
+    assert_ne!(events[0].digest(), events[4].digest());
+
+    assert!(replay::describe(io.as_bytes(), &transcript[1..]).is_err());
+    transcript.push(0);
+    assert!(replay::describe(io.as_bytes(), &transcript).is_err());
+    assert!(replay::describe(b"example.com\0Amsg", &[]).is_err());
+
+    // public inputs are absorbed without being read from the proof
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(1, "statement")
+        .absorb(2, "msg")
+        .squeeze(16, "chal");
+    let mut merlin = io.to_merlin();
+    merlin.public_bytes(&[7]).unwrap();
+    merlin.add_bytes(&[1, 2]).unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    let public: &[(&str, &[u8])] = &[("statement", &[7])];
+    let events =
+        replay::describe_public::<Keccak>(io.as_bytes(), public, merlin.transcript()).unwrap();
+    assert!(
+        matches!(&events[0], Event::Public { label, bytes, .. } if label == "statement" && bytes == &[7])
+    );
+    assert_eq!(events[1].bytes(), [1, 2]);
+    assert_eq!(events[2].bytes(), chal);
+    assert!(replay::describe(io.as_bytes(), merlin.transcript()).is_err());
+    let wrong_length: &[(&str, &[u8])] = &[("statement", &[7, 7])];
+    assert!(
+        replay::describe_public::<Keccak>(io.as_bytes(), wrong_length, merlin.transcript())
+            .is_err()
+    );
+    let unknown: &[(&str, &[u8])] = &[("statement", &[7]), ("witness", &[0])];
+    assert!(
+        replay::describe_public::<Keccak>(io.as_bytes(), unknown, merlin.transcript()).is_err()
+    );
+}
+
+/// The final digest binds the transcript, and requires verification to be complete.
+#[test]
+fn test_final_digest() {
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(2, "msg")
+        .squeeze(16, "chal");
+    let verify = |transcript: &[u8]| {
+        let mut arthur = io.to_arthur(transcript);
+        arthur.next_bytes::<2>().unwrap();
+        arthur.challenge_bytes::<16>().unwrap();
+        arthur.final_digest()
+    };
+    let digest = verify(&[1, 2]).unwrap();
+    assert_eq!(verify(&[1, 2]).unwrap(), digest);
+    assert_ne!(verify(&[1, 3]).unwrap(), digest);
+
+    // the digest depends on the hash function
+    #[cfg(feature = "strobe")]
+    {
+        let mut arthur = IOPattern::<Strobe128>::new("example.com")
+            .absorb(2, "msg")
+            .squeeze(16, "chal")
+            .to_arthur(&[1, 2]);
+        arthur.next_bytes::<2>().unwrap();
+        arthur.challenge_bytes::<16>().unwrap();
+        assert_ne!(arthur.final_digest().unwrap(), digest);
+    }
+
+    // unfinished verification
+    let mut arthur = io.to_arthur(&[1, 2]);
+    arthur.next_bytes::<2>().unwrap();
+    assert!(arthur.final_digest().is_err());
+}
+
+/// Verifiers report the operation at which they failed.
+#[test]
+fn test_fail_at_current_op() {
+    let io = IOPattern::<Keccak>::new("example.com")
+        .rounds("round", 10, |io, _| io.absorb(1, "msg").squeeze(1, "chal"));
+    let mut merlin = io.to_merlin();
+    for i in 0..10 {
+        merlin.add_bytes(&[i]).unwrap();
+        merlin.challenge_bytes::<1>().unwrap();
+    }
+
+    let verify = |arthur: &mut Arthur<Keccak>| -> ProofResult<()> {
+        for i in 0..10 {
+            let [msg] = arthur.next_bytes()?;
+            if msg != i {
+                return Err(arthur.fail_at_current_op(format!("unexpected message {}", msg)));
+            }
+            arthur.challenge_bytes::<1>()?;
+        }
+        Ok(())
+    };
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert!(verify(&mut arthur).is_ok());
+    assert_eq!(arthur.failure(), None);
+
+    let mut transcript = merlin.transcript().to_vec();
+    transcript[7] = 0xff;
+    let mut arthur = io.to_arthur(&transcript);
+    assert!(matches!(verify(&mut arthur), Err(ProofError::InvalidProof)));
+    let failure = arthur.failure().unwrap();
+    assert_eq!(failure.label, "round[7].msg");
+    assert_eq!((failure.completed, failure.total), (15, 20));
+    assert_eq!(failure.reason, "unexpected message 255");
+}
+
+/// Markers enforce phase boundaries without reading from the protocol transcript.
+#[test]
+fn test_markers() {
+    use crate::replay::{self, Event};
+
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(135, "commit")
+        .marker("queries")
+        .squeeze(1, "query");
+    assert_eq!(io.finalize().unwrap().len(), 3);
+    let mut merlin = io.to_merlin();
+    assert!(merlin.add_bytes(&[0u8; 136]).is_err());
+
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[0u8; 135]).unwrap();
+    merlin.marker("queries").unwrap();
+    let chal = merlin.challenge_bytes::<1>().unwrap();
+    assert_eq!(merlin.transcript().len(), 135);
+    assert_eq!(merlin.safe.sponge().permutations(), io.permutation_count());
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.skip_bytes(135).unwrap();
+    arthur.marker("queries").unwrap();
+    assert_eq!(arthur.challenge_bytes::<1>().unwrap(), chal);
+
+    // wrong labels, and markers out of place, are rejected
+    let mut arthur = io.to_arthur(merlin.transcript());
+    arthur.skip_bytes(135).unwrap();
+    assert!(arthur.marker("commit").is_err());
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert!(arthur.marker("queries").is_err());
+
+    // the marker is part of the running sponge state
+    let events = replay::describe(io.as_bytes(), merlin.transcript()).unwrap();
+    assert!(matches!(&events[1], Event::Marker { label, .. } if label == "queries"));
+    assert_eq!(events[2].bytes(), chal);
+
+    assert!(IOPattern::<Keccak>::from_bytes(b"example.com\0M1queries").is_err());
+}
+
+/// Marker labels are neither prefixed by rounds nor redacted, and are checked in every segment.
+#[test]
+fn test_markers_in_rounds_and_segments() {
+    let io = IOPattern::<Keccak>::new("example.com")
+        .rounds("fold", 2, |io, _| {
+            io.absorb(1, "commit").marker("queries").squeeze(1, "query")
+        })
+        .segment()
+        .marker("final")
+        .absorb(1, "response");
+
+    for io in [io.clone(), io.redact_labels(b"salt")] {
+        let mut merlin = io.to_merlin();
+        for _ in 0..2 {
+            merlin.add_bytes(&[1]).unwrap();
+            merlin.marker("queries").unwrap();
+            merlin.challenge_bytes::<1>().unwrap();
+        }
+        let (first, digest) = merlin.seal_segment().unwrap();
+        merlin.marker("final").unwrap();
+        merlin.add_bytes(&[2]).unwrap();
+        let second = merlin.transcript().to_vec();
+
+        let mut arthur = io.to_arthur(&first);
+        for _ in 0..2 {
+            arthur.next_bytes::<1>().unwrap();
+            arthur.marker("queries").unwrap();
+            arthur.challenge_bytes::<1>().unwrap();
+        }
+        arthur.open_segment(&digest, &second).unwrap();
+        arthur.marker("final").unwrap();
+        assert_eq!(arthur.next_bytes().unwrap(), [2]);
+
+        // the second segment is verified given the chaining digest alone
+        let mut arthur = io.to_arthur_segment(1, &digest, &second).unwrap();
+        assert!(arthur.marker("queries").is_err());
+        let mut arthur = io.to_arthur_segment(1, &digest, &second).unwrap();
+        arthur.marker("final").unwrap();
+        assert_eq!(arthur.next_bytes().unwrap(), [2]);
+    }
+}
+
+/// Duplex hashes can be used wherever a `Digest` or an extendable-output function is expected.
+#[cfg(feature = "legacy")]
+#[test]
+fn test_duplex_digest() {
+    use crate::hash::xof::DuplexDigest;
+    use digest::{Digest, ExtendableOutput, Update, XofReader};
+
+    fn hash<D: Digest>(chunks: &[&[u8]]) -> digest::Output<D> {
+        let mut hasher = D::new();
+        chunks
+            .iter()
+            .for_each(|chunk| Digest::update(&mut hasher, chunk));
+        hasher.finalize()
+    }
+
+    let iv = [0x42; 32];
+    let digest = hash::<DuplexDigest<Keccak>>(&[b"yellow ", b"submarine"]);
+    assert_eq!(digest, hash::<DuplexDigest<Keccak>>(&[b"yellow submarine"]));
+    assert_ne!(digest, hash::<DuplexDigest<Keccak>>(&[b"yellow submarinE"]));
+    // trailing zeros are not lost in the padding
+    assert_ne!(
+        hash::<DuplexDigest<Keccak>>(&[b"a"]),
+        hash::<DuplexDigest<Keccak>>(&[b"a\0"])
+    );
+    assert_ne!(
+        hash::<DuplexDigest<Keccak>>(&[]),
+        hash::<DuplexDigest<Keccak>>(&[b"\0"])
+    );
+
+    // the digest is the first 32 bytes of the extendable output, which can be read in a streaming fashion
+    let mut xof = DuplexDigest::<Keccak>::new_with_iv(iv)
+        .chain(b"yellow submarine")
+        .finalize_xof();
+    let mut output = [0u8; 200];
+    xof.read(&mut output[..100]);
+    xof.read(&mut output[100..]);
+    let mut hasher = DuplexDigest::<Keccak>::new_with_iv(iv);
+    Digest::update(&mut hasher, b"yellow submarine");
+    assert_eq!(&hasher.finalize_reset()[..], &output[..32]);
+    // after a reset, the hasher starts over from the IV
+    Digest::update(&mut hasher, b"yellow submarine");
+    assert_eq!(&hasher.finalize()[..], &output[..32]);
+    let mut reader = DuplexDigest::<Keccak>::new_with_iv(iv)
+        .chain(b"yellow submarine")
+        .finalize_xof();
+    let mut long_output = [0u8; 200];
+    reader.read(&mut long_output);
+    assert_eq!(output, long_output);
+    assert_ne!(
+        DuplexDigest::<Keccak>::new_with_iv(iv).finalize(),
+        DuplexDigest::<Keccak>::default().finalize()
+    );
+}
+
+/// Transcript traits are implemented for mutable references and boxes.
+#[test]
+fn test_transcript_through_pointers() {
+    fn prove(t: &mut (impl ByteWriter + ByteChallenges)) -> [u8; 16] {
+        t.add_bytes(&[0x42]).unwrap();
+        t.challenge_bytes().unwrap()
+    }
+
+    fn verify(t: &mut (impl ByteReader + ByteChallenges)) -> [u8; 16] {
+        assert_eq!(t.next_bytes().unwrap(), [0x42]);
+        t.challenge_bytes().unwrap()
+    }
+
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(1, "msg")
+        .squeeze(16, "chal");
+    let mut merlin = io.to_merlin();
+    let chal = prove(&mut &mut merlin);
+    let mut boxed_merlin = Box::new(io.to_merlin());
+    assert_eq!(prove(&mut boxed_merlin), chal);
+
+    let mut arthur = io.to_arthur(merlin.transcript());
+    assert_eq!(verify(&mut &mut arthur), chal);
+    let mut boxed_arthur = Box::new(io.to_arthur(merlin.transcript()));
+    assert_eq!(verify(&mut boxed_arthur), chal);
+}
+
+/// A transcript split in discontiguous chunks is read as if it were contiguous.
+#[test]
+fn test_arthur_from_chunks() {
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(3, "first")
+        .squeeze(16, "chal")
+        .absorb(5, "second")
+        .absorb(2, "third");
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1, 2, 3]).unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    merlin.add_bytes(&[4, 5, 6, 7, 8]).unwrap();
+    merlin.add_bytes(&[9, 10]).unwrap();
+    let transcript = merlin.transcript();
+
+    // reads span chunk boundaries, and empty chunks are skipped
+    let chunks: &[&[u8]] = &[
+        &transcript[..1],
+        &[],
+        &transcript[1..4],
+        &transcript[4..8],
+        &[],
+        &transcript[8..],
+        &[],
+    ];
+    let mut arthur = io.to_arthur_chunks(chunks);
+    assert_eq!(arthur.next_bytes().unwrap(), [1, 2, 3]);
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+    assert_eq!(arthur.next_bytes().unwrap(), [4, 5, 6, 7, 8]);
+    assert!(!arthur.transcript.is_empty());
+    assert_eq!(arthur.next_bytes().unwrap(), [9, 10]);
+    assert!(arthur.transcript.is_empty());
+
+    // a short transcript is detected
+    let chunks: &[&[u8]] = &[&transcript[..2], &transcript[2..9]];
+    let mut arthur = io.to_arthur_chunks(chunks);
+    assert!(arthur.next_bytes::<3>().is_ok());
+    assert!(arthur.challenge_bytes::<16>().is_ok());
+    assert!(arthur.next_bytes::<5>().is_ok());
+    assert!(arthur.next_bytes::<2>().is_err());
+
+    // trailing bytes are rejected, unless explicitly allowed
+    let chunks: &[&[u8]] = &[transcript, &[], &[0xff]];
+    let mut arthur = io.to_arthur_chunks(chunks);
+    arthur.next_bytes::<3>().unwrap();
+    arthur.challenge_bytes::<16>().unwrap();
+    arthur.next_bytes::<5>().unwrap();
+    assert!(arthur.next_bytes::<2>().is_err());
+    let mut arthur = io.to_arthur_chunks(chunks);
+    arthur.allow_trailing_bytes();
+    arthur.next_bytes::<3>().unwrap();
+    arthur.challenge_bytes::<16>().unwrap();
+    arthur.next_bytes::<5>().unwrap();
+    arthur.next_bytes::<2>().unwrap();
+    assert!(!arthur.transcript.is_empty());
+}
+
+/// The first rounds of a proof are sliced out of the verifier's transcript.
+#[test]
+fn test_consumed_transcript() {
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(3, "round 1")
+        .squeeze(16, "chal")
+        .absorb(5, "round 2");
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1, 2, 3]).unwrap();
+    merlin.challenge_bytes::<16>().unwrap();
+    merlin.add_bytes(&[4, 5, 6, 7, 8]).unwrap();
+    let transcript = merlin.transcript();
+
+    let mut arthur = io.to_arthur(transcript);
+    arthur.next_bytes::<3>().unwrap();
+    arthur.challenge_bytes::<16>().unwrap();
+    let first_round = arthur.consumed_transcript().unwrap();
+    assert_eq!(first_round, [1, 2, 3]);
+    arthur.next_bytes::<5>().unwrap();
+    assert_eq!(arthur.consumed_transcript(), Some(transcript));
+    assert_eq!(arthur.transcript(), Some(transcript));
+
+    // the forwarded rounds are verified on their own
+    let io_first_round = IOPattern::<Keccak>::new("example.com")
+        .absorb(3, "round 1")
+        .squeeze(16, "chal");
+    let mut arthur = io_first_round.to_arthur(first_round);
+    arthur.next_bytes::<3>().unwrap();
+    arthur.challenge_bytes::<16>().unwrap();
+
+    // transcripts that are not a single slice
+    let chunks: &[&[u8]] = &[&transcript[..2], &transcript[2..]];
+    let mut arthur = io.to_arthur_chunks(chunks);
+    arthur.next_bytes::<3>().unwrap();
+    assert_eq!(arthur.transcript(), None);
+    assert_eq!(arthur.consumed_transcript(), None);
+}
+
+/// The prover state is exported into a proof, which is verified as its protocol transcript.
+#[test]
+fn test_proof_from_merlin() {
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(2, "msg")
+        .squeeze(16, "chal");
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1, 2]).unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    let transcript = merlin.transcript().to_vec();
+
+    let proof = Proof::try_from(merlin).unwrap();
+    assert_eq!(proof.as_bytes(), transcript);
+    let mut arthur = proof.verifier(&io);
+    assert_eq!(arthur.next_bytes().unwrap(), [1, 2]);
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+
+    // proofs are re-loaded from bytes
+    let proof = Proof::from(proof.into_bytes());
+    let mut arthur = proof.verifier(&io);
+    assert_eq!(arthur.next_bytes().unwrap(), [1, 2]);
+}
+
+/// Skipping messages absorbs them as if they were read.
+#[test]
+fn test_skip_messages() {
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(100, "blob")
+        .squeeze(16, "chal")
+        .absorb(3, "msg");
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[7u8; 100]).unwrap();
+    let chal = merlin.challenge_bytes::<16>().unwrap();
+    merlin.add_bytes(&[1, 2, 3]).unwrap();
+    let transcript = merlin.transcript();
+
+    let chunks: &[&[u8]] = &[&transcript[..10], &[], &transcript[10..]];
+    let mut arthur = io.to_arthur_chunks(chunks);
+    arthur.skip_bytes(40).unwrap();
+    arthur.skip_units(60).unwrap();
+    assert_eq!(arthur.challenge_bytes::<16>().unwrap(), chal);
+    assert_eq!(arthur.next_bytes().unwrap(), [1, 2, 3]);
+
+    // skipping past the message or the transcript fails
+    let mut arthur = io.to_arthur(transcript);
+    assert!(arthur.skip_bytes(101).is_err());
+    let mut arthur = io.to_arthur(&transcript[..50]);
+    assert!(arthur.skip_bytes(51).is_err());
+}
+
+/// Keystreams are bound to the protocol transcript, and declared in the IO Pattern.
+#[test]
+fn test_challenge_keystream() {
+    use crate::designated::{ChallengeKeystream, ChallengeKeystreamIOPattern};
+
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(1, "msg")
+        .challenge_keystream("pad");
+    assert!(io.as_bytes().ends_with(b"\0S32pad.keystream"));
+
+    let keystream = |msg: u8| {
+        let mut merlin = io.to_merlin();
+        merlin.add_bytes(&[msg]).unwrap();
+        merlin.challenge_keystream(100).unwrap()
+    };
+    assert_eq!(keystream(1), keystream(1));
+    assert_ne!(keystream(1), keystream(2));
+
+    // a longer keystream extends a shorter one
+    let mut merlin = io.to_merlin();
+    merlin.add_bytes(&[1]).unwrap();
+    let mut cipher = merlin.challenge_cipher().unwrap();
+    let mut long = [0u8; 200];
+    crate::designated::TranscriptCipher::apply_keystream(&mut cipher, &mut long);
+    assert_eq!(long[..100], keystream(1));
+}
+
+/// Keystreams of designated-verifier transcripts are bound to the IO Pattern and to a nonce, not only to the key.
+#[test]
+fn test_keccak_cipher_binding() {
+    use crate::designated::{KeccakCipher, TranscriptCipher};
+
+    let keystream = |io_digest: [u8; 32], nonce: &[u8]| {
+        let mut keystream = [0u8; 32];
+        KeccakCipher::new(b"shared secret", io_digest, nonce).apply_keystream(&mut keystream);
+        keystream
+    };
+    let io_digest = IOPattern::<Keccak>::new("example.com")
+        .absorb(1, "msg")
+        .digest();
+    let other_digest = IOPattern::<Keccak>::new("example.org")
+        .absorb(1, "msg")
+        .digest();
+
+    let expected = keystream(io_digest, b"nonce");
+    assert_eq!(keystream(io_digest, b"nonce"), expected);
+    assert_ne!(keystream(io_digest, b"other"), expected);
+    assert_ne!(keystream(other_digest, b"nonce"), expected);
+    // parts are length-prefixed: moving bytes between the secret and the nonce changes the keystream.
+    let mut shifted = [0u8; 32];
+    KeccakCipher::new(b"shared secre", io_digest, b"tnonce").apply_keystream(&mut shifted);
+    assert_ne!(shifted, expected);
+}
+
+/// Very long IO Patterns and squeezes do not overflow the stack.
+#[cfg(feature = "sha2")]
+#[test]
+fn test_large_iopattern_and_squeeze() {
+    let mut io = IOPattern::<Sha2>::new("example.com");
+    for _ in 0..100_000 {
+        io = io.absorb(1, "in").squeeze(1, "out");
+    }
+    assert_eq!(io.finalize().unwrap().len(), 200_000);
+
+    let io = IOPattern::<Sha2>::new("example.com").squeeze(1 << 20, "out");
+    let mut merlin = io.to_merlin();
+    let mut chal = vec![0u8; 1 << 20];
+    merlin.fill_challenge_bytes(&mut chal).unwrap();
+    // squeezing in one go is the same as streaming
+    let mut stream_merlin = io.to_merlin();
+    for chunk in chal.chunks(4096) {
+        let mut got = [0u8; 4096];
+        stream_merlin.fill_challenge_bytes(&mut got).unwrap();
+        assert_eq!(chunk, &got[..]);
+    }
+}
+
 /// Absorbs and squeeze over byte-Units should be streamable.
-fn test_streaming_absorb_and_squeeze<H: DuplexHash>()
+pub(crate) fn test_streaming_absorb_and_squeeze<H: DuplexHash>()
 where
     Merlin<H>: ByteWriter + ByteChallenges,
 {
@@ -194,11 +1814,64 @@ where
     }
 }
 
+/// The backend of a runtime-selected hash is bound to the IV, and kept across segments.
+#[cfg(all(feature = "shake", feature = "sha2"))]
+#[test]
+fn test_any_duplex_hash() {
+    use crate::hash::any::domain_separator;
+    use crate::hash::{AnyDuplexHash, HashBackend, Shake128};
+
+    fn prove<H: DuplexHash>(io: &IOPattern<H>) -> (Vec<u8>, [u8; 16]) {
+        let mut merlin = io.to_merlin();
+        merlin.add_bytes(b"ab").unwrap();
+        merlin.seal_segment().unwrap();
+        merlin.add_bytes(b"cd").unwrap();
+        (
+            merlin.transcript().to_vec(),
+            merlin.challenge_bytes().unwrap(),
+        )
+    }
+    fn io<H: DuplexHash>(domsep: &str) -> IOPattern<H> {
+        IOPattern::new(domsep)
+            .absorb(2, "first")
+            .segment()
+            .absorb(2, "second")
+            .squeeze(16, "chal")
+    }
+    let expected = [
+        (HashBackend::Keccak, prove(&io::<Keccak>("[keccak]📝"))),
+        (
+            HashBackend::Shake128,
+            prove(&io::<Shake128>("[shake128]📝")),
+        ),
+        (HashBackend::Sha256, prove(&io::<Sha2>("[sha256-bridge]📝"))),
+    ];
+    for (backend, (transcript, chal)) in expected {
+        assert_eq!(HashBackend::from_id(backend.id()), Some(backend));
+        let any_io = io::<AnyDuplexHash>(&domain_separator(backend.id(), "📝"));
+        assert_eq!(prove(&any_io), (transcript.clone(), chal));
+        for other in HashBackend::ALL.iter().filter(|&&other| other != backend) {
+            let other_io = io::<AnyDuplexHash>(&domain_separator(other.id(), "📝"));
+            assert_ne!(prove(&other_io).1, chal);
+        }
+    }
+
+    let io = IOPattern::<AnyDuplexHash>::new("📝").absorb(1, "🍝");
+    assert!(std::panic::catch_unwind(|| io.to_merlin()).is_err());
+    assert_eq!(
+        Safe::try_new(&io).unwrap_err().kind,
+        OpParseErrorKind::UnknownBackend
+    );
+    assert!(Arthur::try_new(&io, &[0x42]).is_err());
+}
+
+#[cfg(feature = "sha2")]
 #[test]
 fn test_streaming_sha2() {
     test_streaming_absorb_and_squeeze::<Sha2>();
 }
 
+#[cfg(feature = "legacy")]
 #[test]
 fn test_streaming_blake2() {
     test_streaming_absorb_and_squeeze::<Blake2b512>();
@@ -209,3 +1882,197 @@ fn test_streaming_blake2() {
 fn test_streaming_keccak() {
     test_streaming_absorb_and_squeeze::<Keccak>();
 }
+
+#[cfg(feature = "shake")]
+#[test]
+fn test_streaming_shake128() {
+    test_streaming_absorb_and_squeeze::<crate::hash::Shake128>();
+}
+
+#[cfg(feature = "strobe")]
+#[test]
+fn test_streaming_strobe() {
+    test_streaming_absorb_and_squeeze::<Strobe128>();
+}
+
+#[test]
+fn test_misuse_scenarios() {
+    use crate::testing::{check_misuse, expect_mismatch, Misuse, Outcome};
+    use crate::DefaultHash;
+
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue")
+        .absorb(2, "msg")
+        .squeeze(1, "chal");
+    let prove = |merlin: &mut Merlin| -> ProofResult<()> {
+        merlin.add_bytes(&[1, 2])?;
+        merlin.challenge_bytes::<1>()?;
+        Ok(())
+    };
+    // panics on mismatches, and does not check that the IO Pattern was followed
+    let verify = |arthur: &mut Arthur| -> ProofResult<()> {
+        arthur.next_bytes::<2>().unwrap();
+        arthur.challenge_bytes::<1>()?;
+        Ok(())
+    };
+    let reports = check_misuse(&io, prove, verify);
+    let misuses = reports
+        .iter()
+        .map(|report| report.misuse)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        misuses,
+        [
+            Misuse::WrongOrder { op: 0 },
+            Misuse::WrongCount { op: 0, count: 1 },
+            Misuse::WrongCount { op: 0, count: 3 },
+            Misuse::WrongUnit { op: 0, count: 16 },
+            Misuse::DoubleRead { op: 0 },
+            Misuse::WrongCount { op: 1, count: 2 },
+            Misuse::WrongUnit { op: 1, count: 8 },
+            Misuse::DoubleRead { op: 1 },
+        ]
+    );
+    assert_eq!(reports[0].prover, Outcome::Rejected);
+    assert_eq!(reports[0].verifier, Outcome::Panicked);
+    assert_eq!(reports[5].prover, Outcome::Incomplete(1));
+    assert_eq!(reports[5].verifier, Outcome::Incomplete(1));
+    assert!(!reports[5].is_caught());
+    assert!(reports[7].is_caught());
+
+    expect_mismatch!(io.to_arthur(&[]).next_bytes::<2>());
+
+    // markers are checked in the modified IO Patterns, so that a sloppy verifier is still caught
+    let io = IOPattern::<DefaultHash>::new("github.com/mmaker/nimue")
+        .absorb(2, "msg")
+        .marker("queries")
+        .squeeze(1, "chal");
+    let prove = |merlin: &mut Merlin| -> ProofResult<()> {
+        merlin.add_bytes(&[1, 2])?;
+        merlin.marker("queries")?;
+        merlin.challenge_bytes::<1>()?;
+        Ok(())
+    };
+    let verify = |arthur: &mut Arthur| -> ProofResult<()> {
+        arthur.next_bytes::<2>()?;
+        arthur.marker("queries")?;
+        arthur.challenge_bytes::<1>()?;
+        Ok(())
+    };
+    let reports = check_misuse(&io, prove, verify);
+    let longer_chal = reports
+        .iter()
+        .find(|report| report.misuse == Misuse::WrongCount { op: 2, count: 2 })
+        .unwrap();
+    assert_eq!(longer_chal.prover, Outcome::Incomplete(1));
+    assert_eq!(longer_chal.verifier, Outcome::Incomplete(1));
+    assert!(reports
+        .iter()
+        .filter(|report| matches!(report.misuse, Misuse::WrongOrder { .. }))
+        .all(|report| report.is_caught()));
+}
+
+#[test]
+fn test_layered_transcript() {
+    use crate::middleware::{LayeredTranscript, TranscriptMiddleware};
+    use crate::IOPatternError;
+
+    /// Count the units going through each operation, and flip the challenges.
+    #[derive(Default)]
+    struct Metrics {
+        messages: usize,
+        public: usize,
+        challenges: usize,
+        ratchets: usize,
+    }
+
+    impl TranscriptMiddleware for Metrics {
+        fn on_message(&mut self, message: &[u8]) -> Result<(), IOPatternError> {
+            self.messages += message.len();
+            Ok(())
+        }
+
+        fn on_public(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
+            self.public += input.len();
+            Ok(())
+        }
+
+        fn on_challenge(&mut self, output: &mut [u8]) -> Result<(), IOPatternError> {
+            self.challenges += output.len();
+            output.iter_mut().for_each(|x| *x = !*x);
+            Ok(())
+        }
+
+        fn on_ratchet(&mut self) -> Result<(), IOPatternError> {
+            self.ratchets += 1;
+            Ok(())
+        }
+    }
+
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .absorb(2, "public")
+        .absorb(3, "message")
+        .ratchet()
+        .squeeze(4, "chal");
+    let mut control = io.to_merlin();
+    control.public_bytes(&[1, 2]).unwrap();
+    control.add_bytes(&[3, 4, 5]).unwrap();
+    control.ratchet().unwrap();
+    let chal = control.challenge_bytes::<4>().unwrap();
+
+    let mut merlin = LayeredTranscript::new(io.to_merlin(), Metrics::default());
+    merlin.public_bytes(&[1, 2]).unwrap();
+    merlin.add_bytes(&[3, 4, 5]).unwrap();
+    merlin.ratchet().unwrap();
+    assert_eq!(merlin.challenge_bytes::<4>().unwrap(), chal.map(|x| !x));
+    assert_eq!(merlin.transcript(), control.transcript());
+    let metrics = merlin.middleware();
+    assert_eq!(
+        (
+            metrics.public,
+            metrics.messages,
+            metrics.ratchets,
+            metrics.challenges
+        ),
+        (2, 3, 1, 4)
+    );
+
+    let mut arthur = LayeredTranscript::new(io.to_arthur(control.transcript()), Metrics::default());
+    arthur.public_bytes(&[1, 2]).unwrap();
+    assert_eq!(arthur.next_bytes::<3>().unwrap(), [3, 4, 5]);
+    arthur.ratchet().unwrap();
+    assert_eq!(arthur.challenge_bytes::<4>().unwrap(), chal.map(|x| !x));
+    let (arthur, metrics) = arthur.into_parts();
+    assert_eq!(arthur.remaining_ops(), 0);
+    assert_eq!((metrics.messages, metrics.ratchets), (3, 1));
+
+    // segment boundaries and markers go through the middleware as well
+    let io = IOPattern::<Keccak>::new("domain separator")
+        .marker("phase")
+        .absorb(1, "message")
+        .segment();
+    let mut merlin = LayeredTranscript::new(io.to_merlin(), Metrics::default());
+    merlin.marker("phase").unwrap();
+    merlin.add_bytes(&[1]).unwrap();
+    let (segment, _) = merlin.seal_segment().unwrap();
+    assert_eq!(segment, [1]);
+    assert_eq!(merlin.middleware().ratchets, 1);
+    let mut arthur = LayeredTranscript::new(io.to_arthur(&[1]), Metrics::default());
+    arthur.marker("phase").unwrap();
+    arthur.skip_units(1).unwrap();
+    assert_eq!(arthur.middleware().messages, 1);
+}
+
+#[test]
+fn test_to_merlin_pinned() {
+    const PINNED: [u8; 32] =
+        crate::digest_from_hex("01B0EDED3E7A80DFD9A29DA2C65BD78A84F128049EBFC7833D5DA8A94D4FE61C");
+
+    let io = IOPattern::<Keccak>::new("📝").absorb(1, "🍝");
+    assert_eq!(io.digest(), PINNED);
+    io.to_merlin_pinned(&PINNED).add_bytes(&[0x42]).unwrap();
+
+    let stale = IOPattern::<Keccak>::new("📝").absorb(2, "🍝");
+    let err = std::panic::catch_unwind(|| stale.to_merlin_pinned(&PINNED)).unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(msg.contains(&hex::encode(stale.digest())));
+}
@@ -1,3 +1,5 @@
+use core::mem::MaybeUninit;
+
 use crate::errors::IOPatternError;
 use crate::Unit;
 
@@ -10,6 +12,21 @@ pub trait UnitTranscript<U: Unit> {
     fn public_units(&mut self, input: &[U]) -> Result<(), IOPatternError>;
 
     fn fill_challenge_units(&mut self, output: &mut [U]) -> Result<(), IOPatternError>;
+
+    /// Fill an uninitialized buffer with challenges, returning the initialized slice.
+    ///
+    /// Useful for large challenge vectors, where initializing `output` first is not free.
+    /// The default implementation initializes `output` to [`Default::default`] and calls [`UnitTranscript::fill_challenge_units`].
+    fn fill_challenge_units_uninit<'a>(
+        &mut self,
+        output: &'a mut [MaybeUninit<U>],
+    ) -> Result<&'a mut [U], IOPatternError>
+    where
+        U: Default,
+    {
+        let output = crate::hash::init_with(output, U::default);
+        self.fill_challenge_units(output).map(|()| output)
+    }
 }
 
 /// Absorbing bytes from the sponge, without reading or writing them into the protocol transcript.
@@ -79,3 +96,325 @@ impl<T: UnitTranscript<u8>> ByteChallenges for T {
         self.fill_challenge_units(output)
     }
 }
+
+/// Forward the transcript traits through (mutable) pointers, so that generic protocol functions
+/// can be called with `&mut Merlin`, `Box<Arthur>`, etc.
+macro_rules! forward_transcript_traits {
+    ($($ptr:ty),*) => {
+        $(
+            impl<U: Unit, T: UnitTranscript<U> + ?Sized> UnitTranscript<U> for $ptr {
+                #[inline]
+                fn public_units(&mut self, input: &[U]) -> Result<(), IOPatternError> {
+                    (**self).public_units(input)
+                }
+
+                #[inline]
+                fn fill_challenge_units(&mut self, output: &mut [U]) -> Result<(), IOPatternError> {
+                    (**self).fill_challenge_units(output)
+                }
+
+                #[inline]
+                fn fill_challenge_units_uninit<'a>(
+                    &mut self,
+                    output: &'a mut [MaybeUninit<U>],
+                ) -> Result<&'a mut [U], IOPatternError>
+                where
+                    U: Default,
+                {
+                    (**self).fill_challenge_units_uninit(output)
+                }
+            }
+
+            impl<T: ByteWriter + ?Sized> ByteWriter for $ptr {
+                #[inline]
+                fn add_bytes(&mut self, input: &[u8]) -> Result<(), IOPatternError> {
+                    (**self).add_bytes(input)
+                }
+            }
+
+            impl<T: ByteReader + ?Sized> ByteReader for $ptr {
+                #[inline]
+                fn fill_next_bytes(&mut self, input: &mut [u8]) -> Result<(), IOPatternError> {
+                    (**self).fill_next_bytes(input)
+                }
+            }
+        )*
+    };
+}
+
+forward_transcript_traits!(&mut T, Box<T>);
+
+/// Generate the integer-encoding methods of [`IntegerIOPattern`], [`IntegerWriter`], [`IntegerPublic`], and [`IntegerReader`].
+///
+/// Each method carries in its name the type and the endianness of the encoding, so that prover and verifier
+/// implementations cannot silently disagree on them.
+/// The first list describes single integers, the second one slices of integers encoded with `$encode` and `$decode`.
+macro_rules! integer_traits {
+    (
+        $(($int:ty, $add:ident, $public:ident, $next:ident, $to_bytes:ident, $from_bytes:ident, $suffix:literal)),*;
+        $(($sint:ty, $sadd:ident, $spublic:ident, $sfill:ident, $snext:ident, $encode:path, $decode:path, $slabel:literal, $sdoc:literal)),*
+    ) => {
+        /// Methods for adding fixed-size integers to the [`IOPattern`](crate::IOPattern).
+        ///
+        /// The label is suffixed with the type and the endianness of the integer (e.g. `epoch.u64_be`),
+        /// so that changing the encoding changes the domain separator.
+        /// The same pattern is used for integers read from the protocol transcript and for public integers.
+        pub trait IntegerIOPattern: ByteIOPattern + Sized {
+            $(
+                #[doc = concat!("Add a `", stringify!($int), "`, encoded in ", $suffix, ".")]
+                #[inline]
+                fn $add(self, label: &str) -> Self {
+                    self.add_bytes(
+                        core::mem::size_of::<$int>(),
+                        &format!("{}.{}", label, stringify!($add).trim_start_matches("add_")),
+                    )
+                }
+            )*
+            $(
+                #[doc = concat!("Add `count` values of type `", stringify!($sint), "`, each encoded in ", $sdoc, ".")]
+                #[inline]
+                fn $sadd(self, count: usize, label: &str) -> Self {
+                    self.add_bytes(
+                        count * core::mem::size_of::<$sint>(),
+                        &format!("{}.{}", label, $slabel),
+                    )
+                }
+            )*
+        }
+
+        /// Write fixed-size integers to the protocol transcript.
+        pub trait IntegerWriter: ByteWriter {
+            $(
+                #[doc = concat!("Write a `", stringify!($int), "`, encoded in ", $suffix, ".")]
+                #[inline]
+                fn $add(&mut self, input: $int) -> Result<(), IOPatternError> {
+                    self.add_bytes(&input.$to_bytes())
+                }
+            )*
+            $(
+                #[doc = concat!("Write a slice of `", stringify!($sint), "`, each encoded in ", $sdoc, ".")]
+                fn $sadd(&mut self, input: &[$sint]) -> Result<(), IOPatternError> {
+                    let bytes = input.iter().flat_map(|&x| $encode(x)).collect::<Vec<u8>>();
+                    self.add_bytes(&bytes)
+                }
+            )*
+        }
+
+        /// Absorb fixed-size integers, without writing them into the protocol transcript.
+        pub trait IntegerPublic: BytePublic {
+            $(
+                #[doc = concat!("Absorb a `", stringify!($int), "`, encoded in ", $suffix, ".")]
+                #[inline]
+                fn $public(&mut self, input: $int) -> Result<(), IOPatternError> {
+                    self.public_bytes(&input.$to_bytes())
+                }
+            )*
+            $(
+                #[doc = concat!("Absorb a slice of `", stringify!($sint), "`, each encoded in ", $sdoc, ".")]
+                fn $spublic(&mut self, input: &[$sint]) -> Result<(), IOPatternError> {
+                    let bytes = input.iter().flat_map(|&x| $encode(x)).collect::<Vec<u8>>();
+                    self.public_bytes(&bytes)
+                }
+            )*
+        }
+
+        /// Read fixed-size integers from the protocol transcript.
+        pub trait IntegerReader: ByteReader {
+            $(
+                #[doc = concat!("Read a `", stringify!($int), "`, encoded in ", $suffix, ".")]
+                #[inline]
+                fn $next(&mut self) -> Result<$int, IOPatternError> {
+                    self.next_bytes().map(<$int>::$from_bytes)
+                }
+            )*
+            $(
+                #[doc = concat!("Read `output.len()` values of type `", stringify!($sint), "`, each encoded in ", $sdoc, ".")]
+                fn $sfill(&mut self, output: &mut [$sint]) -> Result<(), IOPatternError> {
+                    const SIZE: usize = core::mem::size_of::<$sint>();
+                    let mut bytes = vec![0u8; output.len() * SIZE];
+                    self.fill_next_bytes(&mut bytes)?;
+                    for (o, chunk) in output.iter_mut().zip(bytes.chunks_exact(SIZE)) {
+                        *o = $decode(chunk.try_into().unwrap());
+                    }
+                    Ok(())
+                }
+
+                #[doc = concat!("Read `N` values of type `", stringify!($sint), "`, each encoded in ", $sdoc, ".")]
+                #[inline]
+                fn $snext<const N: usize>(&mut self) -> Result<[$sint; N], IOPatternError> {
+                    let mut output = [0; N];
+                    self.$sfill(&mut output).map(|()| output)
+                }
+            )*
+        }
+    };
+}
+
+/// Encode a signed integer with ZigZag (`0, -1, 1, -2, ...` map to `0, 1, 2, 3, ...`), in little-endian.
+fn i64_to_zigzag_le(x: i64) -> [u8; 8] {
+    (((x << 1) ^ (x >> 63)) as u64).to_le_bytes()
+}
+
+/// Decode a signed integer encoded with [`i64_to_zigzag_le`].
+fn i64_from_zigzag_le(bytes: [u8; 8]) -> i64 {
+    let x = u64::from_le_bytes(bytes);
+    ((x >> 1) as i64) ^ -((x & 1) as i64)
+}
+
+integer_traits! {
+    (u16, add_u16_le, public_u16_le, next_u16_le, to_le_bytes, from_le_bytes, "little-endian"),
+    (u16, add_u16_be, public_u16_be, next_u16_be, to_be_bytes, from_be_bytes, "big-endian"),
+    (u32, add_u32_le, public_u32_le, next_u32_le, to_le_bytes, from_le_bytes, "little-endian"),
+    (u32, add_u32_be, public_u32_be, next_u32_be, to_be_bytes, from_be_bytes, "big-endian"),
+    (u64, add_u64_le, public_u64_le, next_u64_le, to_le_bytes, from_le_bytes, "little-endian"),
+    (u64, add_u64_be, public_u64_be, next_u64_be, to_be_bytes, from_be_bytes, "big-endian"),
+    (u128, add_u128_le, public_u128_le, next_u128_le, to_le_bytes, from_le_bytes, "little-endian"),
+    (u128, add_u128_be, public_u128_be, next_u128_be, to_be_bytes, from_be_bytes, "big-endian");
+    (i64, add_i64s, public_i64s, fill_next_i64s, next_i64s, i64_to_zigzag_le, i64_from_zigzag_le, "i64_zigzag_le", "ZigZag, little-endian"),
+    (u128, add_u128s, public_u128s, fill_next_u128s, next_u128s, u128::to_le_bytes, u128::from_le_bytes, "u128_le", "little-endian")
+}
+
+impl<T: ByteIOPattern> IntegerIOPattern for T {}
+impl<T: ByteWriter + ?Sized> IntegerWriter for T {}
+impl<T: BytePublic + ?Sized> IntegerPublic for T {}
+impl<T: ByteReader + ?Sized> IntegerReader for T {}
+
+/// Number of bytes squeezed for a challenge modulo `modulus`.
+///
+/// The challenge is sampled as an integer 128 bits larger than the modulus, and then reduced:
+/// its statistical distance from the uniform distribution is at most $2^{-128}$.
+pub const fn challenge_mod_bytes(modulus: u64) -> usize {
+    assert!(modulus != 0, "Modulus must be positive.");
+    let bits = (u64::BITS - (modulus - 1).leading_zeros()) as usize;
+    bits.div_ceil(8) + 16
+}
+
+/// Methods for adding challenges modulo an integer to the [`IOPattern`](crate::IOPattern).
+///
+/// The label is suffixed with the modulus (e.g. `index.mod17`), so that both the number of bytes squeezed
+/// and the reduction performed by [`ChallengeMod::challenge_mod`] are recorded in the domain separator.
+pub trait ChallengeModIOPattern: ByteIOPattern + Sized {
+    /// Squeeze a challenge uniformly distributed in `[0, modulus)`.
+    ///
+    /// # Panics
+    ///
+    /// If `modulus` is zero.
+    fn challenge_mod(self, modulus: u64, label: &str) -> Self {
+        self.challenge_bytes(
+            challenge_mod_bytes(modulus),
+            &format!("{}.mod{}", label, modulus),
+        )
+    }
+}
+
+/// Squeeze challenges modulo an integer, as declared by [`ChallengeModIOPattern::challenge_mod`].
+///
+/// **The modulus is not checked against the IO Pattern.**
+/// It is bound to the domain separator through the label, but at runtime only the number of bytes squeezed is checked:
+/// reducing modulo another modulus of the same byte size succeeds, and yields a challenge in the wrong range.
+/// Prover and verifier must use the modulus declared in the IO Pattern.
+pub trait ChallengeMod: ByteChallenges {
+    /// Squeeze a challenge uniformly distributed in `[0, modulus)`, up to a statistical distance of $2^{-128}$.
+    ///
+    /// Returns an error if `modulus` is zero.
+    fn challenge_mod(&mut self, modulus: u64) -> Result<u64, IOPatternError> {
+        if modulus == 0 {
+            return Err("Modulus must be positive.".into());
+        }
+        let mut buf = vec![0u8; challenge_mod_bytes(modulus)];
+        self.fill_challenge_bytes(&mut buf)?;
+        Ok(reduce_mod(&buf, modulus))
+    }
+}
+
+/// Reduce the big-endian integer `bytes` modulo `modulus`.
+fn reduce_mod(bytes: &[u8], modulus: u64) -> u64 {
+    let modulus = modulus as u128;
+    bytes
+        .iter()
+        .fold(0u128, |acc, &b| ((acc << 8) | b as u128) % modulus) as u64
+}
+
+impl<T: ByteIOPattern> ChallengeModIOPattern for T {}
+impl<T: ByteChallenges + ?Sized> ChallengeMod for T {}
+
+/// Methods for adding query indices (e.g. for FRI) to the [`IOPattern`](crate::IOPattern).
+pub trait ChallengeQueriesIOPattern: IntegerIOPattern {
+    /// Squeeze `num_queries` indices in `[0, domain_size)`, each as in [`ChallengeModIOPattern::challenge_mod`].
+    ///
+    /// If `dedup` is set, the number of distinct indices is then absorbed as a public `u64` (label `{label}.count`).
+    ///
+    /// # Panics
+    ///
+    /// If `domain_size` is zero.
+    fn challenge_queries(
+        self,
+        num_queries: usize,
+        domain_size: usize,
+        dedup: bool,
+        label: &str,
+    ) -> Self {
+        let io = self.challenge_bytes(
+            num_queries * challenge_mod_bytes(domain_size as u64),
+            &format!("{}.mod{}", label, domain_size),
+        );
+        if dedup {
+            io.add_u64_le(&format!("{}.count", label))
+        } else {
+            io
+        }
+    }
+}
+
+/// Squeeze query indices, as declared by [`ChallengeQueriesIOPattern::challenge_queries`].
+///
+/// Sampling until `num_queries` distinct indices are found would make the number of bytes squeezed
+/// depend on the challenges, and break the IO Pattern.
+/// Instead, exactly `num_queries` indices are squeezed, and duplicates are removed afterwards:
+/// the number of distinct indices, absorbed as a hint, binds the rest of the transcript to the deduplication.
+///
+/// ```
+/// # use nimue::*;
+/// let io = IOPattern::<DefaultHash>::new("📝").challenge_queries(32, 16, true, "queries");
+/// let mut merlin = io.to_merlin();
+/// let queries = merlin.challenge_queries(32, 16, true).unwrap();
+/// assert!(queries.len() <= 16 && queries.windows(2).all(|w| w[0] < w[1]));
+///
+/// let mut arthur = io.to_arthur(merlin.transcript());
+/// assert_eq!(arthur.challenge_queries(32, 16, true).unwrap(), queries);
+/// ```
+pub trait ChallengeQueries: ChallengeMod + IntegerPublic {
+    /// Squeeze `num_queries` indices in `[0, domain_size)`.
+    ///
+    /// If `dedup` is set, the indices returned are distinct and sorted, and their number is absorbed.
+    /// Otherwise, they are returned in the order they were squeezed.
+    /// As for [`ChallengeMod::challenge_mod`], the domain size is not checked against the IO Pattern.
+    ///
+    /// Returns an error if `domain_size` is zero.
+    fn challenge_queries(
+        &mut self,
+        num_queries: usize,
+        domain_size: usize,
+        dedup: bool,
+    ) -> Result<Vec<usize>, IOPatternError> {
+        if domain_size == 0 {
+            return Err("Domain size must be positive.".into());
+        }
+        let chunk = challenge_mod_bytes(domain_size as u64);
+        let mut buf = vec![0u8; num_queries * chunk];
+        self.fill_challenge_bytes(&mut buf)?;
+        let mut queries = buf
+            .chunks_exact(chunk)
+            .map(|bytes| reduce_mod(bytes, domain_size as u64) as usize)
+            .collect::<Vec<_>>();
+        if dedup {
+            queries.sort_unstable();
+            queries.dedup();
+            self.public_u64_le(queries.len() as u64)?;
+        }
+        Ok(queries)
+    }
+}
+
+impl<T: IntegerIOPattern> ChallengeQueriesIOPattern for T {}
+impl<T: ChallengeMod + IntegerPublic + ?Sized> ChallengeQueries for T {}
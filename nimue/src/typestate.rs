@@ -0,0 +1,243 @@
+//! Compile-time checked transcripts for fixed protocols.
+//!
+//! The [`typestate!`](crate::typestate) macro declares a protocol as a fixed sequence of byte operations.
+//! The prover and the verifier are then wrapped in [`TypedMerlin`] and [`TypedArthur`], whose type
+//! records the operations still to be performed:
+//! each method consumes the wrapper and returns a new one, exposing only the next declared operation.
+//! Mis-sequenced calls are therefore compile errors, rather than [`IOPatternError`]s at runtime.
+//!
+//! ```
+//! use nimue::*;
+//! use nimue::typestate::TypedProtocol;
+//!
+//! nimue::typestate! {
+//!     /// A (byte-level) Schnorr identification protocol.
+//!     pub struct Schnorr {
+//!         absorb(32, "commitment");
+//!         squeeze(16, "challenge");
+//!         absorb(32, "response");
+//!     }
+//! }
+//!
+//! let merlin = Schnorr::to_merlin::<DefaultHash>("📝");
+//! let merlin = merlin.add_bytes(&[1u8; 32]).unwrap();
+//! let (challenge, merlin) = merlin.challenge_bytes().unwrap();
+//! let merlin = merlin.add_bytes(&[2u8; 32]).unwrap();
+//! let transcript = merlin.finish();
+//!
+//! let arthur = Schnorr::to_arthur::<DefaultHash>("📝", &transcript);
+//! let (commitment, arthur) = arthur.next_bytes().unwrap();
+//! let (arthur_challenge, arthur) = arthur.challenge_bytes().unwrap();
+//! let (response, arthur) = arthur.next_bytes().unwrap();
+//! arthur.finish().unwrap();
+//! assert_eq!((commitment, arthur_challenge, response), ([1u8; 32], challenge, [2u8; 32]));
+//! ```
+//!
+//! Squeezing a challenge before sending the commitment does not compile:
+//!
+//! ```compile_fail
+//! # use nimue::*;
+//! # use nimue::typestate::TypedProtocol;
+//! # nimue::typestate! {
+//! #     pub struct Schnorr {
+//! #         absorb(32, "commitment");
+//! #         squeeze(16, "challenge");
+//! #         absorb(32, "response");
+//! #     }
+//! # }
+//! let merlin = Schnorr::to_merlin::<DefaultHash>("📝");
+//! let (challenge, merlin) = merlin.challenge_bytes().unwrap();
+//! ```
+use core::marker::PhantomData;
+
+use crate::{
+    Arthur, ByteChallenges, BytePublic, ByteReader, ByteWriter, DefaultRng, DuplexHash, IOPattern,
+    IOPatternError, Merlin,
+};
+
+/// The next operation is absorbing `N` bytes, then `Next`.
+pub struct Absorb<const N: usize, Next>(PhantomData<Next>);
+/// The next operation is squeezing `N` bytes, then `Next`.
+pub struct Squeeze<const N: usize, Next>(PhantomData<Next>);
+/// The next operation is a ratchet, then `Next`.
+pub struct Ratchet<Next>(PhantomData<Next>);
+/// No operation is left.
+pub struct End;
+
+/// A protocol whose sequence of operations is known at compile time.
+///
+/// Implemented by the [`typestate!`](crate::typestate) macro.
+pub trait TypedProtocol {
+    /// The sequence of operations, as nested [`Absorb`], [`Squeeze`], [`Ratchet`], terminated by [`End`].
+    type Ops;
+
+    /// The [`IOPattern`] declaring the same operations.
+    fn io_pattern<H: DuplexHash>(domsep: &str) -> IOPattern<H>;
+
+    /// Create the prover state.
+    fn to_merlin<H: DuplexHash>(domsep: &str) -> TypedMerlin<H, Self::Ops> {
+        TypedMerlin {
+            merlin: Self::io_pattern(domsep).to_merlin(),
+            _ops: PhantomData,
+        }
+    }
+
+    /// Create the verifier state, reading from `transcript`.
+    fn to_arthur<'a, H: DuplexHash>(
+        domsep: &str,
+        transcript: &'a [u8],
+    ) -> TypedArthur<'a, H, Self::Ops> {
+        TypedArthur {
+            arthur: Self::io_pattern(domsep).to_arthur(transcript),
+            _ops: PhantomData,
+        }
+    }
+}
+
+/// A [`Merlin`] that can only perform the operations `Ops`, in order.
+pub struct TypedMerlin<H: DuplexHash, Ops> {
+    merlin: Merlin<H, u8, DefaultRng>,
+    _ops: PhantomData<Ops>,
+}
+
+/// An [`Arthur`] that can only perform the operations `Ops`, in order.
+pub struct TypedArthur<'a, H: DuplexHash, Ops> {
+    arthur: Arthur<'a, H, u8>,
+    _ops: PhantomData<Ops>,
+}
+
+impl<H: DuplexHash, Ops> TypedMerlin<H, Ops> {
+    fn next<Next>(self) -> TypedMerlin<H, Next> {
+        TypedMerlin {
+            merlin: self.merlin,
+            _ops: PhantomData,
+        }
+    }
+
+    /// Access the prover's private coins.
+    pub fn rng(&mut self) -> &mut (impl rand::CryptoRng + rand::RngCore) {
+        self.merlin.rng()
+    }
+}
+
+impl<H: DuplexHash, const N: usize, Next> TypedMerlin<H, Absorb<N, Next>> {
+    /// Write `N` bytes to the protocol transcript.
+    pub fn add_bytes(mut self, input: &[u8; N]) -> Result<TypedMerlin<H, Next>, IOPatternError> {
+        self.merlin.add_bytes(input).map(|()| self.next())
+    }
+
+    /// Absorb `N` public bytes.
+    pub fn public_bytes(mut self, input: &[u8; N]) -> Result<TypedMerlin<H, Next>, IOPatternError> {
+        self.merlin.public_bytes(input).map(|()| self.next())
+    }
+}
+
+impl<H: DuplexHash, const N: usize, Next> TypedMerlin<H, Squeeze<N, Next>> {
+    /// Squeeze an `N`-byte challenge.
+    pub fn challenge_bytes(mut self) -> Result<([u8; N], TypedMerlin<H, Next>), IOPatternError> {
+        let challenge = self.merlin.challenge_bytes()?;
+        Ok((challenge, self.next()))
+    }
+}
+
+impl<H: DuplexHash, Next> TypedMerlin<H, Ratchet<Next>> {
+    /// Ratchet the sponge state.
+    pub fn ratchet(mut self) -> Result<TypedMerlin<H, Next>, IOPatternError> {
+        self.merlin.ratchet().map(|()| self.next())
+    }
+}
+
+impl<H: DuplexHash> TypedMerlin<H, End> {
+    /// Return the protocol transcript.
+    pub fn finish(self) -> Vec<u8> {
+        self.merlin.transcript().to_vec()
+    }
+}
+
+impl<'a, H: DuplexHash, Ops> TypedArthur<'a, H, Ops> {
+    fn next<Next>(self) -> TypedArthur<'a, H, Next> {
+        TypedArthur {
+            arthur: self.arthur,
+            _ops: PhantomData,
+        }
+    }
+}
+
+impl<'a, H: DuplexHash, const N: usize, Next> TypedArthur<'a, H, Absorb<N, Next>> {
+    /// Read `N` bytes from the protocol transcript.
+    pub fn next_bytes(mut self) -> Result<([u8; N], TypedArthur<'a, H, Next>), IOPatternError> {
+        let input = self.arthur.next_bytes()?;
+        Ok((input, self.next()))
+    }
+
+    /// Absorb `N` public bytes.
+    pub fn public_bytes(
+        mut self,
+        input: &[u8; N],
+    ) -> Result<TypedArthur<'a, H, Next>, IOPatternError> {
+        self.arthur.public_bytes(input).map(|()| self.next())
+    }
+}
+
+impl<'a, H: DuplexHash, const N: usize, Next> TypedArthur<'a, H, Squeeze<N, Next>> {
+    /// Squeeze an `N`-byte challenge.
+    pub fn challenge_bytes(
+        mut self,
+    ) -> Result<([u8; N], TypedArthur<'a, H, Next>), IOPatternError> {
+        let challenge = self.arthur.challenge_bytes()?;
+        Ok((challenge, self.next()))
+    }
+}
+
+impl<'a, H: DuplexHash, Next> TypedArthur<'a, H, Ratchet<Next>> {
+    /// Ratchet the sponge state.
+    pub fn ratchet(mut self) -> Result<TypedArthur<'a, H, Next>, IOPatternError> {
+        self.arthur.ratchet().map(|()| self.next())
+    }
+}
+
+impl<H: DuplexHash> TypedArthur<'_, H, End> {
+    /// Finish verification, checking that the protocol transcript has been read entirely.
+    pub fn finish(mut self) -> Result<(), IOPatternError> {
+        if self.arthur.transcript.is_empty() {
+            Ok(())
+        } else {
+            Err("Trailing bytes in the protocol transcript".into())
+        }
+    }
+}
+
+/// Declare a [`TypedProtocol`](crate::typestate::TypedProtocol) from a fixed sequence of operations.
+///
+/// Each operation is one of `absorb(count, label);`, `squeeze(count, label);`, or `ratchet();`.
+/// See the [`typestate`](crate::typestate) module for an example.
+#[macro_export]
+macro_rules! typestate {
+    ($(#[$meta:meta])* $vis:vis struct $name:ident { $($op:ident($($args:tt)*);)* }) => {
+        $(#[$meta])*
+        $vis struct $name;
+
+        impl $crate::typestate::TypedProtocol for $name {
+            type Ops = $crate::typestate!(@ops $($op($($args)*);)*);
+
+            fn io_pattern<H: $crate::DuplexHash>(domsep: &str) -> $crate::IOPattern<H> {
+                let io = $crate::IOPattern::<H>::new(domsep);
+                $(let io = $crate::typestate!(@io io, $op($($args)*));)*
+                io
+            }
+        }
+    };
+    (@ops) => { $crate::typestate::End };
+    (@ops absorb($count:literal, $label:expr); $($rest:tt)*) => {
+        $crate::typestate::Absorb<$count, $crate::typestate!(@ops $($rest)*)>
+    };
+    (@ops squeeze($count:literal, $label:expr); $($rest:tt)*) => {
+        $crate::typestate::Squeeze<$count, $crate::typestate!(@ops $($rest)*)>
+    };
+    (@ops ratchet(); $($rest:tt)*) => {
+        $crate::typestate::Ratchet<$crate::typestate!(@ops $($rest)*)>
+    };
+    (@io $io:ident, absorb($count:literal, $label:expr)) => { $io.absorb($count, $label) };
+    (@io $io:ident, squeeze($count:literal, $label:expr)) => { $io.squeeze($count, $label) };
+    (@io $io:ident, ratchet()) => { $io.ratchet() };
+}
@@ -0,0 +1,58 @@
+//! The redaction level is process-wide: its test runs in its own binary,
+//! so that it does not race with the unit tests formatting states and errors.
+use nimue::hash::Keccak;
+use nimue::{ByteReader, IOPattern, RedactionLevel};
+
+/// The crate-wide redaction level controls what the states show, and never shows the transcript.
+#[test]
+fn test_redaction_level() {
+    assert_eq!(RedactionLevel::global(), RedactionLevel::default());
+    let io = IOPattern::<Keccak>::new("example.com")
+        .absorb(3, "secret-sauce")
+        .squeeze(16, "chal");
+    let mut arthur = io.to_arthur(b"xyz");
+    arthur.next_bytes::<3>().unwrap();
+
+    RedactionLevel::Labels.set_global();
+    let debug = format!("{:?} {:?}", io, arthur);
+    assert!(debug.contains("secret-sauce") && debug.contains("\"chal\""));
+    RedactionLevel::Progress.set_global();
+    let debug = format!("{:?} {:?}", io, arthur);
+    assert!(!debug.contains("secret-sauce") && !debug.contains("chal"));
+    assert!(debug.contains(&hex::encode(io.digest())) && debug.contains("op 1/2"));
+    RedactionLevel::Opaque.set_global();
+    assert_eq!(
+        format!("{:?} {:?}", io, arthur),
+        "IOPattern Arthur(SAFE sponge)"
+    );
+    RedactionLevel::default().set_global();
+    assert!(!format!("{:?}", arthur).contains("xyz"));
+
+    // errors follow the same level
+    let io = IOPattern::<Keccak>::new("example.com")
+        .marker("secret-phase")
+        .absorb(3, "secret-sauce");
+    let errors = || {
+        let mut arthur = io.to_arthur(b"xyz");
+        let marker = arthur.marker("other-phase").unwrap_err().to_string();
+        let mut arthur = io.to_arthur(b"xyz");
+        let order = arthur.next_bytes::<3>().unwrap_err().to_string();
+        let mut arthur = io.to_arthur(b"xyz");
+        arthur.marker("secret-phase").unwrap();
+        arthur.fail_at_current_op("bad");
+        let failure = arthur.failure().unwrap().to_string();
+        [marker, order, failure]
+    };
+    RedactionLevel::Labels.set_global();
+    let [marker, order, failure] = errors();
+    assert!(marker.contains("secret-phase") && failure.contains("secret-phase"));
+    assert!(order.contains("Marker"));
+    RedactionLevel::Progress.set_global();
+    let [marker, order, failure] = errors();
+    assert!(!marker.contains("phase") && order.contains("Marker"));
+    assert!(!failure.contains("phase") && failure.contains("1/2"));
+    RedactionLevel::Opaque.set_global();
+    let [marker, order, _] = errors();
+    assert!(!marker.contains("phase") && !order.contains("Marker"));
+    RedactionLevel::default().set_global();
+}